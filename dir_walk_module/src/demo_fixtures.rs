@@ -0,0 +1,288 @@
+//! # Shared Demo Fixtures (`demo_fixtures.rs`)
+//!
+//! The error type every demo returns and the test directory tree the
+//! demos walk, kept apart from the demos so each one can be run on its
+//! own (see `main.rs`) against the same fixture.
+
+use crate::dir_walk_module;
+use crate::dir_walk_module::testing::TempTreeGuard;
+
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::Path;
+
+// ============================================================================
+// ERROR TYPE
+// ============================================================================
+
+/// Demo-specific errors.
+///
+/// Unit variants only — no String payload — because:
+/// 1. Debug diagnostics are printed at the error site
+/// 2. Production must not expose system details
+/// 3. The variant name identifies the failure category
+#[derive(Debug)]
+pub enum DemoError {
+    /// I/O failure during demo setup or execution.
+    /// Debug-site prefix: DIO (Demo IO)
+    Io,
+
+    /// Walk operation failure propagated from dir_walk_module.
+    /// Debug-site prefix: DWK (Demo WalK)
+    Walk,
+
+    /// A demo's claim about the walk did not hold (see `check()`).
+    /// Debug-site prefix: DCK (Demo ChecK)
+    Check,
+}
+
+impl std::fmt::Display for DemoError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DemoError::Io => write!(f, "DIO: demo io operation failed"),
+            DemoError::Walk => write!(f, "DWK: demo walk operation failed"),
+            DemoError::Check => write!(f, "DCK: demo check failed"),
+        }
+    }
+}
+
+impl std::error::Error for DemoError {}
+
+impl From<io::Error> for DemoError {
+    fn from(_err: io::Error) -> Self {
+        #[cfg(debug_assertions)]
+        eprintln!("DIO: io::Error: {}", _err);
+        DemoError::Io
+    }
+}
+
+impl From<dir_walk_module::WalkError> for DemoError {
+    fn from(_err: dir_walk_module::WalkError) -> Self {
+        #[cfg(debug_assertions)]
+        eprintln!("DWK: WalkError: {}", _err);
+        DemoError::Walk
+    }
+}
+
+// ============================================================================
+// TEST DIRECTORY STRUCTURE CREATION
+// ============================================================================
+
+/// Create a comprehensive test directory structure for demonstration.
+///
+/// ## Structure Created
+/// ```text
+/// demo_test_dir/
+/// ├── 0.toml                        (metadata file, skipped by sorter)
+/// ├── 1__first_message.toml
+/// ├── 2__second_message.toml
+/// ├── 3__third_message.gpgtoml
+/// ├── README.txt
+/// ├── team_alpha/
+/// │   ├── 1__alpha_msg.toml
+/// │   ├── 2__alpha_msg.toml
+/// │   └── subdir/
+/// │       ├── 1__nested.toml
+/// │       └── deep_file.txt
+/// ├── team_beta/
+/// │   ├── 1__beta_msg.toml
+/// │   └── archive/
+/// │       └── old_message.toml
+/// └── temp_files/
+///     ├── cache.tmp
+///     └── log.txt
+/// ```
+///
+/// # Arguments
+/// * `base_path` - Base directory where test structure will be created
+///
+/// # Returns
+/// `Ok(())` on success, `Err(DemoError)` if any file/dir creation fails.
+pub fn create_test_directory_structure(base_path: &Path) -> Result<(), DemoError> {
+    println!("\n📁 Creating test directory structure...");
+
+    fs::create_dir_all(base_path)?;
+    println!("   Created base directory");
+
+    // -- Root-level files --
+    let root_files: &[(&str, &str)] = &[
+        (
+            "1__first_message.toml",
+            "# First message\n[message]\ncontent = \"Hello\"",
+        ),
+        (
+            "2__second_message.toml",
+            "# Second message\n[message]\ncontent = \"World\"",
+        ),
+        (
+            "3__third_message.gpgtoml",
+            "# Encrypted message\n[message]\ncontent = \"Secret\"",
+        ),
+        ("0.toml", "# Metadata\n[metadata]\nversion = \"1.0\""),
+        (
+            "README.txt",
+            "This is a test directory for directory walking demos.",
+        ),
+    ];
+
+    for (filename, content) in root_files {
+        let file_path = base_path.join(filename);
+        let mut file = File::create(&file_path)?;
+        file.write_all(content.as_bytes())?;
+        println!("   Created file: {}", filename);
+    }
+
+    // -- team_alpha with nested subdir --
+    let team_alpha = base_path.join("team_alpha");
+    fs::create_dir_all(&team_alpha)?;
+    println!("   Created directory: team_alpha/");
+
+    let alpha_files: &[(&str, &str)] = &[
+        ("1__alpha_msg.toml", "[message]\nteam = \"alpha\"\nid = 1"),
+        ("2__alpha_msg.toml", "[message]\nteam = \"alpha\"\nid = 2"),
+    ];
+
+    for (filename, content) in alpha_files {
+        let file_path = team_alpha.join(filename);
+        let mut file = File::create(&file_path)?;
+        file.write_all(content.as_bytes())?;
+        println!("   Created file: team_alpha/{}", filename);
+    }
+
+    let alpha_subdir = team_alpha.join("subdir");
+    fs::create_dir_all(&alpha_subdir)?;
+    println!("   Created directory: team_alpha/subdir/");
+
+    let subdir_files: &[(&str, &str)] = &[
+        ("deep_file.txt", "This is deeply nested"),
+        ("1__nested.toml", "[message]\nnested = true"),
+    ];
+
+    for (filename, content) in subdir_files {
+        let file_path = alpha_subdir.join(filename);
+        let mut file = File::create(&file_path)?;
+        file.write_all(content.as_bytes())?;
+        println!("   Created file: team_alpha/subdir/{}", filename);
+    }
+
+    // -- team_beta with archive subdir --
+    let team_beta = base_path.join("team_beta");
+    fs::create_dir_all(&team_beta)?;
+    println!("   Created directory: team_beta/");
+
+    let beta_file_path = team_beta.join("1__beta_msg.toml");
+    let mut beta_file = File::create(&beta_file_path)?;
+    beta_file.write_all(b"[message]\nteam = \"beta\"\nid = 1")?;
+    println!("   Created file: team_beta/1__beta_msg.toml");
+
+    let beta_archive = team_beta.join("archive");
+    fs::create_dir_all(&beta_archive)?;
+    println!("   Created directory: team_beta/archive/");
+
+    let archive_path = beta_archive.join("old_message.toml");
+    let mut archive_file = File::create(&archive_path)?;
+    archive_file.write_all(b"[message]\narchived = true")?;
+    println!("   Created file: team_beta/archive/old_message.toml");
+
+    // -- temp_files --
+    let temp_files = base_path.join("temp_files");
+    fs::create_dir_all(&temp_files)?;
+    println!("   Created directory: temp_files/");
+
+    let temp_entries: &[(&str, &str)] = &[
+        ("cache.tmp", "temporary cache data"),
+        ("log.txt", "log entry 1\nlog entry 2\nlog entry 3"),
+    ];
+
+    for (filename, content) in temp_entries {
+        let file_path = temp_files.join(filename);
+        let mut file = File::create(&file_path)?;
+        file.write_all(content.as_bytes())?;
+        println!("   Created file: temp_files/{}", filename);
+    }
+
+    println!("✅ Test directory structure created successfully!\n");
+
+    Ok(())
+}
+
+// ============================================================================
+// CHECKS
+// ============================================================================
+
+/// Verify one claim a demo makes about the walk.
+///
+/// Prints `✓ claim` when it holds. Otherwise prints `✗ CHECK FAILED:
+/// claim` and returns `DemoError::Check`, which ends the demo and makes
+/// the process exit with a failure code (see `main()`), so the demo
+/// binary doubles as a smoke test of a vendored copy of the module.
+///
+/// # Arguments
+/// * `holds` - Result of the check
+/// * `claim` - What the demo asserts, phrased as a fact
+pub fn check(holds: bool, claim: &str) -> Result<(), DemoError> {
+    if holds {
+        println!("   ✓ {}", claim);
+        Ok(())
+    } else {
+        println!("   ✗ CHECK FAILED: {}", claim);
+        Err(DemoError::Check)
+    }
+}
+
+// ============================================================================
+// FIXTURE SETUP
+// ============================================================================
+
+/// Create the shared demo tree in the system temp directory.
+///
+/// A tree left by an earlier `--keep` run is removed first. The returned
+/// guard removes the tree when dropped, unless `keep` is set.
+///
+/// # Arguments
+/// * `keep` - Leave the tree in place for manual inspection
+///
+/// # Returns
+/// `Some(guard)` owning the tree, or `None` when it could not be created
+/// (the reason is printed; no demo can run).
+pub fn prepare_fixture(keep: bool) -> Option<TempTreeGuard> {
+    let fixture =
+        TempTreeGuard::new(std::env::temp_dir().join("directory_walk_demo_test")).keep(keep);
+    let test_dir = fixture.path();
+
+    // Production-safe: do not print full temp_dir path in release builds
+    #[cfg(debug_assertions)]
+    println!("Test directory location: {}", test_dir.display());
+
+    #[cfg(not(debug_assertions))]
+    println!("Test directory: (location hidden in release build)");
+
+    if fixture.is_kept() {
+        println!("(You can inspect this directory manually after the demo.)\n");
+    } else {
+        println!("(Removed on exit; run with --keep to inspect it afterwards.)\n");
+    }
+
+    // Clean up any existing test directory from previous runs
+    if test_dir.exists() {
+        match fs::remove_dir_all(test_dir) {
+            Ok(()) => println!("🧹 Cleaned up existing test directory\n"),
+            Err(_e) => {
+                #[cfg(debug_assertions)]
+                eprintln!("MAIN: Could not clean existing directory: {}", _e);
+                // Continue anyway — create_dir_all may still succeed
+            }
+        }
+    }
+
+    // Create test structure — if this fails, no demos can run
+    if let Err(_e) = create_test_directory_structure(test_dir) {
+        #[cfg(debug_assertions)]
+        eprintln!("MAIN: Failed to create test directory structure: {}", _e);
+
+        println!("❌ Could not create test directory. Demos cannot run.");
+        return None;
+    }
+
+    Some(fixture)
+}
@@ -0,0 +1,1033 @@
+//! # Tar Archive Export and Import (`dir_walk_module::archive`)
+//!
+//! A minimal, dependency-free ustar writer driven by `DirWalker`, so a
+//! channel directory can be exported as one archive stream with the
+//! walker's bounds, filters and symlink policy applied, and the matching
+//! reader that restores such a stream without letting entry names escape
+//! the destination.
+//!
+//! ## Project Context
+//! Channel backup used to shell out to `tar`, which follows its own rules
+//! for symlinks and special files. Exporting through the walker means a
+//! backup contains exactly what a scan would see:
+//! - Symlinks are never stored as links (followed links are stored as the
+//!   target's content only when the walk follows symlinks)
+//! - Special files are refused by `DirEntry::open_verified()`
+//! - A file swapped after the scan is refused (`WalkError::EntryChanged`)
+//!
+//! Restoring is the mirror image: `import_tar()` treats the archive as
+//! untrusted input and re-applies the walker's rules to every entry name
+//! (see its docs), so a crafted backup is not a path-traversal vector.
+//!
+//! ## Format Subset
+//! POSIX ustar: regular files (`'0'`) and directories (`'5'`), names up
+//! to 255 bytes via the prefix field, sizes below 8 GiB. Owner ids and
+//! names are written as 0 / empty so archives do not leak local accounts.
+
+use std::ffi::OsStr;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use super::{DirEntry, DirWalker, WalkConfig, WalkError, strip_root};
+
+/// Size of one tar block (headers and data padding).
+const BLOCK_SIZE: usize = 512;
+
+/// Longest name stored in the header's `name` field.
+const NAME_FIELD_LEN: usize = 100;
+
+/// Longest directory part stored in the header's `prefix` field.
+const PREFIX_FIELD_LEN: usize = 155;
+
+/// Largest file size a 12-byte octal size field can hold (8 GiB - 1).
+const MAX_USTAR_FILE_SIZE: u64 = 0o77_777_777_777;
+
+// ============================================================================
+// CONFIGURATION AND REPORT
+// ============================================================================
+
+/// Configuration for `export_tar()`.
+#[derive(Debug, Clone)]
+pub struct ExportConfig {
+    /// Walk used to select entries (bounds, filters, symlink policy).
+    walk_config: WalkConfig,
+}
+
+impl Default for ExportConfig {
+    fn default() -> Self {
+        ExportConfig {
+            walk_config: WalkConfig::channel_scan(),
+        }
+    }
+}
+
+impl ExportConfig {
+    /// Create a config that walks with `WalkConfig::channel_scan()`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the walk configuration used to select entries.
+    ///
+    /// # Arguments
+    /// * `config` - Depth, filters, and error mode for the export walk.
+    ///   `continue_on_error` also decides whether an entry that cannot be
+    ///   archived (e.g. name too long) is skipped or aborts the export.
+    pub fn walk_config(mut self, config: WalkConfig) -> Self {
+        self.walk_config = config;
+        self
+    }
+}
+
+/// Counts from an `export_tar()` run.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExportReport {
+    /// Regular files archived.
+    pub files: usize,
+
+    /// Directories archived.
+    pub dirs: usize,
+
+    /// File content bytes archived (excluding headers and padding).
+    pub bytes: u64,
+
+    /// Entries not archived: symlinks, special files, unrepresentable
+    /// names or sizes, and failures skipped under `continue_on_error`.
+    pub skipped: usize,
+}
+
+// ============================================================================
+// EXPORT
+// ============================================================================
+
+/// Write the walked tree under `path` to `writer` as a ustar archive.
+///
+/// Entry names are relative to `path` and use `/` separators; entries are
+/// written in walk (breadth-first) order and the archive is terminated
+/// with two zero blocks.
+///
+/// # Arguments
+/// * `path` - Directory to export
+/// * `writer` - Destination stream (wrap files in `BufWriter`)
+/// * `config` - Export configuration
+///
+/// # Returns
+/// * `Ok(ExportReport)` - Counts for the run
+/// * `Err(WalkError::IoError)` - Writing to `writer` failed
+/// * `Err(WalkError::EntryChanged)` - A file shrank while being archived
+///   (its header was already written, so the stream cannot be repaired)
+/// * `Err(WalkError)` - Walk or entry failure in strict mode
+pub fn export_tar<W: Write>(
+    path: &Path,
+    writer: &mut W,
+    config: &ExportConfig,
+) -> Result<ExportReport, WalkError> {
+    let continue_on_error = config.walk_config.continue_on_error;
+    let mut report = ExportReport::default();
+
+    for entry_result in DirWalker::new(path, config.walk_config.clone()) {
+        let entry = entry_result?;
+
+        match export_entry(path, &entry, writer, &mut report) {
+            Ok(()) => {}
+            Err(ExportFailure::Skippable(e)) => {
+                if continue_on_error {
+                    report.skipped = report.skipped.saturating_add(1);
+                } else {
+                    return Err(e);
+                }
+            }
+            Err(ExportFailure::Stream(e)) => return Err(e),
+        }
+    }
+
+    // End-of-archive marker: two zero blocks
+    write_all_or_io_error(writer, &[0u8; BLOCK_SIZE * 2])?;
+
+    Ok(report)
+}
+
+/// Why one entry could not be exported.
+enum ExportFailure {
+    /// Nothing was written for this entry; the stream is still valid.
+    Skippable(WalkError),
+
+    /// The stream is partially written or broken; the export must stop.
+    Stream(WalkError),
+}
+
+/// Archive one walked entry (header + content for files).
+fn export_entry<W: Write>(
+    root: &Path,
+    entry: &DirEntry,
+    writer: &mut W,
+    report: &mut ExportReport,
+) -> Result<(), ExportFailure> {
+    // Links are never archived as links; a followed link reports its
+    // target's kind through is_file()/is_dir()
+    let unfollowed_link = entry.is_symlink() && !entry.is_file() && !entry.is_dir();
+    if unfollowed_link || !(entry.is_file() || entry.is_dir()) {
+        report.skipped = report.skipped.saturating_add(1);
+        return Ok(());
+    }
+
+    let mut name = archive_name(root, entry.path()).map_err(ExportFailure::Skippable)?;
+
+    if entry.is_dir() {
+        name.push(b'/');
+        let header = build_header(&name, b'5', 0, directory_mode(entry), modified_secs(entry))
+            .map_err(ExportFailure::Skippable)?;
+        write_all_or_io_error(writer, &header).map_err(ExportFailure::Stream)?;
+        report.dirs = report.dirs.saturating_add(1);
+        return Ok(());
+    }
+
+    let file = entry.open_verified().map_err(ExportFailure::Skippable)?;
+    let size = match file.metadata() {
+        Ok(m) => m.len(),
+        Err(_e) => {
+            #[cfg(debug_assertions)]
+            eprintln!("DWIO: export_tar could not stat open file: {}", _e);
+            return Err(ExportFailure::Skippable(WalkError::IoError));
+        }
+    };
+    if size > MAX_USTAR_FILE_SIZE {
+        #[cfg(debug_assertions)]
+        eprintln!("DWAF: export_tar file too large for ustar ({} bytes)", size);
+        return Err(ExportFailure::Skippable(WalkError::ArchiveFormat));
+    }
+
+    let header = build_header(&name, b'0', size, file_mode(entry), modified_secs(entry))
+        .map_err(ExportFailure::Skippable)?;
+    write_all_or_io_error(writer, &header).map_err(ExportFailure::Stream)?;
+
+    // Copy exactly `size` bytes; a file that grew is truncated to the
+    // header's size, one that shrank breaks the stream
+    let copied = match io::copy(&mut file.take(size), writer) {
+        Ok(n) => n,
+        Err(_e) => {
+            #[cfg(debug_assertions)]
+            eprintln!("DWIO: export_tar content copy failed: {}", _e);
+            return Err(ExportFailure::Stream(WalkError::IoError));
+        }
+    };
+    if copied != size {
+        #[cfg(debug_assertions)]
+        eprintln!("DWCH: export_tar file shrank while archiving");
+        return Err(ExportFailure::Stream(WalkError::EntryChanged));
+    }
+
+    let padding = padding_len(size);
+    write_all_or_io_error(writer, &[0u8; BLOCK_SIZE][..padding]).map_err(ExportFailure::Stream)?;
+
+    report.files = report.files.saturating_add(1);
+    report.bytes = report.bytes.saturating_add(size);
+    Ok(())
+}
+
+// ============================================================================
+// HEADER ENCODING
+// ============================================================================
+
+/// Relative, `/`-separated archive name for `path` under `root`.
+///
+/// Only normal components are accepted (the walker never produces `..`
+/// or root components below its root, but the check is cheap).
+fn archive_name(root: &Path, path: &Path) -> Result<Vec<u8>, WalkError> {
+    let relative = strip_root(path, root).map_err(|_| WalkError::ArchiveFormat)?;
+
+    let mut name: Vec<u8> = Vec::new();
+    for component in relative.components() {
+        match component {
+            Component::Normal(part) => {
+                if !name.is_empty() {
+                    name.push(b'/');
+                }
+                name.extend_from_slice(part.as_encoded_bytes());
+            }
+            _ => return Err(WalkError::ArchiveFormat),
+        }
+    }
+
+    if name.is_empty() {
+        return Err(WalkError::ArchiveFormat);
+    }
+    Ok(name)
+}
+
+/// Build one 512-byte ustar header.
+///
+/// # Arguments
+/// * `name` - Full archive name (directories end with `/`)
+/// * `typeflag` - `b'0'` regular file, `b'5'` directory
+/// * `size` - Content length (0 for directories)
+/// * `mode` - Permission bits
+/// * `mtime` - Seconds since the Unix epoch
+fn build_header(
+    name: &[u8],
+    typeflag: u8,
+    size: u64,
+    mode: u32,
+    mtime: u64,
+) -> Result<[u8; BLOCK_SIZE], WalkError> {
+    let mut header = [0u8; BLOCK_SIZE];
+
+    let (prefix, short_name) = split_name(name)?;
+    header[0..short_name.len()].copy_from_slice(short_name);
+    write_octal(&mut header[100..108], u64::from(mode & 0o7777))?;
+    write_octal(&mut header[108..116], 0)?; // uid
+    write_octal(&mut header[116..124], 0)?; // gid
+    write_octal(&mut header[124..136], size)?;
+    write_octal(&mut header[136..148], mtime)?;
+    header[156] = typeflag;
+    header[257..263].copy_from_slice(b"ustar\0");
+    header[263..265].copy_from_slice(b"00");
+    header[345..345 + prefix.len()].copy_from_slice(prefix);
+
+    // Checksum is computed with the checksum field itself as spaces
+    header[148..156].copy_from_slice(b"        ");
+    let checksum: u64 = header.iter().map(|b| u64::from(*b)).sum();
+    write_octal(&mut header[148..155], checksum)?;
+    header[155] = b' ';
+
+    Ok(header)
+}
+
+/// Split a name into ustar (`prefix`, `name`) fields at a `/` boundary.
+fn split_name(name: &[u8]) -> Result<(&[u8], &[u8]), WalkError> {
+    if name.len() <= NAME_FIELD_LEN {
+        return Ok((&[], name));
+    }
+
+    // A trailing '/' (directories) must stay in the name part, so search
+    // for separators before the final byte only
+    let search_end = name.len().saturating_sub(1);
+    for (index, byte) in name[..search_end].iter().enumerate().rev() {
+        if *byte == b'/' {
+            let (prefix, rest) = (&name[..index], &name[index + 1..]);
+            if prefix.len() <= PREFIX_FIELD_LEN && rest.len() <= NAME_FIELD_LEN && !rest.is_empty()
+            {
+                return Ok((prefix, rest));
+            }
+        }
+    }
+
+    #[cfg(debug_assertions)]
+    eprintln!(
+        "DWAF: archive name too long for ustar ({} bytes)",
+        name.len()
+    );
+    Err(WalkError::ArchiveFormat)
+}
+
+/// Write `value` as zero-padded octal digits followed by a NUL.
+fn write_octal(field: &mut [u8], value: u64) -> Result<(), WalkError> {
+    let digits = field.len().saturating_sub(1);
+    let text = format!("{:0width$o}", value, width = digits);
+    if text.len() != digits {
+        return Err(WalkError::ArchiveFormat);
+    }
+    field[..digits].copy_from_slice(text.as_bytes());
+    field[digits] = 0;
+    Ok(())
+}
+
+/// Bytes of zero padding after `size` bytes of content.
+fn padding_len(size: u64) -> usize {
+    let remainder = (size % BLOCK_SIZE as u64) as usize;
+    if remainder == 0 {
+        0
+    } else {
+        BLOCK_SIZE - remainder
+    }
+}
+
+/// Modification time in whole seconds (pre-epoch and unknown times → 0).
+fn modified_secs(entry: &DirEntry) -> u64 {
+    entry
+        .snapshot()
+        .modified
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Permission bits for a file (Unix mode, else 0o644 / 0o444 readonly).
+fn file_mode(entry: &DirEntry) -> u32 {
+    match entry.snapshot().mode {
+        Some(mode) => mode,
+        None if entry.is_readonly() => 0o444,
+        None => 0o644,
+    }
+}
+
+/// Permission bits for a directory (Unix mode, else 0o755).
+fn directory_mode(entry: &DirEntry) -> u32 {
+    entry.snapshot().mode.unwrap_or(0o755)
+}
+
+/// `write_all`, mapping failures to `WalkError::IoError`.
+fn write_all_or_io_error<W: Write>(writer: &mut W, bytes: &[u8]) -> Result<(), WalkError> {
+    writer.write_all(bytes).map_err(|_e| {
+        #[cfg(debug_assertions)]
+        eprintln!("DWIO: archive write failed: {}", _e);
+        WalkError::IoError
+    })
+}
+
+// ============================================================================
+// IMPORT
+// ============================================================================
+
+/// Configuration for `import_tar()`.
+#[derive(Debug, Clone)]
+pub struct ImportConfig {
+    /// Name rules and bounds re-applied to archive entries.
+    walk_config: WalkConfig,
+}
+
+impl Default for ImportConfig {
+    fn default() -> Self {
+        ImportConfig {
+            walk_config: WalkConfig::channel_scan(),
+        }
+    }
+}
+
+impl ImportConfig {
+    /// Create a config that applies `WalkConfig::channel_scan()` rules.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the walk rules re-applied to archive entries.
+    ///
+    /// # Arguments
+    /// * `config` - Rules applied as if the archive were a walked tree:
+    ///   `max_depth`, `skip_temp_files` and `min_file_size` skip entries,
+    ///   and `continue_on_error` decides whether a rejected entry is
+    ///   skipped or aborts the import.
+    pub fn walk_config(mut self, config: WalkConfig) -> Self {
+        self.walk_config = config;
+        self
+    }
+}
+
+/// Counts from an `import_tar()` run.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImportReport {
+    /// Regular files written.
+    pub files: usize,
+
+    /// Directories created.
+    pub dirs: usize,
+
+    /// File content bytes written.
+    pub bytes: u64,
+
+    /// Entries not extracted: links, special files, entries filtered by
+    /// the walk rules, and rejected entries skipped under
+    /// `continue_on_error`.
+    pub skipped: usize,
+}
+
+/// Extract a ustar archive from `reader` into `destination`.
+///
+/// Every entry path is validated before anything is written for it:
+/// - Absolute paths, `..` components and platform prefixes are rejected
+///   (`WalkError::PathEscape`)
+/// - An existing symlink anywhere between `destination` and the entry is
+///   rejected, so a pre-existing link cannot redirect a write outside
+/// - Only regular files and directories are extracted; link, device and
+///   extension entries are skipped and their data discarded
+/// - The walk rules in `config` (depth, temp-file names, minimum size)
+///   are applied as if the archive were a walked directory
+///
+/// Permissions, owners and times are not restored. Existing files are
+/// overwritten.
+///
+/// # Arguments
+/// * `reader` - Archive stream (wrap files in `BufReader`)
+/// * `destination` - Directory to extract into (created if missing)
+/// * `config` - Import configuration
+///
+/// # Returns
+/// * `Ok(ImportReport)` - Counts for the run
+/// * `Err(WalkError::ArchiveFormat)` - Bad header checksum or magic, or
+///   a truncated archive (the stream cannot be resynchronised)
+/// * `Err(WalkError::PathEscape)` - Entry path rejected in strict mode
+/// * `Err(WalkError::IoError)` - Reading the archive or writing a file
+///   failed
+pub fn import_tar<R: Read>(
+    reader: &mut R,
+    destination: &Path,
+    config: &ImportConfig,
+) -> Result<ImportReport, WalkError> {
+    let rules = &config.walk_config;
+    let mut report = ImportReport::default();
+
+    if let Err(_e) = fs::create_dir_all(destination) {
+        #[cfg(debug_assertions)]
+        eprintln!("DWIO: import_tar could not create destination: {}", _e);
+        return Err(WalkError::IoError);
+    }
+
+    let mut header = [0u8; BLOCK_SIZE];
+    loop {
+        if !read_block(reader, &mut header)? {
+            // Archive ended without the end marker; accept what was read
+            break;
+        }
+        if header.iter().all(|b| *b == 0) {
+            break;
+        }
+
+        let parsed = parse_header(&header)?;
+        let padded_size = parsed.size.saturating_add(padding_len(parsed.size) as u64);
+
+        let outcome = match import_plan(destination, &parsed, rules) {
+            Ok(ImportPlan::Skip) => {
+                report.skipped = report.skipped.saturating_add(1);
+                discard(reader, padded_size)?;
+                Ok(())
+            }
+            Ok(ImportPlan::Directory(target)) => {
+                discard(reader, padded_size)?;
+                fs::create_dir_all(&target)
+                    .map(|()| {
+                        report.dirs = report.dirs.saturating_add(1);
+                    })
+                    .map_err(|_e| {
+                        #[cfg(debug_assertions)]
+                        eprintln!("DWIO: import_tar could not create directory: {}", _e);
+                        WalkError::IoError
+                    })
+            }
+            Ok(ImportPlan::File(target)) => {
+                extract_file(reader, &target, parsed.size)?;
+                discard(reader, padded_size.saturating_sub(parsed.size))?;
+                report.files = report.files.saturating_add(1);
+                report.bytes = report.bytes.saturating_add(parsed.size);
+                Ok(())
+            }
+            Err(e) => {
+                discard(reader, padded_size)?;
+                Err(e)
+            }
+        };
+
+        if let Err(e) = outcome {
+            if rules.continue_on_error {
+                report.skipped = report.skipped.saturating_add(1);
+            } else {
+                return Err(e);
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+/// Fields of one decoded ustar header.
+struct ParsedHeader {
+    /// Full entry name (`prefix/name`), trailing `/` kept.
+    name: Vec<u8>,
+
+    /// Content length in bytes.
+    size: u64,
+
+    /// Entry type (`b'0'`/`0` file, `b'5'` directory, others skipped).
+    typeflag: u8,
+}
+
+/// What to do with one archive entry.
+enum ImportPlan {
+    /// Consume the entry's data without writing anything.
+    Skip,
+
+    /// Create this directory.
+    Directory(PathBuf),
+
+    /// Write the entry's data to this file.
+    File(PathBuf),
+}
+
+/// Read exactly one block; `Ok(false)` on a clean end of stream.
+fn read_block<R: Read>(reader: &mut R, block: &mut [u8; BLOCK_SIZE]) -> Result<bool, WalkError> {
+    let mut filled: usize = 0;
+    // Bounded: each pass either fills bytes or returns
+    while filled < BLOCK_SIZE {
+        match reader.read(&mut block[filled..]) {
+            Ok(0) if filled == 0 => return Ok(false),
+            Ok(0) => {
+                #[cfg(debug_assertions)]
+                eprintln!("DWAF: import_tar archive ends inside a header");
+                return Err(WalkError::ArchiveFormat);
+            }
+            Ok(n) => filled = filled.saturating_add(n),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(_e) => {
+                #[cfg(debug_assertions)]
+                eprintln!("DWIO: import_tar read failed: {}", _e);
+                return Err(WalkError::IoError);
+            }
+        }
+    }
+    Ok(true)
+}
+
+/// Decode and verify one header block (magic and checksum).
+fn parse_header(header: &[u8; BLOCK_SIZE]) -> Result<ParsedHeader, WalkError> {
+    if &header[257..262] != b"ustar" {
+        #[cfg(debug_assertions)]
+        eprintln!("DWAF: import_tar header is not ustar");
+        return Err(WalkError::ArchiveFormat);
+    }
+
+    let stored = parse_octal(&header[148..156])?;
+    let computed: u64 = header
+        .iter()
+        .enumerate()
+        .map(|(i, b)| {
+            if (148..156).contains(&i) {
+                32
+            } else {
+                u64::from(*b)
+            }
+        })
+        .sum();
+    if stored != computed {
+        #[cfg(debug_assertions)]
+        eprintln!("DWAF: import_tar header checksum mismatch");
+        return Err(WalkError::ArchiveFormat);
+    }
+
+    let prefix = field_bytes(&header[345..500]);
+    let short_name = field_bytes(&header[0..100]);
+    let mut name: Vec<u8> = Vec::with_capacity(prefix.len() + short_name.len() + 1);
+    if !prefix.is_empty() {
+        name.extend_from_slice(prefix);
+        name.push(b'/');
+    }
+    name.extend_from_slice(short_name);
+
+    Ok(ParsedHeader {
+        name,
+        size: parse_octal(&header[124..136])?,
+        typeflag: header[156],
+    })
+}
+
+/// Header field up to its first NUL.
+fn field_bytes(field: &[u8]) -> &[u8] {
+    let end = field.iter().position(|b| *b == 0).unwrap_or(field.len());
+    &field[..end]
+}
+
+/// Parse an octal numeric field (NUL/space padded).
+fn parse_octal(field: &[u8]) -> Result<u64, WalkError> {
+    let mut value: u64 = 0;
+    let mut seen_digit = false;
+    for byte in field {
+        match byte {
+            b'0'..=b'7' => {
+                seen_digit = true;
+                value = value
+                    .checked_mul(8)
+                    .and_then(|v| v.checked_add(u64::from(byte - b'0')))
+                    .ok_or(WalkError::ArchiveFormat)?;
+            }
+            b' ' | 0 if !seen_digit => {}
+            b' ' | 0 => break,
+            _ => {
+                #[cfg(debug_assertions)]
+                eprintln!("DWAF: import_tar invalid octal field");
+                return Err(WalkError::ArchiveFormat);
+            }
+        }
+    }
+    Ok(value)
+}
+
+/// Validate an entry and decide where (if anywhere) it is written.
+fn import_plan(
+    destination: &Path,
+    parsed: &ParsedHeader,
+    rules: &WalkConfig,
+) -> Result<ImportPlan, WalkError> {
+    let is_dir = parsed.typeflag == b'5';
+    let is_file = parsed.typeflag == b'0' || parsed.typeflag == 0;
+    if !is_dir && !is_file {
+        return Ok(ImportPlan::Skip);
+    }
+
+    let relative = sanitize_entry_path(&parsed.name)?;
+
+    // Root children are depth 0, as in the walker
+    let depth = relative.components().count().saturating_sub(1);
+    if let Some(max_depth) = rules.max_depth
+        && depth > max_depth
+    {
+        return Ok(ImportPlan::Skip);
+    }
+
+    if is_file {
+        let temp_name =
+            rules.skip_temp_files && relative.file_name().is_some_and(super::is_temp_file_name);
+        if temp_name || parsed.size < rules.min_file_size {
+            return Ok(ImportPlan::Skip);
+        }
+    }
+
+    let target = destination.join(&relative);
+    check_no_symlink_between(destination, &relative)?;
+
+    Ok(if is_dir {
+        ImportPlan::Directory(target)
+    } else {
+        ImportPlan::File(target)
+    })
+}
+
+/// Turn an archive name into a relative path of normal components only.
+///
+/// Rejects (`WalkError::PathEscape`) absolute names, `..`, platform
+/// prefixes (`C:`), NUL bytes and names with no components. `.` and
+/// empty components (`a//b`) are dropped, as the walker never produces
+/// them.
+fn sanitize_entry_path(name: &[u8]) -> Result<PathBuf, WalkError> {
+    if name.starts_with(b"/") || name.contains(&0) {
+        #[cfg(debug_assertions)]
+        eprintln!("DWPE: import_tar absolute or NUL entry name rejected");
+        return Err(WalkError::PathEscape);
+    }
+
+    let mut relative = PathBuf::new();
+    for part in name.split(|b| *b == b'/') {
+        if part.is_empty() || part == b"." {
+            continue;
+        }
+        let part_os = os_str_from_archive(part)?;
+
+        // Each archive component must be exactly one normal path component
+        // on this platform (catches `..`, `C:`, and `\` on Windows)
+        let mut components = Path::new(part_os).components();
+        match (components.next(), components.next()) {
+            (Some(Component::Normal(normal)), None) if normal == part_os => relative.push(normal),
+            _ => {
+                #[cfg(debug_assertions)]
+                eprintln!("DWPE: import_tar entry name component rejected");
+                return Err(WalkError::PathEscape);
+            }
+        }
+    }
+
+    if relative.as_os_str().is_empty() {
+        #[cfg(debug_assertions)]
+        eprintln!("DWPE: import_tar empty entry name rejected");
+        return Err(WalkError::PathEscape);
+    }
+    Ok(relative)
+}
+
+/// Archive name bytes as an `OsStr` (raw bytes on Unix, UTF-8 elsewhere).
+fn os_str_from_archive(bytes: &[u8]) -> Result<&OsStr, WalkError> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        Ok(OsStr::from_bytes(bytes))
+    }
+
+    #[cfg(not(unix))]
+    {
+        std::str::from_utf8(bytes)
+            .map(OsStr::new)
+            .map_err(|_| WalkError::ArchiveFormat)
+    }
+}
+
+/// Refuse if any existing path from `destination` down to the entry is a
+/// symlink (a planted link would redirect the write outside).
+fn check_no_symlink_between(destination: &Path, relative: &Path) -> Result<(), WalkError> {
+    let mut current = destination.to_path_buf();
+    // Bounded: one pass per component of the sanitized relative path
+    for component in relative.components() {
+        current.push(component);
+        match fs::symlink_metadata(&current) {
+            Ok(m) if m.file_type().is_symlink() => {
+                #[cfg(debug_assertions)]
+                eprintln!("DWPE: import_tar path passes through a symlink");
+                return Err(WalkError::PathEscape);
+            }
+            Ok(_) => {}
+            // Nothing exists below a missing component
+            Err(_) => break,
+        }
+    }
+    Ok(())
+}
+
+/// Write exactly `size` bytes from `reader` to a new or truncated file.
+///
+/// A truncated archive is fatal (`WalkError::ArchiveFormat`): the stream
+/// position is lost.
+fn extract_file<R: Read>(reader: &mut R, target: &Path, size: u64) -> Result<(), WalkError> {
+    if let Some(parent) = target.parent()
+        && let Err(_e) = fs::create_dir_all(parent)
+    {
+        #[cfg(debug_assertions)]
+        eprintln!("DWIO: import_tar could not create parent: {}", _e);
+        return Err(WalkError::IoError);
+    }
+
+    let mut file = match fs::File::create(target) {
+        Ok(f) => f,
+        Err(_e) => {
+            #[cfg(debug_assertions)]
+            eprintln!("DWIO: import_tar could not create file: {}", _e);
+            return Err(WalkError::IoError);
+        }
+    };
+
+    let copied = match io::copy(&mut reader.take(size), &mut file) {
+        Ok(n) => n,
+        Err(_e) => {
+            #[cfg(debug_assertions)]
+            eprintln!("DWIO: import_tar content copy failed: {}", _e);
+            return Err(WalkError::IoError);
+        }
+    };
+    if copied != size {
+        #[cfg(debug_assertions)]
+        eprintln!("DWAF: import_tar archive ends inside file data");
+        return Err(WalkError::ArchiveFormat);
+    }
+    Ok(())
+}
+
+/// Consume and drop `count` bytes (skipped data and padding).
+fn discard<R: Read>(reader: &mut R, count: u64) -> Result<(), WalkError> {
+    match io::copy(&mut reader.take(count), &mut io::sink()) {
+        Ok(n) if n == count => Ok(()),
+        Ok(_) => {
+            #[cfg(debug_assertions)]
+            eprintln!("DWAF: import_tar archive ends inside entry data");
+            Err(WalkError::ArchiveFormat)
+        }
+        Err(_e) => {
+            #[cfg(debug_assertions)]
+            eprintln!("DWIO: import_tar read failed: {}", _e);
+            Err(WalkError::IoError)
+        }
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// Helper: unique test directory path (caller cleans up).
+    fn test_dir(name: &str) -> PathBuf {
+        crate::dir_walk_module::testing::unique_test_dir(&format!("archive_{}", name))
+    }
+
+    /// Helper: safely remove test directory, ignoring errors.
+    fn cleanup(path: &Path) {
+        let _ = fs::remove_dir_all(path);
+    }
+
+    /// Test: exported archive has valid ustar headers (magic, checksum),
+    /// block-aligned content, long names split into prefix, and the
+    /// end-of-archive marker.
+    #[test]
+    fn test_export_tar_writes_valid_ustar() {
+        let dir = test_dir("export");
+        cleanup(&dir);
+        let long_dir = "d".repeat(90);
+        assert!(
+            fs::create_dir_all(dir.join(&long_dir)).is_ok()
+                && fs::write(dir.join("1__msg.toml"), b"hello").is_ok()
+                && fs::write(dir.join(&long_dir).join("x".repeat(40)), b"nested").is_ok(),
+            "test_export_tar: failed to create tree"
+        );
+
+        let mut archive: Vec<u8> = Vec::new();
+        let report = export_tar(&dir, &mut archive, &ExportConfig::new());
+        assert_eq!(
+            report,
+            Ok(ExportReport {
+                files: 2,
+                dirs: 1,
+                bytes: 11,
+                skipped: 0
+            }),
+            "test_export_tar: report counts"
+        );
+
+        // 3 headers + 2 content blocks + 2 end blocks
+        assert_eq!(
+            archive.len(),
+            BLOCK_SIZE * 7,
+            "test_export_tar: archive length"
+        );
+        assert!(
+            archive[archive.len() - BLOCK_SIZE * 2..]
+                .iter()
+                .all(|b| *b == 0),
+            "test_export_tar: missing end-of-archive marker"
+        );
+
+        let mut offset: usize = 0;
+        let mut names: Vec<String> = Vec::new();
+        while offset + BLOCK_SIZE <= archive.len() - BLOCK_SIZE * 2 {
+            let header = &archive[offset..offset + BLOCK_SIZE];
+            assert_eq!(&header[257..263], b"ustar\0", "test_export_tar: magic");
+
+            let stored = parse_octal(&header[148..156]).ok();
+            let mut blanked = header.to_vec();
+            blanked[148..156].copy_from_slice(b"        ");
+            let computed: u64 = blanked.iter().map(|b| u64::from(*b)).sum();
+            assert_eq!(stored, Some(computed), "test_export_tar: checksum");
+
+            let field = |range: std::ops::Range<usize>| {
+                let bytes = &header[range];
+                let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+                String::from_utf8_lossy(&bytes[..end]).into_owned()
+            };
+            let prefix = field(345..500);
+            let name = field(0..100);
+            names.push(if prefix.is_empty() {
+                name
+            } else {
+                format!("{}/{}", prefix, name)
+            });
+
+            let size = parse_octal(&header[124..136]).unwrap_or(0) as usize;
+            offset += BLOCK_SIZE + size + padding_len(size as u64);
+        }
+
+        names.sort();
+        let mut expected = vec![
+            "1__msg.toml".to_string(),
+            format!("{}/", long_dir),
+            format!("{}/{}", long_dir, "x".repeat(40)),
+        ];
+        expected.sort();
+        assert_eq!(names, expected, "test_export_tar: archived names");
+
+        cleanup(&dir);
+    }
+
+    /// Helper: one archive entry (header + padded data) built by the
+    /// exporter's own header encoder, so tests can craft hostile names.
+    fn raw_entry(name: &[u8], typeflag: u8, data: &[u8]) -> Vec<u8> {
+        let mut bytes = match build_header(name, typeflag, data.len() as u64, 0o644, 0) {
+            Ok(h) => h.to_vec(),
+            Err(_) => Vec::new(),
+        };
+        bytes.extend_from_slice(data);
+        bytes.resize(bytes.len() + padding_len(data.len() as u64), 0);
+        bytes
+    }
+
+    /// Test: an exported tree imports back identically, and hostile names
+    /// (`..`, absolute, through a planted symlink) are rejected in strict
+    /// mode and skipped, without writing outside, in lenient mode.
+    #[test]
+    fn test_import_tar_round_trip_and_traversal() {
+        let base = test_dir("import");
+        cleanup(&base);
+        let source = base.join("channel");
+        assert!(
+            fs::create_dir_all(source.join("sub")).is_ok()
+                && fs::write(source.join("1__msg.toml"), b"one").is_ok()
+                && fs::write(source.join("sub").join("2__msg.toml"), b"two").is_ok(),
+            "test_import_tar: failed to create source"
+        );
+
+        let mut archive: Vec<u8> = Vec::new();
+        assert!(
+            export_tar(&source, &mut archive, &ExportConfig::new()).is_ok(),
+            "test_import_tar: export failed"
+        );
+        let restored = base.join("restored");
+        let report = import_tar(&mut archive.as_slice(), &restored, &ImportConfig::new());
+        assert_eq!(
+            report,
+            Ok(ImportReport {
+                files: 2,
+                dirs: 1,
+                bytes: 6,
+                skipped: 0
+            }),
+            "test_import_tar: round-trip counts"
+        );
+        assert_eq!(
+            fs::read(restored.join("sub").join("2__msg.toml")).ok(),
+            Some(b"two".to_vec()),
+            "test_import_tar: round-trip content"
+        );
+
+        let strict = ImportConfig::new().walk_config(WalkConfig::new().continue_on_error(false));
+        for hostile in [&b"../escape.toml"[..], b"/tmp/abs.toml", b"a/../../x.toml"] {
+            let mut crafted = raw_entry(hostile, b'0', b"evil");
+            crafted.extend_from_slice(&[0u8; BLOCK_SIZE * 2]);
+            assert_eq!(
+                import_tar(&mut crafted.as_slice(), &restored, &strict),
+                Err(WalkError::PathEscape),
+                "test_import_tar: {:?} must be rejected",
+                String::from_utf8_lossy(hostile)
+            );
+        }
+        assert!(
+            !base.join("escape.toml").exists(),
+            "test_import_tar: nothing may be written outside"
+        );
+
+        // Lenient: hostile entries skipped, the good one still extracted
+        let mut crafted = raw_entry(b"../escape.toml", b'0', b"evil");
+        crafted.extend(raw_entry(b"link", b'2', b""));
+        crafted.extend(raw_entry(b"3__msg.toml", b'0', b"three"));
+        crafted.extend_from_slice(&[0u8; BLOCK_SIZE * 2]);
+        let report = import_tar(&mut crafted.as_slice(), &restored, &ImportConfig::new());
+        assert_eq!(
+            report.map(|r| (r.files, r.skipped)),
+            Ok((1, 2)),
+            "test_import_tar: lenient counts"
+        );
+        assert!(
+            restored.join("3__msg.toml").exists() && !base.join("escape.toml").exists(),
+            "test_import_tar: lenient mode must skip only the hostile entry"
+        );
+
+        #[cfg(unix)]
+        {
+            let outside = base.join("outside");
+            assert!(
+                fs::create_dir_all(&outside).is_ok()
+                    && std::os::unix::fs::symlink(&outside, restored.join("planted")).is_ok(),
+                "test_import_tar: failed to plant symlink"
+            );
+            let mut crafted = raw_entry(b"planted/x.toml", b'0', b"evil");
+            crafted.extend_from_slice(&[0u8; BLOCK_SIZE * 2]);
+            assert_eq!(
+                import_tar(&mut crafted.as_slice(), &restored, &strict),
+                Err(WalkError::PathEscape),
+                "test_import_tar: write through planted symlink must be rejected"
+            );
+            assert!(
+                !outside.join("x.toml").exists(),
+                "test_import_tar: symlink target must be untouched"
+            );
+        }
+
+        cleanup(&base);
+    }
+}
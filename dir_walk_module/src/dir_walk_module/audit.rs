@@ -0,0 +1,209 @@
+//! # Determinism Audit (`dir_walk_module::audit`)
+//!
+//! Runs the same walk, and the same directory hash, twice over one tree
+//! and reports whether the two runs agree: same entries in the same
+//! order, same counters, same hash.
+//!
+//! ## Project Context
+//! Replicas compare channel hashes to decide whether they are in sync.
+//! We have seen those comparisons break on nondeterminism that depends
+//! on the scheduler or the filesystem (listing order, entries appearing
+//! mid-walk) rather than on the content. `audit_determinism()` (and the
+//! demo binary's `audit` command) checks a tree on a given machine before
+//! anyone starts trusting its hashes.
+//!
+//! ## Ordering
+//! Both walks use a deterministic listing order: the config's own
+//! `shuffle_seed` if it has one, otherwise `AUDIT_SHUFFLE_SEED`. OS
+//! listing order is not compared; it is allowed to differ between runs
+//! and is exactly what the hash must not depend on.
+
+use std::path::{Path, PathBuf};
+
+use super::hashing::{HashConfig, HashSummary, hash_directory_summary};
+use super::{DirWalker, WalkConfig, WalkError, WalkStats};
+
+/// Shuffle seed giving both audit walks a deterministic listing order
+/// when the audited config has none.
+pub const AUDIT_SHUFFLE_SEED: u64 = 0;
+
+/// What one walk yielded, in order: path, directory flag, length and
+/// depth of each entry, or the error in its place.
+type YieldRecord = Result<(PathBuf, bool, u64, usize), WalkError>;
+
+// ============================================================================
+// AUDIT
+// ============================================================================
+
+/// Result of `audit_determinism()`.
+///
+/// Holds counts and flags only (no paths), so it is safe to log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeterminismReport {
+    /// Items (entries and errors) the first walk yielded.
+    pub items: usize,
+
+    /// Index of the first item where the two walks differ (in path,
+    /// type, length, depth or error), or the shorter walk's length when
+    /// one is a prefix of the other. `None` when the yields are identical.
+    pub first_divergence: Option<usize>,
+
+    /// Whether the walks' `WalkStats` are identical (the walk id aside).
+    pub stats_match: bool,
+
+    /// Whether both hash runs gave the same result (hash, excluded file
+    /// count, or error).
+    pub hashes_match: bool,
+}
+
+impl DeterminismReport {
+    /// Whether the two runs agreed on yields, counters and hash.
+    pub fn is_deterministic(&self) -> bool {
+        self.first_divergence.is_none() && self.stats_match && self.hashes_match
+    }
+}
+
+/// Walk and hash `path` twice with `config` and compare the runs.
+///
+/// The walk is `config`'s walk config with a deterministic listing order
+/// (see the module docs); the hash uses the same walk config. Errors are
+/// part of what is compared, not a reason to stop: a directory that
+/// fails once and reads the second time is a divergence.
+///
+/// # Arguments
+/// * `path` - Root of the audited tree
+/// * `config` - Hash configuration whose walk and hash are audited
+///
+/// # Returns
+/// The comparison; `DeterminismReport::is_deterministic()` is the verdict.
+pub fn audit_determinism(path: &Path, config: &HashConfig) -> DeterminismReport {
+    let mut walk_config = config.walk().clone();
+    if walk_config.shuffle_seed.is_none() {
+        walk_config = walk_config.shuffle_seed(AUDIT_SHUFFLE_SEED);
+    }
+    let hash_config = config.clone().walk_config(walk_config.clone());
+
+    let (first_yields, first_stats) = record_walk(path, &walk_config);
+    let (second_yields, second_stats) = record_walk(path, &walk_config);
+    let first_hash: Result<HashSummary, WalkError> = hash_directory_summary(path, &hash_config);
+    let second_hash = hash_directory_summary(path, &hash_config);
+
+    DeterminismReport {
+        items: first_yields.len(),
+        first_divergence: first_divergence(&first_yields, &second_yields),
+        stats_match: first_stats == second_stats,
+        hashes_match: first_hash == second_hash,
+    }
+}
+
+/// Run one walk to completion, recording what it yielded and its
+/// counters (with the walk id cleared, since every walk gets a new one).
+fn record_walk(path: &Path, config: &WalkConfig) -> (Vec<YieldRecord>, WalkStats) {
+    let mut walker = DirWalker::new(path, config.clone());
+    let yields = walker
+        .by_ref()
+        .map(|result| {
+            result.map(|entry| {
+                let len = entry.metadata.len();
+                (
+                    entry.path().to_path_buf(),
+                    entry.is_dir(),
+                    len,
+                    entry.depth(),
+                )
+            })
+        })
+        .collect();
+    let mut stats = walker.stats().clone();
+    stats.walk_id = 0;
+    (yields, stats)
+}
+
+/// Index of the first differing item of two runs, if any.
+fn first_divergence(first: &[YieldRecord], second: &[YieldRecord]) -> Option<usize> {
+    match first.iter().zip(second).position(|(a, b)| a != b) {
+        Some(index) => Some(index),
+        None if first.len() != second.len() => Some(first.len().min(second.len())),
+        None => None,
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    /// Helper: unique test directory path (caller cleans up).
+    fn test_dir(name: &str) -> PathBuf {
+        crate::dir_walk_module::testing::unique_test_dir(&format!("audit_{}", name))
+    }
+
+    /// Helper: safely remove test directory, ignoring errors.
+    fn cleanup(path: &Path) {
+        let _ = fs::remove_dir_all(path);
+    }
+
+    /// Test: an unchanging tree audits as deterministic, with every item
+    /// counted.
+    #[test]
+    fn test_audit_determinism() {
+        let dir = test_dir("stable");
+        cleanup(&dir);
+        let created = (0..4).all(|c| {
+            let channel = dir.join(format!("channel{}", c));
+            fs::create_dir_all(channel.join("sub")).is_ok()
+                && fs::write(channel.join("0__a.toml"), b"a").is_ok()
+                && fs::write(channel.join("sub").join("b.toml"), b"bb").is_ok()
+        });
+        assert!(created, "test_audit_determinism: failed to build tree");
+
+        let config = HashConfig::new()
+            .walk_config(WalkConfig::new())
+            .include_contents(true);
+        let report = audit_determinism(&dir, &config);
+        assert!(
+            report.is_deterministic(),
+            "test_audit_determinism: stable tree reported {:?}",
+            report
+        );
+        // 4 channels × (channel, file, sub, sub file)
+        assert_eq!(report.items, 16, "test_audit_determinism: item count");
+
+        cleanup(&dir);
+    }
+
+    /// Test: divergence is reported at the first differing item, or at
+    /// the end of the shorter run.
+    #[test]
+    fn test_first_divergence() {
+        let record = |name: &str| -> YieldRecord { Ok((PathBuf::from(name), false, 1, 0)) };
+        let base = vec![record("a"), record("b"), record("c")];
+
+        assert_eq!(
+            first_divergence(&base, &base),
+            None,
+            "test_first_divergence: identical runs"
+        );
+        let reordered = vec![record("a"), record("c"), record("b")];
+        assert_eq!(
+            first_divergence(&base, &reordered),
+            Some(1),
+            "test_first_divergence: reordered entries"
+        );
+        let failed = vec![record("a"), Err(WalkError::ReadDirectory)];
+        assert_eq!(
+            first_divergence(&base, &failed),
+            Some(1),
+            "test_first_divergence: error in place of an entry"
+        );
+        assert_eq!(
+            first_divergence(&base, &base[..2]),
+            Some(2),
+            "test_first_divergence: truncated run"
+        );
+    }
+}
@@ -0,0 +1,460 @@
+//! # Walk Result Cache (`dir_walk_module::cache`)
+//!
+//! Keeps the collected entry list of recently viewed directories, keyed
+//! by the directory's metadata hash, and hands the cached list back while
+//! the hash is unchanged.
+//!
+//! ## Project Context
+//! The message view re-collects and re-sorts a channel directory on every
+//! focus change, although most channels have not changed since the last
+//! view. A metadata hash (`hashing::hash_directory()`, no file is opened)
+//! is far cheaper than building and sorting `DirEntry` lists, so the view
+//! asks `WalkCache` first and only collects when the hash moved.
+//!
+//! ```ignore
+//! let mut cache = WalkCache::new();
+//! let entries = cache.get_or_collect(&channel_dir, || {
+//!     let mut entries: Vec<DirEntry> = walk_dir(&channel_dir).ok_entries().collect();
+//!     entries.sort_by(|a, b| a.path().cmp(b.path()));
+//!     Ok(entries)
+//! })?;
+//! ```
+//!
+//! ## Watcher Integration
+//! A `ScanScheduler` given the cache (`ScanScheduler::cache()`) evicts the
+//! directories each completed channel walk reports as changed. With
+//! `trust_watcher(true)` lookups then skip the hash entirely, and a
+//! cached listing is never staler than one completed walk of its channel:
+//!
+//! ```ignore
+//! let cache = WalkCache::new().trust_watcher(true).into_shared();
+//! let scheduler = ScanScheduler::new().cache(Arc::clone(&cache));
+//! // UI thread, on focus change:
+//! let entries = lock_shared(&cache).get_or_collect(&channel_dir, collect)?.to_vec();
+//! ```
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use super::hashing::{HashConfig, hash_directory};
+use super::snapshot::ChangeSummary;
+use super::{DirEntry, WalkError};
+
+/// Default `WalkCache::max_dirs`.
+const DEFAULT_MAX_CACHED_DIRS: usize = 64;
+
+// ============================================================================
+// WALK CACHE
+// ============================================================================
+
+/// One cached directory listing.
+#[derive(Debug)]
+struct CachedListing {
+    /// Metadata hash of the directory when the listing was collected;
+    /// `None` when the cache trusts its watcher instead.
+    hash: Option<u64>,
+
+    /// Entries as returned by the collector (already sorted, filtered).
+    entries: Vec<DirEntry>,
+
+    /// `WalkCache::clock` value at the last lookup, for LRU eviction.
+    last_used: u64,
+}
+
+/// Bounded cache of collected directory listings, keyed by each
+/// directory's metadata hash.
+///
+/// Every lookup re-hashes the directory; the cached entries are returned
+/// only if the hash is unchanged, so a hit is never staler than the hash
+/// itself. The hash is taken *before* collecting, so a change that lands
+/// while collecting makes the next lookup miss rather than hiding behind
+/// an old hash.
+#[derive(Debug)]
+pub struct WalkCache {
+    /// Cached listings by directory path.
+    listings: HashMap<PathBuf, CachedListing>,
+
+    /// Most directories kept; the least recently used is evicted first.
+    max_dirs: usize,
+
+    /// How directories are fingerprinted.
+    hash_config: HashConfig,
+
+    /// Whether lookups skip the hash and rely on evictions
+    /// (`trust_watcher`).
+    trust_watcher: bool,
+
+    /// Lookup counter used as the LRU timestamp.
+    clock: u64,
+
+    /// Lookups answered from the cache.
+    hits: u64,
+
+    /// Lookups that had to collect.
+    misses: u64,
+}
+
+impl Default for WalkCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WalkCache {
+    /// Create an empty cache: 64 directories, hashed with
+    /// `HashConfig::new()` at exact mtime granularity.
+    ///
+    /// Exact mtimes matter here: with whole-second truncation a message
+    /// rewritten at the same size within one second would keep its hash
+    /// and be served stale.
+    pub fn new() -> Self {
+        WalkCache {
+            listings: HashMap::new(),
+            max_dirs: DEFAULT_MAX_CACHED_DIRS,
+            hash_config: HashConfig::new().mtime_granularity(Duration::ZERO),
+            trust_watcher: false,
+            clock: 0,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /// Set the most directories kept (clamped to at least 1).
+    pub fn max_dirs(mut self, max_dirs: usize) -> Self {
+        self.max_dirs = max_dirs.max(1);
+        self
+    }
+
+    /// Set how directories are fingerprinted.
+    ///
+    /// The hash walk should cover at least the entries the collectors
+    /// collect (same depth and filters); a change the hash cannot see is
+    /// a change the cache cannot notice.
+    pub fn hash_config(mut self, config: HashConfig) -> Self {
+        self.hash_config = config;
+        self
+    }
+
+    /// Serve cached listings without re-hashing, relying on evictions
+    /// (`apply_changes()`, `invalidate()`) to drop changed directories.
+    ///
+    /// Only sound when a watcher covers every cached directory, e.g. a
+    /// `ScanScheduler` given this cache (`ScanScheduler::cache()`):
+    /// listings are then at most one completed channel walk old. A change
+    /// the watcher's walk does not see (filtered entries, beyond its
+    /// depth) is not noticed.
+    pub fn trust_watcher(mut self, trust: bool) -> Self {
+        self.trust_watcher = trust;
+        self
+    }
+
+    /// Wrap the cache for sharing with a `ScanScheduler` on another
+    /// thread.
+    pub fn into_shared(self) -> SharedWalkCache {
+        Arc::new(Mutex::new(self))
+    }
+
+    /// Return the entries of `dir`, collecting them only if the directory
+    /// changed since they were cached.
+    ///
+    /// # Arguments
+    /// * `dir` - Directory whose listing is wanted (the cache key)
+    /// * `collect` - Builds the listing on a miss (walk, filter, sort)
+    ///
+    /// # Returns
+    /// * `Ok(&[DirEntry])` - Cached or freshly collected entries
+    /// * `Err(WalkError)` - Hashing or collecting failed; any cached
+    ///   listing of `dir` is dropped, as it can no longer be validated
+    pub fn get_or_collect<F>(&mut self, dir: &Path, collect: F) -> Result<&[DirEntry], WalkError>
+    where
+        F: FnOnce() -> Result<Vec<DirEntry>, WalkError>,
+    {
+        self.clock = self.clock.saturating_add(1);
+
+        let hash = if self.trust_watcher {
+            None
+        } else {
+            match hash_directory(dir, &self.hash_config) {
+                Ok(hash) => Some(hash),
+                Err(e) => {
+                    self.listings.remove(dir);
+                    return Err(e);
+                }
+            }
+        };
+
+        let cached = self.listings.get(dir).is_some_and(|l| l.hash == hash);
+        if cached {
+            self.hits = self.hits.saturating_add(1);
+        } else {
+            self.misses = self.misses.saturating_add(1);
+            let entries = match collect() {
+                Ok(entries) => entries,
+                Err(e) => {
+                    self.listings.remove(dir);
+                    return Err(e);
+                }
+            };
+            if !self.listings.contains_key(dir) && self.listings.len() >= self.max_dirs {
+                self.evict_least_recently_used();
+            }
+            self.listings.insert(
+                dir.to_path_buf(),
+                CachedListing {
+                    hash,
+                    entries,
+                    last_used: 0,
+                },
+            );
+        }
+
+        match self.listings.get_mut(dir) {
+            Some(listing) => {
+                listing.last_used = self.clock;
+                Ok(&listing.entries)
+            }
+            // Inserted or found above
+            None => Err(WalkError::IoError),
+        }
+    }
+
+    /// Drop the cached listing of `dir`.
+    ///
+    /// # Returns
+    /// `true` if a listing was cached.
+    pub fn invalidate(&mut self, dir: &Path) -> bool {
+        self.listings.remove(dir).is_some()
+    }
+
+    /// Drop the cached listings of `path`, of every directory below it,
+    /// and of every directory above it (whose listings may include it).
+    ///
+    /// # Returns
+    /// Number of listings dropped.
+    pub fn invalidate_tree(&mut self, path: &Path) -> usize {
+        let before = self.listings.len();
+        self.listings
+            .retain(|dir, _| !path.starts_with(dir) && !dir.starts_with(path));
+        before - self.listings.len()
+    }
+
+    /// Drop every listing a channel walk's changes may have made stale.
+    ///
+    /// # Arguments
+    /// * `root` - Root of the walk that produced `changes`
+    /// * `changes` - Paths relative to `root` (see `ChannelMonitor::poll`)
+    ///
+    /// # Returns
+    /// Number of listings dropped.
+    pub fn apply_changes(&mut self, root: &Path, changes: &ChangeSummary) -> usize {
+        let renamed = changes
+            .renamed
+            .iter()
+            .flat_map(|rename| [&rename.from, &rename.to]);
+        let mut dropped: usize = 0;
+        for relative in changes
+            .added
+            .iter()
+            .chain(&changes.removed)
+            .chain(&changes.modified)
+            .chain(renamed)
+        {
+            if self.listings.is_empty() {
+                break;
+            }
+            dropped = dropped.saturating_add(self.invalidate_tree(&root.join(relative)));
+        }
+        dropped
+    }
+
+    /// Drop every cached listing.
+    pub fn clear(&mut self) {
+        self.listings.clear();
+    }
+
+    /// Number of cached directories.
+    pub fn len(&self) -> usize {
+        self.listings.len()
+    }
+
+    /// `true` when nothing is cached.
+    pub fn is_empty(&self) -> bool {
+        self.listings.is_empty()
+    }
+
+    /// Lookups answered from the cache so far.
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    /// Lookups that collected so far (first views and changed
+    /// directories).
+    pub fn misses(&self) -> u64 {
+        self.misses
+    }
+
+    /// Remove the least recently used listing (one pass over at most
+    /// `max_dirs` listings).
+    fn evict_least_recently_used(&mut self) {
+        let oldest = self
+            .listings
+            .iter()
+            .min_by_key(|(_, listing)| listing.last_used)
+            .map(|(dir, _)| dir.clone());
+        if let Some(dir) = oldest {
+            self.listings.remove(&dir);
+        }
+    }
+}
+
+/// A `WalkCache` shared between a `ScanScheduler` and its readers.
+pub type SharedWalkCache = Arc<Mutex<WalkCache>>;
+
+/// Lock a shared cache.
+///
+/// A panic while the cache was locked may have left a listing half
+/// updated, so a poisoned cache is cleared (and unpoisoned) rather than
+/// trusted.
+pub fn lock_shared(cache: &SharedWalkCache) -> MutexGuard<'_, WalkCache> {
+    match cache.lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => {
+            #[cfg(debug_assertions)]
+            eprintln!("DW_DBG: walk cache lock poisoned, clearing the cache");
+            cache.clear_poison();
+            let mut guard = poisoned.into_inner();
+            guard.clear();
+            guard
+        }
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dir_walk_module::{WalkConfig, walk_dir};
+    use std::fs;
+
+    /// Helper: unique test directory path (caller cleans up).
+    fn test_dir(name: &str) -> PathBuf {
+        crate::dir_walk_module::testing::unique_test_dir(&format!("cache_{}", name))
+    }
+
+    /// Helper: safely remove test directory, ignoring errors.
+    fn cleanup(path: &Path) {
+        let _ = fs::remove_dir_all(path);
+    }
+
+    /// Helper: sorted file names of a directory, as the message view
+    /// collects them.
+    fn collect_sorted(dir: &Path) -> Result<Vec<DirEntry>, WalkError> {
+        let mut entries: Vec<DirEntry> = walk_dir(dir).ok_entries().collect();
+        entries.sort_by(|a, b| a.path().cmp(b.path()));
+        Ok(entries)
+    }
+
+    /// Test: unchanged directories are served from the cache, changed
+    /// ones are re-collected, failures drop the entry, and the least
+    /// recently used directory is evicted at the bound.
+    #[test]
+    fn test_walk_cache_hits_until_changed() {
+        let base = test_dir("hits");
+        cleanup(&base);
+        let general = base.join("general");
+        let random = base.join("random");
+        assert!(
+            fs::create_dir_all(&general).is_ok()
+                && fs::create_dir_all(&random).is_ok()
+                && fs::write(general.join("1__msg.toml"), b"a").is_ok()
+                && fs::write(random.join("1__msg.toml"), b"b").is_ok(),
+            "test_walk_cache: failed to create channels"
+        );
+
+        let mut cache = WalkCache::new().max_dirs(1);
+        let mut collections = 0;
+        let mut view = |cache: &mut WalkCache, dir: &Path| -> Result<usize, WalkError> {
+            cache
+                .get_or_collect(dir, || {
+                    collections += 1;
+                    collect_sorted(dir)
+                })
+                .map(<[DirEntry]>::len)
+        };
+
+        assert_eq!(
+            view(&mut cache, &general),
+            Ok(1),
+            "test_walk_cache: first view"
+        );
+        assert_eq!(
+            view(&mut cache, &general),
+            Ok(1),
+            "test_walk_cache: second view"
+        );
+        assert_eq!(
+            (cache.hits(), cache.misses()),
+            (1, 1),
+            "test_walk_cache: unchanged directory must hit"
+        );
+
+        assert!(
+            fs::write(general.join("2__msg.toml"), b"c").is_ok(),
+            "test_walk_cache: failed to add message"
+        );
+        assert_eq!(
+            view(&mut cache, &general),
+            Ok(2),
+            "test_walk_cache: changed directory must be re-collected"
+        );
+
+        // Bound of one: viewing another channel evicts the first
+        assert_eq!(
+            view(&mut cache, &random),
+            Ok(1),
+            "test_walk_cache: other channel"
+        );
+        assert_eq!(cache.len(), 1, "test_walk_cache: bound exceeded");
+        assert!(
+            !cache.invalidate(&general) && cache.invalidate(&random) && cache.is_empty(),
+            "test_walk_cache: least recently used channel must be evicted"
+        );
+
+        // A failed collection caches nothing
+        let failed = cache.get_or_collect(&general, || Err(WalkError::ReadDirectory));
+        assert_eq!(
+            failed.map(<[DirEntry]>::len),
+            Err(WalkError::ReadDirectory),
+            "test_walk_cache: collector error is returned"
+        );
+        assert!(
+            cache.is_empty(),
+            "test_walk_cache: failure must not be cached"
+        );
+
+        // A vanished directory fails the hash (strict walk) and drops its entry
+        let mut strict = WalkCache::new()
+            .hash_config(HashConfig::new().walk_config(WalkConfig::new().continue_on_error(false)));
+        assert!(
+            strict
+                .get_or_collect(&random, || collect_sorted(&random))
+                .is_ok(),
+            "test_walk_cache: strict first view"
+        );
+        cleanup(&random);
+        assert!(
+            strict
+                .get_or_collect(&random, || collect_sorted(&random))
+                .is_err()
+                && strict.is_empty(),
+            "test_walk_cache: vanished directory must drop its listing"
+        );
+
+        cleanup(&base);
+    }
+}
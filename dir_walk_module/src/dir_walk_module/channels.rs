@@ -0,0 +1,990 @@
+//! # Channel Directories (`dir_walk_module::channels`)
+//!
+//! Naming rules for channel directories, idempotent creation of a new
+//! channel, and `ChannelDir`, the typed view of an existing channel.
+//!
+//! ## Project Context
+//! A channel is a directory of message files plus its metadata file
+//! (`0.toml` by default). The create path and the scan path must agree on
+//! what a valid channel looks like: a channel created under a name the
+//! scanner filters out (a temp-looking name, for instance) would exist on
+//! disk but never be listed. `validate_channel_name()` guards the create
+//! side only. The scan side (`WalkConfig::channel_scan()`,
+//! `workspace::scan_workspace()`) does not call it. Instead, its rules
+//! refuse every name those scans hide (temp names, the trash folder,
+//! attachment directories), so any channel that can be created is listed.
+//! `ensure_channel_dir()` takes the metadata file name from the same
+//! `WalkConfig` the scanner uses.
+//!
+//! Readers ask `ChannelDir` for a channel's messages, metadata file and
+//! attachments instead of reassembling them from raw walks at each call
+//! site. Code that already holds a channel walk's entries splits them
+//! with `classify_channel_entries()`, which applies the same rules.
+
+use std::ffi::OsStr;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use super::filters::{EntryFilter, any_of, by_extension};
+use super::messages::parse_numeric_prefix;
+use super::{
+    DirEntry, DirWalker, EntryTag, OkEntries, TRASH_DIR_NAME, WalkConfig, WalkError, WalkStats,
+    is_temp_file_name,
+};
+
+/// Longest channel name accepted, in bytes (common filesystem limit).
+const MAX_CHANNEL_NAME_LEN: usize = 255;
+
+/// Default number of retries after a failed directory creation.
+const DEFAULT_CREATE_RETRIES: u32 = 3;
+
+/// Default pause between creation attempts.
+const DEFAULT_RETRY_DELAY: Duration = Duration::from_millis(50);
+
+// ============================================================================
+// NAME RULES
+// ============================================================================
+
+/// Check a channel directory name against the channel naming rules.
+///
+/// A valid name is:
+/// - Valid UTF-8, 1 to 255 bytes
+/// - A single plain component: not `.` or `..`, no `/` or `\`
+/// - Free of control characters (including NUL and newlines)
+/// - Not a temp / partial-write name (`is_temp_file_name()`), which
+///   `WalkConfig::skip_temp_files` would hide from every scan
+/// - Not the trash folder name (`TRASH_DIR_NAME`), which
+///   `WalkConfig::skip_trash` would hide from every scan
+/// - Not a message attachment directory name (`N__attachments`, see
+///   `attachments_dir_number()`), which channel walks treat as a
+///   message's attachments
+/// - Without leading or trailing whitespace
+///
+/// # Arguments
+/// * `name` - Directory name (not a path)
+///
+/// # Returns
+/// * `Ok(())` - Name is valid
+/// * `Err(WalkError::InvalidName)` - Name breaks a rule
+pub fn validate_channel_name(name: &OsStr) -> Result<(), WalkError> {
+    let valid = match name.to_str() {
+        Some(text) => {
+            !text.is_empty()
+                && text.len() <= MAX_CHANNEL_NAME_LEN
+                && text != "."
+                && text != ".."
+                && !text.contains(['/', '\\'])
+                && !text.chars().any(char::is_control)
+                && text.trim() == text
+                && !is_temp_file_name(name)
+                && text != TRASH_DIR_NAME
+                && attachments_dir_number(text).is_none()
+        }
+        None => false,
+    };
+
+    if valid {
+        Ok(())
+    } else {
+        #[cfg(debug_assertions)]
+        eprintln!("DWIN: channel name violates naming rules");
+        Err(WalkError::InvalidName)
+    }
+}
+
+// ============================================================================
+// CHANNEL CREATION
+// ============================================================================
+
+/// Configuration for `ensure_channel_dir()`.
+#[derive(Debug, Clone)]
+pub struct CreateConfig {
+    /// Walk configuration the channel will be scanned with; its first
+    /// `metadata_file_names` entry names the metadata file.
+    walk_config: WalkConfig,
+
+    /// Retries after a failed directory creation (bounded).
+    max_retries: u32,
+
+    /// Pause between attempts.
+    retry_delay: Duration,
+}
+
+impl Default for CreateConfig {
+    fn default() -> Self {
+        CreateConfig {
+            walk_config: WalkConfig::message_loading(),
+            max_retries: DEFAULT_CREATE_RETRIES,
+            retry_delay: DEFAULT_RETRY_DELAY,
+        }
+    }
+}
+
+impl CreateConfig {
+    /// Create a config with defaults: `WalkConfig::message_loading()`
+    /// (metadata file `0.toml`), 3 retries, 50 ms apart.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the walk configuration the channel will be scanned with.
+    ///
+    /// # Arguments
+    /// * `config` - Its first metadata file name is the file created; with
+    ///   no metadata names, no metadata file is created
+    pub fn walk_config(mut self, config: WalkConfig) -> Self {
+        self.walk_config = config;
+        self
+    }
+
+    /// Set the number of retries after a failed directory creation.
+    pub fn max_retries(mut self, retries: u32) -> Self {
+        self.max_retries = retries;
+        self
+    }
+
+    /// Set the pause between creation attempts.
+    ///
+    /// The pause is taken on the walk configuration's clock
+    /// (`WalkConfig::clock`), so a `MockClock` makes it instant.
+    pub fn retry_delay(mut self, delay: Duration) -> Self {
+        self.retry_delay = delay;
+        self
+    }
+}
+
+/// What `ensure_channel_dir()` had to create.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EnsureReport {
+    /// The channel directory did not exist and was created.
+    pub created_dir: bool,
+
+    /// The metadata file did not exist and was created (empty).
+    pub created_metadata: bool,
+}
+
+/// Make sure a channel directory and its metadata file exist.
+///
+/// Idempotent: an existing channel is left as it is (an existing metadata
+/// file is never truncated), and a concurrent creator is tolerated.
+/// Missing parent directories are created. Directory creation is retried
+/// up to `max_retries` times; the metadata file is created with
+/// `create_new`, so it is never overwritten.
+///
+/// # Arguments
+/// * `path` - Channel directory; its last component must pass
+///   `validate_channel_name()`
+/// * `config` - Creation configuration
+///
+/// # Returns
+/// * `Ok(EnsureReport)` - Channel exists; what was created
+/// * `Err(WalkError::InvalidName)` - Channel name breaks the naming rules
+/// * `Err(WalkError::IoError)` - Path (or metadata path) exists but is not
+///   a directory (regular file), or creation kept failing
+pub fn ensure_channel_dir(path: &Path, config: CreateConfig) -> Result<EnsureReport, WalkError> {
+    match path.file_name() {
+        Some(name) => validate_channel_name(name)?,
+        None => {
+            #[cfg(debug_assertions)]
+            eprintln!("DWIN: channel path has no directory name");
+            return Err(WalkError::InvalidName);
+        }
+    }
+
+    let mut report = EnsureReport::default();
+
+    // Bounded: max_retries + 1 attempts
+    let mut attempt: u32 = 0;
+    loop {
+        match fs::symlink_metadata(path) {
+            Ok(metadata) if metadata.is_dir() => break,
+            Ok(_) => {
+                #[cfg(debug_assertions)]
+                eprintln!("DWIO: channel path exists but is not a directory");
+                return Err(WalkError::IoError);
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(_e) => {
+                #[cfg(debug_assertions)]
+                eprintln!("DWIO: channel path could not be checked: {}", _e);
+                return Err(WalkError::IoError);
+            }
+        }
+
+        match fs::create_dir_all(path) {
+            Ok(()) => {
+                report.created_dir = true;
+                break;
+            }
+            // Created concurrently: the next check sees it
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {}
+            Err(_e) if attempt < config.max_retries => {
+                #[cfg(debug_assertions)]
+                eprintln!("DWIO: channel creation failed, retrying: {}", _e);
+                config.walk_config.clock.sleep(config.retry_delay);
+            }
+            Err(_e) => {
+                #[cfg(debug_assertions)]
+                eprintln!("DWIO: channel creation failed: {}", _e);
+                return Err(WalkError::IoError);
+            }
+        }
+
+        if attempt >= config.max_retries {
+            #[cfg(debug_assertions)]
+            eprintln!("DWIO: channel creation retries exhausted");
+            return Err(WalkError::IoError);
+        }
+        attempt = attempt.saturating_add(1);
+    }
+
+    let metadata_name = match config.walk_config.metadata_file_names.first() {
+        Some(name) => name,
+        None => return Ok(report),
+    };
+    let metadata_path = path.join(metadata_name);
+
+    match fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&metadata_path)
+    {
+        Ok(_) => report.created_metadata = true,
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+            if !fs::symlink_metadata(&metadata_path).is_ok_and(|m| m.is_file()) {
+                #[cfg(debug_assertions)]
+                eprintln!("DWIO: channel metadata path is not a regular file");
+                return Err(WalkError::IoError);
+            }
+        }
+        Err(_e) => {
+            #[cfg(debug_assertions)]
+            eprintln!("DWIO: channel metadata file could not be created: {}", _e);
+            return Err(WalkError::IoError);
+        }
+    }
+
+    Ok(report)
+}
+
+// ============================================================================
+// CHANNEL MODEL
+// ============================================================================
+
+/// An existing channel directory, read through walks configured for it.
+///
+/// Each file directly in the channel directory is one of:
+/// - Metadata: named in `WalkConfig::metadata_file_names` (`0.toml`)
+/// - Message: `.toml` or `.gpgtoml` with a valid numeric prefix
+///   (`12__hello.toml`)
+/// - Attachment: any other file
+///
+/// `.toml` / `.gpgtoml` files with a malformed prefix belong to none of
+/// these (`messages::list_messages()` reports them).
+///
+/// A subdirectory named `N__attachments` holds the attachments of
+/// message `N` (see `message_attachments()`); any other subdirectory is
+/// a channel of its own. Neither is read by the listings above.
+#[derive(Debug, Clone)]
+pub struct ChannelDir {
+    /// The channel directory.
+    path: PathBuf,
+
+    /// Walk configuration, with depth 0 and no directories enforced.
+    config: WalkConfig,
+}
+
+impl ChannelDir {
+    /// Open a channel with `WalkConfig::message_loading()`.
+    ///
+    /// # Arguments
+    /// * `path` - Channel directory
+    ///
+    /// # Returns
+    /// * `Ok(ChannelDir)` - `path` is a directory (not a symlink to one)
+    /// * `Err(WalkError::ReadDirectory)` - `path` is missing or is not a
+    ///   directory
+    pub fn open(path: &Path) -> Result<Self, WalkError> {
+        Self::open_with(path, WalkConfig::message_loading())
+    }
+
+    /// Open a channel with a custom walk configuration.
+    ///
+    /// The config's filters, bounds and `metadata_file_names` apply;
+    /// depth is forced to 0, directories are never yielded, and any
+    /// `tag_entries` classifier is replaced by the channel's own.
+    ///
+    /// # Arguments
+    /// * `path` - Channel directory
+    /// * `config` - Walk configuration for every listing of this channel
+    pub fn open_with(path: &Path, config: WalkConfig) -> Result<Self, WalkError> {
+        match fs::symlink_metadata(path) {
+            Ok(metadata) if metadata.is_dir() => {}
+            Ok(_) => {
+                #[cfg(debug_assertions)]
+                eprintln!("DWRD: channel path is not a directory");
+                return Err(WalkError::ReadDirectory);
+            }
+            Err(_e) => {
+                #[cfg(debug_assertions)]
+                eprintln!("DWRD: channel directory unreadable: {}", _e);
+                return Err(WalkError::ReadDirectory);
+            }
+        }
+
+        let metadata_names = config.metadata_file_names.clone();
+        let config = config
+            .max_depth(0)
+            .yield_directories(false)
+            .tag_entries(move |entry| channel_file_tag(entry, &metadata_names));
+
+        Ok(ChannelDir {
+            path: path.to_path_buf(),
+            config,
+        })
+    }
+
+    /// The channel directory.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Message files, in directory order.
+    ///
+    /// Each call walks the directory anew. For load order, collect and
+    /// sort with `messages::sort_by_numeric_prefix()`.
+    pub fn messages(&self) -> ChannelFiles {
+        self.files(EntryTag::Message)
+    }
+
+    /// The channel's metadata file, if it has one.
+    ///
+    /// With several `metadata_file_names` present, the first one found
+    /// in directory order is returned.
+    pub fn metadata_file(&self) -> Option<DirEntry> {
+        self.files(EntryTag::Metadata).next()
+    }
+
+    /// Attachment files (neither messages nor metadata), in directory
+    /// order.
+    pub fn attachments(&self) -> ChannelFiles {
+        self.files(EntryTag::Attachment)
+    }
+
+    /// Files in the `N__attachments` directory of message `N`.
+    ///
+    /// Only the directory's own files are listed (nested directories are
+    /// not read), under the channel's walk bounds: `max_entries_per_dir`
+    /// and `max_yield_per_dir` cap a message with runaway attachments.
+    ///
+    /// # Arguments
+    /// * `message` - A message entry, e.g. from `messages()`
+    ///
+    /// # Returns
+    /// `None` if the message name has no valid numeric prefix, or the
+    /// message has no attachment directory.
+    pub fn message_attachments(&self, message: &DirEntry) -> Option<ChannelFiles> {
+        let number = parse_numeric_prefix(message.file_name()?).ok()?;
+        let dir = self
+            .path
+            .join(format!("{}{}", number, ATTACHMENTS_DIR_SUFFIX));
+        if !fs::symlink_metadata(&dir).is_ok_and(|metadata| metadata.is_dir()) {
+            return None;
+        }
+
+        Some(ChannelFiles {
+            entries: DirWalker::new(&dir, self.config.clone()).ok_entries(),
+            tag: None,
+        })
+    }
+
+    /// Walk the channel, keeping the files tagged `tag`.
+    fn files(&self, tag: EntryTag) -> ChannelFiles {
+        ChannelFiles {
+            entries: DirWalker::new(&self.path, self.config.clone()).ok_entries(),
+            tag: Some(tag),
+        }
+    }
+}
+
+/// Iterator returned by `ChannelDir::messages()`,
+/// `ChannelDir::attachments()` and `ChannelDir::message_attachments()`.
+///
+/// Yields plain `DirEntry` values: skipped errors are counted in
+/// `stats()`, as with `DirWalker::ok_entries()`.
+#[derive(Debug)]
+pub struct ChannelFiles {
+    entries: OkEntries,
+
+    /// Files kept; `None` keeps every file.
+    tag: Option<EntryTag>,
+}
+
+impl ChannelFiles {
+    /// Counters of the underlying walk so far.
+    pub fn stats(&self) -> &WalkStats {
+        self.entries.stats()
+    }
+}
+
+impl Iterator for ChannelFiles {
+    type Item = DirEntry;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.tag {
+            Some(tag) => self.entries.by_ref().find(|entry| entry.tag() == tag),
+            None => self.entries.next(),
+        }
+    }
+}
+
+// ============================================================================
+// ATTACHMENT DIRECTORIES
+// ============================================================================
+
+/// Suffix of a message's attachment directory: message `12__hello.toml`
+/// keeps its attachments in `12__attachments/`.
+pub const ATTACHMENTS_DIR_SUFFIX: &str = "__attachments";
+
+/// Message number of an attachment directory name.
+///
+/// # Returns
+/// `Some(N)` for exactly `N__attachments` with a valid numeric prefix
+/// (`messages::parse_numeric_prefix()` rules), `None` otherwise.
+pub fn attachments_dir_number(name: &str) -> Option<u64> {
+    let prefix = name.strip_suffix(ATTACHMENTS_DIR_SUFFIX)?;
+    // `12__x__attachments` names no single message
+    if prefix.contains("__") {
+        return None;
+    }
+    parse_numeric_prefix(name).ok()
+}
+
+/// Whether `entry` is a message attachment directory, or lies inside one.
+///
+/// Every component of the entry's path is checked, so this also holds
+/// for attachments nested deeper in the directory. Walk with
+/// `yield_relative_paths(true)` when the walk root itself may lie inside
+/// an attachment directory.
+///
+/// # Project Context
+/// Channel listings and counts treat every directory as a channel;
+/// filtering with `filter_entry(not(in_attachments_dir))` keeps
+/// attachment directories (and their files) out of them.
+pub fn in_attachments_dir(entry: &DirEntry) -> bool {
+    path_in_attachments_dir(entry.path())
+}
+
+/// Whether any component of `path` is an attachment directory name.
+pub(super) fn path_in_attachments_dir(path: &Path) -> bool {
+    path.components().any(|component| {
+        component
+            .as_os_str()
+            .to_str()
+            .and_then(attachments_dir_number)
+            .is_some()
+    })
+}
+
+/// Classify one file of a channel directory (see `ChannelDir`).
+fn channel_file_tag(entry: &DirEntry, metadata_names: &[String]) -> EntryTag {
+    match channel_entry_class(entry, metadata_names) {
+        ChannelEntryClass::Metadata => EntryTag::Metadata,
+        ChannelEntryClass::PlaintextMessage | ChannelEntryClass::EncryptedMessage => {
+            EntryTag::Message
+        }
+        ChannelEntryClass::Attachment => EntryTag::Attachment,
+        ChannelEntryClass::Unknown => EntryTag::Junk,
+    }
+}
+
+// ============================================================================
+// ENTRY CLASSIFICATION
+// ============================================================================
+
+/// What an entry of a channel directory is, by the project's naming
+/// conventions (see `channel_entry_class()` for the rules).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChannelEntryClass {
+    /// The channel's metadata file (`WalkConfig::metadata_file_names`).
+    Metadata,
+
+    /// `N__name.toml` with a valid numeric prefix.
+    PlaintextMessage,
+
+    /// `N__name.gpgtoml` with a valid numeric prefix (by name only; see
+    /// `messages::classify_encrypted()` for the contents).
+    EncryptedMessage,
+
+    /// An attachment directory (`N__attachments`), a file inside one, or
+    /// any other file directly in the channel.
+    Attachment,
+
+    /// Anything else: message-like names with a malformed prefix, temp /
+    /// partial-write files, and directories that are not attachment
+    /// directories (channels of their own).
+    Unknown,
+}
+
+/// Entries of one channel, split by `ChannelEntryClass`.
+///
+/// Each list keeps the order the entries were given in.
+#[derive(Debug, Clone, Default)]
+pub struct ChannelClassification {
+    /// Metadata files.
+    pub metadata: Vec<DirEntry>,
+
+    /// Plaintext (`.toml`) messages.
+    pub plaintext_messages: Vec<DirEntry>,
+
+    /// Encrypted (`.gpgtoml`) messages.
+    pub encrypted_messages: Vec<DirEntry>,
+
+    /// Attachments and attachment directories.
+    pub attachments: Vec<DirEntry>,
+
+    /// Entries no convention accounts for.
+    pub unknown: Vec<DirEntry>,
+}
+
+impl ChannelClassification {
+    /// Total number of classified entries.
+    pub fn len(&self) -> usize {
+        self.metadata.len()
+            + self.plaintext_messages.len()
+            + self.encrypted_messages.len()
+            + self.attachments.len()
+            + self.unknown.len()
+    }
+
+    /// Whether no entries were classified.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Split the entries of a channel walk by `ChannelEntryClass`, with the
+/// default metadata file name (`0.toml`).
+///
+/// Meant for one channel's entries: its files, plus its attachment
+/// directories and their files when the walk descended into them. Use
+/// `classify_channel_entries_with()` for a custom `metadata_file_names`.
+///
+/// # Arguments
+/// * `entries` - Entries of the channel walk, e.g. `ok_entries()`
+///
+/// # Project Context
+/// The loader, the sync check, the admin view and the hash filters each
+/// used to tell messages from metadata and attachments with their own
+/// match block; they now share this one.
+pub fn classify_channel_entries<I>(entries: I) -> ChannelClassification
+where
+    I: IntoIterator<Item = DirEntry>,
+{
+    classify_channel_entries_with(entries, &WalkConfig::new())
+}
+
+/// Split the entries of a channel walk by `ChannelEntryClass`, taking the
+/// metadata file names from `config` (as `ChannelDir` does).
+///
+/// # Arguments
+/// * `entries` - Entries of the channel walk
+/// * `config` - Config whose `metadata_file_names` apply
+pub fn classify_channel_entries_with<I>(entries: I, config: &WalkConfig) -> ChannelClassification
+where
+    I: IntoIterator<Item = DirEntry>,
+{
+    let mut classification = ChannelClassification::default();
+    for entry in entries {
+        let list = match channel_entry_class(&entry, &config.metadata_file_names) {
+            ChannelEntryClass::Metadata => &mut classification.metadata,
+            ChannelEntryClass::PlaintextMessage => &mut classification.plaintext_messages,
+            ChannelEntryClass::EncryptedMessage => &mut classification.encrypted_messages,
+            ChannelEntryClass::Attachment => &mut classification.attachments,
+            ChannelEntryClass::Unknown => &mut classification.unknown,
+        };
+        list.push(entry);
+    }
+    classification
+}
+
+/// Classify one channel entry.
+///
+/// Rules, first match wins:
+/// 1. Inside an attachment directory, or one itself: `Attachment`
+/// 2. Any other directory: `Unknown`
+/// 3. Named in `metadata_names`: `Metadata`
+/// 4. Temp / partial-write name (`is_temp_file_name()`): `Unknown`
+/// 5. `.toml` / `.gpgtoml`: a message if the numeric prefix is valid
+///    (`messages::parse_numeric_prefix()`), else `Unknown`
+/// 6. Any other file (including non-UTF-8 names): `Attachment`
+///
+/// # Arguments
+/// * `entry` - Entry from a channel walk
+/// * `metadata_names` - Metadata file names (`WalkConfig::metadata_file_names`)
+pub fn channel_entry_class(entry: &DirEntry, metadata_names: &[String]) -> ChannelEntryClass {
+    if in_attachments_dir(entry) {
+        return ChannelEntryClass::Attachment;
+    }
+    if entry.is_dir() {
+        return ChannelEntryClass::Unknown;
+    }
+    let Some(name) = entry.file_name() else {
+        // Non-UTF-8 names are never messages or metadata
+        return ChannelEntryClass::Attachment;
+    };
+
+    if metadata_names.iter().any(|meta| meta == name) {
+        ChannelEntryClass::Metadata
+    } else if is_temp_file_name(OsStr::new(name)) {
+        ChannelEntryClass::Unknown
+    } else if any_of(by_extension("toml"), by_extension("gpgtoml")).matches(entry) {
+        match parse_numeric_prefix(name) {
+            Ok(_) if by_extension("gpgtoml").matches(entry) => ChannelEntryClass::EncryptedMessage,
+            Ok(_) => ChannelEntryClass::PlaintextMessage,
+            Err(_) => ChannelEntryClass::Unknown,
+        }
+    } else {
+        ChannelEntryClass::Attachment
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// Helper: unique test directory path (caller cleans up).
+    fn test_dir(name: &str) -> PathBuf {
+        crate::dir_walk_module::testing::unique_test_dir(&format!("channels_{}", name))
+    }
+
+    /// Helper: safely remove test directory, ignoring errors.
+    fn cleanup(path: &Path) {
+        let _ = fs::remove_dir_all(path);
+    }
+
+    /// Test: valid and invalid channel names.
+    #[test]
+    fn test_validate_channel_name() {
+        for name in [
+            "general",
+            "team-chat_2",
+            "caf\u{e9}",
+            ".hidden",
+            "notes__attachments",
+        ] {
+            assert_eq!(
+                validate_channel_name(OsStr::new(name)),
+                Ok(()),
+                "test_validate_channel_name: {:?} must be valid",
+                name
+            );
+        }
+        let too_long = "x".repeat(MAX_CHANNEL_NAME_LEN + 1);
+        for name in [
+            "",
+            ".",
+            "..",
+            "a/b",
+            "a\\b",
+            "line\nbreak",
+            " padded",
+            "draft.tmp",
+            ".#lock",
+            TRASH_DIR_NAME,
+            "12__attachments",
+            too_long.as_str(),
+        ] {
+            assert_eq!(
+                validate_channel_name(OsStr::new(name)),
+                Err(WalkError::InvalidName),
+                "test_validate_channel_name: {:?} must be invalid",
+                name
+            );
+        }
+    }
+
+    /// Test: creation is idempotent, keeps an existing metadata file, uses
+    /// the configured metadata name, and refuses invalid names and
+    /// non-directories.
+    #[test]
+    fn test_ensure_channel_dir_idempotent() {
+        let root = test_dir("ensure");
+        cleanup(&root);
+        let channel = root.join("general");
+
+        assert_eq!(
+            ensure_channel_dir(&channel, CreateConfig::new()),
+            Ok(EnsureReport {
+                created_dir: true,
+                created_metadata: true
+            }),
+            "test_ensure_channel_dir: first call creates both"
+        );
+        assert_eq!(
+            fs::read(channel.join("0.toml")).ok(),
+            Some(Vec::new()),
+            "test_ensure_channel_dir: metadata file must be empty"
+        );
+
+        assert!(
+            fs::write(channel.join("0.toml"), b"name = \"general\"").is_ok(),
+            "test_ensure_channel_dir: failed to write metadata"
+        );
+        assert_eq!(
+            ensure_channel_dir(&channel, CreateConfig::new()),
+            Ok(EnsureReport::default()),
+            "test_ensure_channel_dir: second call creates nothing"
+        );
+        assert_eq!(
+            fs::read(channel.join("0.toml")).ok(),
+            Some(b"name = \"general\"".to_vec()),
+            "test_ensure_channel_dir: existing metadata must be kept"
+        );
+
+        let custom = CreateConfig::new()
+            .walk_config(WalkConfig::message_loading().metadata_file_names(&["channel.toml"]));
+        assert!(
+            ensure_channel_dir(&root.join("custom"), custom).is_ok()
+                && root.join("custom").join("channel.toml").is_file(),
+            "test_ensure_channel_dir: configured metadata name"
+        );
+
+        assert_eq!(
+            ensure_channel_dir(&root.join("draft.tmp"), CreateConfig::new()),
+            Err(WalkError::InvalidName),
+            "test_ensure_channel_dir: temp-looking name must be refused"
+        );
+        assert!(
+            fs::write(root.join("file"), b"x").is_ok(),
+            "test_ensure_channel_dir: failed to write file"
+        );
+        assert_eq!(
+            ensure_channel_dir(&root.join("file"), CreateConfig::new()),
+            Err(WalkError::IoError),
+            "test_ensure_channel_dir: existing file must be refused"
+        );
+
+        cleanup(&root);
+    }
+
+    /// Test: ChannelDir sorts the channel's files into messages, metadata
+    /// and attachments, and refuses a path that is not a directory.
+    #[test]
+    fn test_channel_dir_model() {
+        let dir = test_dir("model");
+        cleanup(&dir);
+        let created = fs::create_dir_all(dir.join("nested")).is_ok()
+            && [
+                "0.toml",
+                "1__hi.toml",
+                "2__secret.gpgtoml",
+                "x__bad.toml",
+                "diagram.png",
+            ]
+            .iter()
+            .all(|name| fs::write(dir.join(name), b"x").is_ok())
+            && fs::write(dir.join("nested").join("3__deep.toml"), b"x").is_ok();
+        assert!(created, "test_channel_dir_model: failed to create channel");
+
+        let channel = ChannelDir::open(&dir);
+        assert!(channel.is_ok(), "test_channel_dir_model: open failed");
+        let Ok(channel) = channel else {
+            return;
+        };
+
+        let names = |files: ChannelFiles| -> Vec<String> {
+            let mut names: Vec<String> = files
+                .filter_map(|e| e.file_name().map(str::to_string))
+                .collect();
+            names.sort();
+            names
+        };
+        assert_eq!(
+            names(channel.messages()),
+            vec!["1__hi.toml", "2__secret.gpgtoml"],
+            "test_channel_dir_model: messages"
+        );
+        assert_eq!(
+            names(channel.attachments()),
+            vec!["diagram.png"],
+            "test_channel_dir_model: attachments"
+        );
+        assert_eq!(
+            channel
+                .metadata_file()
+                .and_then(|e| e.file_name().map(str::to_string)),
+            Some("0.toml".to_string()),
+            "test_channel_dir_model: metadata file"
+        );
+
+        assert_eq!(
+            ChannelDir::open(&dir.join("0.toml")).map(|_| ()),
+            Err(WalkError::ReadDirectory),
+            "test_channel_dir_model: file is not a channel"
+        );
+
+        cleanup(&dir);
+    }
+
+    /// Test: a channel walk that descends into attachment directories is
+    /// split into the five classes, and ChannelDir agrees on the files.
+    #[test]
+    fn test_classify_channel_entries() {
+        let dir = test_dir("classify");
+        cleanup(&dir);
+        let created = fs::create_dir_all(dir.join("3__attachments")).is_ok()
+            && fs::create_dir_all(dir.join("subchannel")).is_ok()
+            && [
+                "0.toml",
+                "1__hi.toml",
+                "2__secret.gpgtoml",
+                "3__photo.toml",
+                "x__bad.gpgtoml",
+                "draft.toml.tmp",
+                "diagram.png",
+                "3__attachments/photo.jpg",
+            ]
+            .iter()
+            .all(|name| fs::write(dir.join(name), b"x").is_ok());
+        assert!(
+            created,
+            "test_classify_channel_entries: failed to create channel"
+        );
+
+        let entries = DirWalker::new(&dir, WalkConfig::new().max_depth(1)).ok_entries();
+        let classification = classify_channel_entries(entries);
+        let names = |list: &[DirEntry]| -> Vec<String> {
+            let mut names: Vec<String> = list
+                .iter()
+                .filter_map(|e| e.file_name().map(str::to_string))
+                .collect();
+            names.sort();
+            names
+        };
+        assert_eq!(
+            names(&classification.metadata),
+            vec!["0.toml"],
+            "test_classify_channel_entries: metadata"
+        );
+        assert_eq!(
+            names(&classification.plaintext_messages),
+            vec!["1__hi.toml", "3__photo.toml"],
+            "test_classify_channel_entries: plaintext messages"
+        );
+        assert_eq!(
+            names(&classification.encrypted_messages),
+            vec!["2__secret.gpgtoml"],
+            "test_classify_channel_entries: encrypted messages"
+        );
+        assert_eq!(
+            names(&classification.attachments),
+            vec!["3__attachments", "diagram.png", "photo.jpg"],
+            "test_classify_channel_entries: attachments"
+        );
+        assert_eq!(
+            names(&classification.unknown),
+            vec!["draft.toml.tmp", "subchannel", "x__bad.gpgtoml"],
+            "test_classify_channel_entries: unknown"
+        );
+        assert_eq!(
+            classification.len(),
+            10,
+            "test_classify_channel_entries: every entry is classified once"
+        );
+
+        // Under a custom metadata name, `0.toml` is a malformed message name
+        let custom = classify_channel_entries_with(
+            DirWalker::new(&dir, WalkConfig::new().max_depth(0)).ok_entries(),
+            &WalkConfig::new().metadata_file_names(&["diagram.png"]),
+        );
+        assert_eq!(
+            (names(&custom.metadata), custom.unknown.len()),
+            (vec!["diagram.png".to_string()], 4),
+            "test_classify_channel_entries: custom metadata names"
+        );
+
+        cleanup(&dir);
+    }
+
+    /// Test: N__attachments directories are found per message, recognized
+    /// by name, and can be left out of hashes.
+    #[test]
+    fn test_message_attachments() {
+        use crate::dir_walk_module::filters::not;
+
+        let dir = test_dir("attachments");
+        cleanup(&dir);
+        let attachments = dir.join("1__attachments");
+        let created = fs::create_dir_all(&attachments).is_ok()
+            && fs::write(dir.join("1__hi.toml"), b"x").is_ok()
+            && fs::write(dir.join("2__bye.toml"), b"x").is_ok()
+            && fs::write(attachments.join("photo.jpg"), b"x").is_ok()
+            && fs::write(attachments.join("notes.txt"), b"x").is_ok();
+        assert!(
+            created,
+            "test_message_attachments: failed to create channel"
+        );
+
+        assert_eq!(
+            (
+                attachments_dir_number("1__attachments"),
+                attachments_dir_number("01__attachments"),
+                attachments_dir_number("1__x__attachments"),
+                attachments_dir_number("attachments"),
+            ),
+            (Some(1), None, None, None),
+            "test_message_attachments: directory names"
+        );
+
+        let channel = ChannelDir::open(&dir);
+        assert!(channel.is_ok(), "test_message_attachments: open failed");
+        let Ok(channel) = channel else {
+            return;
+        };
+        let mut messages: Vec<DirEntry> = channel.messages().collect();
+        messages.sort_by(|a, b| a.path().cmp(b.path()));
+        let counts: Vec<Option<usize>> = messages
+            .iter()
+            .map(|message| channel.message_attachments(message).map(Iterator::count))
+            .collect();
+        assert_eq!(
+            counts,
+            vec![Some(2), None],
+            "test_message_attachments: per-message attachments"
+        );
+
+        let outside = DirWalker::new(&dir, WalkConfig::new())
+            .filter_entry(not(in_attachments_dir))
+            .count();
+        assert_eq!(outside, 2, "test_message_attachments: excluded from counts");
+
+        #[cfg(not(feature = "minimal"))]
+        {
+            use crate::dir_walk_module::hashing::{HashConfig, hash_directory};
+
+            let hash_of = |config: &HashConfig| hash_directory(&dir, config).ok();
+            let with = hash_of(&HashConfig::new());
+            let without = hash_of(&HashConfig::new().include_attachments(false));
+            assert!(
+                fs::write(attachments.join("more.jpg"), b"x").is_ok(),
+                "test_message_attachments: failed to add attachment"
+            );
+            assert!(
+                with.is_some()
+                    && hash_of(&HashConfig::new()) != with
+                    && hash_of(&HashConfig::new().include_attachments(false)) == without,
+                "test_message_attachments: attachments excluded from the hash"
+            );
+        }
+
+        cleanup(&dir);
+    }
+}
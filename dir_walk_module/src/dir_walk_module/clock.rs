@@ -0,0 +1,259 @@
+//! # Walk-Time Clock (`dir_walk_module::clock`)
+//!
+//! The time source behind every time-dependent feature of the module:
+//! age pruning (`WalkConfig::skip_dirs_older_than`), tick budgets
+//! (`ScanScheduler::tick`), timed summaries and estimates
+//! (`DirWalker::summarize`, `estimate::estimate_walk`), and retry backoff
+//! (`channels::ensure_channel_dir`).
+//!
+//! ## Project Context
+//! Those features used to read `SystemTime::now()` / `Instant::now()` and
+//! call `thread::sleep` directly, so their tests had to age files on disk
+//! or sleep. A `MockClock` moves time forward on request instead:
+//!
+//! ```ignore
+//! let clock = MockClock::new(SystemTime::now());
+//! let config = WalkConfig::new()
+//!     .skip_dirs_older_than(Duration::from_secs(30 * 24 * 3600))
+//!     .clock(clock.clone());
+//! clock.advance(Duration::from_secs(40 * 24 * 3600));
+//! // Directories modified before the advance are now "old"
+//! ```
+//!
+//! `SystemClock` (the default everywhere) is the real clock.
+
+use std::fmt;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant, SystemTime};
+
+// ============================================================================
+// CLOCK TRAIT
+// ============================================================================
+
+/// A source of wall-clock time, monotonic time, and pauses.
+///
+/// Implementations are shared between threads (configs are cloned into
+/// a scheduler's polling thread), hence `Send + Sync`.
+pub trait Clock: fmt::Debug + Send + Sync {
+    /// Current wall-clock time; compared against file mtimes.
+    fn now(&self) -> SystemTime;
+
+    /// Current monotonic time; used to measure budgets and durations.
+    fn instant(&self) -> Instant;
+
+    /// Pause the calling thread for `duration` (retry backoff).
+    fn sleep(&self, duration: Duration);
+}
+
+/// The operating system's clocks (the default).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+
+    fn instant(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        std::thread::sleep(duration);
+    }
+}
+
+/// The default clock, shared (see `WalkConfig::clock`).
+pub(crate) fn system_clock() -> Arc<dyn Clock> {
+    Arc::new(SystemClock)
+}
+
+// ============================================================================
+// MOCK CLOCK
+// ============================================================================
+
+/// A manually driven clock for deterministic tests.
+///
+/// Time stands still until `advance()` is called, `sleep()` advances it
+/// instead of blocking, and `auto_advance()` makes every reading move it
+/// forward by a fixed step (simulating work that takes time). Clones share
+/// one time line, so a test keeps a clone to drive the clock it handed to
+/// a config.
+#[derive(Debug, Clone)]
+pub struct MockClock {
+    /// Wall-clock time at offset zero.
+    start_time: SystemTime,
+
+    /// Monotonic time at offset zero (`Instant` cannot be built from a
+    /// number, so the real one at creation is the base).
+    start_instant: Instant,
+
+    /// Nanoseconds elapsed on this time line; saturates at `u64::MAX`
+    /// (about 584 years).
+    offset_nanos: Arc<AtomicU64>,
+
+    /// Nanoseconds added after every `now()` / `instant()` reading.
+    auto_step_nanos: u64,
+}
+
+impl MockClock {
+    /// Create a clock reading `start` (wall-clock) that does not move by
+    /// itself.
+    ///
+    /// # Arguments
+    /// * `start` - Value of the first `now()`
+    pub fn new(start: SystemTime) -> Self {
+        MockClock {
+            start_time: start,
+            start_instant: Instant::now(),
+            offset_nanos: Arc::new(AtomicU64::new(0)),
+            auto_step_nanos: 0,
+        }
+    }
+
+    /// Advance the clock by `step` after every reading.
+    ///
+    /// # Arguments
+    /// * `step` - e.g. `Duration::from_millis(1)`: a loop that checks a
+    ///   budget once per slice then sees each slice take 1 ms
+    pub fn auto_advance(mut self, step: Duration) -> Self {
+        self.auto_step_nanos = duration_nanos(step);
+        self
+    }
+
+    /// Move the clock forward by `duration` (all clones see it).
+    pub fn advance(&self, duration: Duration) {
+        self.add_nanos(duration_nanos(duration));
+    }
+
+    /// Total time the clock has moved since it was created.
+    pub fn elapsed(&self) -> Duration {
+        Duration::from_nanos(self.offset_nanos.load(Ordering::SeqCst))
+    }
+
+    /// Saturating add to the shared offset.
+    fn add_nanos(&self, nanos: u64) {
+        if nanos == 0 {
+            return;
+        }
+        // Cannot fail: the closure always returns Some
+        let _ = self
+            .offset_nanos
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |current| {
+                Some(current.saturating_add(nanos))
+            });
+    }
+
+    /// Read the offset, then apply the auto-advance step.
+    fn read_offset(&self) -> Duration {
+        let offset = self.elapsed();
+        self.add_nanos(self.auto_step_nanos);
+        offset
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> SystemTime {
+        let offset = self.read_offset();
+        self.start_time
+            .checked_add(offset)
+            .unwrap_or(self.start_time)
+    }
+
+    fn instant(&self) -> Instant {
+        let offset = self.read_offset();
+        self.start_instant
+            .checked_add(offset)
+            .unwrap_or(self.start_instant)
+    }
+
+    fn sleep(&self, duration: Duration) {
+        self.advance(duration);
+    }
+}
+
+/// A `Duration` in nanoseconds, saturating at `u64::MAX`.
+fn duration_nanos(duration: Duration) -> u64 {
+    u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX)
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dir_walk_module::{DirWalker, WalkConfig};
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    /// Helper: unique test directory path (caller cleans up).
+    fn test_dir(name: &str) -> PathBuf {
+        crate::dir_walk_module::testing::unique_test_dir(&format!("clock_{}", name))
+    }
+
+    /// Helper: safely remove test directory, ignoring errors.
+    fn cleanup(path: &Path) {
+        let _ = fs::remove_dir_all(path);
+    }
+
+    /// Test: a mock clock moves only when told to, shares its time line
+    /// with clones, and drives age pruning without touching mtimes.
+    #[test]
+    fn test_mock_clock_drives_age_pruning() {
+        let start = SystemTime::now();
+        let clock = MockClock::new(start);
+        let handed_out = clock.clone();
+        let before = handed_out.instant();
+        assert_eq!(handed_out.now(), start, "test_mock_clock: stands still");
+
+        handed_out.sleep(Duration::from_secs(5));
+        assert_eq!(
+            (clock.now(), clock.instant().duration_since(before)),
+            (start + Duration::from_secs(5), Duration::from_secs(5)),
+            "test_mock_clock: sleep advances every clone"
+        );
+
+        let stepping = MockClock::new(start).auto_advance(Duration::from_millis(1));
+        let first = stepping.instant();
+        assert_eq!(
+            stepping.instant().duration_since(first),
+            Duration::from_millis(1),
+            "test_mock_clock: auto_advance per reading"
+        );
+
+        let dir = test_dir("age_pruning");
+        cleanup(&dir);
+        assert!(
+            fs::create_dir_all(dir.join("archive")).is_ok()
+                && fs::write(dir.join("archive").join("1__old.toml"), b"x").is_ok(),
+            "test_mock_clock: failed to create tree"
+        );
+
+        let month = Duration::from_secs(30 * 24 * 3600);
+        let clock = MockClock::new(SystemTime::now());
+        let config = WalkConfig::new()
+            .skip_dirs_older_than(month)
+            .clock(clock.clone());
+        let count = |config: &WalkConfig| {
+            let mut walker = DirWalker::new(&dir, config.clone());
+            let files = walker
+                .by_ref()
+                .filter_map(|r| r.ok())
+                .filter(|e| e.is_file())
+                .count();
+            (files, walker.stats().old_dirs_skipped)
+        };
+        assert_eq!(count(&config), (1, 0), "test_mock_clock: fresh directory");
+        clock.advance(month + month);
+        assert_eq!(
+            count(&config),
+            (0, 1),
+            "test_mock_clock: directory aged by the clock alone"
+        );
+
+        cleanup(&dir);
+    }
+}
@@ -0,0 +1,202 @@
+//! # Walk Cost Estimates (`dir_walk_module::estimate`)
+//!
+//! A bounded dry run that reads the first directories of a walk and
+//! extrapolates how many directories and entries the full walk would
+//! visit, and how long it would take.
+//!
+//! ## Project Context
+//! A full-workspace scan on a large NAS share can take minutes. The UI
+//! calls `estimate_walk()` first and warns ("about 2 million entries,
+//! roughly 3 minutes") before the user commits to it.
+//!
+//! ## Method
+//! The walk is breadth-first, so the sample is the top of the tree. From
+//! it come the mean entries per directory and the branching factor
+//! (subdirectories found per directory read). Each directory still
+//! queued when the sample stops is assumed to root a subtree of
+//! `1 / (1 - branching)` directories. This is a heuristic: trees that
+//! keep widening below the sampled levels are underestimated, so the
+//! branching factor is capped and the result marked as an estimate.
+
+use std::ops::ControlFlow;
+use std::path::Path;
+use std::time::Duration;
+
+use super::{WalkConfig, WalkError, walk_with};
+
+/// Highest branching factor used for extrapolation; a widening frontier
+/// would otherwise extrapolate to infinity.
+const MAX_BRANCHING: f64 = 0.9;
+
+// ============================================================================
+// ESTIMATE
+// ============================================================================
+
+/// Result of `estimate_walk()`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WalkEstimate {
+    /// The sample covered the whole walk: every figure is exact (the
+    /// duration is the measured one).
+    pub complete: bool,
+
+    /// Directories read by the sample.
+    pub sampled_dirs: usize,
+
+    /// Entries the sample yielded (after the config's filters).
+    pub sampled_entries: usize,
+
+    /// Time the sample took.
+    pub sample_elapsed: Duration,
+
+    /// Directories the full walk is expected to read.
+    pub estimated_dirs: u64,
+
+    /// Entries the full walk is expected to yield.
+    pub estimated_entries: u64,
+
+    /// Expected wall-clock time of the full walk.
+    pub estimated_duration: Duration,
+}
+
+/// Sample the first `sample_budget` directories of a walk and
+/// extrapolate the full walk's cost.
+///
+/// The sample uses `config` as given (filters, depth, bounds), with
+/// `max_dirs` lowered to the budget. A `max_dirs` already set in
+/// `config` caps the estimate, since the full walk stops there too.
+///
+/// # Arguments
+/// * `path` - Root the full walk would start from
+/// * `config` - Configuration of the full walk
+/// * `sample_budget` - Most directories the sample reads (at least 1)
+///
+/// # Returns
+/// * `Ok(WalkEstimate)` - Sampled and extrapolated figures
+/// * `Err(WalkError)` - Fatal sample error (strict mode only)
+pub fn estimate_walk(
+    path: &Path,
+    config: WalkConfig,
+    sample_budget: usize,
+) -> Result<WalkEstimate, WalkError> {
+    let walk_cap = config.max_dirs;
+    let sample_cap = match walk_cap {
+        Some(cap) => cap.min(sample_budget.max(1)),
+        None => sample_budget.max(1),
+    };
+
+    let clock = config.clock.clone();
+    let started = clock.instant();
+    let mut sampled_entries: usize = 0;
+    let stats = walk_with(path, config.max_dirs(sample_cap), |_entry| {
+        sampled_entries = sampled_entries.saturating_add(1);
+        ControlFlow::Continue(())
+    })?;
+    let sample_elapsed = clock.instant().saturating_duration_since(started);
+
+    let read = stats.directories_read;
+    // Queued directories the sample dropped unread
+    let pending = stats.dirs_limit_skipped;
+    let complete = pending == 0 || walk_cap.is_some_and(|cap| cap <= sample_cap);
+
+    if complete || read == 0 {
+        return Ok(WalkEstimate {
+            complete: true,
+            sampled_dirs: read,
+            sampled_entries,
+            sample_elapsed,
+            estimated_dirs: read as u64,
+            estimated_entries: sampled_entries as u64,
+            estimated_duration: sample_elapsed,
+        });
+    }
+
+    // Directories found per directory read (the root was not found)
+    let discovered = read.saturating_sub(1).saturating_add(pending);
+    let branching = (discovered as f64 / read as f64).min(MAX_BRANCHING);
+    let mut estimated_dirs = read as f64 + pending as f64 / (1.0 - branching);
+    if let Some(cap) = walk_cap {
+        estimated_dirs = estimated_dirs.min(cap as f64);
+    }
+
+    let scale = estimated_dirs / read as f64;
+    // Float-to-int `as` saturates; values are finite and non-negative
+    Ok(WalkEstimate {
+        complete: false,
+        sampled_dirs: read,
+        sampled_entries,
+        sample_elapsed,
+        estimated_dirs: estimated_dirs.round() as u64,
+        estimated_entries: (sampled_entries as f64 * scale).round() as u64,
+        estimated_duration: sample_elapsed.mul_f64(scale),
+    })
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+
+    /// Helper: unique test directory path (caller cleans up).
+    fn test_dir(name: &str) -> PathBuf {
+        crate::dir_walk_module::testing::unique_test_dir(&format!("estimate_{}", name))
+    }
+
+    /// Helper: safely remove test directory, ignoring errors.
+    fn cleanup(path: &Path) {
+        let _ = fs::remove_dir_all(path);
+    }
+
+    /// Test: a sample covering the tree is exact; a partial sample
+    /// extrapolates to more than it saw, within the tree's order of
+    /// magnitude.
+    #[test]
+    fn test_estimate_walk() {
+        let dir = test_dir("estimate");
+        cleanup(&dir);
+        // 10 channels × (3 files + 1 subdirectory with 2 files)
+        let created = (0..10).all(|c| {
+            let channel = dir.join(format!("channel{}", c));
+            fs::create_dir_all(channel.join("sub")).is_ok()
+                && (0..3).all(|m| fs::write(channel.join(format!("{}__m.toml", m)), b"x").is_ok())
+                && (0..2).all(|m| {
+                    fs::write(channel.join("sub").join(format!("{}__m.toml", m)), b"x").is_ok()
+                })
+        });
+        assert!(created, "test_estimate_walk: failed to create tree");
+        // 21 directories, 70 entries (dirs included)
+
+        let full = estimate_walk(&dir, WalkConfig::new(), 100);
+        assert!(
+            full.is_ok_and(|e| e.complete && e.estimated_dirs == 21 && e.estimated_entries == 70),
+            "test_estimate_walk: exact when the sample covers the tree"
+        );
+
+        let partial = estimate_walk(&dir, WalkConfig::new(), 5);
+        assert!(partial.is_ok(), "test_estimate_walk: partial sample failed");
+        let Ok(partial) = partial else {
+            return;
+        };
+        assert!(
+            !partial.complete
+                && partial.sampled_dirs == 5
+                && partial.estimated_dirs > 5
+                && partial.estimated_dirs < 210
+                && partial.estimated_entries > partial.sampled_entries as u64,
+            "test_estimate_walk: unexpected extrapolation {:?}",
+            partial
+        );
+
+        let capped = estimate_walk(&dir, WalkConfig::new().max_dirs(3), 5);
+        assert!(
+            capped.is_ok_and(|e| e.complete && e.estimated_dirs == 3),
+            "test_estimate_walk: walk's own max_dirs caps the estimate"
+        );
+
+        cleanup(&dir);
+    }
+}
@@ -0,0 +1,283 @@
+//! # Composable Entry Filters (`dir_walk_module::filters`)
+//!
+//! Small filter values that test a `DirEntry` without allocating, and
+//! combinators to build larger filters from them.
+//!
+//! ## Project Context
+//! Channel code used to repeat closures such as
+//! `|e| e.file_name().is_some_and(|n| n.ends_with(".toml"))` with small
+//! differences (case, dot handling, non-UTF-8 names). One definition per
+//! rule keeps every consumer in agreement:
+//!
+//! ```ignore
+//! let messages = all_of(
+//!     any_of(by_extension("toml"), by_extension("gpgtoml")),
+//!     not(by_name_prefix("0.")),
+//! );
+//! for entry in DirWalker::new(&channel, config).filter_entry(messages) { /* ... */ }
+//! if messages.matches(&entry) { /* ... */ }
+//! ```
+//!
+//! Filters are `Copy` when their parts are, so one filter value can be
+//! passed to several walks.
+
+use std::path::Path;
+
+use super::{DirEntry, DirWalker, WalkError, WalkStats};
+
+// ============================================================================
+// FILTER TRAIT
+// ============================================================================
+
+/// A test on one entry.
+///
+/// Implemented by the filters in this module and by any
+/// `Fn(&DirEntry) -> bool` closure, so the two mix freely in combinators.
+pub trait EntryFilter {
+    /// Whether `entry` passes the filter.
+    fn matches(&self, entry: &DirEntry) -> bool;
+}
+
+impl<F> EntryFilter for F
+where
+    F: Fn(&DirEntry) -> bool,
+{
+    fn matches(&self, entry: &DirEntry) -> bool {
+        self(entry)
+    }
+}
+
+// ============================================================================
+// BASIC FILTERS
+// ============================================================================
+
+/// Filter returned by `by_extension()`.
+#[derive(Debug, Clone, Copy)]
+pub struct ByExtension<'a> {
+    extension: &'a str,
+}
+
+/// Entries whose name has the extension `extension`.
+///
+/// # Arguments
+/// * `extension` - Extension without the dot, e.g. `"toml"`; compared
+///   ASCII case-insensitively, as in `reports::extension_histogram()`
+///
+/// Only the last extension counts (`a.toml.tmp` has `tmp`), and a
+/// leading dot does not start one (`.toml` has none).
+pub fn by_extension(extension: &str) -> ByExtension<'_> {
+    ByExtension { extension }
+}
+
+impl EntryFilter for ByExtension<'_> {
+    fn matches(&self, entry: &DirEntry) -> bool {
+        Path::new(entry.file_name_os())
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| ext.eq_ignore_ascii_case(self.extension))
+    }
+}
+
+/// Filter returned by `by_name_prefix()`.
+#[derive(Debug, Clone, Copy)]
+pub struct ByNamePrefix<'a> {
+    prefix: &'a str,
+}
+
+/// Entries whose name starts with `prefix` (byte comparison, case
+/// sensitive; non-UTF-8 names are compared on their raw bytes).
+pub fn by_name_prefix(prefix: &str) -> ByNamePrefix<'_> {
+    ByNamePrefix { prefix }
+}
+
+impl EntryFilter for ByNamePrefix<'_> {
+    fn matches(&self, entry: &DirEntry) -> bool {
+        entry
+            .file_name_os()
+            .as_encoded_bytes()
+            .starts_with(self.prefix.as_bytes())
+    }
+}
+
+// ============================================================================
+// COMBINATORS
+// ============================================================================
+
+/// Filter returned by `not()`.
+#[derive(Debug, Clone, Copy)]
+pub struct Not<F> {
+    inner: F,
+}
+
+/// Entries that do NOT pass `filter`.
+pub fn not<F: EntryFilter>(filter: F) -> Not<F> {
+    Not { inner: filter }
+}
+
+impl<F: EntryFilter> EntryFilter for Not<F> {
+    fn matches(&self, entry: &DirEntry) -> bool {
+        !self.inner.matches(entry)
+    }
+}
+
+/// Filter returned by `all_of()`.
+#[derive(Debug, Clone, Copy)]
+pub struct AllOf<A, B> {
+    first: A,
+    second: B,
+}
+
+/// Entries that pass both filters (`first` is tested first; `second`
+/// only if it passes). Nest for more: `all_of(a, all_of(b, c))`.
+pub fn all_of<A: EntryFilter, B: EntryFilter>(first: A, second: B) -> AllOf<A, B> {
+    AllOf { first, second }
+}
+
+impl<A: EntryFilter, B: EntryFilter> EntryFilter for AllOf<A, B> {
+    fn matches(&self, entry: &DirEntry) -> bool {
+        self.first.matches(entry) && self.second.matches(entry)
+    }
+}
+
+/// Filter returned by `any_of()`.
+#[derive(Debug, Clone, Copy)]
+pub struct AnyOf<A, B> {
+    first: A,
+    second: B,
+}
+
+/// Entries that pass either filter (`first` is tested first; `second`
+/// only if it fails). Nest for more: `any_of(a, any_of(b, c))`.
+pub fn any_of<A: EntryFilter, B: EntryFilter>(first: A, second: B) -> AnyOf<A, B> {
+    AnyOf { first, second }
+}
+
+impl<A: EntryFilter, B: EntryFilter> EntryFilter for AnyOf<A, B> {
+    fn matches(&self, entry: &DirEntry) -> bool {
+        self.first.matches(entry) || self.second.matches(entry)
+    }
+}
+
+// ============================================================================
+// WALKER ADAPTER
+// ============================================================================
+
+/// Iterator returned by `DirWalker::filter_entry()`.
+///
+/// Yields the walker's items, minus the entries `filter` rejects. Errors
+/// are passed through unfiltered.
+#[derive(Debug)]
+pub struct FilterEntry<F> {
+    walker: DirWalker,
+    filter: F,
+}
+
+impl DirWalker {
+    /// Yield only the entries that pass `filter`.
+    ///
+    /// Rejected entries are dropped after they are read; a rejected
+    /// directory is still descended into (its contents are tested on
+    /// their own). Use `max_depth` or `skip_dirs_older_than` to avoid
+    /// reading subtrees at all.
+    ///
+    /// # Arguments
+    /// * `filter` - Any `EntryFilter`, including a plain closure
+    pub fn filter_entry<F: EntryFilter>(self, filter: F) -> FilterEntry<F> {
+        FilterEntry {
+            walker: self,
+            filter,
+        }
+    }
+}
+
+impl<F> FilterEntry<F> {
+    /// Counters accumulated so far (see `DirWalker::stats()`).
+    pub fn stats(&self) -> &WalkStats {
+        self.walker.stats()
+    }
+}
+
+impl<F: EntryFilter> Iterator for FilterEntry<F> {
+    type Item = Result<DirEntry, WalkError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Bounded: each iteration consumes one item of the finite walk
+        loop {
+            match self.walker.next()? {
+                Ok(entry) if !self.filter.matches(&entry) => continue,
+                item => return Some(item),
+            }
+        }
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dir_walk_module::WalkConfig;
+    use std::fs;
+    use std::path::PathBuf;
+
+    /// Helper: unique test directory path (caller cleans up).
+    fn test_dir(name: &str) -> PathBuf {
+        crate::dir_walk_module::testing::unique_test_dir(&format!("filters_{}", name))
+    }
+
+    /// Helper: safely remove test directory, ignoring errors.
+    fn cleanup(path: &Path) {
+        let _ = fs::remove_dir_all(path);
+    }
+
+    /// Test: basic filters and combinators, both standalone and through
+    /// filter_entry.
+    #[test]
+    fn test_filter_composition() {
+        let dir = test_dir("composition");
+        cleanup(&dir);
+        let names = [
+            "0.toml",
+            "1__a.toml",
+            "2__b.GPGTOML",
+            "3__c.toml.tmp",
+            ".toml",
+            "notes.txt",
+        ];
+        let created = fs::create_dir_all(dir.join("sub.toml")).is_ok()
+            && names
+                .iter()
+                .all(|name| fs::write(dir.join(name), b"x").is_ok());
+        assert!(created, "test_filter_composition: failed to create files");
+
+        let messages = all_of(
+            any_of(by_extension("toml"), by_extension("gpgtoml")),
+            all_of(not(by_name_prefix("0.")), |e: &DirEntry| e.is_file()),
+        );
+        let mut found: Vec<String> = DirWalker::new(&dir, WalkConfig::new())
+            .filter_entry(messages)
+            .filter_map(|r| r.ok())
+            .filter_map(|e| e.file_name().map(str::to_string))
+            .collect();
+        found.sort();
+        assert_eq!(
+            found,
+            vec!["1__a.toml", "2__b.GPGTOML"],
+            "test_filter_composition: message filter"
+        );
+
+        // Standalone use agrees with the adapter
+        let standalone = DirWalker::new(&dir, WalkConfig::new())
+            .filter_map(|r| r.ok())
+            .filter(|e| messages.matches(e))
+            .count();
+        assert_eq!(
+            standalone, 2,
+            "test_filter_composition: standalone matches()"
+        );
+
+        cleanup(&dir);
+    }
+}
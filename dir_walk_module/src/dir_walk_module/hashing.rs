@@ -0,0 +1,1220 @@
+//! # Directory Content Hashing (`dir_walk_module::hashing`)
+//!
+//! Computes a single `u64` fingerprint of a directory tree for change
+//! detection, built on `DirWalker`.
+//!
+//! ## Project Context
+//! The application polls channel directories and only reloads messages
+//! when the directory hash changes (see Demo 6 in `main.rs` for the
+//! original inline pattern). Replicas on different devices compare these
+//! hashes, so the hash must be:
+//! - Order-independent: records are sorted by relative path before hashing
+//!   (`read_dir` order differs between filesystems)
+//! - Algorithm-stable: FNV-1a with little-endian integer encoding
+//!   (`DefaultHasher` output is not guaranteed across Rust versions or
+//!   platforms)
+//! - Root-independent: paths are hashed relative to the walk root
+//! - Filesystem-independent: mtimes are truncated to
+//!   `HashConfig::mtime_granularity` (whole seconds by default), so
+//!   nanosecond and 2-second filesystems can agree
+//!
+//! ## Special File Protection
+//! Content hashing opens files only through `open_regular_file()`, so a
+//! FIFO, socket, or device node can never hang or flood a hash job — even
+//! one that replaced a regular file after the walk saw it. This is a
+//! guarantee of the subsystem, not an option (`HashConfig::REFUSES_SPECIAL_FILES`).
+
+use std::fmt;
+use std::fs;
+use std::hash::Hasher;
+use std::io::Read;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::channels::path_in_attachments_dir;
+use super::filters::EntryFilter;
+use super::platform;
+use super::{
+    DirEntry, DirWalker, WalkConfig, WalkError, open_regular_file, permission_bits, strip_root,
+};
+
+/// Read buffer size for content hashing (stack-allocated).
+const CONTENT_READ_BUFFER_SIZE: usize = 8 * 1024;
+
+/// Default `HashConfig::mtime_granularity`: whole seconds.
+const DEFAULT_MTIME_GRANULARITY: Duration = Duration::from_secs(1);
+
+/// Nanoseconds per second, for `truncate_duration()`.
+const NANOS_PER_SEC: u128 = 1_000_000_000;
+
+// ============================================================================
+// STABLE HASHER
+// ============================================================================
+
+/// FNV-1a 64-bit hasher with platform-independent integer encoding.
+///
+/// All integer `write_*` methods are overridden to feed little-endian
+/// bytes (`usize`/`isize` are widened to 64 bits first), so the same
+/// sequence of writes produces the same hash on every platform and Rust
+/// version.
+///
+/// Not a cryptographic hash: it detects change, it does not resist
+/// deliberate collisions.
+#[derive(Debug, Clone)]
+pub struct StableHasher {
+    /// Current FNV-1a state.
+    state: u64,
+}
+
+impl StableHasher {
+    /// FNV-1a 64-bit offset basis.
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+
+    /// FNV-1a 64-bit prime.
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    /// Create a hasher in its initial state.
+    pub fn new() -> Self {
+        StableHasher {
+            state: Self::OFFSET_BASIS,
+        }
+    }
+}
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Hasher for StableHasher {
+    fn finish(&self) -> u64 {
+        self.state
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.state ^= u64::from(*byte);
+            self.state = self.state.wrapping_mul(Self::PRIME);
+        }
+    }
+
+    fn write_u8(&mut self, i: u8) {
+        self.write(&[i]);
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u128(&mut self, i: u128) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+
+    fn write_i8(&mut self, i: i8) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_i16(&mut self, i: i16) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_i32(&mut self, i: i32) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_i64(&mut self, i: i64) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_i128(&mut self, i: i128) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_isize(&mut self, i: isize) {
+        self.write_i64(i as i64);
+    }
+}
+
+// ============================================================================
+// HASH CONFIGURATION
+// ============================================================================
+
+/// What a symbolic link contributes to a directory hash
+/// (`HashConfig::symlink_hashing`).
+///
+/// Independent of how the walk treats links: with `follow_symlinks(true)`
+/// the walk still descends into linked directories; this only decides the
+/// link entry's own record.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SymlinkHashing {
+    /// Whatever metadata the walk captured (default): the link's own
+    /// size and mtime, or the target's when the walk follows symlinks.
+    #[default]
+    AsWalked,
+
+    /// The link's target path, as stored in the link (`fs::read_link`).
+    /// Links with the same text hash the same on every replica, wherever
+    /// they point and whenever they were created.
+    TargetPath,
+
+    /// The target's type, size and mtime (the link is followed for the
+    /// hash). A broken link contributes a fixed "unavailable" record.
+    TargetMetadata,
+
+    /// Nothing: links are left out of the hash.
+    Ignore,
+}
+
+/// Configuration for `hash_directory()`.
+///
+/// Built using method chaining, like `WalkConfig`.
+///
+/// ## Example
+/// ```rust,no_run
+/// # use crate::dir_walk_module::WalkConfig;
+/// # use crate::dir_walk_module::hashing::HashConfig;
+/// let config = HashConfig::new()
+///     .walk_config(WalkConfig::channel_scan().max_depth(0))
+///     .include_contents(true);
+/// ```
+#[derive(Debug, Clone)]
+pub struct HashConfig {
+    /// Walk used to discover the entries that contribute to the hash.
+    ///
+    /// Its bounds, filters, symlink policy and error mode all apply.
+    /// Default: `WalkConfig::new().max_depth(1)` (the Demo 6 pattern).
+    ///
+    /// The default does not skip zero-byte files, while
+    /// `WalkConfig::message_loading()` does: a channel's hash covers the
+    /// empty partial writes that message loading hides. To hash exactly
+    /// what is loaded, add `skip_empty_files(true)` to the walk.
+    walk_config: WalkConfig,
+
+    /// Whether file contents (not just size and mtime) are hashed.
+    ///
+    /// - `false` (default): metadata only — cheap, no file is opened
+    /// - `true`: every regular file is read in full, in bounded chunks,
+    ///   through `open_regular_file()`
+    include_contents: bool,
+
+    /// Whether content hashing verifies each opened file is the object the
+    /// walk scanned (`DirEntry::open_verified()`).
+    ///
+    /// A file swapped between scan and open then fails with
+    /// `WalkError::EntryChanged` (strict walks) or contributes the
+    /// "content unavailable" marker (continue-on-error walks), so the hash
+    /// changes on the next poll rather than silently covering an unscanned
+    /// object. Only meaningful with `include_contents(true)`.
+    verify_identity: bool,
+
+    /// Whether message attachment directories (`N__attachments`, see
+    /// `channels::ATTACHMENTS_DIR_SUFFIX`) and their files contribute.
+    ///
+    /// - `true` (default): hashed like any other entry
+    /// - `false`: left out, so adding an attachment does not change the
+    ///   channel's hash
+    include_attachments: bool,
+
+    /// Whether permission bits (`DirEntry::permission_bits()`) of files
+    /// and directories contribute.
+    ///
+    /// - `false` (default): a chmod-only change leaves the hash unchanged
+    /// - `true`: a chmod changes the hash even when size and mtime do not
+    include_permissions: bool,
+
+    /// What symlink entries contribute (see `SymlinkHashing`).
+    symlink_hashing: SymlinkHashing,
+
+    /// Modification times are truncated to a multiple of this before
+    /// hashing. Default: 1 second. `Duration::ZERO` hashes them exactly.
+    mtime_granularity: Duration,
+
+    /// Filter a non-directory entry must pass to contribute
+    /// (`file_filter()`). Default: `None` (every entry contributes).
+    file_filter: Option<FileFilter>,
+
+    /// Regular files longer than this (in bytes) are left out
+    /// (`ignore_files_larger_than()`). Default: `None` (no limit).
+    max_file_len: Option<u64>,
+}
+
+/// Shared filter of `HashConfig::file_filter`.
+///
+/// Behind an `Arc` so cloned configs share one filter.
+#[derive(Clone)]
+struct FileFilter(Arc<dyn EntryFilter + Send + Sync>);
+
+/// Manual Debug impl: filters need not implement Debug.
+impl fmt::Debug for FileFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("FileFilter(..)")
+    }
+}
+
+impl Default for HashConfig {
+    fn default() -> Self {
+        HashConfig {
+            walk_config: WalkConfig::new().max_depth(1),
+            include_contents: false,
+            verify_identity: false,
+            include_attachments: true,
+            include_permissions: false,
+            symlink_hashing: SymlinkHashing::AsWalked,
+            mtime_granularity: DEFAULT_MTIME_GRANULARITY,
+            file_filter: None,
+            max_file_len: None,
+        }
+    }
+}
+
+impl HashConfig {
+    /// Guarantee: content hashing never opens FIFOs, sockets, or device
+    /// nodes. This is not configurable; it is exposed so callers (and
+    /// tests) can assert the guarantee they depend on.
+    pub const REFUSES_SPECIAL_FILES: bool = true;
+
+    /// Create a config with default settings (depth 1, metadata only).
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a default config whose mtime granularity suits the
+    /// filesystem holding `dir` (`platform::mtime_granularity()`).
+    ///
+    /// Filesystems coarser than the default second (FAT's two seconds)
+    /// get their own granularity; everything else, and a failed probe,
+    /// keeps the defaults.
+    ///
+    /// # Arguments
+    /// * `dir` - Existing writable directory on the hashed filesystem
+    pub fn for_filesystem(dir: &Path) -> Self {
+        platform::hash_config_for(platform::mtime_granularity(dir).ok().flatten())
+    }
+
+    /// Set the walk used to discover hashed entries.
+    ///
+    /// Zero-byte files contribute unless `config` skips them
+    /// (`skip_empty_files(true)`, as `WalkConfig::message_loading()` does).
+    ///
+    /// # Arguments
+    /// * `config` - Walk configuration (bounds, filters, symlink policy)
+    pub fn walk_config(mut self, config: WalkConfig) -> Self {
+        self.walk_config = config;
+        self
+    }
+
+    /// The walk used to discover hashed entries (for audits that rerun it).
+    pub(super) fn walk(&self) -> &WalkConfig {
+        &self.walk_config
+    }
+
+    /// Set whether file contents are hashed in addition to metadata.
+    ///
+    /// # Arguments
+    /// * `include` - If `true`, every regular file is read in full.
+    pub fn include_contents(mut self, include: bool) -> Self {
+        self.include_contents = include;
+        self
+    }
+
+    /// Set whether content hashing re-verifies file identity at open time.
+    ///
+    /// # Arguments
+    /// * `verify` - If `true`, files are opened with
+    ///   `DirEntry::open_verified()` instead of by path alone.
+    pub fn verify_identity(mut self, verify: bool) -> Self {
+        self.verify_identity = verify;
+        self
+    }
+
+    /// Set whether message attachment directories contribute to the hash.
+    ///
+    /// # Arguments
+    /// * `include` - If `false`, entries at or below an `N__attachments`
+    ///   directory (relative to the hashed root) are skipped. They are
+    ///   still walked: skipping is by path, after the walk yields them.
+    pub fn include_attachments(mut self, include: bool) -> Self {
+        self.include_attachments = include;
+        self
+    }
+
+    /// Set whether permission bits contribute to the hash.
+    ///
+    /// # Arguments
+    /// * `include` - If `true`, each file's and directory's permission
+    ///   bits are hashed (Unix mode bits; only the read-only flag
+    ///   elsewhere). Link-target records (`SymlinkHashing::TargetPath`)
+    ///   never include them.
+    ///
+    /// # Project Context
+    /// `chmod` changes neither size nor mtime. Deployments where a
+    /// permission change must trigger a reload turn this on; the rest
+    /// keep it off so an ownership/permission sweep does not reload every
+    /// channel. Replicas should agree on the setting, and on whether
+    /// their filesystems keep Unix modes, for hashes to compare.
+    pub fn include_permissions(mut self, include: bool) -> Self {
+        self.include_permissions = include;
+        self
+    }
+
+    /// Set what symlink entries contribute to the hash.
+    ///
+    /// # Arguments
+    /// * `mode` - See `SymlinkHashing`; with `TargetPath` and
+    ///   `TargetMetadata`, contents are never hashed through a link
+    ///
+    /// # Project Context
+    /// Replicas on different machines resolve the same link to different
+    /// places (or not at all); `TargetPath` or `Ignore` keeps their hashes
+    /// comparable.
+    pub fn symlink_hashing(mut self, mode: SymlinkHashing) -> Self {
+        self.symlink_hashing = mode;
+        self
+    }
+
+    /// Set the granularity modification times are truncated to.
+    ///
+    /// # Arguments
+    /// * `granularity` - e.g. `Duration::from_secs(2)` when any replica
+    ///   sits on FAT/exFAT (2-second mtimes); `Duration::ZERO` keeps full
+    ///   precision (the hash then differs between filesystems)
+    ///
+    /// # Project Context
+    /// ext4 stores nanoseconds, NTFS 100 ns, FAT 2 s: byte-identical
+    /// replicated channels only hash alike when every replica's mtime is
+    /// cut to the coarsest granularity among them. Truncation is toward
+    /// the epoch.
+    pub fn mtime_granularity(mut self, granularity: Duration) -> Self {
+        self.mtime_granularity = granularity;
+        self
+    }
+
+    /// Hash only the files that pass `filter`.
+    ///
+    /// # Arguments
+    /// * `filter` - Any `filters::EntryFilter` (the same values passed to
+    ///   `DirWalker::filter_entry()`), tested on every non-directory
+    ///   entry. Directories always contribute, so the tree's shape still
+    ///   counts; files that fail the filter are neither recorded nor
+    ///   opened for content hashing.
+    ///
+    /// # Project Context
+    /// Channel directories also hold logs and caches that change on every
+    /// poll. Hashing only messages keeps that churn from reporting a
+    /// change each time:
+    ///
+    /// ```ignore
+    /// let config = HashConfig::new()
+    ///     .file_filter(any_of(by_extension("toml"), by_extension("gpgtoml")));
+    /// ```
+    pub fn file_filter<F>(mut self, filter: F) -> Self
+    where
+        F: EntryFilter + Send + Sync + 'static,
+    {
+        self.file_filter = Some(FileFilter(Arc::new(filter)));
+        self
+    }
+
+    /// Leave regular files longer than `bytes` out of the hash.
+    ///
+    /// # Arguments
+    /// * `bytes` - Largest file length (as the walk saw it) that still
+    ///   contributes; longer files are neither recorded nor opened.
+    ///   `hash_directory_summary()` reports how many were left out.
+    ///
+    /// # Project Context
+    /// A large attachment being streamed in changes size on every poll
+    /// and, with `include_contents(true)`, dominates hashing time. Its
+    /// message file still changes the hash when it arrives.
+    pub fn ignore_files_larger_than(mut self, bytes: u64) -> Self {
+        self.max_file_len = Some(bytes);
+        self
+    }
+}
+
+// ============================================================================
+// HASHING FUNCTIONS
+// ============================================================================
+
+/// One entry's contribution to a directory hash.
+///
+/// Collected for every walked entry, then sorted by `relative_path` so the
+/// final hash does not depend on `read_dir` order.
+struct HashRecord {
+    /// Root-relative path, components joined by `/` (platform-independent).
+    relative_path: Vec<u8>,
+
+    /// Whether the entry is a directory (directories contribute name only).
+    is_dir: bool,
+
+    /// Logical length in bytes (files only).
+    len: u64,
+
+    /// Modification time as (seconds, nanoseconds) relative to the epoch.
+    modified: Option<(i64, u32)>,
+
+    /// Content hash, when `include_contents` is on and the read succeeded.
+    content_hash: Option<u64>,
+
+    /// Permission bits (hashed only when `include_permissions` is on).
+    permissions: u32,
+
+    /// Link target text (`SymlinkHashing::TargetPath` only); replaces the
+    /// type, size and mtime.
+    link_target: Option<Vec<u8>>,
+}
+
+/// Result of `hash_directory_summary()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HashSummary {
+    /// Fingerprint of the directory contents (what `hash_directory()`
+    /// returns).
+    pub hash: u64,
+
+    /// Regular files left out by `HashConfig::ignore_files_larger_than`;
+    /// non-zero means the hash does not cover every file.
+    pub excluded_large_files: usize,
+}
+
+/// Compute a change-detection hash for a directory tree.
+///
+/// Walks `path` with the configured `WalkConfig` and hashes, for every
+/// yielded entry, its root-relative path, type, size and modification
+/// time (plus contents when `include_contents` is on).
+///
+/// ## Project Context
+/// Replaces the inline Demo 6 pattern the application polls with: if the
+/// returned value differs from the previous poll, the channel is reloaded.
+///
+/// # Arguments
+/// * `path` - Directory to hash
+/// * `config` - Hash configuration
+///
+/// # Returns
+/// * `Ok(hash)` - Fingerprint of the directory contents
+/// * `Err(WalkError)` - A walk error, when the walk config has
+///   `continue_on_error(false)`; or a content read failure in that mode.
+///   With `continue_on_error(true)` unreadable entries are skipped and an
+///   unreadable file's contents are replaced by a fixed marker.
+pub fn hash_directory(path: &Path, config: &HashConfig) -> Result<u64, WalkError> {
+    hash_directory_summary(path, config).map(|summary| summary.hash)
+}
+
+/// Compute a directory hash (as `hash_directory()`) and report how much
+/// of the tree it covers.
+///
+/// # Arguments
+/// * `path` - Directory to hash
+/// * `config` - Hash configuration
+///
+/// # Returns
+/// * `Ok(HashSummary)` - The hash and the number of excluded files
+/// * `Err(WalkError)` - As for `hash_directory()`
+///
+/// # Project Context
+/// Operators limiting hashing by size need to know when that limit
+/// applies: a channel whose files all exceed it hashes like an empty one.
+pub fn hash_directory_summary(path: &Path, config: &HashConfig) -> Result<HashSummary, WalkError> {
+    let mut records: Vec<HashRecord> = Vec::new();
+    let mut excluded_large_files: usize = 0;
+
+    for entry_result in DirWalker::new(path, config.walk_config.clone()) {
+        let entry = entry_result?;
+        if entry.is_symlink() && config.symlink_hashing == SymlinkHashing::Ignore {
+            continue;
+        }
+        if !config.include_attachments {
+            let relative = strip_root(entry.path(), path).unwrap_or(entry.path());
+            if path_in_attachments_dir(relative) {
+                continue;
+            }
+        }
+        if let Some(FileFilter(filter)) = &config.file_filter
+            && !entry.is_dir()
+            && !filter.matches(&entry)
+        {
+            continue;
+        }
+        if let Some(max_file_len) = config.max_file_len
+            && entry.is_file()
+            && entry.len() > max_file_len
+        {
+            excluded_large_files = excluded_large_files.saturating_add(1);
+            continue;
+        }
+        records.push(build_hash_record(path, &entry, config)?);
+    }
+
+    // Order-independent: read_dir order varies between filesystems
+    records.sort_unstable_by(|a, b| a.relative_path.cmp(&b.relative_path));
+
+    let mut hasher = StableHasher::new();
+    hasher.write_usize(records.len());
+
+    for record in &records {
+        hasher.write_usize(record.relative_path.len());
+        hasher.write(&record.relative_path);
+
+        // Type byte: 0 file, 1 directory, 2 link target
+        if let Some(target) = &record.link_target {
+            hasher.write_u8(2);
+            hasher.write_usize(target.len());
+            hasher.write(target);
+            continue;
+        }
+        hasher.write_u8(u8::from(record.is_dir));
+
+        if config.include_permissions {
+            hasher.write_u32(record.permissions);
+        }
+
+        if record.is_dir {
+            continue;
+        }
+
+        hasher.write_u64(record.len);
+        match record.modified {
+            Some((secs, nanos)) => {
+                hasher.write_u8(1);
+                hasher.write_i64(secs);
+                hasher.write_u32(nanos);
+            }
+            None => hasher.write_u8(0),
+        }
+
+        if config.include_contents {
+            match record.content_hash {
+                Some(content_hash) => {
+                    hasher.write_u8(1);
+                    hasher.write_u64(content_hash);
+                }
+                None => hasher.write_u8(0),
+            }
+        }
+    }
+
+    Ok(HashSummary {
+        hash: hasher.finish(),
+        excluded_large_files,
+    })
+}
+
+/// Build the hash record for one walked entry.
+///
+/// # Arguments
+/// * `root` - Walk root (for the relative path)
+/// * `entry` - Entry yielded by the walk
+/// * `config` - Hash configuration (content and error mode)
+fn build_hash_record(
+    root: &Path,
+    entry: &DirEntry,
+    config: &HashConfig,
+) -> Result<HashRecord, WalkError> {
+    let relative = strip_root(entry.path(), root).unwrap_or(entry.path());
+
+    let mut relative_path: Vec<u8> = Vec::new();
+    for (index, component) in relative.components().enumerate() {
+        if index > 0 {
+            relative_path.push(b'/');
+        }
+        relative_path.extend_from_slice(component.as_os_str().as_encoded_bytes());
+    }
+
+    let link_mode = if entry.is_symlink() {
+        config.symlink_hashing
+    } else {
+        SymlinkHashing::AsWalked
+    };
+    match link_mode {
+        SymlinkHashing::TargetPath => {
+            let target = match fs::read_link(entry.path()) {
+                Ok(target) => target.into_os_string().into_encoded_bytes(),
+                Err(_e) => {
+                    #[cfg(debug_assertions)]
+                    eprintln!("DWEM: symlink target text unreadable: {}", _e);
+                    if !config.walk_config.continue_on_error {
+                        return Err(WalkError::EntryMetadata);
+                    }
+                    Vec::new()
+                }
+            };
+            return Ok(HashRecord {
+                relative_path,
+                is_dir: false,
+                len: 0,
+                modified: None,
+                content_hash: None,
+                permissions: 0,
+                link_target: Some(target),
+            });
+        }
+        SymlinkHashing::TargetMetadata => {
+            let (is_dir, len, modified, permissions) = match fs::metadata(entry.path()) {
+                Ok(target) => (
+                    target.is_dir(),
+                    target.len(),
+                    target
+                        .modified()
+                        .ok()
+                        .and_then(|time| system_time_parts(time, config.mtime_granularity)),
+                    permission_bits(&target),
+                ),
+                Err(_e) => {
+                    #[cfg(debug_assertions)]
+                    eprintln!("DWSL: symlink target unreadable for hashing: {}", _e);
+                    if !config.walk_config.continue_on_error {
+                        return Err(WalkError::EntryMetadata);
+                    }
+                    (false, 0, None, 0)
+                }
+            };
+            return Ok(HashRecord {
+                relative_path,
+                is_dir,
+                len,
+                modified,
+                content_hash: None,
+                permissions,
+                link_target: None,
+            });
+        }
+        SymlinkHashing::AsWalked | SymlinkHashing::Ignore => {}
+    }
+
+    let content_hash = if config.include_contents && entry.is_file() {
+        let opened = if config.verify_identity {
+            entry.open_verified()
+        } else {
+            open_regular_file(entry.path())
+        };
+
+        match opened.and_then(hash_open_file) {
+            Ok(content_hash) => Some(content_hash),
+            Err(e) => {
+                if config.walk_config.continue_on_error {
+                    None
+                } else {
+                    return Err(e);
+                }
+            }
+        }
+    } else {
+        None
+    };
+
+    Ok(HashRecord {
+        relative_path,
+        is_dir: entry.is_dir(),
+        len: entry.len(),
+        modified: entry
+            .metadata
+            .modified()
+            .ok()
+            .and_then(|time| system_time_parts(time, config.mtime_granularity)),
+        content_hash,
+        permissions: entry.permission_bits(),
+        link_target: None,
+    })
+}
+
+/// Hash the full contents of a regular file.
+///
+/// Opens the file through `open_regular_file()` (special files refused)
+/// and reads it in fixed-size stack-buffer chunks; the file is never
+/// loaded into memory whole.
+///
+/// ## Bounds
+/// The read loop is bounded by the size the open handle reports plus one
+/// extra chunk; a file still growing while it is hashed is hashed up to
+/// that bound. Interrupted reads are retried within the same bound.
+///
+/// # Arguments
+/// * `path` - File to hash
+///
+/// # Returns
+/// * `Ok(hash)` - `StableHasher` hash of the file's bytes
+/// * `Err(WalkError::SpecialFile)` - Not a regular file
+/// * `Err(WalkError)` - Open or read failure
+pub fn hash_file_contents(path: &Path) -> Result<u64, WalkError> {
+    hash_open_file(open_regular_file(path)?)
+}
+
+/// Hash the contents of an already-opened (and already-checked) file.
+///
+/// # Arguments
+/// * `file` - Handle from `open_regular_file()` or `DirEntry::open_verified()`
+fn hash_open_file(mut file: std::fs::File) -> Result<u64, WalkError> {
+    let expected_len = match file.metadata() {
+        Ok(m) => m.len(),
+        Err(_e) => {
+            #[cfg(debug_assertions)]
+            eprintln!("DWEM: hash_file_contents handle stat failed: {}", _e);
+            return Err(WalkError::EntryMetadata);
+        }
+    };
+
+    let chunk_size = CONTENT_READ_BUFFER_SIZE as u64;
+    let max_reads = (expected_len / chunk_size).saturating_add(2);
+
+    let mut buffer = [0u8; CONTENT_READ_BUFFER_SIZE];
+    let mut hasher = StableHasher::new();
+    let mut reads_done: u64 = 0;
+
+    while reads_done < max_reads {
+        reads_done += 1;
+
+        match file.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(bytes_read) => hasher.write(&buffer[..bytes_read]),
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(_e) => {
+                #[cfg(debug_assertions)]
+                eprintln!("DWIO: hash_file_contents read failed: {}", _e);
+                return Err(WalkError::IoError);
+            }
+        }
+    }
+
+    Ok(hasher.finish())
+}
+
+/// Split a `SystemTime` into (seconds, nanoseconds) relative to the epoch,
+/// truncated toward the epoch to a multiple of `granularity`.
+///
+/// Pre-epoch times produce negative seconds (nanoseconds are then the
+/// sub-second part toward the epoch). Returns `None` only for values that
+/// do not fit in `i64` seconds.
+fn system_time_parts(time: SystemTime, granularity: Duration) -> Option<(i64, u32)> {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(after) => {
+            let after = truncate_duration(after, granularity)?;
+            Some((i64::try_from(after.as_secs()).ok()?, after.subsec_nanos()))
+        }
+        Err(before) => {
+            let before = truncate_duration(before.duration(), granularity)?;
+            let secs = i64::try_from(before.as_secs()).ok()?;
+            Some((secs.checked_neg()?, before.subsec_nanos()))
+        }
+    }
+}
+
+/// Round `duration` down to a multiple of `granularity` (unchanged when
+/// `granularity` is zero).
+fn truncate_duration(duration: Duration, granularity: Duration) -> Option<Duration> {
+    let step = granularity.as_nanos();
+    if step == 0 {
+        return Some(duration);
+    }
+    let nanos = duration.as_nanos();
+    let truncated = nanos - nanos % step;
+    let secs = u64::try_from(truncated / NANOS_PER_SEC).ok()?;
+    // Remainder of a division by 10^9 always fits in u32
+    let subsec = u32::try_from(truncated % NANOS_PER_SEC).ok()?;
+    Some(Duration::new(secs, subsec))
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+
+    /// Helper: unique test directory path (caller cleans up).
+    fn test_dir(name: &str) -> PathBuf {
+        crate::dir_walk_module::testing::unique_test_dir(&format!("hash_{}", name))
+    }
+
+    /// Helper: safely remove test directory, ignoring errors.
+    fn cleanup(path: &Path) {
+        let _ = fs::remove_dir_all(path);
+    }
+
+    /// Test: the hash is repeatable, independent of the root location, and
+    /// changes when a file's contents change (with include_contents).
+    #[test]
+    fn test_hash_directory_stable_and_detects_change() {
+        let dir_a = test_dir("stable_a");
+        let dir_b = test_dir("stable_b");
+        cleanup(&dir_a);
+        cleanup(&dir_b);
+
+        for dir in [&dir_a, &dir_b] {
+            assert!(
+                fs::create_dir_all(dir.join("sub")).is_ok()
+                    && fs::write(dir.join("1__msg.toml"), b"aaaa").is_ok()
+                    && fs::write(dir.join("sub").join("2__msg.toml"), b"bbbb").is_ok(),
+                "test_hash_directory_stable: failed to create tree"
+            );
+        }
+
+        // Metadata hashing includes mtime, which differs between the two
+        // trees; content-only comparison across roots is checked below via
+        // hash_file_contents.
+        let config = HashConfig::new().include_contents(true);
+        let first = hash_directory(&dir_a, &config);
+        let second = hash_directory(&dir_a, &config);
+        assert!(
+            first.is_ok() && second.is_ok(),
+            "test_hash_directory_stable: hashing failed"
+        );
+        assert_eq!(
+            first.ok(),
+            second.ok(),
+            "test_hash_directory_stable: repeated hash differs"
+        );
+
+        assert_eq!(
+            hash_file_contents(&dir_a.join("1__msg.toml")).ok(),
+            hash_file_contents(&dir_b.join("1__msg.toml")).ok(),
+            "test_hash_directory_stable: identical contents hash differently"
+        );
+
+        // Same length, different bytes: only content hashing can see it
+        let before = hash_directory(&dir_a, &config).ok();
+        assert!(
+            fs::write(dir_a.join("1__msg.toml"), b"zzzz").is_ok(),
+            "test_hash_directory_stable: failed to rewrite file"
+        );
+        let after = hash_directory(&dir_a, &config).ok();
+        assert_ne!(
+            before, after,
+            "test_hash_directory_stable: content change not detected"
+        );
+
+        cleanup(&dir_a);
+        cleanup(&dir_b);
+    }
+
+    /// Test: with a file filter, churn in filtered-out files leaves the
+    /// hash unchanged while a matching file still changes it.
+    #[test]
+    fn test_file_filter_ignores_churn() {
+        use crate::dir_walk_module::filters::{any_of, by_extension};
+
+        let dir = test_dir("file_filter");
+        cleanup(&dir);
+        assert!(
+            fs::create_dir_all(dir.join("cache")).is_ok()
+                && fs::write(dir.join("1__msg.toml"), b"a").is_ok()
+                && fs::write(dir.join("2__msg.gpgtoml"), b"b").is_ok()
+                && fs::write(dir.join("app.log"), b"l").is_ok()
+                && fs::write(dir.join("cache").join("index.bin"), b"c").is_ok(),
+            "test_file_filter: failed to create tree"
+        );
+
+        let filtered =
+            HashConfig::new().file_filter(any_of(by_extension("toml"), by_extension("gpgtoml")));
+        let unfiltered = HashConfig::new();
+        let before = hash_directory(&dir, &filtered).ok();
+        let before_all = hash_directory(&dir, &unfiltered).ok();
+        assert!(before.is_some(), "test_file_filter: hashing failed");
+
+        assert!(
+            fs::write(dir.join("app.log"), b"longer log line").is_ok()
+                && fs::write(dir.join("cache").join("index.bin"), b"rebuilt").is_ok(),
+            "test_file_filter: failed to write churn"
+        );
+        assert_eq!(
+            hash_directory(&dir, &filtered).ok(),
+            before,
+            "test_file_filter: log/cache churn changed the filtered hash"
+        );
+        assert_ne!(
+            hash_directory(&dir, &unfiltered).ok(),
+            before_all,
+            "test_file_filter: unfiltered hash should see the churn"
+        );
+
+        assert!(
+            fs::write(dir.join("2__msg.gpgtoml"), b"edited").is_ok(),
+            "test_file_filter: failed to edit message"
+        );
+        assert_ne!(
+            hash_directory(&dir, &filtered).ok(),
+            before,
+            "test_file_filter: message change not detected"
+        );
+
+        cleanup(&dir);
+    }
+
+    /// Test: files over the size limit are left out of the hash and
+    /// counted, so a growing attachment does not change it.
+    #[test]
+    fn test_ignore_files_larger_than() {
+        let dir = test_dir("size_limit");
+        cleanup(&dir);
+        assert!(
+            fs::create_dir_all(&dir).is_ok()
+                && fs::write(dir.join("1__msg.toml"), b"small").is_ok()
+                && fs::write(dir.join("video.bin"), [0_u8; 64]).is_ok(),
+            "test_ignore_files_larger_than: failed to create tree"
+        );
+
+        let config = HashConfig::new()
+            .include_contents(true)
+            .ignore_files_larger_than(16);
+        let before = hash_directory_summary(&dir, &config);
+        assert_eq!(
+            before.map(|s| s.excluded_large_files).ok(),
+            Some(1),
+            "test_ignore_files_larger_than: large file not counted"
+        );
+
+        // Still streaming in: grows, but stays excluded
+        assert!(
+            fs::write(dir.join("video.bin"), [1_u8; 128]).is_ok(),
+            "test_ignore_files_larger_than: failed to grow file"
+        );
+        let after = hash_directory_summary(&dir, &config);
+        assert_eq!(
+            after.map(|s| s.hash).ok(),
+            before.map(|s| s.hash).ok(),
+            "test_ignore_files_larger_than: excluded file changed the hash"
+        );
+
+        // A file exactly at the limit still contributes
+        assert_eq!(
+            hash_directory_summary(&dir, &HashConfig::new().ignore_files_larger_than(128))
+                .map(|s| s.excluded_large_files)
+                .ok(),
+            Some(0),
+            "test_ignore_files_larger_than: limit must be inclusive"
+        );
+
+        cleanup(&dir);
+    }
+
+    /// Test: mtimes differing below the granularity hash alike; a zero
+    /// granularity tells them apart.
+    #[test]
+    fn test_mtime_granularity() {
+        let dir_a = test_dir("granularity_a");
+        let dir_b = test_dir("granularity_b");
+        let base = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let mut created = true;
+        for (dir, offset_ms) in [(&dir_a, 300), (&dir_b, 1_900)] {
+            cleanup(dir);
+            let path = dir.join("1__msg.toml");
+            created &= fs::create_dir_all(dir).is_ok()
+                && fs::write(&path, b"same").is_ok()
+                && fs::File::options()
+                    .write(true)
+                    .open(&path)
+                    .and_then(|file| file.set_modified(base + Duration::from_millis(offset_ms)))
+                    .is_ok();
+        }
+        assert!(created, "test_mtime_granularity: failed to create trees");
+
+        let hashes = |granularity: Duration| {
+            let config = HashConfig::new().mtime_granularity(granularity);
+            (
+                hash_directory(&dir_a, &config).ok(),
+                hash_directory(&dir_b, &config).ok(),
+            )
+        };
+        let (a, b) = hashes(Duration::from_secs(2));
+        assert!(
+            a.is_some() && a == b,
+            "test_mtime_granularity: FAT-style 2 s granularity"
+        );
+        let (a, b) = hashes(Duration::from_secs(1));
+        assert!(
+            a != b,
+            "test_mtime_granularity: 0.3 s vs 1.9 s differ at 1 s"
+        );
+        let (a, b) = hashes(Duration::ZERO);
+        assert!(a != b, "test_mtime_granularity: exact mtimes differ");
+
+        cleanup(&dir_a);
+        cleanup(&dir_b);
+    }
+
+    /// Test: a chmod-only change is invisible by default and changes the
+    /// hash with include_permissions(true).
+    #[cfg(unix)]
+    #[test]
+    fn test_include_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = test_dir("permissions");
+        cleanup(&dir);
+        let path = dir.join("1__msg.toml");
+        assert!(
+            fs::create_dir_all(&dir).is_ok()
+                && fs::write(&path, b"msg").is_ok()
+                && fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).is_ok(),
+            "test_include_permissions: failed to create file"
+        );
+
+        let excluded = HashConfig::new();
+        let included = HashConfig::new().include_permissions(true);
+        let before = (
+            hash_directory(&dir, &excluded).ok(),
+            hash_directory(&dir, &included).ok(),
+        );
+        assert!(
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o600)).is_ok(),
+            "test_include_permissions: chmod failed"
+        );
+        let after = (
+            hash_directory(&dir, &excluded).ok(),
+            hash_directory(&dir, &included).ok(),
+        );
+
+        assert!(
+            before.0.is_some() && before.0 == after.0,
+            "test_include_permissions: excluded by default"
+        );
+        assert!(
+            before.1.is_some() && before.1 != after.1,
+            "test_include_permissions: chmod detected when included"
+        );
+
+        cleanup(&dir);
+    }
+
+    /// Test: symlink_hashing decides what a link contributes: its target
+    /// text, its target's metadata, or nothing.
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_hashing_modes() {
+        use std::os::unix::fs::symlink;
+
+        let dir = test_dir("symlink_modes");
+        let outside = test_dir("symlink_modes_target");
+        cleanup(&dir);
+        cleanup(&outside);
+        let link = dir.join("link");
+        let target = outside.join("target.toml");
+        assert!(
+            fs::create_dir_all(&dir).is_ok()
+                && fs::create_dir_all(&outside).is_ok()
+                && fs::write(dir.join("1__msg.toml"), b"aaaa").is_ok()
+                && fs::write(&target, b"t").is_ok(),
+            "test_symlink_hashing_modes: failed to create tree"
+        );
+        let hash_with = |mode: SymlinkHashing| {
+            hash_directory(&dir, &HashConfig::new().symlink_hashing(mode)).ok()
+        };
+        let relink = |to: &Path| {
+            let _ = fs::remove_file(&link);
+            symlink(to, &link).is_ok()
+        };
+
+        let ignored = hash_with(SymlinkHashing::Ignore);
+        assert!(relink(&target), "test_symlink_hashing_modes: symlink");
+        let by_path = hash_with(SymlinkHashing::TargetPath);
+        let by_target = hash_with(SymlinkHashing::TargetMetadata);
+        assert!(
+            ignored.is_some() && hash_with(SymlinkHashing::Ignore) == ignored,
+            "test_symlink_hashing_modes: ignored link contributes nothing"
+        );
+
+        // Same text, new link: only the link's own mtime changed
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        assert!(relink(&target), "test_symlink_hashing_modes: relink");
+        assert!(
+            by_path.is_some()
+                && hash_with(SymlinkHashing::TargetPath) == by_path
+                && hash_with(SymlinkHashing::TargetMetadata) == by_target,
+            "test_symlink_hashing_modes: recreated link hashes the same"
+        );
+
+        assert!(
+            fs::write(&target, b"longer").is_ok(),
+            "test_symlink_hashing_modes: failed to grow target"
+        );
+        assert!(
+            hash_with(SymlinkHashing::TargetMetadata) != by_target
+                && hash_with(SymlinkHashing::TargetPath) == by_path,
+            "test_symlink_hashing_modes: target metadata vs target text"
+        );
+
+        assert!(
+            relink(&outside.join("missing.toml")),
+            "test_symlink_hashing_modes: retarget"
+        );
+        assert!(
+            hash_with(SymlinkHashing::TargetPath) != by_path
+                && hash_with(SymlinkHashing::TargetMetadata).is_some(),
+            "test_symlink_hashing_modes: retargeted (now broken) link"
+        );
+
+        cleanup(&dir);
+        cleanup(&outside);
+    }
+
+    /// Test: special files are refused by open_regular_file() and by the
+    /// content hasher — a device node (/dev/null) and a Unix socket.
+    #[cfg(unix)]
+    #[test]
+    fn test_special_files_refused_for_content_access() {
+        const {
+            assert!(
+                HashConfig::REFUSES_SPECIAL_FILES,
+                "test_special_files_refused: guarantee constant must be true"
+            )
+        };
+
+        let device_result = hash_file_contents(Path::new("/dev/null"));
+        assert!(
+            matches!(device_result, Err(WalkError::SpecialFile)),
+            "test_special_files_refused: device node must be refused"
+        );
+
+        let dir = test_dir("special_files");
+        cleanup(&dir);
+        assert!(
+            fs::create_dir_all(&dir).is_ok(),
+            "test_special_files_refused: failed to create base directory"
+        );
+
+        let socket_path = dir.join("channel.sock");
+        let listener = std::os::unix::net::UnixListener::bind(&socket_path);
+        if listener.is_ok() {
+            assert!(
+                matches!(open_regular_file(&socket_path), Err(WalkError::SpecialFile)),
+                "test_special_files_refused: socket must be refused"
+            );
+
+            // Hashing the directory with contents skips the socket (the
+            // walker does not classify it as a file) and does not hang
+            let hashed = hash_directory(&dir, &HashConfig::new().include_contents(true));
+            assert!(
+                hashed.is_ok(),
+                "test_special_files_refused: directory with socket should hash"
+            );
+        }
+
+        let destination = dir.join("copy_of_null");
+        assert!(
+            matches!(
+                crate::dir_walk_module::copy_regular_file(Path::new("/dev/null"), &destination),
+                Err(WalkError::SpecialFile)
+            ),
+            "test_special_files_refused: copy must refuse device node"
+        );
+
+        drop(listener);
+        cleanup(&dir);
+    }
+}
@@ -0,0 +1,1400 @@
+//! # Message File Conventions (`dir_walk_module::messages`)
+//!
+//! Helpers for the channel message files the walker discovers:
+//! `.toml` messages, `.gpgtoml` encrypted messages, and their naming
+//! rules.
+//!
+//! ## Project Context
+//! A channel directory holds one file per message. Encrypted messages use
+//! the `.gpgtoml` extension; a file with that name that is not actually
+//! OpenPGP data is a misconfiguration (plaintext written under the wrong
+//! name, or a broken export) that scanning should flag without holding
+//! any key material. Everything here reads at most a few bytes per file
+//! and never decrypts.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::io::Read;
+use std::ops::{ControlFlow, RangeInclusive};
+use std::path::{Path, PathBuf};
+
+use super::mutate::{
+    AuditLog, Mode, RollbackPlan, RollbackStep, rename_two_phase, try_lock_scan,
+    write_rollback_plan,
+};
+use super::{
+    DirEntry, SCAN_LOCK_FILE_NAME, WalkConfig, WalkError, WalkStats, open_regular_file, walk_with,
+};
+
+/// Bytes read from a file to classify it.
+const CLASSIFY_PROBE_LEN: usize = 64;
+
+/// First line of an ASCII-armored OpenPGP message.
+const ARMOR_MESSAGE_HEADER: &[u8] = b"-----BEGIN PGP MESSAGE-----";
+
+/// Separator between a message file's numeric prefix and its name.
+const PREFIX_SEPARATOR: &str = "__";
+
+/// Default cap on renames in one `renumber_messages()` call.
+const DEFAULT_MAX_RENAMES: usize = 10_000;
+
+// ============================================================================
+// ENCRYPTION CLASSIFICATION
+// ============================================================================
+
+/// What the first bytes of a (supposedly) encrypted message file contain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EncryptionKind {
+    /// Binary OpenPGP: the first byte is a packet header for an
+    /// encrypted-message packet (public/symmetric-key session key,
+    /// symmetrically encrypted data, or a marker packet).
+    OpenPgpBinary,
+
+    /// ASCII-armored OpenPGP (`-----BEGIN PGP MESSAGE-----`).
+    OpenPgpArmored,
+
+    /// Readable text that is not armored OpenPGP — typically a plaintext
+    /// message saved under an encrypted name.
+    Plaintext,
+
+    /// Zero-length file.
+    Empty,
+
+    /// Binary data that is not an OpenPGP encrypted message.
+    Unknown,
+}
+
+impl EncryptionKind {
+    /// Whether the file looks like an encrypted OpenPGP message.
+    pub fn is_encrypted(self) -> bool {
+        matches!(
+            self,
+            EncryptionKind::OpenPgpBinary | EncryptionKind::OpenPgpArmored
+        )
+    }
+}
+
+/// Classify a `.gpgtoml` candidate from its first bytes, without
+/// decrypting anything.
+///
+/// Reads at most 64 bytes, through `open_regular_file()` (special files
+/// are refused).
+///
+/// # Arguments
+/// * `path` - File to inspect
+///
+/// # Returns
+/// * `Ok(EncryptionKind)` - Classification
+/// * `Err(WalkError::SpecialFile)` - Not a regular file
+/// * `Err(WalkError)` - Open or read failed
+pub fn classify_encrypted(path: &Path) -> Result<EncryptionKind, WalkError> {
+    let file = open_regular_file(path)?;
+
+    let mut probe: Vec<u8> = Vec::with_capacity(CLASSIFY_PROBE_LEN);
+    if let Err(_e) = file.take(CLASSIFY_PROBE_LEN as u64).read_to_end(&mut probe) {
+        #[cfg(debug_assertions)]
+        eprintln!("DWIO: classify_encrypted read failed: {}", _e);
+        return Err(WalkError::IoError);
+    }
+
+    Ok(classify_bytes(&probe))
+}
+
+/// Classify the leading bytes of a file (see `classify_encrypted`).
+fn classify_bytes(probe: &[u8]) -> EncryptionKind {
+    let first = match probe.first() {
+        Some(byte) => *byte,
+        None => return EncryptionKind::Empty,
+    };
+
+    // RFC 4880 §4.2: bit 7 always set in a packet header
+    if first & 0x80 != 0 {
+        let tag = if first & 0x40 != 0 {
+            first & 0x3F // new format
+        } else {
+            (first >> 2) & 0x0F // old format
+        };
+        // 1 PKESK, 3 SKESK, 9 SED, 10 marker, 18 SEIPD, 20 AEAD
+        if matches!(tag, 1 | 3 | 9 | 10 | 18 | 20) {
+            return EncryptionKind::OpenPgpBinary;
+        }
+        // A UTF-8 lead byte also has bit 7 set; fall through to the text
+        // check for those
+    }
+
+    // Armor may follow a UTF-8 BOM and blank lines
+    let text = probe.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(probe);
+    let start = text
+        .iter()
+        .position(|b| !b.is_ascii_whitespace())
+        .unwrap_or(text.len());
+    if text[start..].starts_with(ARMOR_MESSAGE_HEADER) {
+        return EncryptionKind::OpenPgpArmored;
+    }
+
+    if looks_like_text(probe) {
+        EncryptionKind::Plaintext
+    } else {
+        EncryptionKind::Unknown
+    }
+}
+
+/// Whether `probe` is plausibly text: valid UTF-8 (allowing a code point
+/// cut off at the end of the probe) without control bytes other than
+/// whitespace.
+fn looks_like_text(probe: &[u8]) -> bool {
+    let valid_len = match std::str::from_utf8(probe) {
+        Ok(_) => probe.len(),
+        // Only an incomplete final sequence is tolerated
+        Err(e) if e.error_len().is_none() => e.valid_up_to(),
+        Err(_) => return false,
+    };
+
+    String::from_utf8_lossy(&probe[..valid_len])
+        .chars()
+        .all(|c| !c.is_control() || c == '\n' || c == '\r' || c == '\t')
+}
+
+// ============================================================================
+// NUMERIC PREFIX
+// ============================================================================
+
+/// Why a message file name has no valid numeric prefix.
+///
+/// Unit variants with prefix codes, like `WalkError`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrefixError {
+    /// MPMS: No `__` separator in the name
+    MissingSeparator,
+
+    /// MPEM: Nothing before the `__` separator
+    Empty,
+
+    /// MPND: Prefix contains something other than ASCII `0`-`9`
+    /// (signs, spaces, non-ASCII digits)
+    NonDigit,
+
+    /// MPLZ: Prefix has a leading zero (`007__x`); only `0` itself may
+    /// start with one
+    LeadingZero,
+
+    /// MPOF: Prefix does not fit in a `u64`
+    Overflow,
+}
+
+impl fmt::Display for PrefixError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PrefixError::MissingSeparator => write!(f, "MPMS: missing __ separator"),
+            PrefixError::Empty => write!(f, "MPEM: empty numeric prefix"),
+            PrefixError::NonDigit => write!(f, "MPND: prefix is not ASCII digits"),
+            PrefixError::LeadingZero => write!(f, "MPLZ: prefix has a leading zero"),
+            PrefixError::Overflow => write!(f, "MPOF: prefix does not fit in u64"),
+        }
+    }
+}
+
+impl std::error::Error for PrefixError {}
+
+/// Parse the number before the first `__` in a message file name
+/// (`12__hello.toml` → `12`).
+///
+/// Stricter than `str::parse::<u64>()`: a `+` sign is rejected, and so
+/// are leading zeros, so every number has exactly one spelling and two
+/// files cannot claim the same slot with `7__` and `07__`.
+///
+/// # Arguments
+/// * `name` - File name (not a path)
+///
+/// # Returns
+/// * `Ok(u64)` - The prefix
+/// * `Err(PrefixError)` - Why the name is malformed
+pub fn parse_numeric_prefix(name: &str) -> Result<u64, PrefixError> {
+    let (prefix, _) = name
+        .split_once(PREFIX_SEPARATOR)
+        .ok_or(PrefixError::MissingSeparator)?;
+
+    if prefix.is_empty() {
+        return Err(PrefixError::Empty);
+    }
+    if !prefix.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(PrefixError::NonDigit);
+    }
+    if prefix.len() > 1 && prefix.starts_with('0') {
+        return Err(PrefixError::LeadingZero);
+    }
+
+    // Only ASCII digits remain, so the only possible failure is overflow
+    prefix.parse::<u64>().map_err(|_| PrefixError::Overflow)
+}
+
+// ============================================================================
+// MESSAGE LISTING
+// ============================================================================
+
+/// Message files of one walk, in load order, with the metadata files and
+/// malformed names set aside.
+#[derive(Debug, Clone)]
+pub struct MessageListing {
+    /// Message files, sorted by numeric prefix (see
+    /// `sort_by_numeric_prefix`).
+    pub messages: Vec<DirEntry>,
+
+    /// Files matching `WalkConfig::metadata_file_names`, in walk order.
+    pub metadata: Vec<DirEntry>,
+
+    /// Files whose name has no valid numeric prefix, with the reason,
+    /// in walk order. Reported to operators rather than loaded.
+    pub malformed: Vec<(DirEntry, PrefixError)>,
+
+    /// Counters of the walk (temp / empty files skipped, errors, ...).
+    pub stats: WalkStats,
+}
+
+/// Collect the message files under `path` and sort them for loading.
+///
+/// Only regular files are collected. Files named in the config's
+/// `metadata_file_names` (default `0.toml`) go to `metadata`, and files
+/// rejected by `parse_numeric_prefix` go to `malformed`, instead of being
+/// sorted in with the messages. A name that is not valid UTF-8 is
+/// malformed with `PrefixError::NonDigit`. Scan lock files
+/// (`SCAN_LOCK_FILE_NAME`) are left out.
+///
+/// # Arguments
+/// * `path` - Channel directory
+/// * `config` - Walk configuration, usually `WalkConfig::message_loading()`
+///
+/// # Returns
+/// * `Ok(MessageListing)` - Messages in load order, metadata, walk stats
+/// * `Err(WalkError)` - Fatal walk error (strict mode only)
+pub fn list_messages(path: &Path, config: WalkConfig) -> Result<MessageListing, WalkError> {
+    let metadata_names = config.metadata_file_names.clone();
+    let mut messages: Vec<DirEntry> = Vec::new();
+    let mut metadata: Vec<DirEntry> = Vec::new();
+    let mut malformed: Vec<(DirEntry, PrefixError)> = Vec::new();
+
+    let stats = walk_with(path, config, |entry| {
+        if entry.is_file() && entry.file_name() != Some(SCAN_LOCK_FILE_NAME) {
+            let is_metadata = entry
+                .file_name()
+                .is_some_and(|name| metadata_names.iter().any(|meta| meta == name));
+            if is_metadata {
+                metadata.push(entry.clone());
+            } else if let Err(e) = entry_prefix(entry) {
+                malformed.push((entry.clone(), e));
+            } else {
+                messages.push(entry.clone());
+            }
+        }
+        ControlFlow::Continue(())
+    })?;
+
+    sort_by_numeric_prefix(&mut messages);
+
+    Ok(MessageListing {
+        messages,
+        metadata,
+        malformed,
+        stats,
+    })
+}
+
+/// Sort message entries by `parse_numeric_prefix` of their names
+/// (`2__b.toml` before `10__a.toml`).
+///
+/// Malformed names sort after all valid ones (`list_messages()` reports
+/// them separately instead). The sort is stable, so entries with equal
+/// keys keep their relative order.
+pub fn sort_by_numeric_prefix(entries: &mut [DirEntry]) {
+    // Ok(_) orders before Err(_)
+    entries.sort_by_key(|entry| entry_prefix(entry).map_err(|_| ()));
+}
+
+// ============================================================================
+// PREFIX COLLISIONS
+// ============================================================================
+
+/// Message files that share one numeric prefix.
+#[derive(Debug, Clone)]
+pub struct PrefixCollision {
+    /// The shared prefix.
+    pub prefix: u64,
+
+    /// All files with that prefix (two or more), in walk order.
+    pub entries: Vec<DirEntry>,
+}
+
+impl MessageListing {
+    /// Groups of messages that share a numeric prefix, by ascending
+    /// prefix.
+    ///
+    /// Each group lists every file involved, so the application can
+    /// renumber them; an empty result means the order is unambiguous.
+    pub fn prefix_collisions(&self) -> Vec<PrefixCollision> {
+        let mut collisions: Vec<PrefixCollision> = Vec::new();
+        let mut group: Vec<DirEntry> = Vec::new();
+        let mut group_prefix: Option<u64> = None;
+
+        // `messages` is sorted by prefix, so equal prefixes are adjacent
+        for entry in &self.messages {
+            let prefix = entry_prefix(entry).ok();
+            if prefix != group_prefix {
+                push_collision(&mut collisions, group_prefix, &mut group);
+                group_prefix = prefix;
+            }
+            group.push(entry.clone());
+        }
+        push_collision(&mut collisions, group_prefix, &mut group);
+
+        collisions
+    }
+}
+
+/// Scan a channel directory for duplicate numeric prefixes (two
+/// `7__*.toml` files), which make the chronological order ambiguous.
+///
+/// Metadata files and malformed names are not considered (see
+/// `list_messages()`).
+///
+/// # Arguments
+/// * `path` - Channel directory
+/// * `config` - Walk configuration, usually `WalkConfig::message_loading()`
+///
+/// # Returns
+/// * `Ok(Vec<PrefixCollision>)` - Conflicting groups; empty if none
+/// * `Err(WalkError)` - Fatal walk error (strict mode only)
+pub fn find_prefix_collisions(
+    path: &Path,
+    config: WalkConfig,
+) -> Result<Vec<PrefixCollision>, WalkError> {
+    Ok(list_messages(path, config)?.prefix_collisions())
+}
+
+/// Move `group` into `collisions` if it holds more than one entry;
+/// always leaves `group` empty.
+fn push_collision(
+    collisions: &mut Vec<PrefixCollision>,
+    prefix: Option<u64>,
+    group: &mut Vec<DirEntry>,
+) {
+    let entries = std::mem::take(group);
+    if let Some(prefix) = prefix
+        && entries.len() > 1
+    {
+        collisions.push(PrefixCollision { prefix, entries });
+    }
+}
+
+// ============================================================================
+// SEQUENCE GAPS
+// ============================================================================
+
+/// Shape of a channel's numeric-prefix sequence.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SequenceReport {
+    /// Lowest prefix (`None` if the channel has no messages).
+    pub min: Option<u64>,
+
+    /// Highest prefix (`None` if the channel has no messages).
+    pub max: Option<u64>,
+
+    /// Message files with a valid prefix (duplicates counted).
+    pub count: usize,
+
+    /// Numbers between `min` and `max` that no message uses, as
+    /// ascending inclusive ranges (so one huge gap stays one element).
+    pub missing: Vec<RangeInclusive<u64>>,
+}
+
+impl SequenceReport {
+    /// Total count of missing numbers.
+    pub fn missing_count(&self) -> u64 {
+        self.missing.iter().fold(0u64, |total, range| {
+            total.saturating_add((range.end() - range.start()).saturating_add(1))
+        })
+    }
+
+    /// Whether every number from `min` to `max` is present.
+    pub fn is_contiguous(&self) -> bool {
+        self.missing.is_empty()
+    }
+}
+
+/// Summarize the numeric-prefix sequence of a channel: range, count and
+/// gaps.
+///
+/// Gaps are only looked for between the lowest and highest prefix
+/// present; messages missing after the newest one cannot be seen here.
+/// Metadata files and malformed names are not considered (see
+/// `list_messages()`).
+///
+/// # Arguments
+/// * `path` - Channel directory
+/// * `config` - Walk configuration, usually `WalkConfig::message_loading()`
+///
+/// # Returns
+/// * `Ok(SequenceReport)` - Sequence summary
+/// * `Err(WalkError)` - Fatal walk error (strict mode only)
+///
+/// # Project Context
+/// Sync tooling compares reports instead of full listings and requests
+/// retransmission of exactly the `missing` ranges.
+pub fn sequence_report(path: &Path, config: WalkConfig) -> Result<SequenceReport, WalkError> {
+    let listing = list_messages(path, config)?;
+
+    // `messages` is sorted and every entry in it has a valid prefix
+    let prefixes: Vec<u64> = listing
+        .messages
+        .iter()
+        .filter_map(|entry| entry_prefix(entry).ok())
+        .collect();
+
+    let mut missing: Vec<RangeInclusive<u64>> = Vec::new();
+    for pair in prefixes.windows(2) {
+        let (low, high) = (pair[0], pair[1]);
+        if high > low.saturating_add(1) {
+            missing.push((low + 1)..=(high - 1));
+        }
+    }
+
+    Ok(SequenceReport {
+        min: prefixes.first().copied(),
+        max: prefixes.last().copied(),
+        count: prefixes.len(),
+        missing,
+    })
+}
+
+// ============================================================================
+// DELIVERY VERIFICATION
+// ============================================================================
+
+/// Result of `verify_expected_files()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifyReport {
+    /// Expected files found as regular files with the expected size.
+    pub present: usize,
+
+    /// Expected names with no regular file of that name, in the order
+    /// given.
+    pub missing: Vec<String>,
+
+    /// Expected files found with another size, as (name, expected size,
+    /// actual size), in the order given.
+    pub size_mismatches: Vec<(String, u64, u64)>,
+}
+
+impl VerifyReport {
+    /// Whether every expected file is present with its expected size.
+    pub fn is_complete(&self) -> bool {
+        self.missing.is_empty() && self.size_mismatches.is_empty()
+    }
+}
+
+/// Check that `dir` holds each expected file with the expected size,
+/// reading the directory once.
+///
+/// Only `dir`'s own entries are read (no recursion), bounded like any
+/// walk by the default `max_entries_per_dir`. An expected name counts
+/// as present only for a regular file (a directory or symlink of that
+/// name is missing); symlinks are not followed. Unexpected files are
+/// ignored.
+///
+/// # Arguments
+/// * `dir` - Directory the files were delivered to
+/// * `expected` - (file name, size in bytes) of each expected file
+///
+/// # Returns
+/// * `Ok(VerifyReport)` - What is present, missing or the wrong size
+/// * `Err(WalkError)` - The directory (or an entry in it) could not be
+///   read, or it holds more entries than the limit; nothing is reported
+///   as missing on a partial read
+///
+/// # Project Context
+/// The sync protocol's acknowledgment step confirms a batch of received
+/// messages before acknowledging it. One bounded listing replaces the N
+/// independent `fs::metadata` calls it used to make, which mattered for
+/// large batches on network filesystems.
+pub fn verify_expected_files(
+    dir: &Path,
+    expected: &[(&str, u64)],
+) -> Result<VerifyReport, WalkError> {
+    let wanted: HashSet<&str> = expected.iter().map(|(name, _)| *name).collect();
+    // Bounded by `expected`: only wanted names are kept
+    let mut found: HashMap<String, u64> = HashMap::new();
+
+    let config = WalkConfig::new()
+        .max_depth(0)
+        .yield_directories(false)
+        .continue_on_error(false);
+    walk_with(dir, config, |entry| {
+        if entry.is_file()
+            && let Some(name) = entry.file_name()
+            && wanted.contains(name)
+        {
+            found.insert(name.to_string(), entry.len());
+        }
+        ControlFlow::Continue(())
+    })?;
+
+    let mut report = VerifyReport {
+        present: 0,
+        missing: Vec::new(),
+        size_mismatches: Vec::new(),
+    };
+    for (name, size) in expected {
+        match found.get(*name) {
+            Some(actual) if actual == size => report.present = report.present.saturating_add(1),
+            Some(actual) => report
+                .size_mismatches
+                .push((name.to_string(), *size, *actual)),
+            None => report.missing.push(name.to_string()),
+        }
+    }
+    Ok(report)
+}
+
+// ============================================================================
+// RENUMBERING
+// ============================================================================
+
+/// Configuration for `renumber_messages()`.
+#[derive(Debug, Clone)]
+pub struct RenumberConfig {
+    /// Walk used to list the channel (filters, metadata names).
+    walk_config: WalkConfig,
+
+    /// Prefix given to the first message.
+    start: u64,
+
+    /// Most renames one call may perform; a larger plan is refused.
+    max_renames: usize,
+
+    /// Whether the plan is executed or only reported.
+    mode: Mode,
+}
+
+impl Default for RenumberConfig {
+    fn default() -> Self {
+        RenumberConfig {
+            walk_config: WalkConfig::message_loading(),
+            start: 1,
+            max_renames: DEFAULT_MAX_RENAMES,
+            mode: Mode::DryRun,
+        }
+    }
+}
+
+impl RenumberConfig {
+    /// Create a config with defaults: `WalkConfig::message_loading()`,
+    /// numbering from 1, at most 10,000 renames, `Mode::DryRun`.
+    ///
+    /// The default mode is a dry run: executing must be asked for.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the walk configuration used to list the channel.
+    ///
+    /// Renumbering is per directory: the walk is always limited to the
+    /// channel directory's immediate files (depth 0, full paths).
+    pub fn walk_config(mut self, config: WalkConfig) -> Self {
+        self.walk_config = config;
+        self
+    }
+
+    /// Set the prefix of the first message (default 1; 0 is usually the
+    /// metadata file's slot).
+    pub fn start(mut self, start: u64) -> Self {
+        self.start = start;
+        self
+    }
+
+    /// Set the maximum number of renames one call may perform.
+    pub fn max_renames(mut self, limit: usize) -> Self {
+        self.max_renames = limit;
+        self
+    }
+
+    /// Set whether the plan is executed (`Mode::Execute`) or only
+    /// reported (`Mode::DryRun`, the default).
+    pub fn mode(mut self, mode: Mode) -> Self {
+        self.mode = mode;
+        self
+    }
+}
+
+/// One planned or performed rename.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rename {
+    /// Current path.
+    pub from: PathBuf,
+
+    /// New path (same directory, new prefix, same rest of the name).
+    pub to: PathBuf,
+}
+
+/// Result of `renumber_messages()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenumberReport {
+    /// Mode the plan ran in.
+    pub mode: Mode,
+
+    /// Renames in message order (planned in a dry run, performed when
+    /// executing).
+    pub renames: Vec<Rename>,
+
+    /// Messages that already had their target prefix.
+    pub unchanged: usize,
+
+    /// Malformed names left untouched (see `list_messages()`).
+    pub malformed: usize,
+
+    /// Plan written before executing (`WalkConfig::rollback_plan`);
+    /// `None` otherwise.
+    pub rollback: Option<RollbackPlan>,
+}
+
+/// Renumber a channel's messages to contiguous prefixes, keeping their
+/// order.
+///
+/// Messages are ordered by prefix (files sharing a prefix by name), then
+/// given `start`, `start + 1`, ...; the part of the name after `__` is
+/// kept. Metadata files and malformed names are not touched.
+///
+/// The plan is checked before anything is renamed:
+/// - More than `max_renames` renames: refused
+/// - A target name taken by a file the plan does not move (including
+///   files the walk filtered out): refused
+/// - Any planned file changed since the scan
+///   (`DirEntry::verify_unchanged()`): refused
+///
+/// Execution uses the two-phase journaled rename of the `mutate` module:
+/// all files first move to temp staging names, then to their final
+/// names. A crash therefore never leaves an old and a new prefix side by
+/// side (two files claiming one slot); `mutate::resume_renames()`
+/// finishes an interrupted run. With `WalkConfig::rollback_plan(true)`
+/// the inverse renames are written first (`RenumberReport::rollback`).
+/// An executing run holds the channel's scan lock
+/// (`mutate::try_lock_scan()`) from before the listing to the end.
+///
+/// # Arguments
+/// * `path` - Channel directory
+/// * `config` - Renumbering configuration (dry run by default)
+///
+/// # Returns
+/// * `Ok(RenumberReport)` - Plan (dry run) or renames performed
+/// * `Err(WalkError::EntryLimitExceeded)` - Plan exceeds `max_renames`
+/// * `Err(WalkError::RenameConflict)` - A target is taken, prefixes
+///   would exceed `u64::MAX`, or an interrupted run must be resumed first
+/// * `Err(WalkError::ScanLocked)` - Another replica is renumbering the
+///   channel (executing runs only)
+/// * `Err(WalkError)` - Walk failure, changed entry, or rename failure
+///
+/// # Project Context
+/// Replaces hand-written renumbering scripts, which have corrupted
+/// channels by renaming one message over another.
+pub fn renumber_messages(path: &Path, config: RenumberConfig) -> Result<RenumberReport, WalkError> {
+    renumber_messages_steps(path, config, usize::MAX)
+}
+
+/// `renumber_messages()` with a rename step budget (see
+/// `mutate::rename_two_phase`), for crash simulation.
+fn renumber_messages_steps(
+    path: &Path,
+    config: RenumberConfig,
+    max_steps: usize,
+) -> Result<RenumberReport, WalkError> {
+    let walk_config = config.walk_config.max_depth(0).yield_relative_paths(false);
+    // Held until the plan is executed; released on drop
+    let _lock = match config.mode {
+        Mode::Execute => Some(try_lock_scan(path, &walk_config)?),
+        Mode::DryRun => None,
+    };
+    // Opened before anything is planned, so an unloggable run is refused
+    let mut audit = AuditLog::open(path, &walk_config, config.mode, "renumber_messages")?;
+    let write_rollback = walk_config.rollback_plan.then(|| walk_config.clone());
+    let listing = list_messages(path, walk_config)?;
+    let mut messages = listing.messages;
+    // Ties broken by name so the plan does not depend on directory order
+    messages.sort_by(|a, b| {
+        entry_prefix(a)
+            .ok()
+            .cmp(&entry_prefix(b).ok())
+            .then_with(|| a.file_name().cmp(&b.file_name()))
+    });
+
+    // (rename, entry, from name, to name)
+    let mut plan: Vec<(Rename, &DirEntry, String, String)> = Vec::new();
+    let mut unchanged: usize = 0;
+
+    for (index, entry) in messages.iter().enumerate() {
+        // Every listed message has a UTF-8 name with a valid prefix
+        let (old, name, rest) = match (entry_prefix(entry), entry.file_name()) {
+            (Ok(old), Some(name)) => match name.split_once(PREFIX_SEPARATOR) {
+                Some((_, rest)) => (old, name, rest),
+                None => continue,
+            },
+            _ => continue,
+        };
+        let new = match config.start.checked_add(index as u64) {
+            Some(new) => new,
+            None => {
+                #[cfg(debug_assertions)]
+                eprintln!("DWRC: renumbering would exceed the largest prefix");
+                return Err(WalkError::RenameConflict);
+            }
+        };
+
+        if new == old {
+            unchanged = unchanged.saturating_add(1);
+            continue;
+        }
+
+        let new_name = format!("{}{}{}", new, PREFIX_SEPARATOR, rest);
+        let rename = Rename {
+            from: entry.path().to_path_buf(),
+            to: entry.path().with_file_name(&new_name),
+        };
+        plan.push((rename, entry, name.to_string(), new_name));
+    }
+
+    if plan.len() > config.max_renames {
+        #[cfg(debug_assertions)]
+        eprintln!("DWEL: renumbering plan exceeds max_renames");
+        return Err(WalkError::EntryLimitExceeded);
+    }
+
+    let sources: HashSet<&Path> = plan.iter().map(|(r, _, _, _)| r.from.as_path()).collect();
+    for (rename, _, _, _) in &plan {
+        if !sources.contains(rename.to.as_path()) && fs::symlink_metadata(&rename.to).is_ok() {
+            #[cfg(debug_assertions)]
+            eprintln!("DWRC: renumbering target is taken by an unplanned file");
+            return Err(WalkError::RenameConflict);
+        }
+    }
+
+    let mut rollback = None;
+    if config.mode == Mode::Execute {
+        for (_, entry, _, _) in &plan {
+            entry.verify_unchanged()?;
+        }
+        if let Some(walk_config) = &write_rollback {
+            let steps = plan
+                .iter()
+                .map(|(_, _, from, to)| RollbackStep::Rename {
+                    from: PathBuf::from(to),
+                    to: PathBuf::from(from),
+                })
+                .collect();
+            rollback = Some(write_rollback_plan(path, walk_config, steps)?);
+        }
+        let names: Vec<(String, String)> = plan
+            .iter()
+            .map(|(_, _, from, to)| (from.clone(), to.clone()))
+            .collect();
+        let outcome = rename_two_phase(path, &names, max_steps);
+        // Renames are all-or-nothing (journaled), so only a completed run
+        // logs them
+        if outcome.is_ok() {
+            for (rename, _, _, _) in &plan {
+                audit.rename(&rename.from, &rename.to);
+            }
+        }
+        audit.finish(outcome)?;
+    }
+
+    Ok(RenumberReport {
+        mode: config.mode,
+        renames: plan.into_iter().map(|(rename, _, _, _)| rename).collect(),
+        unchanged,
+        malformed: listing.malformed.len(),
+        rollback,
+    })
+}
+
+/// `parse_numeric_prefix` of an entry's file name; non-UTF-8 names are
+/// `NonDigit`.
+fn entry_prefix(entry: &DirEntry) -> Result<u64, PrefixError> {
+    entry
+        .file_name()
+        .ok_or(PrefixError::NonDigit)
+        .and_then(parse_numeric_prefix)
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+
+    /// Helper: unique test directory path (caller cleans up).
+    fn test_dir(name: &str) -> PathBuf {
+        crate::dir_walk_module::testing::unique_test_dir(&format!("messages_{}", name))
+    }
+
+    /// Helper: safely remove test directory, ignoring errors.
+    fn cleanup(path: &Path) {
+        let _ = fs::remove_dir_all(path);
+    }
+
+    /// Test: binary (old and new packet format), armored, plaintext,
+    /// empty and foreign binary files are told apart from their first
+    /// bytes.
+    #[test]
+    fn test_classify_encrypted_kinds() {
+        let dir = test_dir("classify");
+        cleanup(&dir);
+        let cases: [(&str, &[u8], EncryptionKind); 7] = [
+            // Old format, tag 1 (PKESK), two-byte length
+            (
+                "pkesk.gpgtoml",
+                &[0x85, 0x01, 0x0C, 0x03],
+                EncryptionKind::OpenPgpBinary,
+            ),
+            // New format, tag 3 (SKESK)
+            (
+                "skesk.gpgtoml",
+                &[0xC3, 0x0D, 0x04, 0x07],
+                EncryptionKind::OpenPgpBinary,
+            ),
+            (
+                "armored.gpgtoml",
+                b"\n-----BEGIN PGP MESSAGE-----\n\nhQEMA...",
+                EncryptionKind::OpenPgpArmored,
+            ),
+            (
+                "plain.gpgtoml",
+                "title = \"caf\u{e9}\"\nbody = \"hi\"\n".as_bytes(),
+                EncryptionKind::Plaintext,
+            ),
+            ("empty.gpgtoml", b"", EncryptionKind::Empty),
+            // PNG signature: bit 7 set but tag 2 (signature), then binary
+            (
+                "image.gpgtoml",
+                &[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A],
+                EncryptionKind::Unknown,
+            ),
+            ("nul.gpgtoml", b"abc\0def", EncryptionKind::Unknown),
+        ];
+        assert!(
+            fs::create_dir_all(&dir).is_ok(),
+            "test_classify_encrypted: failed to create dir"
+        );
+
+        for (name, content, expected) in cases {
+            assert!(
+                fs::write(dir.join(name), content).is_ok(),
+                "test_classify_encrypted: failed to write {}",
+                name
+            );
+            assert_eq!(
+                classify_encrypted(&dir.join(name)),
+                Ok(expected),
+                "test_classify_encrypted: {}",
+                name
+            );
+        }
+        assert!(
+            classify_encrypted(&dir.join("missing.gpgtoml")).is_err(),
+            "test_classify_encrypted: missing file must be an error"
+        );
+
+        cleanup(&dir);
+    }
+
+    /// Test: metadata names are configurable and set aside; messages are
+    /// sorted numerically with unprefixed names last.
+    #[test]
+    fn test_list_messages_metadata_names() {
+        let dir = test_dir("list");
+        cleanup(&dir);
+        let names = [
+            "10__late.toml",
+            "0.toml",
+            "channel.toml",
+            "2__early.toml",
+            "07__padded.toml",
+        ];
+        assert!(
+            fs::create_dir_all(&dir).is_ok()
+                && names
+                    .iter()
+                    .all(|name| fs::write(dir.join(name), b"x").is_ok()),
+            "test_list_messages: failed to create files"
+        );
+        let file_names = |entries: &[DirEntry]| -> Vec<String> {
+            entries
+                .iter()
+                .filter_map(|e| e.file_name().map(str::to_string))
+                .collect()
+        };
+
+        let default = list_messages(&dir, WalkConfig::message_loading());
+        assert_eq!(
+            default.as_ref().map(|l| file_names(&l.metadata)),
+            Ok(vec!["0.toml".to_string()]),
+            "test_list_messages: default metadata is 0.toml"
+        );
+        assert_eq!(
+            default.as_ref().map(|l| file_names(&l.messages)),
+            Ok(vec![
+                "2__early.toml".to_string(),
+                "10__late.toml".to_string()
+            ]),
+            "test_list_messages: numeric order"
+        );
+        assert_eq!(
+            default.as_ref().map(|l| {
+                let mut reasons: Vec<(String, PrefixError)> = l
+                    .malformed
+                    .iter()
+                    .filter_map(|(e, reason)| e.file_name().map(|n| (n.to_string(), *reason)))
+                    .collect();
+                reasons.sort_by(|a, b| a.0.cmp(&b.0));
+                reasons
+            }),
+            Ok(vec![
+                ("07__padded.toml".to_string(), PrefixError::LeadingZero),
+                ("channel.toml".to_string(), PrefixError::MissingSeparator),
+            ]),
+            "test_list_messages: malformed names are reported"
+        );
+
+        let custom = list_messages(
+            &dir,
+            WalkConfig::message_loading().metadata_file_names(&["channel.toml"]),
+        );
+        assert_eq!(
+            custom.as_ref().map(|l| file_names(&l.metadata)),
+            Ok(vec!["channel.toml".to_string()]),
+            "test_list_messages: custom metadata name"
+        );
+        assert_eq!(
+            custom.as_ref().map(|l| l.malformed.len()),
+            Ok(2),
+            "test_list_messages: 0.toml is no longer set aside as metadata"
+        );
+
+        cleanup(&dir);
+    }
+
+    /// Test: only prefixes used by two or more messages are reported,
+    /// with every file involved.
+    #[test]
+    fn test_find_prefix_collisions() {
+        let dir = test_dir("collisions");
+        cleanup(&dir);
+        let names = [
+            "7__a.toml",
+            "7__b.gpgtoml",
+            "8__c.toml",
+            "9__d.toml",
+            "9__e.toml",
+            "9__f.toml",
+            "0.toml",
+        ];
+        assert!(
+            fs::create_dir_all(&dir).is_ok()
+                && names
+                    .iter()
+                    .all(|name| fs::write(dir.join(name), b"x").is_ok()),
+            "test_find_prefix_collisions: failed to create files"
+        );
+
+        let collisions = find_prefix_collisions(&dir, WalkConfig::message_loading()).map(|c| {
+            c.iter()
+                .map(|collision| {
+                    let mut files: Vec<String> = collision
+                        .entries
+                        .iter()
+                        .filter_map(|e| e.file_name().map(str::to_string))
+                        .collect();
+                    files.sort();
+                    (collision.prefix, files)
+                })
+                .collect::<Vec<_>>()
+        });
+        assert_eq!(
+            collisions,
+            Ok(vec![
+                (7, vec!["7__a.toml".to_string(), "7__b.gpgtoml".to_string()]),
+                (
+                    9,
+                    vec![
+                        "9__d.toml".to_string(),
+                        "9__e.toml".to_string(),
+                        "9__f.toml".to_string()
+                    ]
+                ),
+            ]),
+            "test_find_prefix_collisions: groups"
+        );
+
+        cleanup(&dir);
+    }
+
+    /// Test: gaps are reported as ranges between min and max, with
+    /// duplicates counted but not creating gaps.
+    #[test]
+    fn test_sequence_report_gaps() {
+        let dir = test_dir("sequence");
+        cleanup(&dir);
+        let names = [
+            "3__a.toml",
+            "4__b.toml",
+            "4__b2.toml",
+            "6__c.toml",
+            "10__d.toml",
+            "0.toml",
+        ];
+        assert!(
+            fs::create_dir_all(&dir).is_ok()
+                && names
+                    .iter()
+                    .all(|name| fs::write(dir.join(name), b"x").is_ok()),
+            "test_sequence_report: failed to create files"
+        );
+
+        let report = sequence_report(&dir, WalkConfig::message_loading());
+        assert_eq!(
+            report,
+            Ok(SequenceReport {
+                min: Some(3),
+                max: Some(10),
+                count: 5,
+                missing: vec![5..=5, 7..=9],
+            }),
+            "test_sequence_report: report"
+        );
+        assert_eq!(
+            report.as_ref().map(|r| r.missing_count()),
+            Ok(4),
+            "test_sequence_report: missing count"
+        );
+
+        cleanup(&dir);
+    }
+
+    /// Test: expected files are reported present, missing (absent, or a
+    /// directory of that name) or with the wrong size; an unreadable
+    /// directory is an error, not a report of missing files.
+    #[test]
+    fn test_verify_expected_files() {
+        let dir = test_dir("verify");
+        cleanup(&dir);
+        assert!(
+            fs::create_dir_all(dir.join("5__dir.toml")).is_ok()
+                && fs::write(dir.join("1__a.toml"), b"aaa").is_ok()
+                && fs::write(dir.join("2__b.toml"), b"bb").is_ok()
+                && fs::write(dir.join("3__extra.toml"), b"x").is_ok(),
+            "test_verify_expected_files: failed to create files"
+        );
+
+        let expected = [
+            ("1__a.toml", 3),
+            ("2__b.toml", 5),
+            ("4__gone.toml", 1),
+            ("5__dir.toml", 0),
+        ];
+        let report = verify_expected_files(&dir, &expected);
+        assert_eq!(
+            report,
+            Ok(VerifyReport {
+                present: 1,
+                missing: vec!["4__gone.toml".to_string(), "5__dir.toml".to_string()],
+                size_mismatches: vec![("2__b.toml".to_string(), 5, 2)],
+            }),
+            "test_verify_expected_files: report"
+        );
+        assert!(
+            verify_expected_files(&dir, &expected[..1]).is_ok_and(|r| r.is_complete()),
+            "test_verify_expected_files: complete subset"
+        );
+        assert!(
+            verify_expected_files(&dir.join("missing"), &expected).is_err(),
+            "test_verify_expected_files: unreadable directory"
+        );
+
+        cleanup(&dir);
+    }
+
+    /// Test: renumbering plans contiguous prefixes without clobbering,
+    /// the dry run writes nothing, execution matches the plan, and a
+    /// target taken by an unplanned file is refused.
+    #[test]
+    fn test_renumber_messages_plan_and_execute() {
+        let dir = test_dir("renumber");
+        cleanup(&dir);
+        // 3__a must move to 2__a while 2__a moves to 1__a: only a safe
+        // order avoids renaming one over the other
+        let names = ["2__a.toml", "3__a.toml", "3__c.toml", "9__d.toml", "0.toml"];
+        assert!(
+            fs::create_dir_all(&dir).is_ok()
+                && names
+                    .iter()
+                    .all(|name| fs::write(dir.join(name), name).is_ok()),
+            "test_renumber: failed to create files"
+        );
+        let listed = |dir: &Path| -> Vec<String> {
+            let mut names: Vec<String> = fs::read_dir(dir)
+                .map(|rd| {
+                    rd.filter_map(|e| e.ok())
+                        .map(|e| e.file_name().to_string_lossy().into_owned())
+                        .collect()
+                })
+                .unwrap_or_default();
+            names.sort();
+            names
+        };
+
+        let dry_run = renumber_messages(&dir, RenumberConfig::new());
+        assert_eq!(
+            dry_run.as_ref().map(|r| (r.renames.len(), r.unchanged)),
+            Ok((3, 1)),
+            "test_renumber: three messages move, 3__c stays"
+        );
+        assert_eq!(
+            listed(&dir),
+            vec!["0.toml", "2__a.toml", "3__a.toml", "3__c.toml", "9__d.toml"],
+            "test_renumber: dry run must write nothing"
+        );
+
+        let executed = renumber_messages(&dir, RenumberConfig::new().mode(Mode::Execute));
+        assert_eq!(
+            executed.as_ref().map(|r| r.renames.clone()),
+            dry_run.as_ref().map(|r| r.renames.clone()),
+            "test_renumber: execution follows the plan"
+        );
+        assert_eq!(
+            listed(&dir),
+            vec!["0.toml", "1__a.toml", "2__a.toml", "3__c.toml", "4__d.toml"],
+            "test_renumber: contiguous result"
+        );
+        assert_eq!(
+            fs::read(dir.join("2__a.toml")).ok(),
+            Some(b"3__a.toml".to_vec()),
+            "test_renumber: content moved with its file"
+        );
+
+        // An empty file is filtered out of the listing but still occupies
+        // the name 4__d.toml would be renamed to
+        assert!(
+            fs::write(dir.join("5__d.toml"), b"").is_ok(),
+            "test_renumber: failed to create blocker"
+        );
+        assert_eq!(
+            renumber_messages(&dir, RenumberConfig::new().start(2).mode(Mode::Execute))
+                .map(|r| r.renames.len()),
+            Err(WalkError::RenameConflict),
+            "test_renumber: taken target must be refused"
+        );
+        assert_eq!(
+            renumber_messages(&dir, RenumberConfig::new().start(7).max_renames(3))
+                .map(|r| r.renames.len()),
+            Err(WalkError::EntryLimitExceeded),
+            "test_renumber: plan larger than max_renames must be refused"
+        );
+
+        cleanup(&dir);
+    }
+
+    /// Test: an executed renumbering with a rollback plan writes the
+    /// inverse renames first, and applying them restores the old names.
+    #[test]
+    fn test_renumber_rollback_plan() {
+        use super::super::mutate::ROLLBACK_PLAN_FILE;
+
+        let base = test_dir("renumber_rollback");
+        cleanup(&base);
+        let dir = base.join("general");
+        assert!(
+            fs::create_dir_all(&dir).is_ok()
+                && ["3__a.toml", "7__b.toml", "0.toml"]
+                    .iter()
+                    .all(|name| fs::write(dir.join(name), name).is_ok()),
+            "test_renumber_rollback: failed to create files"
+        );
+
+        let report = renumber_messages(
+            &dir,
+            RenumberConfig::new()
+                .walk_config(WalkConfig::message_loading().rollback_plan(true))
+                .mode(Mode::Execute),
+        );
+        let plan = report.ok().and_then(|r| r.rollback);
+        assert_eq!(
+            plan.as_ref().map(|p| p.steps.clone()),
+            Some(vec![
+                RollbackStep::Rename {
+                    from: PathBuf::from("1__a.toml"),
+                    to: PathBuf::from("3__a.toml"),
+                },
+                RollbackStep::Rename {
+                    from: PathBuf::from("2__b.toml"),
+                    to: PathBuf::from("7__b.toml"),
+                },
+            ]),
+            "test_renumber_rollback: inverse renames"
+        );
+        let Some(plan) = plan else { return };
+        assert!(
+            plan.dir.parent() == Some(base.as_path())
+                && fs::read_to_string(plan.dir.join(ROLLBACK_PLAN_FILE))
+                    .is_ok_and(|text| text
+                        == "rename\t1__a.toml\t3__a.toml\nrename\t2__b.toml\t7__b.toml\n"),
+            "test_renumber_rollback: plan file next to the channel"
+        );
+
+        // Applied as one batch, the plan restores the original names
+        let inverse: Vec<(String, String)> = plan
+            .steps
+            .iter()
+            .filter_map(|step| match step {
+                RollbackStep::Rename { from, to } => Some((
+                    from.to_string_lossy().into_owned(),
+                    to.to_string_lossy().into_owned(),
+                )),
+                _ => None,
+            })
+            .collect();
+        assert!(
+            rename_two_phase(&dir, &inverse, usize::MAX).is_ok()
+                && dir.join("3__a.toml").is_file()
+                && dir.join("7__b.toml").is_file(),
+            "test_renumber_rollback: applying the plan failed"
+        );
+
+        cleanup(&base);
+    }
+
+    /// Test: a renumbering interrupted after every possible step never
+    /// leaves two messages claiming one prefix, never loses a file, and
+    /// `resume_renames()` finishes it.
+    #[test]
+    fn test_renumber_crash_simulation() {
+        use super::super::mutate::resume_renames;
+
+        let dir = test_dir("renumber_crash");
+        // 1__a -> 2__a while 2__b -> 3__b: renaming in place in either
+        // order has a moment with two files at prefix 2 (or 3)
+        let originals = ["1__a.toml", "2__b.toml", "0.toml"];
+        let original_listing = vec!["0.toml", "1__a.toml", "2__b.toml"];
+        let renumbered_listing = vec!["0.toml", "2__a.toml", "3__b.toml"];
+        let listed = |dir: &Path| -> Vec<String> {
+            let mut names: Vec<String> = fs::read_dir(dir)
+                .map(|rd| {
+                    rd.filter_map(|e| e.ok())
+                        .map(|e| e.file_name().to_string_lossy().into_owned())
+                        .collect()
+                })
+                .unwrap_or_default();
+            names.sort();
+            names
+        };
+
+        // 2 journal steps + 2 staging + commit + 2 final + journal removal
+        for crash_after in 0..=8 {
+            cleanup(&dir);
+            assert!(
+                fs::create_dir_all(&dir).is_ok()
+                    && originals
+                        .iter()
+                        .all(|name| fs::write(dir.join(name), name).is_ok()),
+                "test_renumber_crash: failed to create files"
+            );
+
+            let config = RenumberConfig::new().start(2).mode(Mode::Execute);
+            assert!(
+                renumber_messages_steps(&dir, config, crash_after).is_ok(),
+                "test_renumber_crash: run {} failed",
+                crash_after
+            );
+
+            // Invariant at the crash point: visible (non-temp) messages
+            // have distinct prefixes, and every original file still exists
+            let mut prefixes: Vec<u64> = listed(&dir)
+                .iter()
+                .filter(|name| !name.starts_with(".~"))
+                .filter_map(|name| parse_numeric_prefix(name).ok())
+                .collect();
+            let visible = prefixes.len();
+            prefixes.dedup();
+            assert_eq!(
+                prefixes.len(),
+                visible,
+                "test_renumber_crash: duplicate prefix after {} steps",
+                crash_after
+            );
+            let mut contents: Vec<Vec<u8>> = listed(&dir)
+                .iter()
+                .filter(|name| !name.starts_with(".~dwm.journal"))
+                .filter_map(|name| fs::read(dir.join(name)).ok())
+                .collect();
+            contents.sort();
+            assert_eq!(
+                contents,
+                vec![
+                    b"0.toml".to_vec(),
+                    b"1__a.toml".to_vec(),
+                    b"2__b.toml".to_vec()
+                ],
+                "test_renumber_crash: file lost after {} steps",
+                crash_after
+            );
+
+            assert!(
+                resume_renames(&dir).is_ok(),
+                "test_renumber_crash: resume after {} steps failed",
+                crash_after
+            );
+            // Before the journal is in place nothing has been renamed, and
+            // resuming only discards the partial journal
+            let expected = if crash_after < 2 {
+                &original_listing
+            } else {
+                &renumbered_listing
+            };
+            assert_eq!(
+                &listed(&dir),
+                expected,
+                "test_renumber_crash: resumed result after {} steps",
+                crash_after
+            );
+        }
+
+        cleanup(&dir);
+    }
+
+    /// Test: accepted prefixes and every rejection reason.
+    #[test]
+    fn test_parse_numeric_prefix() {
+        let cases: [(&str, Result<u64, PrefixError>); 12] = [
+            ("0__first.toml", Ok(0)),
+            ("12__hello.toml", Ok(12)),
+            ("3__a__b.toml", Ok(3)),
+            ("18446744073709551615__max.toml", Ok(u64::MAX)),
+            ("18446744073709551616__big.toml", Err(PrefixError::Overflow)),
+            ("007__bond.toml", Err(PrefixError::LeadingZero)),
+            ("00__x.toml", Err(PrefixError::LeadingZero)),
+            ("hello.toml", Err(PrefixError::MissingSeparator)),
+            ("5_single.toml", Err(PrefixError::MissingSeparator)),
+            ("__x.toml", Err(PrefixError::Empty)),
+            ("+5__x.toml", Err(PrefixError::NonDigit)),
+            ("\u{0661}\u{0662}__arabic.toml", Err(PrefixError::NonDigit)),
+        ];
+        for (name, expected) in cases {
+            assert_eq!(
+                parse_numeric_prefix(name),
+                expected,
+                "test_parse_numeric_prefix: {}",
+                name
+            );
+        }
+    }
+}
@@ -0,0 +1,474 @@
+//! # Channel Monitor (`dir_walk_module::monitor`)
+//!
+//! A long-lived, per-directory change detector: one reusable walker plus
+//! the previous snapshot, re-polled cheaply on every tick.
+//!
+//! ## Project Context
+//! Demo 6 in `main.rs` shows the original pattern: build a walker, hash
+//! the channel's metadata, compare with the last hash, repeat every
+//! second. `ChannelMonitor` packages that loop as one object that keeps
+//! its walker queue, visited set and snapshot tables between polls, and
+//! reports *what* changed rather than only *that* something changed.
+//!
+//! Each successful poll is also kept in a short history, from which
+//! `change_rate()` derives entries-added-per-hour style rates; the
+//! application picks each channel's polling interval from them.
+//!
+//! ```ignore
+//! let mut monitor = ChannelMonitor::new(&channel_dir, WalkConfig::channel_scan().max_depth(1));
+//! loop {
+//!     if let Some(changes) = monitor.poll() {
+//!         reload(&changes.added);
+//!     }
+//!     std::thread::sleep(poll_interval);
+//! }
+//! ```
+
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use super::snapshot::{ChangeSummary, DirectorySnapshot};
+use super::{DirWalker, StepStatus, WalkConfig, WalkError};
+
+/// Successful polls kept for `ChannelMonitor::change_rate()`.
+pub const RATE_HISTORY_LEN: usize = 64;
+
+/// Seconds per hour, the unit rates are reported in.
+const SECS_PER_HOUR: f64 = 3600.0;
+
+// ============================================================================
+// CHANNEL MONITOR
+// ============================================================================
+
+/// Cached change detector for one directory.
+///
+/// The first `poll()` records a baseline and reports nothing; each later
+/// poll reports the differences since the previous successful poll.
+#[derive(Debug)]
+pub struct ChannelMonitor {
+    /// Directory being watched.
+    root: PathBuf,
+
+    /// Reused walker (reset to `root` on every poll).
+    walker: DirWalker,
+
+    /// State at the last successful poll.
+    previous: DirectorySnapshot,
+
+    /// Scratch snapshot filled by the current poll, swapped into
+    /// `previous` afterwards so both tables keep their capacity.
+    current: DirectorySnapshot,
+
+    /// Whether `previous` holds a real baseline yet.
+    has_baseline: bool,
+
+    /// Whether a sliced poll (`poll_step`) is part-way through a walk.
+    in_progress: bool,
+
+    /// Error from the most recent poll, if it failed.
+    last_error: Option<WalkError>,
+
+    /// Successful polls, oldest first, bounded by `RATE_HISTORY_LEN`.
+    history: VecDeque<PollSample>,
+}
+
+/// What one successful poll saw, for `ChannelMonitor::change_rate()`.
+#[derive(Debug, Clone, Copy)]
+struct PollSample {
+    /// When the poll finished (the walk config's clock).
+    at: Instant,
+    /// Entries added since the previous poll (0 for the baseline).
+    added: usize,
+    /// All changes since the previous poll (`ChangeSummary::len()`).
+    changes: usize,
+}
+
+/// Change rates of a monitored directory over its recent polls (see
+/// `ChannelMonitor::change_rate()`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChangeRate {
+    /// Entries added per hour.
+    pub added_per_hour: f64,
+
+    /// Changes of any kind (added, removed, modified, renamed) per hour.
+    pub changes_per_hour: f64,
+
+    /// Time between the oldest and newest poll the rates cover.
+    pub window: Duration,
+
+    /// Polls the rates cover (at least 2).
+    pub polls: usize,
+}
+
+impl ChannelMonitor {
+    /// Create a monitor for `root`. No I/O happens until the first poll.
+    ///
+    /// # Arguments
+    /// * `root` - Channel directory to watch
+    /// * `config` - Walk bounds and filters used on every poll
+    pub fn new(root: &Path, config: WalkConfig) -> Self {
+        ChannelMonitor {
+            root: root.to_path_buf(),
+            walker: DirWalker::new(root, config),
+            previous: DirectorySnapshot::new(),
+            current: DirectorySnapshot::new(),
+            has_baseline: false,
+            in_progress: false,
+            last_error: None,
+            history: VecDeque::with_capacity(RATE_HISTORY_LEN),
+        }
+    }
+
+    /// Re-walk the directory and report changes since the last poll.
+    ///
+    /// # Returns
+    /// * `Some(ChangeSummary)` - Something was added, removed or modified
+    /// * `None` - No change, the first (baseline) poll, or a failed walk
+    ///   (see `last_error()`); a failed walk leaves the baseline untouched
+    ///   so its changes are reported by the next successful poll
+    pub fn poll(&mut self) -> Option<ChangeSummary> {
+        // A full poll always starts from scratch, abandoning any slice
+        self.in_progress = false;
+
+        match self.poll_step(usize::MAX) {
+            PollStep::Done(summary) => summary,
+            // Unreachable in practice: an unbounded step drains the queue
+            PollStep::Pending => None,
+        }
+    }
+
+    /// Advance a poll by at most `max_directories` directory reads.
+    ///
+    /// Starts a new walk if none is in progress. When the walk finishes,
+    /// the result is exactly what `poll()` would have returned.
+    pub(super) fn poll_step(&mut self, max_directories: usize) -> PollStep {
+        if !self.in_progress {
+            self.current.clear();
+            self.walker.reset(&self.root);
+            self.in_progress = true;
+        }
+
+        let root = &self.root;
+        let current = &mut self.current;
+        let status = self
+            .walker
+            .step(max_directories, |entry| current.record(root, entry));
+
+        match status {
+            Ok(StepStatus::Pending) => PollStep::Pending,
+            Ok(StepStatus::Complete) => {
+                self.in_progress = false;
+                PollStep::Done(self.finish_poll())
+            }
+            Err(e) => {
+                #[cfg(debug_assertions)]
+                eprintln!(
+                    "DWCM: [walk {}] channel poll failed: {}",
+                    self.walker.walk_id(),
+                    e
+                );
+
+                self.in_progress = false;
+                self.last_error = Some(e);
+                PollStep::Done(None)
+            }
+        }
+    }
+
+    /// Promote the completed walk to the baseline and diff against the
+    /// previous one.
+    fn finish_poll(&mut self) -> Option<ChangeSummary> {
+        self.last_error = None;
+
+        std::mem::swap(&mut self.previous, &mut self.current);
+
+        if !self.has_baseline {
+            self.has_baseline = true;
+            self.record_sample(None);
+            return None;
+        }
+
+        // After the swap, `current` holds the older state
+        let summary = self.current.diff(&self.previous);
+        self.record_sample(Some(&summary));
+        if summary.is_empty() {
+            None
+        } else {
+            Some(summary)
+        }
+    }
+
+    /// Add a successful poll to the rate history, dropping the oldest
+    /// sample once it is full.
+    fn record_sample(&mut self, summary: Option<&ChangeSummary>) {
+        if self.history.len() >= RATE_HISTORY_LEN {
+            self.history.pop_front();
+        }
+        self.history.push_back(PollSample {
+            at: self.walker.config.clock.instant(),
+            added: summary.map_or(0, |s| s.added.len()),
+            changes: summary.map_or(0, ChangeSummary::len),
+        });
+    }
+
+    /// Change rates over the recent successful polls (at most
+    /// `RATE_HISTORY_LEN`, baseline included).
+    ///
+    /// Changes reported by every poll after the oldest one kept are
+    /// divided by the time from the oldest to the newest poll, so quiet
+    /// polls lower the rate and an old burst ages out of the history.
+    /// Failed polls are not part of it; their changes are counted by the
+    /// next successful poll.
+    ///
+    /// # Returns
+    /// `None` until two successful polls a measurable time apart exist.
+    ///
+    /// # Project Context
+    /// The application polls busy channels every second and idle ones
+    /// far less often, choosing each interval from `added_per_hour`.
+    /// The heuristics live here, next to the polls they are measured
+    /// from, instead of in every caller.
+    pub fn change_rate(&self) -> Option<ChangeRate> {
+        let (first, last) = (self.history.front()?, self.history.back()?);
+        let window = last.at.saturating_duration_since(first.at);
+        if window.is_zero() {
+            return None;
+        }
+
+        let (added, changes) =
+            self.history
+                .iter()
+                .skip(1)
+                .fold((0usize, 0usize), |(added, changes), sample| {
+                    (
+                        added.saturating_add(sample.added),
+                        changes.saturating_add(sample.changes),
+                    )
+                });
+        let hours = window.as_secs_f64() / SECS_PER_HOUR;
+        Some(ChangeRate {
+            added_per_hour: added as f64 / hours,
+            changes_per_hour: changes as f64 / hours,
+            window,
+            polls: self.history.len(),
+        })
+    }
+
+    /// Merge entries found by a separate scan under this root into the
+    /// baseline, returning (sorted) the paths the baseline did not have.
+    ///
+    /// The merged entries are not reported again by the next poll. Known
+    /// entries are left alone, so their modifications still are.
+    ///
+    /// # Arguments
+    /// * `scan` - Entries keyed relative to this monitor's root
+    pub(super) fn absorb(&mut self, scan: &DirectorySnapshot) -> Vec<PathBuf> {
+        let mut added = Vec::new();
+        for (path, entry) in scan.iter() {
+            if self.previous.get(path).is_none() {
+                self.previous.insert(path.to_path_buf(), *entry);
+                added.push(path.to_path_buf());
+            }
+        }
+        added.sort();
+        added
+    }
+
+    /// Walk configuration used on every poll.
+    pub(super) fn config(&self) -> &WalkConfig {
+        &self.walker.config
+    }
+
+    /// Directory this monitor watches.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Id of the current or most recently completed walk
+    /// (`DirWalker::walk_id()`); each poll starts a new one.
+    pub fn walk_id(&self) -> u64 {
+        self.walker.walk_id()
+    }
+
+    /// Whether a successful poll has recorded a baseline (later polls
+    /// report changes against it).
+    pub fn has_baseline(&self) -> bool {
+        self.has_baseline
+    }
+
+    /// Snapshot from the last successful poll (empty before the first).
+    pub fn snapshot(&self) -> &DirectorySnapshot {
+        &self.previous
+    }
+
+    /// Error from the most recent poll, or `None` if it succeeded.
+    ///
+    /// Only strict walks (`continue_on_error(false)`) fail as a whole;
+    /// with the default configuration unreadable entries are skipped.
+    pub fn last_error(&self) -> Option<WalkError> {
+        self.last_error
+    }
+}
+
+/// Progress of a sliced poll (see `ChannelMonitor::poll_step`).
+#[derive(Debug)]
+pub(super) enum PollStep {
+    /// The walk needs more slices.
+    Pending,
+
+    /// The walk finished; carries what `poll()` would have returned.
+    Done(Option<ChangeSummary>),
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dir_walk_module::clock::MockClock;
+    use std::fs;
+
+    /// Helper: unique test directory path (caller cleans up).
+    fn test_dir(name: &str) -> PathBuf {
+        crate::dir_walk_module::testing::unique_test_dir(&format!("monitor_{}", name))
+    }
+
+    /// Helper: safely remove test directory, ignoring errors.
+    fn cleanup(path: &Path) {
+        let _ = fs::remove_dir_all(path);
+    }
+
+    /// Test: baseline poll is silent, changes are reported once, and a
+    /// failed strict poll keeps the baseline for the next success.
+    #[test]
+    fn test_channel_monitor_polls_changes() {
+        let dir = test_dir("polls");
+        cleanup(&dir);
+        assert!(
+            fs::create_dir_all(&dir).is_ok() && fs::write(dir.join("1__msg.toml"), b"a").is_ok(),
+            "test_channel_monitor: failed to create channel"
+        );
+
+        let mut monitor = ChannelMonitor::new(&dir, WalkConfig::new().continue_on_error(false));
+        assert!(
+            monitor.poll().is_none(),
+            "test_channel_monitor: baseline poll must report nothing"
+        );
+        assert!(
+            monitor.poll().is_none(),
+            "test_channel_monitor: unchanged channel must report nothing"
+        );
+
+        assert!(
+            fs::write(dir.join("2__msg.toml"), b"b").is_ok(),
+            "test_channel_monitor: failed to add message"
+        );
+        let changes = monitor.poll();
+        assert_eq!(
+            changes.map(|c| c.added),
+            Some(vec![PathBuf::from("2__msg.toml")]),
+            "test_channel_monitor: new message not reported"
+        );
+        assert!(
+            monitor.poll().is_none(),
+            "test_channel_monitor: change must be reported only once"
+        );
+
+        // Channel disappears: strict walk fails, baseline is kept
+        cleanup(&dir);
+        assert!(
+            monitor.poll().is_none() && monitor.last_error().is_some(),
+            "test_channel_monitor: missing channel should record an error"
+        );
+        assert_eq!(
+            monitor.snapshot().len(),
+            2,
+            "test_channel_monitor: failed poll must not replace the baseline"
+        );
+
+        // Channel comes back with one message
+        assert!(
+            fs::create_dir_all(&dir).is_ok() && fs::write(dir.join("1__msg.toml"), b"a").is_ok(),
+            "test_channel_monitor: failed to recreate channel"
+        );
+        let changes = monitor.poll();
+        assert!(
+            monitor.last_error().is_none(),
+            "test_channel_monitor: error should clear on success"
+        );
+        assert_eq!(
+            changes.map(|c| c.removed),
+            Some(vec![PathBuf::from("2__msg.toml")]),
+            "test_channel_monitor: removal since last good poll not reported"
+        );
+
+        cleanup(&dir);
+    }
+
+    /// Test: change rates cover the polls since the oldest kept one,
+    /// quiet polls lower them, and the history stays bounded.
+    #[test]
+    fn test_change_rate() {
+        let dir = test_dir("rate");
+        cleanup(&dir);
+        assert!(
+            fs::create_dir_all(&dir).is_ok(),
+            "test_change_rate: failed to create channel"
+        );
+
+        let clock = MockClock::new(std::time::SystemTime::now());
+        let mut monitor = ChannelMonitor::new(&dir, WalkConfig::new().clock(clock.clone()));
+        let half_hour = Duration::from_secs(1800);
+        assert!(
+            monitor.poll().is_none() && monitor.change_rate().is_none(),
+            "test_change_rate: a baseline alone has no rate"
+        );
+
+        clock.advance(half_hour);
+        assert!(
+            fs::write(dir.join("1__a.toml"), b"a").is_ok()
+                && fs::write(dir.join("2__b.toml"), b"b").is_ok(),
+            "test_change_rate: failed to add messages"
+        );
+        assert!(
+            monitor.poll().is_some(),
+            "test_change_rate: additions not reported"
+        );
+        let rate = monitor.change_rate();
+        assert!(
+            rate.is_some_and(|r| r.added_per_hour == 4.0 && r.polls == 2),
+            "test_change_rate: 2 added in half an hour, got {:?}",
+            rate
+        );
+
+        clock.advance(half_hour);
+        assert!(
+            fs::write(dir.join("1__a.toml"), b"changed").is_ok() && monitor.poll().is_some(),
+            "test_change_rate: modification not reported"
+        );
+        let rate = monitor.change_rate();
+        assert!(
+            rate.is_some_and(|r| r.added_per_hour == 2.0
+                && r.changes_per_hour == 3.0
+                && r.window == Duration::from_secs(3600)),
+            "test_change_rate: quiet half hour must halve the rate, got {:?}",
+            rate
+        );
+
+        for _ in 0..RATE_HISTORY_LEN {
+            clock.advance(half_hour);
+            monitor.poll();
+        }
+        let rate = monitor.change_rate();
+        assert!(
+            rate.is_some_and(|r| r.polls == RATE_HISTORY_LEN && r.changes_per_hour == 0.0),
+            "test_change_rate: old changes must age out, got {:?}",
+            rate
+        );
+
+        cleanup(&dir);
+    }
+}
@@ -0,0 +1,381 @@
+//! # Multi-Root Walks (`dir_walk_module::multi`)
+//!
+//! One iterator over several roots (typically several channel
+//! directories), each walked by its own `DirWalker` with a shared
+//! configuration.
+//!
+//! ## Project Context
+//! Loading a workspace walks every channel. Walking them one after the
+//! other lets one giant channel hold back all the others: the UI shows
+//! nothing new for the small channels until the big one is done.
+//! `RootOrder::RoundRobin` takes one entry from each root in turn, so
+//! every channel makes progress from the first entries on.
+//!
+//! ## Bounds
+//! Each root has its own walker, so queue and per-directory bounds apply
+//! per root: memory is at most the number of roots times one walk's
+//! bounds.
+//!
+//! ## Overlapping Roots
+//! The walkers share one visit map, so `WalkConfig::max_visits_per_dir`
+//! holds across roots: with roots `/a` and `/a/b`, `/a/b` is read by
+//! whichever walker reaches it first and skipped by the other
+//! (`WalkStats::dirs_visited_elsewhere`). Each directory read costs one
+//! `canonicalize` for this.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use super::{DirEntry, DirWalker, WalkConfig, WalkError, WalkStats};
+
+// ============================================================================
+// ROOT ORDER
+// ============================================================================
+
+/// How a `MultiWalker` interleaves the entries of its roots.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RootOrder {
+    /// Walk each root to completion, in the order given (default).
+    #[default]
+    Sequential,
+
+    /// Take one item from each unfinished root in turn.
+    RoundRobin,
+}
+
+// ============================================================================
+// MULTI-ROOT WALKER
+// ============================================================================
+
+/// Iterator over the entries of several roots.
+///
+/// Yields the same items as `DirWalker` (`Result<DirEntry, WalkError>`).
+/// In strict mode, a fatal error ends only the root it came from; the
+/// other roots continue.
+#[derive(Debug)]
+pub struct MultiWalker {
+    /// One walker per root; `None` once that root is exhausted.
+    walkers: Vec<Option<DirWalker>>,
+
+    /// Stats of finished roots, kept when their walker is dropped.
+    finished_stats: Vec<Option<WalkStats>>,
+
+    /// Caller-provided root labels, by root index (may be shorter than
+    /// the root list).
+    labels: Vec<String>,
+
+    /// Interleaving policy.
+    order: RootOrder,
+
+    /// Root the next item is taken from.
+    cursor: usize,
+}
+
+impl MultiWalker {
+    /// Create a walker over `roots`, each walked with `config`.
+    ///
+    /// The roots' walkers share one visit map (see "Overlapping Roots"
+    /// in the module docs).
+    ///
+    /// # Arguments
+    /// * `roots` - Root directories, in order
+    /// * `config` - Configuration shared by every root's walker
+    pub fn new<I, P>(roots: I, config: WalkConfig) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<Path>,
+    {
+        let visits = Arc::new(Mutex::new(HashMap::new()));
+        let walkers: Vec<Option<DirWalker>> = roots
+            .into_iter()
+            .map(|root| {
+                let mut walker = DirWalker::new(root.as_ref(), config.clone());
+                walker.share_visits(Arc::clone(&visits));
+                Some(walker)
+            })
+            .collect();
+        let finished_stats = vec![None; walkers.len()];
+
+        MultiWalker {
+            walkers,
+            finished_stats,
+            labels: Vec::new(),
+            order: RootOrder::default(),
+            cursor: 0,
+        }
+    }
+
+    /// Set how entries of different roots are interleaved.
+    pub fn order(mut self, order: RootOrder) -> Self {
+        self.order = order;
+        self
+    }
+
+    /// Label the roots, in the same order as the roots given to `new()`.
+    ///
+    /// # Arguments
+    /// * `labels` - One label per root, e.g. channel names; missing
+    ///   trailing labels leave those roots unlabeled
+    pub fn labels(mut self, labels: &[&str]) -> Self {
+        self.labels = labels.iter().map(|label| label.to_string()).collect();
+        self
+    }
+
+    /// Number of roots.
+    pub fn root_count(&self) -> usize {
+        self.walkers.len()
+    }
+
+    /// Label of a root, as set with `labels()`.
+    pub fn root_label(&self, root_index: usize) -> Option<&str> {
+        self.labels.get(root_index).map(String::as_str)
+    }
+
+    /// Iterate items tagged with the index of the root they came from.
+    ///
+    /// The index is the root's position in the list given to `new()`;
+    /// `root_label()` maps it to the caller's label. Tagging costs one
+    /// `usize` per item (no per-entry allocation).
+    ///
+    /// # Project Context
+    /// Workspace loading routes each entry to its channel's handler by
+    /// index, instead of matching channel path prefixes.
+    pub fn tagged(self) -> TaggedEntries {
+        TaggedEntries { walker: self }
+    }
+
+    /// Counters of one root's walk so far.
+    ///
+    /// # Arguments
+    /// * `root_index` - Position of the root in the list given to `new()`
+    ///
+    /// # Returns
+    /// `None` if `root_index` is out of range.
+    pub fn root_stats(&self, root_index: usize) -> Option<&WalkStats> {
+        match self.walkers.get(root_index)? {
+            Some(walker) => Some(walker.stats()),
+            None => self.finished_stats.get(root_index)?.as_ref(),
+        }
+    }
+
+    /// Take the next item from the root at `cursor`, retiring it when
+    /// exhausted.
+    fn next_from_cursor(&mut self) -> Option<Result<DirEntry, WalkError>> {
+        let slot = self.walkers.get_mut(self.cursor)?;
+        let walker = slot.as_mut()?;
+        let item = walker.next();
+        if item.is_none()
+            && let Some(finished) = slot.take()
+            && let Some(stats) = self.finished_stats.get_mut(self.cursor)
+        {
+            *stats = Some(finished.finish());
+        }
+        item
+    }
+
+    /// Next item under the interleaving policy, with its root index.
+    fn next_tagged(&mut self) -> Option<(usize, Result<DirEntry, WalkError>)> {
+        let root_count = self.walkers.len();
+
+        match self.order {
+            RootOrder::Sequential => {
+                // Bounded: the cursor only moves forward
+                while self.cursor < root_count {
+                    if let Some(item) = self.next_from_cursor() {
+                        return Some((self.cursor, item));
+                    }
+                    self.cursor += 1;
+                }
+                None
+            }
+            RootOrder::RoundRobin => {
+                // Bounded: at most one visit per root per call
+                for _ in 0..root_count {
+                    if self.cursor >= root_count {
+                        self.cursor = 0;
+                    }
+                    let root_index = self.cursor;
+                    let item = self.next_from_cursor();
+                    self.cursor += 1;
+                    if let Some(item) = item {
+                        return Some((root_index, item));
+                    }
+                }
+                None
+            }
+        }
+    }
+}
+
+impl Iterator for MultiWalker {
+    type Item = Result<DirEntry, WalkError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_tagged().map(|(_, item)| item)
+    }
+}
+
+/// Iterator returned by `MultiWalker::tagged()`.
+///
+/// Yields `(root_index, item)`; errors are tagged too, so a failing root
+/// can be identified.
+#[derive(Debug)]
+pub struct TaggedEntries {
+    walker: MultiWalker,
+}
+
+impl TaggedEntries {
+    /// The underlying walker (labels, per-root stats).
+    pub fn walker(&self) -> &MultiWalker {
+        &self.walker
+    }
+}
+
+impl Iterator for TaggedEntries {
+    type Item = (usize, Result<DirEntry, WalkError>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.walker.next_tagged()
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+
+    /// Helper: unique test directory path (caller cleans up).
+    fn test_dir(name: &str) -> PathBuf {
+        crate::dir_walk_module::testing::unique_test_dir(&format!("multi_{}", name))
+    }
+
+    /// Helper: safely remove test directory, ignoring errors.
+    fn cleanup(path: &Path) {
+        let _ = fs::remove_dir_all(path);
+    }
+
+    /// Test: sequential order finishes each root first; round-robin
+    /// alternates until the small root runs out.
+    #[test]
+    fn test_multi_walker_orders() {
+        let dir = test_dir("orders");
+        cleanup(&dir);
+        let big = dir.join("big");
+        let small = dir.join("small");
+        assert!(
+            fs::create_dir_all(&big).is_ok()
+                && fs::create_dir_all(&small).is_ok()
+                && (0..4).all(|i| fs::write(big.join(format!("{}.toml", i)), b"x").is_ok())
+                && fs::write(small.join("0.toml"), b"x").is_ok(),
+            "test_multi_walker_orders: failed to create roots"
+        );
+
+        let roots_of = |order: RootOrder| -> Vec<bool> {
+            MultiWalker::new([&big, &small], WalkConfig::new())
+                .order(order)
+                .filter_map(|r| r.ok())
+                .map(|entry| entry.path().starts_with(&big))
+                .collect()
+        };
+
+        assert_eq!(
+            roots_of(RootOrder::Sequential),
+            vec![true, true, true, true, false],
+            "test_multi_walker_orders: sequential"
+        );
+        assert_eq!(
+            roots_of(RootOrder::RoundRobin),
+            vec![true, false, true, true, true],
+            "test_multi_walker_orders: round-robin"
+        );
+
+        let mut tagged = MultiWalker::new([&big, &small], WalkConfig::new())
+            .order(RootOrder::RoundRobin)
+            .labels(&["big"])
+            .tagged();
+        let routed: Vec<(usize, bool)> = tagged
+            .by_ref()
+            .filter_map(|(root, r)| r.ok().map(|entry| (root, entry.path().starts_with(&big))))
+            .collect();
+        assert!(
+            routed.len() == 5 && routed.iter().all(|(root, in_big)| (*root == 0) == *in_big),
+            "test_multi_walker_orders: root tags match origin, got {:?}",
+            routed
+        );
+        assert_eq!(
+            (tagged.walker().root_label(0), tagged.walker().root_label(1)),
+            (Some("big"), None),
+            "test_multi_walker_orders: labels by root index"
+        );
+
+        let mut walker = MultiWalker::new([&big, &small], WalkConfig::new());
+        let total = walker.by_ref().count();
+        assert!(
+            total == 5
+                && walker
+                    .root_stats(0)
+                    .is_some_and(|s| s.directories_read == 1)
+                && walker.root_stats(1).is_some()
+                && walker.root_stats(2).is_none(),
+            "test_multi_walker_orders: per-root stats survive the walk"
+        );
+
+        cleanup(&dir);
+    }
+
+    /// Test: overlapping roots share one visit map, so a directory inside
+    /// another root is read once between them.
+    #[test]
+    fn test_multi_walker_overlapping_roots() {
+        let dir = test_dir("overlap");
+        cleanup(&dir);
+        let outer = dir.join("a");
+        let inner = outer.join("b");
+        assert!(
+            fs::create_dir_all(&inner).is_ok()
+                && fs::write(outer.join("1.toml"), b"x").is_ok()
+                && fs::write(inner.join("2.toml"), b"x").is_ok()
+                && fs::write(inner.join("3.toml"), b"x").is_ok(),
+            "test_multi_walker_overlapping_roots: failed to create roots"
+        );
+
+        for order in [RootOrder::Sequential, RootOrder::RoundRobin] {
+            let walker =
+                MultiWalker::new([&outer, &inner], WalkConfig::new().max_visits_per_dir(1))
+                    .order(order);
+            let mut files: Vec<String> = walker
+                .tagged()
+                .filter_map(|(_, item)| item.ok())
+                .filter(|entry| entry.is_file())
+                .filter_map(|entry| entry.file_name().map(str::to_string))
+                .collect();
+            files.sort();
+            assert_eq!(
+                files,
+                vec!["1.toml", "2.toml", "3.toml"],
+                "test_multi_walker_overlapping_roots: each file once ({:?})",
+                order
+            );
+        }
+
+        let mut walker = MultiWalker::new([&outer, &inner], WalkConfig::new());
+        let yielded = walker.by_ref().filter(|item| item.is_ok()).count();
+        let skipped = (0..walker.root_count())
+            .filter_map(|index| walker.root_stats(index))
+            .map(|stats| stats.dirs_visited_elsewhere)
+            .sum::<usize>();
+        assert_eq!(
+            (yielded, skipped),
+            (4, 1),
+            "test_multi_walker_overlapping_roots: inner root skipped as already read"
+        );
+
+        cleanup(&dir);
+    }
+}
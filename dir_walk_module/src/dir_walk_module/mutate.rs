@@ -0,0 +1,3055 @@
+//! # Mutating Tree Helpers (`dir_walk_module::mutate`)
+//!
+//! Whole-tree operations that write to the filesystem, built on
+//! `DirWalker` so they inherit its bounds, filters and symlink policy.
+//!
+//! ## Project Context
+//! Channel backup and replica seeding copy a channel directory somewhere
+//! else; retention jobs delete old messages and prune emptied folders.
+//! - Every helper takes a `Mode` and returns an `ActionReport`, so each
+//!   destructive operation can be previewed with `Mode::DryRun` using the
+//!   same call that performs it
+//! - Helpers with a destination refuse one that lies inside the source
+//!   tree (`check_destination_outside()`): walking a tree while writing
+//!   into it would copy the copy, forever
+//! - Files are never written under their final name: copies are written
+//!   to a staging name and renamed into place, and renumbering renames in
+//!   two journaled phases (`resume_renames()`), so a crash leaves only
+//!   temp-named leftovers, never a partial or duplicate message
+//! - With `WalkConfig::audit_log`, every performed action is appended to
+//!   a bounded log next to the operation's root (`AuditLog`)
+//! - With `WalkConfig::rollback_plan`, syncs and renumberings first write
+//!   the steps that undo them (`RollbackPlan`)
+//! - With `WalkConfig::max_destructive_entries` / `max_destructive_bytes`,
+//!   deleting helpers first count their target and refuse one that is
+//!   too large (`check_destructive_target()`)
+//! - Deleting a message can be made reversible: `move_to_trash()` moves
+//!   it into its directory's `.trash/` folder, where
+//!   `WalkConfig::skip_trash` hides it until `purge_trash()` removes it
+//! - Replicas sharing a tree take `try_lock_scan()` on a directory before
+//!   changing it, and walks with `WalkConfig::skip_locked_dirs` leave a
+//!   directory another replica holds unread
+
+use std::collections::HashSet;
+use std::ffi::OsString;
+use std::fmt;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::ops::ControlFlow;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use super::clock::Clock;
+use super::{
+    DirEntry, DirWalker, SCAN_LOCK_FILE_NAME, TRASH_DIR_NAME, WalkConfig, WalkError,
+    copy_entry_verified, same_file_object, strip_root, walk_with,
+};
+
+// ============================================================================
+// SOURCE / DESTINATION GUARD
+// ============================================================================
+
+/// Refuse a destination that is the source or lies inside it.
+///
+/// The destination need not exist yet: its deepest existing ancestor is
+/// resolved instead. Two independent checks are made:
+/// - Canonical prefix: the resolved destination starts with the
+///   canonical source path (catches `..` and symlinked aliases)
+/// - File identity (Unix): no ancestor of the resolved destination has the
+///   source directory's (device, inode) (catches bind mounts and other
+///   aliases that canonicalization cannot see)
+///
+/// # Arguments
+/// * `source` - Tree that will be walked
+/// * `destination` - Where output will be written
+///
+/// # Returns
+/// * `Ok(())` - Destination is outside the source tree
+/// * `Err(WalkError::DestinationInsideSource)` - Refused
+/// * `Err(WalkError::IoError)` - Either path could not be resolved
+pub fn check_destination_outside(source: &Path, destination: &Path) -> Result<(), WalkError> {
+    let source_canonical = match fs::canonicalize(source) {
+        Ok(p) => p,
+        Err(_e) => {
+            #[cfg(debug_assertions)]
+            eprintln!("DWIO: source could not be resolved: {}", _e);
+            return Err(WalkError::IoError);
+        }
+    };
+
+    let destination_resolved = resolve_existing_prefix(destination)?;
+
+    if destination_resolved.starts_with(&source_canonical) {
+        #[cfg(debug_assertions)]
+        eprintln!("DWDS: destination is inside the source tree (path prefix)");
+        return Err(WalkError::DestinationInsideSource);
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+
+        let source_metadata = match fs::metadata(&source_canonical) {
+            Ok(m) => m,
+            Err(_e) => {
+                #[cfg(debug_assertions)]
+                eprintln!("DWIO: source could not be stat-ed: {}", _e);
+                return Err(WalkError::IoError);
+            }
+        };
+
+        // Bounded: a path has finitely many ancestors
+        for ancestor in destination_resolved.ancestors() {
+            if let Ok(ancestor_metadata) = fs::metadata(ancestor)
+                && ancestor_metadata.dev() == source_metadata.dev()
+                && ancestor_metadata.ino() == source_metadata.ino()
+            {
+                #[cfg(debug_assertions)]
+                eprintln!("DWDS: destination is inside the source tree (file identity)");
+                return Err(WalkError::DestinationInsideSource);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Canonicalize the deepest existing ancestor of `path` and re-append the
+/// components that do not exist yet.
+fn resolve_existing_prefix(path: &Path) -> Result<PathBuf, WalkError> {
+    let absolute = match std::path::absolute(path) {
+        Ok(p) => p,
+        Err(_e) => {
+            #[cfg(debug_assertions)]
+            eprintln!("DWIO: destination could not be made absolute: {}", _e);
+            return Err(WalkError::IoError);
+        }
+    };
+
+    // Bounded: a path has finitely many ancestors; the filesystem root
+    // always exists, so some ancestor canonicalizes
+    for ancestor in absolute.ancestors() {
+        if let Ok(canonical) = fs::canonicalize(ancestor) {
+            return match absolute.strip_prefix(ancestor) {
+                Ok(missing) => Ok(canonical.join(missing)),
+                Err(_) => Err(WalkError::IoError),
+            };
+        }
+    }
+
+    #[cfg(debug_assertions)]
+    eprintln!("DWIO: no ancestor of the destination could be resolved");
+    Err(WalkError::IoError)
+}
+
+// ============================================================================
+// DESTRUCTIVE TARGET GUARD
+// ============================================================================
+
+/// Refuse a destructive run whose target holds more files or bytes than
+/// the config's thresholds allow.
+///
+/// Counts the regular files (not symlinks) a walk of `target` with
+/// `config` yields, i.e. what a deleting walk could remove, and stops as
+/// soon as `max_destructive_entries` or `max_destructive_bytes` is
+/// passed. Nothing is counted when neither threshold is set, when
+/// `force_destructive` is set, or when `target` does not exist.
+///
+/// # Arguments
+/// * `target` - Tree the helper would delete from
+/// * `config` - Walk configuration of the helper, with its thresholds
+///
+/// # Returns
+/// * `Ok(())` - Within the thresholds (or not checked)
+/// * `Err(WalkError::TargetTooLarge)` - Refused
+/// * `Err(WalkError)` - Counting walk failure in strict mode
+pub fn check_destructive_target(target: &Path, config: &WalkConfig) -> Result<(), WalkError> {
+    let max_entries = config.max_destructive_entries;
+    let max_bytes = config.max_destructive_bytes;
+    if config.force_destructive
+        || (max_entries.is_none() && max_bytes.is_none())
+        || !target.is_dir()
+    {
+        return Ok(());
+    }
+
+    let mut entries: usize = 0;
+    let mut bytes: u64 = 0;
+    let mut exceeded = false;
+    walk_with(target, config.clone(), |entry| {
+        if entry.is_file() && !entry.is_symlink() {
+            entries = entries.saturating_add(1);
+            bytes = bytes.saturating_add(entry.len());
+            exceeded = max_entries.is_some_and(|limit| entries > limit)
+                || max_bytes.is_some_and(|limit| bytes > limit);
+            if exceeded {
+                return ControlFlow::Break(());
+            }
+        }
+        ControlFlow::Continue(())
+    })?;
+
+    if exceeded {
+        #[cfg(debug_assertions)]
+        eprintln!(
+            "DWTL: destructive target holds more than {:?} files / {:?} bytes, refusing",
+            max_entries, max_bytes
+        );
+        return Err(WalkError::TargetTooLarge);
+    }
+    Ok(())
+}
+
+// ============================================================================
+// MODE AND ACTION REPORT
+// ============================================================================
+
+/// Whether a mutating helper changes the filesystem or only reports what
+/// it would change.
+///
+/// Every helper in this module takes a `Mode` and returns an
+/// `ActionReport`, so any destructive operation can be previewed (and
+/// tested) with the same call that performs it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// Walk and plan, but write nothing. Read-only checks (the
+    /// source/destination guard, walking) still run.
+    DryRun,
+
+    /// Perform the changes.
+    Execute,
+}
+
+/// Kind of one planned or performed change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ActionKind {
+    /// A directory is (or would be) created.
+    CreateDir,
+
+    /// A file is (or would be) copied to the action's path.
+    CopyFile,
+
+    /// A file is (or would be) deleted.
+    DeleteFile,
+
+    /// An empty directory is (or would be) removed.
+    RemoveDir,
+
+    /// A file is (or would be) moved into a trash folder; the action's
+    /// path is its name in the trash.
+    TrashFile,
+}
+
+impl ActionKind {
+    /// Action name written to the audit log.
+    fn audit_name(self) -> &'static str {
+        match self {
+            ActionKind::CreateDir => "create_dir",
+            ActionKind::CopyFile => "copy_file",
+            ActionKind::DeleteFile => "delete_file",
+            ActionKind::RemoveDir => "remove_dir",
+            ActionKind::TrashFile => "trash_file",
+        }
+    }
+}
+
+/// One planned or performed change.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Action {
+    /// What happens.
+    pub kind: ActionKind,
+
+    /// Path that is changed (the destination path for copies).
+    pub path: PathBuf,
+}
+
+/// Uniform result of every mutating helper.
+///
+/// In `Mode::DryRun` the actions are those that *would* be performed; in
+/// `Mode::Execute` they are those that *were* performed. Failed actions
+/// are not listed (they are counted in `skipped`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActionReport {
+    /// Mode the helper ran in.
+    pub mode: Mode,
+
+    /// Changes, in the order they were planned or made.
+    pub actions: Vec<Action>,
+
+    /// File bytes copied or deleted (from discovery-time sizes in a dry
+    /// run).
+    pub bytes: u64,
+
+    /// Entries not acted on: symlinks, special files, and failures skipped
+    /// under `continue_on_error`.
+    pub skipped: usize,
+
+    /// Plan written before executing (`WalkConfig::rollback_plan`);
+    /// `None` otherwise.
+    pub rollback: Option<RollbackPlan>,
+}
+
+impl ActionReport {
+    /// Empty report for `mode`.
+    fn new(mode: Mode) -> Self {
+        ActionReport {
+            mode,
+            actions: Vec::new(),
+            bytes: 0,
+            skipped: 0,
+            rollback: None,
+        }
+    }
+
+    /// Number of actions of `kind`.
+    pub fn count(&self, kind: ActionKind) -> usize {
+        self.actions.iter().filter(|a| a.kind == kind).count()
+    }
+
+    /// Whether nothing was (or would be) changed.
+    pub fn is_empty(&self) -> bool {
+        self.actions.is_empty()
+    }
+
+    /// Record a successful (or planned) action.
+    fn record(&mut self, kind: ActionKind, path: PathBuf) {
+        self.actions.push(Action { kind, path });
+    }
+
+    /// Count a skipped entry.
+    fn skip(&mut self) {
+        self.skipped = self.skipped.saturating_add(1);
+    }
+
+    /// Apply the `continue_on_error` policy to one action's outcome.
+    fn settle(
+        &mut self,
+        outcome: Result<(), WalkError>,
+        continue_on_error: bool,
+    ) -> Result<(), WalkError> {
+        match outcome {
+            Ok(()) => Ok(()),
+            Err(_) if continue_on_error => {
+                self.skip();
+                Ok(())
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
+
+// ============================================================================
+// AUDIT LOG
+// ============================================================================
+
+/// Suffix appended to the root's name to form its audit log's name.
+pub const AUDIT_LOG_SUFFIX: &str = ".dwm-audit.log";
+
+/// Path of the audit log for operations on `root`: a sibling of `root`
+/// named after it plus `AUDIT_LOG_SUFFIX`.
+///
+/// # Returns
+/// `None` if `root` has no file name (a filesystem root, `.` or `..`).
+pub fn audit_log_path(root: &Path) -> Option<PathBuf> {
+    let name = root.file_name()?;
+    let mut log_name = name.to_os_string();
+    log_name.push(AUDIT_LOG_SUFFIX);
+    Some(root.with_file_name(log_name))
+}
+
+/// Open the audit log at `path` for appending, and confirm the handle is
+/// the file that was checked.
+///
+/// `open` follows links, so a link planted after the caller's check is
+/// caught afterwards: the opened file must be the regular file now at
+/// the path (not a link to elsewhere) and, if one was there, the file
+/// `checked` describes (`same_file_object()`).
+///
+/// # Returns
+/// * `Ok((file, len))` - Handle and current log length
+/// * `Err(WalkError::SpecialFile)` - The handle is not the checked file
+/// * `Err(WalkError::IoError)` - The log cannot be opened
+fn open_audit_file(
+    path: &Path,
+    checked: Option<&fs::Metadata>,
+) -> Result<(fs::File, u64), WalkError> {
+    let file = match fs::OpenOptions::new().append(true).create(true).open(path) {
+        Ok(file) => file,
+        Err(_e) => {
+            #[cfg(debug_assertions)]
+            eprintln!("DWIO: audit log could not be opened: {}", _e);
+            return Err(WalkError::IoError);
+        }
+    };
+
+    match (file.metadata().ok(), fs::symlink_metadata(path).ok()) {
+        (Some(opened), Some(at_path))
+            if opened.is_file()
+                && at_path.is_file()
+                && same_file_object(&at_path, &opened)
+                && checked.is_none_or(|checked| same_file_object(checked, &opened)) =>
+        {
+            Ok((file, opened.len()))
+        }
+        _ => {
+            #[cfg(debug_assertions)]
+            eprintln!("DWSF: audit log handle is not the checked regular file");
+            Err(WalkError::SpecialFile)
+        }
+    }
+}
+
+/// Append-only action log of one mutating helper run
+/// (`WalkConfig::audit_log`).
+///
+/// One line per performed action, tab-separated:
+///
+/// ```text
+/// <unix seconds>\t<operation>\t<action>\t<relative path>[\t<relative path>]
+/// ```
+///
+/// e.g. `1767225600\tdelete_files\tdelete_file\tsub/3__msg.toml`.
+/// Operations are the helper names; actions are `create_dir`,
+/// `copy_file`, `delete_file`, `remove_dir`, `trash_file`, `rename`
+/// (old and new path), and `error` with the `WalkError::code()` that
+/// ended a run early. Paths are relative to the operation's root and written with
+/// control characters escaped, so every action stays on one line.
+///
+/// The log is opened before anything is changed: a run whose log cannot
+/// be opened is refused. Later write failures stop logging but not the
+/// run, which would otherwise be left half done.
+pub(super) struct AuditLog {
+    /// Open log; `None` when not logging (disabled, dry run, or given
+    /// up after a write failure).
+    file: Option<fs::File>,
+
+    /// Root the logged paths are relative to.
+    root: PathBuf,
+
+    /// Helper name written on every line.
+    operation: &'static str,
+
+    /// Source of the line timestamps (`WalkConfig::clock`).
+    clock: Arc<dyn Clock>,
+
+    /// Current size of the log file.
+    written: u64,
+
+    /// Size the log may grow to.
+    max_bytes: u64,
+
+    /// Actions of the run's `ActionReport` already logged.
+    logged: usize,
+
+    /// Lines dropped because the log is full.
+    dropped: usize,
+}
+
+impl AuditLog {
+    /// Open the audit log for `operation` on `root`, if `config` enables
+    /// one and `mode` executes; otherwise return a log that writes
+    /// nothing.
+    ///
+    /// # Returns
+    /// * `Ok(AuditLog)` - Ready to log (or disabled)
+    /// * `Err(WalkError::SpecialFile)` - The log path is a symlink or not
+    ///   a regular file, or was swapped while it was being opened
+    /// * `Err(WalkError::IoError)` - `root` has no file name, or the log
+    ///   cannot be opened
+    pub(super) fn open(
+        root: &Path,
+        config: &WalkConfig,
+        mode: Mode,
+        operation: &'static str,
+    ) -> Result<Self, WalkError> {
+        let mut log = AuditLog {
+            file: None,
+            root: root.to_path_buf(),
+            operation,
+            clock: config.clock.clone(),
+            written: 0,
+            max_bytes: 0,
+            logged: 0,
+            dropped: 0,
+        };
+        let max_bytes = match config.audit_log_max_bytes {
+            Some(max_bytes) if mode == Mode::Execute => max_bytes,
+            _ => return Ok(log),
+        };
+
+        let path = match audit_log_path(root) {
+            Some(path) => path,
+            None => {
+                #[cfg(debug_assertions)]
+                eprintln!("DWIO: audit log needs a root with a file name");
+                return Err(WalkError::IoError);
+            }
+        };
+        // Never append through a link planted at the log path
+        let before = fs::symlink_metadata(&path).ok();
+        if before.as_ref().is_some_and(|m| !m.is_file()) {
+            #[cfg(debug_assertions)]
+            eprintln!("DWSF: audit log path is not a regular file");
+            return Err(WalkError::SpecialFile);
+        }
+
+        let (file, written) = open_audit_file(&path, before.as_ref())?;
+        log.written = written;
+        log.file = Some(file);
+        log.max_bytes = max_bytes;
+        Ok(log)
+    }
+
+    /// Log the actions `report` gained since the last call.
+    ///
+    /// Called after every walked entry, so a crash loses at most the
+    /// action in progress.
+    pub(super) fn catch_up(&mut self, report: &ActionReport) {
+        for action in report.actions.iter().skip(self.logged) {
+            let path = self.relative(&action.path);
+            self.write_line(action.kind.audit_name(), &[&path]);
+        }
+        self.logged = report.actions.len();
+    }
+
+    /// Log one performed rename.
+    pub(super) fn rename(&mut self, from: &Path, to: &Path) {
+        let from = self.relative(from);
+        let to = self.relative(to);
+        self.write_line("rename", &[&from, &to]);
+    }
+
+    /// End the run: log the error that ended it, if any, and pass the
+    /// outcome through.
+    pub(super) fn finish(mut self, outcome: Result<(), WalkError>) -> Result<(), WalkError> {
+        if let Err(e) = outcome {
+            self.write_line("error", &[e.code()]);
+        }
+
+        #[cfg(debug_assertions)]
+        if self.dropped > 0 {
+            eprintln!("DWEL: audit log full, {} lines dropped", self.dropped);
+        }
+        outcome
+    }
+
+    /// `path` relative to the root, escaped onto one line.
+    fn relative(&self, path: &Path) -> String {
+        match strip_root(path, &self.root) {
+            Ok(relative) => escape_line_field(relative),
+            // Never log an absolute path
+            Err(_) => "?".to_string(),
+        }
+    }
+
+    /// Append one line unless the log is off or full.
+    fn write_line(&mut self, action: &str, fields: &[&str]) {
+        let file = match &mut self.file {
+            Some(file) => file,
+            None => return,
+        };
+
+        let seconds = self
+            .clock
+            .now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |since| since.as_secs());
+        let mut line = format!("{}\t{}\t{}", seconds, self.operation, action);
+        for field in fields {
+            line.push('\t');
+            line.push_str(field);
+        }
+        line.push('\n');
+
+        let length = line.len() as u64;
+        if self.written.saturating_add(length) > self.max_bytes {
+            self.dropped = self.dropped.saturating_add(1);
+            return;
+        }
+        // One write_all per line: appends from concurrent runs do not
+        // interleave within a line
+        match file.write_all(line.as_bytes()) {
+            Ok(()) => self.written = self.written.saturating_add(length),
+            Err(_e) => {
+                #[cfg(debug_assertions)]
+                eprintln!("DWIO: audit log write failed, logging stopped: {}", _e);
+                self.file = None;
+            }
+        }
+    }
+}
+
+/// A relative path as one field of a tab-separated line: lossy UTF-8 with
+/// tabs, newlines and other control characters escaped.
+fn escape_line_field(path: &Path) -> String {
+    path.to_string_lossy().escape_debug().to_string()
+}
+
+// ============================================================================
+// ROLLBACK PLANS
+// ============================================================================
+
+/// Infix between the root's name and the timestamp in a rollback plan
+/// directory's name.
+pub const ROLLBACK_DIR_INFIX: &str = ".dwm-rollback.";
+
+/// Name of the plan file inside a rollback plan directory.
+pub const ROLLBACK_PLAN_FILE: &str = "plan";
+
+/// Subdirectory of a rollback plan directory holding the file backups.
+pub const ROLLBACK_FILES_DIR: &str = "files";
+
+/// Most name suffixes tried when several plans share one second.
+const MAX_ROLLBACK_DIR_ATTEMPTS: usize = 100;
+
+/// One step that undoes one action of a run. Paths are relative to the
+/// operation's root.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RollbackStep {
+    /// Delete a file the run created.
+    DeleteFile(PathBuf),
+
+    /// Copy the backup `files/<path>` in the plan directory back to
+    /// `path` (a file the run overwrote or deleted).
+    CopyBack(PathBuf),
+
+    /// Remove a directory the run created.
+    RemoveDir(PathBuf),
+
+    /// Rename `from` (the run's new name) back to `to`.
+    Rename {
+        /// Name after the run.
+        from: PathBuf,
+
+        /// Name before the run.
+        to: PathBuf,
+    },
+}
+
+/// Steps that undo a `sync_tree()` or `renumber_messages()` run, written
+/// before the run executes (`WalkConfig::rollback_plan`).
+///
+/// The plan directory sits next to the root and holds:
+/// - `plan`: one tab-separated step per line, in the order to apply
+///   them: `delete_file <path>`, `copy_back <path>`, `remove_dir <path>`
+///   or `rename <from> <to>`, with control characters in paths escaped
+/// - `files/`: the backups `copy_back` restores, at their relative paths
+///
+/// The plan is built from a dry run just before executing; a file changed
+/// by someone else in between is not covered. Renames should be applied
+/// as one batch (via temp names), as the renumbering did: a name being
+/// restored may still be held by another renamed file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RollbackPlan {
+    /// Directory holding the plan file and the backups.
+    pub dir: PathBuf,
+
+    /// Steps in the order to apply them.
+    pub steps: Vec<RollbackStep>,
+}
+
+/// Back up what `steps` restore and write the plan next to `root`.
+///
+/// # Arguments
+/// * `root` - Root of the operation about to run
+/// * `config` - Supplies the clock that stamps the directory name
+/// * `steps` - Undo steps in the order to apply them
+///
+/// # Returns
+/// * `Ok(RollbackPlan)` - Plan and backups written
+/// * `Err(WalkError::SpecialFile)` - A file to back up is not a regular
+///   file
+/// * `Err(WalkError::IoError)` - `root` has no file name, or a write
+///   failed (the partial plan directory is left for inspection)
+pub(super) fn write_rollback_plan(
+    root: &Path,
+    config: &WalkConfig,
+    steps: Vec<RollbackStep>,
+) -> Result<RollbackPlan, WalkError> {
+    let dir = create_rollback_dir(root, config)?;
+
+    let mut plan = String::new();
+    for step in &steps {
+        match step {
+            RollbackStep::DeleteFile(path) => {
+                plan.push_str("delete_file\t");
+                plan.push_str(&escape_line_field(path));
+            }
+            RollbackStep::CopyBack(path) => {
+                back_up_file(&root.join(path), &dir.join(ROLLBACK_FILES_DIR).join(path))?;
+                plan.push_str("copy_back\t");
+                plan.push_str(&escape_line_field(path));
+            }
+            RollbackStep::RemoveDir(path) => {
+                plan.push_str("remove_dir\t");
+                plan.push_str(&escape_line_field(path));
+            }
+            RollbackStep::Rename { from, to } => {
+                plan.push_str("rename\t");
+                plan.push_str(&escape_line_field(from));
+                plan.push('\t');
+                plan.push_str(&escape_line_field(to));
+            }
+        }
+        plan.push('\n');
+    }
+
+    let written = fs::File::create(dir.join(ROLLBACK_PLAN_FILE)).and_then(|mut file| {
+        file.write_all(plan.as_bytes())
+            .and_then(|()| file.sync_all())
+    });
+    if let Err(_e) = written {
+        #[cfg(debug_assertions)]
+        eprintln!("DWIO: rollback plan could not be written: {}", _e);
+        return Err(WalkError::IoError);
+    }
+
+    Ok(RollbackPlan { dir, steps })
+}
+
+/// Create a fresh `<root name>.dwm-rollback.<seconds>[-n]` directory.
+fn create_rollback_dir(root: &Path, config: &WalkConfig) -> Result<PathBuf, WalkError> {
+    let (name, parent) = match (root.file_name(), root.parent()) {
+        (Some(name), Some(parent)) => (name, parent),
+        _ => {
+            #[cfg(debug_assertions)]
+            eprintln!("DWIO: rollback plan needs a root with a file name");
+            return Err(WalkError::IoError);
+        }
+    };
+    if !parent.as_os_str().is_empty()
+        && let Err(_e) = fs::create_dir_all(parent)
+    {
+        #[cfg(debug_assertions)]
+        eprintln!("DWIO: rollback plan parent could not be created: {}", _e);
+        return Err(WalkError::IoError);
+    }
+
+    let seconds = config
+        .clock
+        .now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs());
+    // Bounded: a few plans per second at most in practice
+    for attempt in 0..MAX_ROLLBACK_DIR_ATTEMPTS {
+        let mut dir_name = name.to_os_string();
+        dir_name.push(ROLLBACK_DIR_INFIX);
+        dir_name.push(seconds.to_string());
+        if attempt > 0 {
+            dir_name.push(format!("-{}", attempt));
+        }
+        let dir = root.with_file_name(dir_name);
+        match fs::create_dir(&dir) {
+            Ok(()) => return Ok(dir),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(_e) => {
+                #[cfg(debug_assertions)]
+                eprintln!("DWIO: rollback plan directory could not be created: {}", _e);
+                return Err(WalkError::IoError);
+            }
+        }
+    }
+
+    #[cfg(debug_assertions)]
+    eprintln!("DWRC: no free rollback plan directory name");
+    Err(WalkError::RenameConflict)
+}
+
+/// Copy the regular file `source` to `backup`, creating its parents.
+fn back_up_file(source: &Path, backup: &Path) -> Result<(), WalkError> {
+    if !fs::symlink_metadata(source).is_ok_and(|m| m.is_file()) {
+        #[cfg(debug_assertions)]
+        eprintln!("DWSF: rollback backup source is not a regular file");
+        return Err(WalkError::SpecialFile);
+    }
+
+    let copied = match backup.parent() {
+        Some(parent) => fs::create_dir_all(parent),
+        None => Ok(()),
+    }
+    .and_then(|()| fs::copy(source, backup));
+    match copied {
+        Ok(_) => Ok(()),
+        Err(_e) => {
+            #[cfg(debug_assertions)]
+            eprintln!("DWIO: rollback backup could not be written: {}", _e);
+            Err(WalkError::IoError)
+        }
+    }
+}
+
+/// Undo steps for the actions of a `sync_tree()` dry run, in reverse
+/// order: files first, then the directories that held them.
+fn sync_rollback_steps(destination: &Path, planned: &ActionReport) -> Vec<RollbackStep> {
+    let mut steps = Vec::with_capacity(planned.actions.len());
+    for action in planned.actions.iter().rev() {
+        let relative = match strip_root(&action.path, destination) {
+            Ok(relative) => relative.to_path_buf(),
+            Err(_) => continue,
+        };
+        let step = match action.kind {
+            ActionKind::CreateDir => RollbackStep::RemoveDir(relative),
+            ActionKind::CopyFile if fs::symlink_metadata(&action.path).is_err() => {
+                RollbackStep::DeleteFile(relative)
+            }
+            ActionKind::CopyFile | ActionKind::DeleteFile => RollbackStep::CopyBack(relative),
+            // Syncs never remove directories or trash files
+            ActionKind::RemoveDir | ActionKind::TrashFile => continue,
+        };
+        steps.push(step);
+    }
+    steps
+}
+
+// ============================================================================
+// TWO-PHASE RENAME
+// ============================================================================
+
+/// Name prefix of staged files and rename journals.
+///
+/// Starts with `.~`, so `is_temp_file_name()` matches (and
+/// `skip_temp_files` hides) every staged file, and no staged name has a
+/// valid numeric prefix.
+const STAGING_PREFIX: &str = ".~dwm.";
+
+/// Name prefix of files staged by a journaled rename; the rest of the
+/// name is the rename's index in the journal (`.~dwm.rn.0`, ...).
+///
+/// Kept apart from the staged copies of `copy_staged()` (plain
+/// `STAGING_PREFIX`): a copy left behind by a crashed `copy_tree()` must
+/// never be taken for a message that phase 1 already moved. Being short
+/// and fixed, the staged names fit wherever the final names do.
+const RENAME_STAGING_PREFIX: &str = ".~dwm.rn.";
+
+/// Longest file name accepted, in bytes (the usual `NAME_MAX`).
+const MAX_NAME_BYTES: usize = 255;
+
+/// Journal being written; nothing has been renamed yet.
+const JOURNAL_PART: &str = ".~dwm.journal.part";
+
+/// Journal of a rename in phase 1 (moving files to staging names).
+const JOURNAL_STAGE: &str = ".~dwm.journal.stage";
+
+/// Journal of a rename in phase 2 (moving staged files to final names).
+const JOURNAL_COMMIT: &str = ".~dwm.journal.commit";
+
+/// Largest journal `resume_renames()` will read.
+const MAX_JOURNAL_BYTES: u64 = 16 * 1024 * 1024;
+
+/// Staging path for `target`: same directory, `STAGING_PREFIX` + name.
+///
+/// A name with no room for the prefix within `MAX_NAME_BYTES` is staged
+/// as `STAGING_PREFIX` + `cp.` + the FNV-1a hash of the name instead, so
+/// every name that fits on disk can be staged.
+///
+/// # Returns
+/// `None` if `target` has no file name.
+pub(super) fn staging_path(target: &Path) -> Option<PathBuf> {
+    let name = target.file_name()?;
+    let mut staged = OsString::from(STAGING_PREFIX);
+    if STAGING_PREFIX.len().saturating_add(name.len()) <= MAX_NAME_BYTES {
+        staged.push(name);
+    } else {
+        let hash = name
+            .as_encoded_bytes()
+            .iter()
+            .fold(0xCBF2_9CE4_8422_2325_u64, |hash, byte| {
+                (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01B3)
+            });
+        staged.push(format!("cp.{:016x}", hash));
+    }
+    Some(target.with_file_name(staged))
+}
+
+/// Counts filesystem steps of a two-phase rename; when the budget runs
+/// out the rename stops as if the process had died there.
+///
+/// Only crash-simulation tests pass a finite budget.
+struct StepBudget(usize);
+
+impl StepBudget {
+    /// Whether another step may run (consumes one).
+    fn take(&mut self) -> bool {
+        match self.0.checked_sub(1) {
+            Some(rest) => {
+                self.0 = rest;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Rename files within `dir` in two journaled phases, so that at no
+/// point do old and new names coexist.
+///
+/// 1. The plan is written to a journal (`.part`, then renamed to
+///    `.stage`, so a journal is never half-written)
+/// 2. Phase 1: every `from` is renamed to its staging name
+///    (`RENAME_STAGING_PREFIX` + its index in the journal)
+/// 3. The journal is renamed to `.commit`
+/// 4. Phase 2: every staged file is renamed to its `to`
+/// 5. The journal is removed
+///
+/// A crash leaves old names plus staged files (phase 1) or staged files
+/// plus new names (phase 2); staged names (`RENAME_STAGING_PREFIX`) are
+/// temp names with no numeric prefix. `resume_renames()` finishes an
+/// interrupted run.
+///
+/// Before the journal is written, every `from` must exist, every `to`
+/// must fit in `MAX_NAME_BYTES` and no staged name may be taken; only a
+/// resumed run treats a staged file as one phase 1 already moved.
+///
+/// # Arguments
+/// * `dir` - Directory holding all files
+/// * `renames` - `(from, to)` file names (single components); targets
+///   must be distinct
+/// * `max_steps` - Step budget (`usize::MAX` outside crash simulation)
+///
+/// # Returns
+/// * `Ok(())` - All files renamed (or the budget ran out)
+/// * `Err(WalkError::RenameConflict)` - An unfinished journal exists, a
+///   `from` is missing or a staged name is taken (nothing written), or a
+///   target appeared; the journal stays for `resume_renames()`
+/// * `Err(WalkError::InvalidName)` - A `to` is longer than
+///   `MAX_NAME_BYTES` (nothing written)
+/// * `Err(WalkError)` - Journal or rename failure
+pub(super) fn rename_two_phase(
+    dir: &Path,
+    renames: &[(String, String)],
+    max_steps: usize,
+) -> Result<(), WalkError> {
+    let mut steps = StepBudget(max_steps);
+
+    if dir.join(JOURNAL_STAGE).exists() || dir.join(JOURNAL_COMMIT).exists() {
+        #[cfg(debug_assertions)]
+        eprintln!("DWRC: unfinished rename journal; run resume_renames first");
+        return Err(WalkError::RenameConflict);
+    }
+
+    let mut journal: Vec<u8> = Vec::new();
+    for (index, (from, to)) in renames.iter().enumerate() {
+        check_journal_name(from)?;
+        check_journal_name(to)?;
+        if to.len() > MAX_NAME_BYTES {
+            #[cfg(debug_assertions)]
+            eprintln!("DWIN: rename target name too long");
+            return Err(WalkError::InvalidName);
+        }
+        if fs::symlink_metadata(dir.join(from)).is_err()
+            || fs::symlink_metadata(rename_staging_path(dir, index)).is_ok()
+        {
+            #[cfg(debug_assertions)]
+            eprintln!("DWRC: rename source missing or staging name taken");
+            return Err(WalkError::RenameConflict);
+        }
+        for name in [from, to] {
+            journal.extend_from_slice(name.as_bytes());
+            journal.push(0);
+        }
+    }
+
+    if !steps.take() {
+        return Ok(());
+    }
+    let written = fs::File::create(dir.join(JOURNAL_PART))
+        .and_then(|mut file| file.write_all(&journal).and_then(|()| file.sync_all()));
+    if let Err(_e) = written {
+        #[cfg(debug_assertions)]
+        eprintln!("DWIO: rename journal could not be written: {}", _e);
+        return Err(WalkError::IoError);
+    }
+
+    if !steps.take() {
+        return Ok(());
+    }
+    rename_io(&dir.join(JOURNAL_PART), &dir.join(JOURNAL_STAGE))?;
+
+    run_journal(dir, renames, JournalState::Fresh, &mut steps)
+}
+
+/// Finish a two-phase rename in `dir` interrupted by a crash.
+///
+/// Reads the journal left by the interrupted run and completes it: a
+/// phase-1 journal stages the remaining files and then commits, a
+/// phase-2 journal commits the remaining staged files. A journal that was
+/// never completed is discarded (nothing had been renamed).
+///
+/// # Arguments
+/// * `dir` - Directory the interrupted rename ran in
+///
+/// # Returns
+/// * `Ok(n)` - Renames in the finished journal (0 if there was none)
+/// * `Err(WalkError::RenameConflict)` - A target is taken; fix it and
+///   resume again
+/// * `Err(WalkError::PathEscape)` - The journal names a path outside `dir`
+/// * `Err(WalkError)` - Journal unreadable or malformed, or rename failure
+///
+/// # Project Context
+/// Run before loading or renumbering a channel after an unclean
+/// shutdown.
+pub fn resume_renames(dir: &Path) -> Result<usize, WalkError> {
+    let (journal_name, committed) = if dir.join(JOURNAL_COMMIT).exists() {
+        (JOURNAL_COMMIT, true)
+    } else if dir.join(JOURNAL_STAGE).exists() {
+        (JOURNAL_STAGE, false)
+    } else {
+        if dir.join(JOURNAL_PART).exists()
+            && let Err(_e) = fs::remove_file(dir.join(JOURNAL_PART))
+        {
+            #[cfg(debug_assertions)]
+            eprintln!(
+                "DWIO: incomplete rename journal could not be removed: {}",
+                _e
+            );
+            return Err(WalkError::IoError);
+        }
+        return Ok(0);
+    };
+
+    let renames = read_journal(&dir.join(journal_name))?;
+    let state = if committed {
+        JournalState::Committed
+    } else {
+        JournalState::Staging
+    };
+    run_journal(dir, &renames, state, &mut StepBudget(usize::MAX))?;
+    Ok(renames.len())
+}
+
+/// Where `run_journal()` starts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum JournalState {
+    /// A new rename: nothing is staged yet.
+    Fresh,
+
+    /// A resumed `.stage` journal: phase 1 ran for some files.
+    Staging,
+
+    /// A resumed `.commit` journal: phase 1 is done.
+    Committed,
+}
+
+/// Staging path of the journal's `index`-th rename:
+/// `RENAME_STAGING_PREFIX` + index, in `dir`.
+fn rename_staging_path(dir: &Path, index: usize) -> PathBuf {
+    dir.join(format!("{}{}", RENAME_STAGING_PREFIX, index))
+}
+
+/// Phases 1 (unless `Committed`) and 2 of a journaled rename.
+///
+/// Idempotent per file when resumed, so a resumed run skips what was
+/// already done; a fresh run refuses a staged name that is taken.
+fn run_journal(
+    dir: &Path,
+    renames: &[(String, String)],
+    state: JournalState,
+    steps: &mut StepBudget,
+) -> Result<(), WalkError> {
+    if state != JournalState::Committed {
+        for (index, (from, _)) in renames.iter().enumerate() {
+            let staged = rename_staging_path(dir, index);
+            if fs::symlink_metadata(&staged).is_ok() {
+                if state == JournalState::Staging {
+                    continue;
+                }
+                #[cfg(debug_assertions)]
+                eprintln!("DWRC: rename staging name appeared during phase 1");
+                return Err(WalkError::RenameConflict);
+            }
+            if !steps.take() {
+                return Ok(());
+            }
+            rename_io(&dir.join(from), &staged)?;
+        }
+
+        if !steps.take() {
+            return Ok(());
+        }
+        rename_io(&dir.join(JOURNAL_STAGE), &dir.join(JOURNAL_COMMIT))?;
+    }
+
+    for (index, (_, to)) in renames.iter().enumerate() {
+        let staged = rename_staging_path(dir, index);
+        if fs::symlink_metadata(&staged).is_err() {
+            continue;
+        }
+        let target = dir.join(to);
+        if fs::symlink_metadata(&target).is_ok() {
+            #[cfg(debug_assertions)]
+            eprintln!("DWRC: rename target appeared during phase 2");
+            return Err(WalkError::RenameConflict);
+        }
+        if !steps.take() {
+            return Ok(());
+        }
+        rename_io(&staged, &target)?;
+    }
+
+    if !steps.take() {
+        return Ok(());
+    }
+    if let Err(_e) = fs::remove_file(dir.join(JOURNAL_COMMIT)) {
+        #[cfg(debug_assertions)]
+        eprintln!("DWIO: rename journal could not be removed: {}", _e);
+        return Err(WalkError::IoError);
+    }
+    Ok(())
+}
+
+/// Read `(from, to)` pairs from a journal (NUL-separated names).
+fn read_journal(path: &Path) -> Result<Vec<(String, String)>, WalkError> {
+    let mut bytes: Vec<u8> = Vec::new();
+    let read =
+        fs::File::open(path).and_then(|file| file.take(MAX_JOURNAL_BYTES).read_to_end(&mut bytes));
+    if let Err(_e) = read {
+        #[cfg(debug_assertions)]
+        eprintln!("DWIO: rename journal could not be read: {}", _e);
+        return Err(WalkError::IoError);
+    }
+
+    let text = match String::from_utf8(bytes) {
+        Ok(text) => text,
+        Err(_) => {
+            #[cfg(debug_assertions)]
+            eprintln!("DWIO: rename journal is not UTF-8");
+            return Err(WalkError::IoError);
+        }
+    };
+
+    let names: Vec<&str> = text.split_terminator('\0').collect();
+    if !names.len().is_multiple_of(2) || !text.is_empty() && !text.ends_with('\0') {
+        #[cfg(debug_assertions)]
+        eprintln!("DWIO: rename journal is truncated");
+        return Err(WalkError::IoError);
+    }
+
+    let mut renames: Vec<(String, String)> = Vec::with_capacity(names.len() / 2);
+    for pair in names.chunks_exact(2) {
+        check_journal_name(pair[0])?;
+        check_journal_name(pair[1])?;
+        renames.push((pair[0].to_string(), pair[1].to_string()));
+    }
+    Ok(renames)
+}
+
+/// Refuse a journal name that is not a single plain path component.
+fn check_journal_name(name: &str) -> Result<(), WalkError> {
+    let mut components = Path::new(name).components();
+    match (components.next(), components.next()) {
+        (Some(std::path::Component::Normal(component)), None) if component == name => Ok(()),
+        _ => {
+            #[cfg(debug_assertions)]
+            eprintln!("DWPE: rename journal name is not a plain file name");
+            Err(WalkError::PathEscape)
+        }
+    }
+}
+
+/// `fs::rename` mapped to `WalkError::IoError`.
+fn rename_io(from: &Path, to: &Path) -> Result<(), WalkError> {
+    fs::rename(from, to).map_err(|_e| {
+        #[cfg(debug_assertions)]
+        eprintln!("DWIO: rename failed: {}", _e);
+        WalkError::IoError
+    })
+}
+
+// ============================================================================
+// TREE COPY AND SYNC
+// ============================================================================
+
+/// Copy the files of a walked tree into `destination`, preserving layout.
+///
+/// The walk uses `config` (depth, filters, symlink policy). Regular files
+/// are copied with `copy_entry_verified()`, so special files and files
+/// swapped after the scan are never copied; symlinks are not recreated.
+/// Each file is written to a staging name and renamed over its target,
+/// so an existing destination file is replaced whole or not at all.
+///
+/// # Arguments
+/// * `source` - Tree to copy
+/// * `destination` - Target directory (created if missing); must not lie
+///   inside `source`
+/// * `config` - Walk configuration; `continue_on_error` also decides
+///   whether a failed copy aborts the run
+/// * `mode` - `Mode::DryRun` to only report
+///
+/// # Returns
+/// * `Ok(ActionReport)` - `CreateDir` and `CopyFile` actions
+/// * `Err(WalkError::DestinationInsideSource)` - Refused before any write
+/// * `Err(WalkError)` - Walk or copy failure in strict mode
+pub fn copy_tree(
+    source: &Path,
+    destination: &Path,
+    config: WalkConfig,
+    mode: Mode,
+) -> Result<ActionReport, WalkError> {
+    check_destination_outside(source, destination)?;
+
+    let mut audit = AuditLog::open(destination, &config, mode, "copy_tree")?;
+    let mut report = ActionReport::new(mode);
+    let outcome = copy_walk(source, destination, config, false, &mut report, &mut audit);
+    audit.catch_up(&report);
+    audit.finish(outcome)?;
+    Ok(report)
+}
+
+/// Make `destination` mirror the files of a walked `source` tree.
+///
+/// Files missing from `destination`, or differing in size, or older than
+/// the source are copied (as `copy_tree()` does); regular files in
+/// `destination` with no counterpart in `source` are deleted, each
+/// checked with `DirEntry::verify_unchanged()` first. Both sides are
+/// walked with `config`, so filtered entries are neither copied nor
+/// deleted. Directories left empty are not removed (use
+/// `prune_empty_dirs()`).
+///
+/// # Arguments
+/// * `source` - Tree to mirror
+/// * `destination` - Mirror directory (created if missing); must not lie
+///   inside `source`
+/// * `config` - Walk configuration for both sides; with
+///   `rollback_plan(true)` an executing run first writes its undo plan
+///   (`ActionReport::rollback`)
+/// * `mode` - `Mode::DryRun` to only report
+///
+/// # Returns
+/// * `Ok(ActionReport)` - `CreateDir`, `CopyFile` and `DeleteFile` actions
+/// * `Err(WalkError::DestinationInsideSource)` - Refused before any write
+/// * `Err(WalkError::TargetTooLarge)` - `destination` is over the
+///   destructive thresholds (`check_destructive_target()`); refused
+///   before any write
+/// * `Err(WalkError)` - Walk, copy or delete failure in strict mode
+pub fn sync_tree(
+    source: &Path,
+    destination: &Path,
+    config: WalkConfig,
+    mode: Mode,
+) -> Result<ActionReport, WalkError> {
+    check_destination_outside(source, destination)?;
+    check_destructive_target(destination, &config)?;
+
+    let rollback = if config.rollback_plan && mode == Mode::Execute {
+        let mut planned = ActionReport::new(Mode::DryRun);
+        let mut no_audit = AuditLog::open(destination, &config, Mode::DryRun, "sync_tree")?;
+        sync_walk(
+            source,
+            destination,
+            config.clone(),
+            &mut planned,
+            &mut no_audit,
+        )?;
+        let steps = sync_rollback_steps(destination, &planned);
+        Some(write_rollback_plan(destination, &config, steps)?)
+    } else {
+        None
+    };
+
+    let mut audit = AuditLog::open(destination, &config, mode, "sync_tree")?;
+    let mut report = ActionReport::new(mode);
+    report.rollback = rollback;
+    let outcome = sync_walk(source, destination, config, &mut report, &mut audit);
+    audit.catch_up(&report);
+    audit.finish(outcome)?;
+    Ok(report)
+}
+
+/// Copy and delete phases of `sync_tree()`.
+fn sync_walk(
+    source: &Path,
+    destination: &Path,
+    config: WalkConfig,
+    report: &mut ActionReport,
+    audit: &mut AuditLog,
+) -> Result<(), WalkError> {
+    let continue_on_error = config.continue_on_error;
+    copy_walk(source, destination, config.clone(), true, report, audit)?;
+
+    // Nothing to delete if the mirror does not exist (dry run into a new
+    // destination)
+    if !destination.is_dir() {
+        return Ok(());
+    }
+
+    for entry_result in DirWalker::new(destination, config) {
+        let entry = entry_result?;
+        if !entry.is_file() || entry.is_symlink() {
+            continue;
+        }
+
+        let counterpart = match strip_root(entry.path(), destination) {
+            Ok(relative) => source.join(relative),
+            Err(_) => {
+                report.skip();
+                continue;
+            }
+        };
+        let still_in_source = fs::symlink_metadata(&counterpart).is_ok_and(|m| m.is_file());
+        if !still_in_source {
+            let outcome = delete_walked_file(&entry, report);
+            audit.catch_up(report);
+            report.settle(outcome, continue_on_error)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Shared walk behind `copy_tree()` and `sync_tree()`.
+///
+/// # Arguments
+/// * `only_changed` - Skip files whose destination copy has the same
+///   size and is at least as new (sync); `false` copies everything
+fn copy_walk(
+    source: &Path,
+    destination: &Path,
+    config: WalkConfig,
+    only_changed: bool,
+    report: &mut ActionReport,
+    audit: &mut AuditLog,
+) -> Result<(), WalkError> {
+    let continue_on_error = config.continue_on_error;
+    let execute = report.mode == Mode::Execute;
+
+    if execute && let Err(_e) = fs::create_dir_all(destination) {
+        #[cfg(debug_assertions)]
+        eprintln!("DWIO: copy_tree could not create destination: {}", _e);
+        return Err(WalkError::IoError);
+    }
+
+    for entry_result in DirWalker::new(source, config) {
+        let entry = entry_result?;
+
+        let relative = match strip_root(entry.path(), source) {
+            Ok(r) => r,
+            Err(_) => {
+                report.skip();
+                continue;
+            }
+        };
+        let target = destination.join(relative);
+
+        let outcome = if entry.is_dir() && !entry.is_symlink() {
+            if target.is_dir() {
+                Ok(())
+            } else if execute {
+                fs::create_dir_all(&target)
+                    .map(|()| report.record(ActionKind::CreateDir, target))
+                    .map_err(|_e| {
+                        #[cfg(debug_assertions)]
+                        eprintln!("DWIO: copy_tree could not create directory: {}", _e);
+                        WalkError::IoError
+                    })
+            } else {
+                report.record(ActionKind::CreateDir, target);
+                Ok(())
+            }
+        } else if entry.is_file() && !entry.is_symlink() {
+            if only_changed && destination_is_current(&entry, &target) {
+                Ok(())
+            } else if execute {
+                // Parents normally exist already (breadth-first order), but
+                // yield_directories(false) configs never create them
+                let parent_ready = match target.parent() {
+                    Some(parent) => fs::create_dir_all(parent).map_err(|_e| {
+                        #[cfg(debug_assertions)]
+                        eprintln!("DWIO: copy_tree could not create parent: {}", _e);
+                        WalkError::IoError
+                    }),
+                    None => Ok(()),
+                };
+                parent_ready
+                    .and_then(|()| copy_staged(&entry, &target))
+                    .map(|bytes| {
+                        report.bytes = report.bytes.saturating_add(bytes);
+                        report.record(ActionKind::CopyFile, target);
+                    })
+            } else {
+                report.bytes = report.bytes.saturating_add(entry.len());
+                report.record(ActionKind::CopyFile, target);
+                Ok(())
+            }
+        } else {
+            report.skip();
+            Ok(())
+        };
+
+        audit.catch_up(report);
+        report.settle(outcome, continue_on_error)?;
+    }
+
+    Ok(())
+}
+
+/// Copy `entry` to the staging name of `target`, then rename it into
+/// place; a failed copy removes its staged file.
+fn copy_staged(entry: &DirEntry, target: &Path) -> Result<u64, WalkError> {
+    let staged = match staging_path(target) {
+        Some(staged) => staged,
+        None => return Err(WalkError::IoError),
+    };
+
+    let copied = copy_entry_verified(entry, &staged).and_then(|bytes| {
+        rename_io(&staged, target)?;
+        Ok(bytes)
+    });
+    if copied.is_err() {
+        let _ = fs::remove_file(&staged);
+    }
+    copied
+}
+
+/// Whether `target` already holds an up-to-date copy of `entry` (same
+/// size, modified no earlier than the source).
+fn destination_is_current(entry: &DirEntry, target: &Path) -> bool {
+    let target_metadata = match fs::symlink_metadata(target) {
+        Ok(m) if m.is_file() => m,
+        _ => return false,
+    };
+    if target_metadata.len() != entry.len() {
+        return false;
+    }
+
+    match (entry.snapshot().modified, target_metadata.modified().ok()) {
+        (Some(source_time), Some(target_time)) => target_time >= source_time,
+        _ => false,
+    }
+}
+
+// ============================================================================
+// DELETE AND PRUNE
+// ============================================================================
+
+/// Delete the regular files of a walked tree.
+///
+/// Only files the walk yields are deleted, so `config` filters decide
+/// what is removed (e.g. `skip_temp_files(false).min_file_size(..)`).
+/// Symlinks, special files and directories are never deleted. Each file
+/// is checked with `DirEntry::verify_unchanged()` immediately before
+/// removal, so a file swapped after the scan is left alone.
+///
+/// # Arguments
+/// * `root` - Tree to delete files from
+/// * `config` - Walk configuration (selects the files)
+/// * `mode` - `Mode::DryRun` to only report
+///
+/// # Returns
+/// * `Ok(ActionReport)` - `DeleteFile` actions
+/// * `Err(WalkError::TargetTooLarge)` - `root` is over the destructive
+///   thresholds (`check_destructive_target()`); nothing is deleted
+/// * `Err(WalkError::EntryChanged)` - A file was swapped (strict mode)
+/// * `Err(WalkError)` - Walk or delete failure in strict mode
+pub fn delete_files(
+    root: &Path,
+    config: WalkConfig,
+    mode: Mode,
+) -> Result<ActionReport, WalkError> {
+    check_destructive_target(root, &config)?;
+    let mut audit = AuditLog::open(root, &config, mode, "delete_files")?;
+    let mut report = ActionReport::new(mode);
+    let outcome = delete_walk(root, config, &mut report, &mut audit);
+    audit.finish(outcome)?;
+    Ok(report)
+}
+
+/// Walk behind `delete_files()`.
+fn delete_walk(
+    root: &Path,
+    config: WalkConfig,
+    report: &mut ActionReport,
+    audit: &mut AuditLog,
+) -> Result<(), WalkError> {
+    let continue_on_error = config.continue_on_error;
+
+    for entry_result in DirWalker::new(root, config) {
+        let entry = entry_result?;
+        if entry.is_file() && !entry.is_symlink() {
+            let outcome = delete_walked_file(&entry, report);
+            audit.catch_up(report);
+            report.settle(outcome, continue_on_error)?;
+        } else if !entry.is_dir() {
+            report.skip();
+        }
+    }
+
+    Ok(())
+}
+
+/// Delete (or plan deleting) one walked regular file.
+fn delete_walked_file(entry: &DirEntry, report: &mut ActionReport) -> Result<(), WalkError> {
+    if report.mode == Mode::Execute {
+        entry.verify_unchanged()?;
+        if let Err(_e) = fs::remove_file(entry.path()) {
+            #[cfg(debug_assertions)]
+            eprintln!("DWIO: delete_files could not remove file: {}", _e);
+            return Err(WalkError::IoError);
+        }
+    }
+
+    report.bytes = report.bytes.saturating_add(entry.len());
+    report.record(ActionKind::DeleteFile, entry.path().to_path_buf());
+    Ok(())
+}
+
+/// Remove empty directories below `root` (never `root` itself).
+///
+/// Directories are visited deepest first, so a directory containing only
+/// directories that are themselves pruned is pruned too (also in a dry
+/// run). Emptiness is decided by reading the directory, not from the
+/// walk, so files hidden by `config` filters keep their directory. In
+/// `Mode::Execute` removal uses `fs::remove_dir`, which refuses a
+/// directory that gained an entry after the check.
+///
+/// # Arguments
+/// * `root` - Tree to prune
+/// * `config` - Walk configuration (depth bounds, symlink policy);
+///   directories are collected even if it does not yield them
+/// * `mode` - `Mode::DryRun` to only report
+///
+/// # Returns
+/// * `Ok(ActionReport)` - `RemoveDir` actions
+/// * `Err(WalkError)` - Walk or removal failure in strict mode
+pub fn prune_empty_dirs(
+    root: &Path,
+    config: WalkConfig,
+    mode: Mode,
+) -> Result<ActionReport, WalkError> {
+    let mut audit = AuditLog::open(root, &config, mode, "prune_empty_dirs")?;
+    let mut report = ActionReport::new(mode);
+    let outcome = prune_walk(root, config, &mut report, &mut audit);
+    audit.finish(outcome)?;
+    Ok(report)
+}
+
+/// Walk and removal behind `prune_empty_dirs()`.
+fn prune_walk(
+    root: &Path,
+    config: WalkConfig,
+    report: &mut ActionReport,
+    audit: &mut AuditLog,
+) -> Result<(), WalkError> {
+    let continue_on_error = config.continue_on_error;
+    let entries_limit = config.max_entries_per_dir;
+    let mode = report.mode;
+
+    let mut directories: Vec<(usize, PathBuf)> = Vec::new();
+    for entry_result in DirWalker::new(root, config.yield_directories(true)) {
+        let entry = entry_result?;
+        if entry.is_dir() && !entry.is_symlink() {
+            directories.push((entry.depth(), entry.path().to_path_buf()));
+        }
+    }
+    directories.sort_by_key(|(depth, _)| std::cmp::Reverse(*depth));
+
+    let mut pruned: HashSet<PathBuf> = HashSet::new();
+    for (_, directory) in directories {
+        if !is_empty_after_pruning(&directory, &pruned, entries_limit) {
+            continue;
+        }
+
+        let outcome = if mode == Mode::Execute {
+            fs::remove_dir(&directory).map_err(|_e| {
+                #[cfg(debug_assertions)]
+                eprintln!("DWIO: prune_empty_dirs could not remove directory: {}", _e);
+                WalkError::IoError
+            })
+        } else {
+            Ok(())
+        };
+
+        if outcome.is_ok() {
+            pruned.insert(directory.clone());
+            report.record(ActionKind::RemoveDir, directory);
+        }
+        audit.catch_up(report);
+        report.settle(outcome, continue_on_error)?;
+    }
+
+    Ok(())
+}
+
+/// Whether `directory` has no entries other than already-pruned ones.
+///
+/// Unreadable directories, and directories with more than `limit`
+/// entries, count as non-empty.
+fn is_empty_after_pruning(directory: &Path, pruned: &HashSet<PathBuf>, limit: usize) -> bool {
+    let read_dir = match fs::read_dir(directory) {
+        Ok(r) => r,
+        Err(_) => return false,
+    };
+
+    // Bounded by the walker's per-directory entry limit
+    for (index, entry_result) in read_dir.enumerate() {
+        if index >= limit {
+            return false;
+        }
+        match entry_result {
+            Ok(child) if pruned.contains(&child.path()) => {}
+            _ => return false,
+        }
+    }
+    true
+}
+
+// ============================================================================
+// TRASH
+// ============================================================================
+
+/// Most names tried for one trashed file when earlier ones are taken.
+const MAX_TRASH_NAME_ATTEMPTS: u32 = 1_000;
+
+/// Move one walked regular file into the `.trash/` folder
+/// (`TRASH_DIR_NAME`) of its directory, creating the folder if needed.
+///
+/// The file is renamed to `<unix seconds>_<name>`, or
+/// `<unix seconds>-<n>_<name>` if that is taken, with the time from
+/// `config.clock`: the time of trashing (a rename keeps the file's own
+/// mtime) and the original name both stay readable, for `purge_trash()`
+/// and `untrashed_path()`. The file is checked with
+/// `DirEntry::verify_unchanged()` first. Symlinks, directories and
+/// special files are not moved (counted in `skipped`).
+///
+/// # Arguments
+/// * `entry` - File to trash, from a walk with full paths (not
+///   `yield_relative_paths`)
+/// * `config` - Supplies the clock and the audit log setting (the log is
+///   kept next to the file's directory)
+/// * `mode` - `Mode::DryRun` to only report
+///
+/// # Returns
+/// * `Ok(ActionReport)` - A `TrashFile` action, after a `CreateDir`
+///   action if the trash folder is (or would be) created
+/// * `Err(WalkError::EntryChanged)` - The file was swapped since the walk
+/// * `Err(WalkError::SpecialFile)` - `.trash` exists but is not a
+///   directory (or is a symlink)
+/// * `Err(WalkError::IoError)` - No free trash name, or the folder or
+///   rename failed
+pub fn move_to_trash(
+    entry: &DirEntry,
+    config: &WalkConfig,
+    mode: Mode,
+) -> Result<ActionReport, WalkError> {
+    let mut report = ActionReport::new(mode);
+    let (Some(directory), Some(name)) = (entry.path().parent(), entry.path().file_name()) else {
+        return Err(WalkError::IoError);
+    };
+    if !entry.is_file() || entry.is_symlink() {
+        report.skip();
+        return Ok(report);
+    }
+
+    let mut audit = AuditLog::open(directory, config, mode, "move_to_trash")?;
+    let outcome = trash_walked_file(entry, directory, name, config, &mut report);
+    audit.catch_up(&report);
+    audit.finish(outcome)?;
+    Ok(report)
+}
+
+/// Create the trash folder (if needed) and move one file into it.
+fn trash_walked_file(
+    entry: &DirEntry,
+    directory: &Path,
+    name: &std::ffi::OsStr,
+    config: &WalkConfig,
+    report: &mut ActionReport,
+) -> Result<(), WalkError> {
+    let trash = directory.join(TRASH_DIR_NAME);
+    let trash_exists = match fs::symlink_metadata(&trash) {
+        Ok(metadata) if metadata.is_dir() => true,
+        Ok(_) => {
+            #[cfg(debug_assertions)]
+            eprintln!("DWSF: move_to_trash found a non-directory trash folder");
+            return Err(WalkError::SpecialFile);
+        }
+        Err(_) => false,
+    };
+
+    let trashed_secs = config
+        .clock
+        .now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs());
+    let Some(target) = (0..MAX_TRASH_NAME_ATTEMPTS)
+        .map(|attempt| trash.join(trashed_name(trashed_secs, attempt, name)))
+        .find(|candidate| fs::symlink_metadata(candidate).is_err())
+    else {
+        #[cfg(debug_assertions)]
+        eprintln!("DWIO: move_to_trash found no free trash name");
+        return Err(WalkError::IoError);
+    };
+
+    if report.mode == Mode::Execute {
+        entry.verify_unchanged()?;
+        if !trash_exists && let Err(_e) = fs::create_dir(&trash) {
+            #[cfg(debug_assertions)]
+            eprintln!(
+                "DWIO: move_to_trash could not create the trash folder: {}",
+                _e
+            );
+            return Err(WalkError::IoError);
+        }
+    }
+    if !trash_exists {
+        report.record(ActionKind::CreateDir, trash);
+    }
+    if report.mode == Mode::Execute
+        && let Err(_e) = fs::rename(entry.path(), &target)
+    {
+        #[cfg(debug_assertions)]
+        eprintln!("DWIO: move_to_trash could not move the file: {}", _e);
+        return Err(WalkError::IoError);
+    }
+
+    report.bytes = report.bytes.saturating_add(entry.len());
+    report.record(ActionKind::TrashFile, target);
+    Ok(())
+}
+
+/// Name of a file trashed at `secs`: `<secs>_<name>` for the first
+/// attempt, `<secs>-<attempt>_<name>` after that.
+fn trashed_name(secs: u64, attempt: u32, name: &std::ffi::OsStr) -> OsString {
+    let mut trashed = OsString::from(if attempt == 0 {
+        format!("{}_", secs)
+    } else {
+        format!("{}-{}_", secs, attempt)
+    });
+    trashed.push(name);
+    trashed
+}
+
+/// Length of the `<secs>[-<n>]_` prefix `trashed_name()` puts on a name
+/// and the trashing time it encodes.
+///
+/// # Returns
+/// `None` for names `move_to_trash()` did not produce.
+fn parse_trash_prefix(trashed: &std::ffi::OsStr) -> Option<(usize, std::time::SystemTime)> {
+    let bytes = trashed.as_encoded_bytes();
+    let split = bytes.iter().position(|byte| *byte == b'_')?;
+    let stamp = std::str::from_utf8(&bytes[..split]).ok()?;
+    let secs = stamp.split_once('-').map_or(stamp, |(secs, _)| secs);
+    if secs.is_empty()
+        || !secs.bytes().all(|byte| byte.is_ascii_digit())
+        || split + 1 >= bytes.len()
+    {
+        return None;
+    }
+    let at =
+        std::time::UNIX_EPOCH.checked_add(std::time::Duration::from_secs(secs.parse().ok()?))?;
+    Some((split.saturating_add(1), at))
+}
+
+/// Where a trashed file came from: its directory (the trash folder's
+/// parent) joined with its original name. Restoring is renaming it back.
+///
+/// # Arguments
+/// * `trashed` - Path of a file inside a trash folder
+///
+/// # Returns
+/// `None` if `trashed` is not directly inside a trash folder, its name
+/// was not produced by `move_to_trash()`, or the original name is not
+/// UTF-8 (strip the prefix by hand then).
+pub fn untrashed_path(trashed: &Path) -> Option<PathBuf> {
+    let trash = trashed.parent()?;
+    if trash.file_name()? != TRASH_DIR_NAME {
+        return None;
+    }
+    let name = trashed.file_name()?;
+    let (prefix_len, _) = parse_trash_prefix(name)?;
+    let original = name.to_str()?.get(prefix_len..)?;
+    Some(trash.parent()?.join(original))
+}
+
+/// Delete the files that have been in trash folders for at least
+/// `older_than`.
+///
+/// Walks `dir` with `config` (trash folders are walked even if it sets
+/// `skip_trash`) and deletes, like `delete_files()`, each regular file
+/// directly inside a `TRASH_DIR_NAME` folder whose trashing time (from
+/// its name, see `move_to_trash()`) is `older_than` or more before
+/// `config.clock`'s now. Files in a trash folder with names
+/// `move_to_trash()` did not produce are left alone and counted in
+/// `skipped`. Emptied trash folders are kept.
+///
+/// # Arguments
+/// * `dir` - Tree whose trash folders are purged
+/// * `older_than` - Minimum time in the trash
+/// * `config` - Walk configuration (bounds, clock, audit log)
+/// * `mode` - `Mode::DryRun` to only report
+///
+/// # Returns
+/// * `Ok(ActionReport)` - `DeleteFile` actions
+/// * `Err(WalkError)` - Walk or delete failure in strict mode
+pub fn purge_trash(
+    dir: &Path,
+    older_than: std::time::Duration,
+    config: WalkConfig,
+    mode: Mode,
+) -> Result<ActionReport, WalkError> {
+    let mut audit = AuditLog::open(dir, &config, mode, "purge_trash")?;
+    let mut report = ActionReport::new(mode);
+    let outcome = purge_walk(dir, older_than, config, &mut report, &mut audit);
+    audit.finish(outcome)?;
+    Ok(report)
+}
+
+/// Walk behind `purge_trash()`.
+fn purge_walk(
+    dir: &Path,
+    older_than: std::time::Duration,
+    config: WalkConfig,
+    report: &mut ActionReport,
+    audit: &mut AuditLog,
+) -> Result<(), WalkError> {
+    let continue_on_error = config.continue_on_error;
+    let now = config.clock.now();
+
+    for entry_result in DirWalker::new(dir, config.skip_trash(false)) {
+        let entry = entry_result?;
+        let in_trash = entry
+            .path()
+            .parent()
+            .and_then(Path::file_name)
+            .is_some_and(|name| name == TRASH_DIR_NAME);
+        if !in_trash || !entry.is_file() || entry.is_symlink() {
+            continue;
+        }
+        let Some((_, trashed_at)) = entry.path().file_name().and_then(parse_trash_prefix) else {
+            report.skip();
+            continue;
+        };
+        // A trashing time in the future is not old enough
+        if now
+            .duration_since(trashed_at)
+            .is_ok_and(|in_trash_for| in_trash_for >= older_than)
+        {
+            let outcome = delete_walked_file(&entry, report);
+            audit.catch_up(report);
+            report.settle(outcome, continue_on_error)?;
+        }
+    }
+
+    Ok(())
+}
+
+// ============================================================================
+// SCAN LOCK
+// ============================================================================
+
+/// Longest owner name written into a scan lock, in bytes.
+const MAX_SCAN_LOCK_OWNER_LEN: usize = 128;
+
+/// Most bytes read from an existing scan lock file.
+const MAX_SCAN_LOCK_BYTES: u64 = 512;
+
+/// Advisory lock on one directory, held while a mutating helper runs in
+/// it (see `try_lock_scan()`).
+///
+/// The lock file (`SCAN_LOCK_FILE_NAME`) records the time it was taken
+/// and its owner (`WalkConfig::scan_lock_owner`). It is removed by
+/// `release()` or on drop, unless another owner has taken it over since.
+pub struct ScanLock {
+    path: PathBuf,
+    owner: String,
+    clock: Arc<dyn Clock>,
+    held: bool,
+}
+
+impl ScanLock {
+    /// Path of the lock file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Owner name written into the lock file.
+    pub fn owner(&self) -> &str {
+        &self.owner
+    }
+
+    /// Restart the lock's lifetime (`WalkConfig::scan_lock_ttl`) from
+    /// now, for helpers that run longer than it.
+    ///
+    /// # Returns
+    /// * `Ok(())` - The lock file carries the new time
+    /// * `Err(WalkError::ScanLocked)` - The lock expired and another
+    ///   owner took it over (or it was removed): stop changing the tree
+    /// * `Err(WalkError)` - The lock file could not be rewritten
+    pub fn refresh(&self) -> Result<(), WalkError> {
+        match read_scan_lock(&self.path)? {
+            LockFile::Held { owner, .. } if owner == self.owner => {
+                write_scan_lock(&self.path, &self.owner, &self.clock, false)
+            }
+            _ => {
+                #[cfg(debug_assertions)]
+                eprintln!("DWLK: scan lock was taken over before its refresh");
+                Err(WalkError::ScanLocked)
+            }
+        }
+    }
+
+    /// Remove the lock file.
+    ///
+    /// # Returns
+    /// * `Ok(())` - Removed, or already taken over by another owner (left
+    ///   in place)
+    /// * `Err(WalkError::IoError)` - The lock file could not be removed
+    pub fn release(mut self) -> Result<(), WalkError> {
+        self.held = false;
+        self.remove_if_ours()
+    }
+
+    /// Delete the lock file if it still names this lock's owner.
+    fn remove_if_ours(&self) -> Result<(), WalkError> {
+        match read_scan_lock(&self.path) {
+            Ok(LockFile::Held { owner, .. }) if owner == self.owner => {}
+            _ => return Ok(()),
+        }
+        match fs::remove_file(&self.path) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            Err(_e) => {
+                #[cfg(debug_assertions)]
+                eprintln!("DWIO: could not remove scan lock: {}", _e);
+                Err(WalkError::IoError)
+            }
+        }
+    }
+}
+
+impl Drop for ScanLock {
+    fn drop(&mut self) {
+        if self.held {
+            // Best effort: a lock left behind expires after its TTL
+            let _ = self.remove_if_ours();
+        }
+    }
+}
+
+impl fmt::Debug for ScanLock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ScanLock")
+            .field("owner", &self.owner)
+            .field("held", &self.held)
+            .finish()
+    }
+}
+
+/// Contents of a directory's scan lock file.
+enum LockFile {
+    /// No lock file.
+    Absent,
+    /// A lock taken at `secs` (Unix seconds) by `owner`.
+    Held { secs: u64, owner: String },
+    /// A lock file that is not in the format `write_scan_lock()` writes
+    /// (or could not be read).
+    Garbled,
+}
+
+/// Take the scan lock of `dir` before changing it.
+///
+/// Creates `SCAN_LOCK_FILE_NAME` in `dir` (atomically: a file that
+/// already exists is never overwritten by the create), recording the
+/// time from `config.clock` and `config.scan_lock_owner`. An existing
+/// lock file is handled by what it holds:
+/// - Same owner: refreshed and returned (a rerun of the same job)
+/// - Another owner, taken less than `config.scan_lock_ttl` ago (or at
+///   most that far in the future, for clock skew between replicas):
+///   refused
+/// - Another owner, older; or unreadable content: abandoned by a crashed
+///   run, removed and taken once more
+///
+/// The lock is advisory: only helpers that take it, and walks with
+/// `WalkConfig::skip_locked_dirs`, honor it. Two takers that find the
+/// same abandoned lock at once can both succeed; a lock is a guard
+/// against overlapping runs, not against races within one second.
+///
+/// # Arguments
+/// * `dir` - Directory to lock
+/// * `config` - Supplies the owner, TTL and clock
+///
+/// # Returns
+/// * `Ok(ScanLock)` - Held until released or dropped
+/// * `Err(WalkError::ScanLocked)` - Another owner holds a fresh lock
+/// * `Err(WalkError::SpecialFile)` - The lock path is not a regular file
+///   (or is a symlink)
+/// * `Err(WalkError)` - The lock file could not be created or removed
+///
+/// # Project Context
+/// Renumbering a channel that another replica renumbers at the same time
+/// interleaves two rename plans; `messages::renumber_messages()` takes
+/// this lock before it executes.
+pub fn try_lock_scan(dir: &Path, config: &WalkConfig) -> Result<ScanLock, WalkError> {
+    let path = dir.join(SCAN_LOCK_FILE_NAME);
+    let owner = scan_lock_owner(config);
+
+    // A second attempt only follows the removal of an abandoned lock
+    for _ in 0..2 {
+        match read_scan_lock(&path)? {
+            LockFile::Absent => {}
+            LockFile::Held { owner: held_by, .. } if held_by == owner => {
+                write_scan_lock(&path, &owner, &config.clock, false)?;
+                return Ok(held_scan_lock(path, owner, config));
+            }
+            LockFile::Held { secs, .. } if lock_is_fresh(secs, config) => {
+                #[cfg(debug_assertions)]
+                eprintln!("DWLK: directory holds a fresh scan lock of another owner");
+                return Err(WalkError::ScanLocked);
+            }
+            LockFile::Held { .. } | LockFile::Garbled => match fs::remove_file(&path) {
+                Ok(()) => {}
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                Err(_e) => {
+                    #[cfg(debug_assertions)]
+                    eprintln!("DWIO: could not remove an abandoned scan lock: {}", _e);
+                    return Err(WalkError::IoError);
+                }
+            },
+        }
+        match write_scan_lock(&path, &owner, &config.clock, true) {
+            Ok(()) => return Ok(held_scan_lock(path, owner, config)),
+            // Taken by someone else since it was read: look again
+            Err(WalkError::RenameConflict) => continue,
+            Err(e) => return Err(e),
+        }
+    }
+
+    #[cfg(debug_assertions)]
+    eprintln!("DWLK: scan lock was taken by another owner while acquiring it");
+    Err(WalkError::ScanLocked)
+}
+
+/// Whether `dir` holds a fresh scan lock of an owner other than
+/// `config`'s (the check behind `WalkConfig::skip_locked_dirs`).
+///
+/// Abandoned, unreadable and non-regular lock files do not count.
+pub(super) fn locked_by_other(dir: &Path, config: &WalkConfig) -> bool {
+    match read_scan_lock(&dir.join(SCAN_LOCK_FILE_NAME)) {
+        Ok(LockFile::Held { secs, owner }) => {
+            owner != scan_lock_owner(config) && lock_is_fresh(secs, config)
+        }
+        _ => false,
+    }
+}
+
+/// Owner name of the locks taken with `config`: its
+/// `scan_lock_owner`, cleaned and cut to `MAX_SCAN_LOCK_OWNER_LEN`, or
+/// `pid-<process id>`.
+fn scan_lock_owner(config: &WalkConfig) -> String {
+    let Some(owner) = &config.scan_lock_owner else {
+        return format!("pid-{}", std::process::id());
+    };
+    let mut cleaned = String::new();
+    for c in owner.chars() {
+        let c = if c.is_control() { '_' } else { c };
+        if cleaned.len().saturating_add(c.len_utf8()) > MAX_SCAN_LOCK_OWNER_LEN {
+            break;
+        }
+        cleaned.push(c);
+    }
+    cleaned
+}
+
+/// Whether a lock taken at `secs` is still within `config.scan_lock_ttl`.
+fn lock_is_fresh(secs: u64, config: &WalkConfig) -> bool {
+    let ttl = config.scan_lock_ttl.as_secs();
+    let now = unix_secs(&config.clock);
+    if secs <= now {
+        now.saturating_sub(secs) < ttl
+    } else {
+        secs.saturating_sub(now) <= ttl && ttl > 0
+    }
+}
+
+/// Current time of `clock` in Unix seconds (0 before the epoch).
+fn unix_secs(clock: &Arc<dyn Clock>) -> u64 {
+    clock
+        .now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs())
+}
+
+/// A `ScanLock` for a lock file just written.
+fn held_scan_lock(path: PathBuf, owner: String, config: &WalkConfig) -> ScanLock {
+    ScanLock {
+        path,
+        owner,
+        clock: Arc::clone(&config.clock),
+        held: true,
+    }
+}
+
+/// Read a scan lock file: `<unix seconds> <owner>\n`.
+///
+/// # Returns
+/// * `Ok(LockFile)` - What the file holds
+/// * `Err(WalkError::SpecialFile)` - The path is not a regular file
+fn read_scan_lock(path: &Path) -> Result<LockFile, WalkError> {
+    match fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.is_file() => {}
+        Ok(_) => {
+            #[cfg(debug_assertions)]
+            eprintln!("DWSF: scan lock path is not a regular file");
+            return Err(WalkError::SpecialFile);
+        }
+        Err(_) => return Ok(LockFile::Absent),
+    }
+    let mut content = String::new();
+    let read = fs::File::open(path)
+        .and_then(|file| file.take(MAX_SCAN_LOCK_BYTES).read_to_string(&mut content));
+    match read {
+        Ok(_) => {}
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(LockFile::Absent),
+        Err(_) => return Ok(LockFile::Garbled),
+    }
+    let parsed = content
+        .strip_suffix('\n')
+        .and_then(|line| line.split_once(' '))
+        .and_then(|(secs, owner)| Some((secs.parse::<u64>().ok()?, owner)));
+    Ok(match parsed {
+        Some((secs, owner)) if !owner.is_empty() => LockFile::Held {
+            secs,
+            owner: owner.to_string(),
+        },
+        _ => LockFile::Garbled,
+    })
+}
+
+/// Write a scan lock file taken now by `owner`.
+///
+/// # Arguments
+/// * `create` - `true` to create the file, failing if it exists
+///   (`WalkError::RenameConflict`); `false` to rewrite an existing one
+fn write_scan_lock(
+    path: &Path,
+    owner: &str,
+    clock: &Arc<dyn Clock>,
+    create: bool,
+) -> Result<(), WalkError> {
+    let line = format!("{} {}\n", unix_secs(clock), owner);
+    let opened = if create {
+        fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(path)
+    } else {
+        fs::OpenOptions::new().write(true).truncate(true).open(path)
+    };
+    let mut file = match opened {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+            return Err(WalkError::RenameConflict);
+        }
+        Err(_e) => {
+            #[cfg(debug_assertions)]
+            eprintln!("DWIO: could not open scan lock: {}", _e);
+            return Err(WalkError::IoError);
+        }
+    };
+    if let Err(_e) = file.write_all(line.as_bytes()) {
+        #[cfg(debug_assertions)]
+        eprintln!("DWIO: could not write scan lock: {}", _e);
+        if create {
+            let _ = fs::remove_file(path);
+        }
+        return Err(WalkError::IoError);
+    }
+    Ok(())
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Helper: unique test directory path (caller cleans up).
+    fn test_dir(name: &str) -> PathBuf {
+        crate::dir_walk_module::testing::unique_test_dir(&format!("mutate_{}", name))
+    }
+
+    /// Helper: safely remove test directory, ignoring errors.
+    fn cleanup(path: &Path) {
+        let _ = fs::remove_dir_all(path);
+    }
+
+    /// Test: copy_tree copies into a sibling, and refuses destinations
+    /// inside the source (directly, via `..`, and via a symlink alias)
+    /// before writing anything.
+    #[test]
+    fn test_copy_tree_refuses_destination_inside_source() {
+        let base = test_dir("inside_source");
+        cleanup(&base);
+        let source = base.join("channel");
+        assert!(
+            fs::create_dir_all(source.join("sub")).is_ok()
+                && fs::write(source.join("1__msg.toml"), b"one").is_ok()
+                && fs::write(source.join("sub").join("2__msg.toml"), b"two").is_ok(),
+            "test_copy_tree_guard: failed to create source"
+        );
+
+        let inside = source.join("backup");
+        assert_eq!(
+            copy_tree(&source, &inside, WalkConfig::new(), Mode::Execute),
+            Err(WalkError::DestinationInsideSource),
+            "test_copy_tree_guard: nested destination must be refused"
+        );
+        assert!(
+            !inside.exists(),
+            "test_copy_tree_guard: nothing may be written when refused"
+        );
+
+        let same = base.join("channel").join("..").join("channel");
+        assert_eq!(
+            check_destination_outside(&source, &same),
+            Err(WalkError::DestinationInsideSource),
+            "test_copy_tree_guard: source itself must be refused"
+        );
+
+        #[cfg(unix)]
+        {
+            let alias = base.join("alias");
+            assert!(
+                std::os::unix::fs::symlink(&source, &alias).is_ok(),
+                "test_copy_tree_guard: failed to create alias"
+            );
+            assert_eq!(
+                check_destination_outside(&source, &alias.join("out")),
+                Err(WalkError::DestinationInsideSource),
+                "test_copy_tree_guard: symlink alias must be refused"
+            );
+        }
+
+        let outside = base.join("backup");
+        let report = copy_tree(&source, &outside, WalkConfig::new(), Mode::Execute);
+        assert_eq!(
+            report.as_ref().map(|r| (
+                r.count(ActionKind::CopyFile),
+                r.count(ActionKind::CreateDir),
+                r.bytes
+            )),
+            Ok((2, 1, 6)),
+            "test_copy_tree_guard: sibling copy counts"
+        );
+        assert_eq!(
+            fs::read(outside.join("sub").join("2__msg.toml")).ok(),
+            Some(b"two".to_vec()),
+            "test_copy_tree_guard: nested file content"
+        );
+
+        cleanup(&base);
+    }
+
+    /// Test: every helper's dry run reports exactly the actions the
+    /// execute run then performs, without touching the filesystem.
+    #[test]
+    fn test_dry_run_matches_execute() {
+        let base = test_dir("dry_run");
+        cleanup(&base);
+        let source = base.join("channel");
+        let mirror = base.join("mirror");
+        assert!(
+            fs::create_dir_all(source.join("sub")).is_ok()
+                && fs::create_dir_all(source.join("empty").join("nested")).is_ok()
+                && fs::write(source.join("1__msg.toml"), b"one").is_ok()
+                && fs::write(source.join("sub").join("2__msg.toml"), b"two").is_ok(),
+            "test_dry_run: failed to create source"
+        );
+
+        // Sync into a new mirror: dry run writes nothing
+        let planned = sync_tree(&source, &mirror, WalkConfig::new(), Mode::DryRun);
+        assert!(
+            !mirror.exists(),
+            "test_dry_run: dry-run sync must not create the mirror"
+        );
+        let done = sync_tree(&source, &mirror, WalkConfig::new(), Mode::Execute);
+        assert_eq!(
+            planned.as_ref().map(|r| (&r.actions, r.bytes)),
+            done.as_ref().map(|r| (&r.actions, r.bytes)),
+            "test_dry_run: sync plan differs from execution"
+        );
+        assert_eq!(
+            done.as_ref().map(|r| r.count(ActionKind::CopyFile)),
+            Ok(2),
+            "test_dry_run: sync should copy both files"
+        );
+
+        // Second sync: nothing changed; then an extra mirror file is deleted
+        assert!(
+            sync_tree(&source, &mirror, WalkConfig::new(), Mode::Execute)
+                .is_ok_and(|r| r.is_empty()),
+            "test_dry_run: unchanged sync should do nothing"
+        );
+        assert!(
+            fs::write(mirror.join("stale.toml"), b"old").is_ok(),
+            "test_dry_run: failed to create stale file"
+        );
+        let planned = sync_tree(&source, &mirror, WalkConfig::new(), Mode::DryRun);
+        assert_eq!(
+            planned.map(|r| r.actions),
+            Ok(vec![Action {
+                kind: ActionKind::DeleteFile,
+                path: mirror.join("stale.toml")
+            }]),
+            "test_dry_run: sync should plan deleting the stale file"
+        );
+        assert!(
+            mirror.join("stale.toml").exists(),
+            "test_dry_run: dry-run sync must not delete"
+        );
+
+        // Prune: `empty/nested` then `empty`, deepest first
+        let planned = prune_empty_dirs(&source, WalkConfig::new(), Mode::DryRun);
+        assert!(
+            source.join("empty").join("nested").exists(),
+            "test_dry_run: dry-run prune must not remove"
+        );
+        let done = prune_empty_dirs(&source, WalkConfig::new(), Mode::Execute);
+        assert_eq!(
+            planned.as_ref().map(|r| &r.actions),
+            done.as_ref().map(|r| &r.actions),
+            "test_dry_run: prune plan differs from execution"
+        );
+        assert_eq!(
+            done.map(|r| r.count(ActionKind::RemoveDir)),
+            Ok(2),
+            "test_dry_run: prune should remove both empty dirs"
+        );
+
+        // Delete: dry run keeps the files, execute removes exactly those
+        let planned = delete_files(&mirror, WalkConfig::new(), Mode::DryRun);
+        assert!(
+            mirror.join("1__msg.toml").exists(),
+            "test_dry_run: dry-run delete must not remove"
+        );
+        let done = delete_files(&mirror, WalkConfig::new(), Mode::Execute);
+        assert_eq!(
+            planned.as_ref().map(|r| r.count(ActionKind::DeleteFile)),
+            done.as_ref().map(|r| r.count(ActionKind::DeleteFile)),
+            "test_dry_run: delete plan differs from execution"
+        );
+        assert!(
+            !mirror.join("sub").join("2__msg.toml").exists() && mirror.join("sub").is_dir(),
+            "test_dry_run: delete should remove files and keep directories"
+        );
+
+        cleanup(&base);
+    }
+
+    /// Test: deleting helpers refuse a target over the entry or byte
+    /// threshold, in a dry run too, unless forced; small targets proceed.
+    #[test]
+    fn test_destructive_thresholds() {
+        let base = test_dir("thresholds");
+        cleanup(&base);
+        let source = base.join("channel");
+        let target = base.join("workspace");
+        assert!(
+            fs::create_dir_all(&source).is_ok()
+                && fs::create_dir_all(target.join("sub")).is_ok()
+                && (0..4).all(|i| fs::write(target.join(format!("{}.toml", i)), b"12345").is_ok())
+                && fs::write(target.join("sub").join("big.bin"), vec![0u8; 100]).is_ok(),
+            "test_destructive_thresholds: failed to create tree"
+        );
+
+        let by_entries = WalkConfig::new().max_destructive_entries(3);
+        let by_bytes = WalkConfig::new().max_destructive_bytes(50);
+        for config in [&by_entries, &by_bytes] {
+            for mode in [Mode::DryRun, Mode::Execute] {
+                assert_eq!(
+                    delete_files(&target, config.clone(), mode)
+                        .map(|r| r.count(ActionKind::DeleteFile)),
+                    Err(WalkError::TargetTooLarge),
+                    "test_destructive_thresholds: delete must be refused"
+                );
+            }
+            assert_eq!(
+                sync_tree(&source, &target, config.clone(), Mode::Execute).map(|r| r.is_empty()),
+                Err(WalkError::TargetTooLarge),
+                "test_destructive_thresholds: sync must be refused"
+            );
+        }
+        assert!(
+            target.join("0.toml").exists() && target.join("sub").join("big.bin").exists(),
+            "test_destructive_thresholds: a refused run deletes nothing"
+        );
+
+        let generous = WalkConfig::new()
+            .max_destructive_entries(5)
+            .max_destructive_bytes(120);
+        assert!(
+            delete_files(&target, generous, Mode::DryRun)
+                .is_ok_and(|r| r.count(ActionKind::DeleteFile) == 5),
+            "test_destructive_thresholds: a target within both thresholds proceeds"
+        );
+        assert!(
+            delete_files(&target, by_entries.force_destructive(true), Mode::Execute)
+                .is_ok_and(|r| r.count(ActionKind::DeleteFile) == 5),
+            "test_destructive_thresholds: a forced run proceeds"
+        );
+
+        cleanup(&base);
+    }
+
+    /// Test: trashed files are hidden from channel scans, keep their
+    /// original name and trashing time, and are purged only once old
+    /// enough.
+    #[test]
+    fn test_trash_move_and_purge() {
+        use crate::dir_walk_module::clock::MockClock;
+        use std::time::{Duration, UNIX_EPOCH};
+
+        let base = test_dir("trash");
+        cleanup(&base);
+        let channel = base.join("channel");
+        assert!(
+            fs::create_dir_all(channel.join("sub")).is_ok()
+                && fs::write(channel.join("1__a.toml"), b"one").is_ok()
+                && fs::write(channel.join("2__b.toml"), b"two").is_ok(),
+            "test_trash: failed to create channel"
+        );
+        let clock = MockClock::new(UNIX_EPOCH + Duration::from_secs(1_000_000));
+        let config = WalkConfig::new().clock(clock.clone());
+        let find = |name: &str| {
+            DirWalker::new(&channel, WalkConfig::new())
+                .ok_entries()
+                .find(|e| e.file_name() == Some(name))
+        };
+
+        let first = find("1__a.toml");
+        assert!(first.is_some(), "test_trash: file not found");
+        let Some(first) = first else { return };
+        let planned = move_to_trash(&first, &config, Mode::DryRun);
+        assert!(
+            planned.is_ok_and(
+                |r| r.count(ActionKind::CreateDir) == 1 && r.count(ActionKind::TrashFile) == 1
+            ) && channel.join("1__a.toml").exists()
+                && !channel.join(TRASH_DIR_NAME).exists(),
+            "test_trash: dry run plans and changes nothing"
+        );
+        let trashed = channel.join(TRASH_DIR_NAME).join("1000000_1__a.toml");
+        let done = move_to_trash(&first, &config, Mode::Execute);
+        assert_eq!(
+            done.map(|r| r.actions.last().map(|a| a.path.clone())),
+            Ok(Some(trashed.clone())),
+            "test_trash: file moved under a timestamped name"
+        );
+        assert_eq!(
+            untrashed_path(&trashed),
+            Some(channel.join("1__a.toml")),
+            "test_trash: original path recoverable"
+        );
+
+        // Same name, same second: a second free name
+        assert!(
+            fs::write(channel.join("1__a.toml"), b"again").is_ok(),
+            "test_trash: failed to recreate file"
+        );
+        let again = find("1__a.toml");
+        assert!(again.is_some(), "test_trash: recreated file not found");
+        let Some(again) = again else { return };
+        assert!(
+            move_to_trash(&again, &config, Mode::Execute).is_ok()
+                && channel
+                    .join(TRASH_DIR_NAME)
+                    .join("1000000-1_1__a.toml")
+                    .exists(),
+            "test_trash: taken trash names are not overwritten"
+        );
+
+        clock.advance(Duration::from_secs(24 * 3600));
+        let second = find("2__b.toml");
+        assert!(second.is_some(), "test_trash: second file not found");
+        let Some(second) = second else { return };
+        let sub = find("sub");
+        assert!(sub.is_some(), "test_trash: directory not found");
+        let Some(sub) = sub else { return };
+        assert!(
+            move_to_trash(&second, &config, Mode::Execute).is_ok()
+                && move_to_trash(&sub, &config, Mode::Execute).is_ok_and(|r| r.skipped == 1)
+                && fs::write(channel.join(TRASH_DIR_NAME).join("notes.txt"), b"x").is_ok(),
+            "test_trash: second trashing failed"
+        );
+
+        let scanned = DirWalker::new(&channel, WalkConfig::channel_scan())
+            .ok_entries()
+            .count();
+        let walked = DirWalker::new(&channel, WalkConfig::new())
+            .ok_entries()
+            .count();
+        assert_eq!(
+            (scanned, walked),
+            (1, 6),
+            "test_trash: channel scans skip the trash folder"
+        );
+
+        clock.advance(Duration::from_secs(24 * 3600));
+        let older_than = Duration::from_secs(36 * 3600);
+        let planned = purge_trash(&channel, older_than, config.clone(), Mode::DryRun);
+        assert!(
+            planned.is_ok_and(|r| r.count(ActionKind::DeleteFile) == 2 && r.skipped == 1)
+                && trashed.exists(),
+            "test_trash: dry run purges nothing"
+        );
+        let purged = purge_trash(&channel, older_than, config.skip_trash(true), Mode::Execute);
+        assert!(
+            purged.is_ok_and(|r| r.count(ActionKind::DeleteFile) == 2)
+                && !trashed.exists()
+                && channel
+                    .join(TRASH_DIR_NAME)
+                    .join("1086400_2__b.toml")
+                    .exists(),
+            "test_trash: only files old enough are purged"
+        );
+
+        cleanup(&base);
+    }
+
+    /// Test: a scan lock refuses other owners while fresh, is taken over
+    /// once expired, hides its directory from walks that skip locked
+    /// directories, and blocks renumbering by another replica.
+    #[test]
+    fn test_scan_lock() {
+        use crate::dir_walk_module::clock::MockClock;
+        use crate::dir_walk_module::messages::{RenumberConfig, renumber_messages};
+        use std::time::{Duration, UNIX_EPOCH};
+
+        let base = test_dir("scan_lock");
+        cleanup(&base);
+        let channel = base.join("channel");
+        assert!(
+            fs::create_dir_all(&channel).is_ok()
+                && fs::write(channel.join("1__a.toml"), b"one").is_ok()
+                && fs::write(channel.join("5__b.toml"), b"two").is_ok()
+                && fs::write(base.join("1__top.toml"), b"top").is_ok(),
+            "test_scan_lock: failed to create channel"
+        );
+        let clock = MockClock::new(UNIX_EPOCH + Duration::from_secs(1_000_000));
+        let replica = |owner: &str| {
+            WalkConfig::new()
+                .clock(clock.clone())
+                .scan_lock_owner(owner)
+                .scan_lock_ttl(Duration::from_secs(60))
+        };
+        let (a, b) = (replica("replica-a"), replica("replica-b\nforged"));
+
+        let held = try_lock_scan(&channel, &a);
+        assert!(held.is_ok(), "test_scan_lock: first lock refused");
+        let Ok(held) = held else { return };
+        assert_eq!(
+            fs::read_to_string(channel.join(SCAN_LOCK_FILE_NAME)).ok(),
+            Some("1000000 replica-a\n".to_string()),
+            "test_scan_lock: lock records time and owner"
+        );
+        assert_eq!(
+            try_lock_scan(&channel, &b).map(|lock| lock.owner().to_string()),
+            Err(WalkError::ScanLocked),
+            "test_scan_lock: fresh foreign lock refused"
+        );
+        // Kept until the takeover: dropping it would remove the shared file
+        let again = try_lock_scan(&channel, &a);
+        assert!(
+            again.as_ref().is_ok_and(|lock| lock.owner() == "replica-a"),
+            "test_scan_lock: own lock is taken again"
+        );
+
+        // The locked channel is left unread by the other replica only
+        let names = |config: WalkConfig| {
+            DirWalker::new(&base, config)
+                .ok_entries()
+                .filter_map(|e| e.file_name().map(str::to_string))
+                .filter(|name| name.ends_with(".toml"))
+                .count()
+        };
+        assert_eq!(
+            names(b.clone().skip_locked_dirs(true)),
+            1,
+            "test_scan_lock: locked channel skipped"
+        );
+        assert_eq!(
+            names(a.clone().skip_locked_dirs(true)),
+            3,
+            "test_scan_lock: own lock not skipped"
+        );
+        assert!(
+            DirWalker::new(
+                &channel,
+                b.clone().skip_locked_dirs(true).continue_on_error(false),
+            )
+            .any(|r| matches!(r, Err(WalkError::ScanLocked))),
+            "test_scan_lock: strict walk fails with DWLK"
+        );
+        assert_eq!(
+            renumber_messages(
+                &channel,
+                RenumberConfig::new()
+                    .walk_config(b.clone())
+                    .mode(Mode::Execute)
+            )
+            .map(|r| r.renames.len()),
+            Err(WalkError::ScanLocked),
+            "test_scan_lock: renumbering refused while locked"
+        );
+
+        // Expired: taken over, and the old holder notices
+        clock.advance(Duration::from_secs(61));
+        let taken = try_lock_scan(&channel, &b);
+        assert!(
+            taken
+                .as_ref()
+                .is_ok_and(|lock| lock.owner() == "replica-b_forged"),
+            "test_scan_lock: expired lock taken over"
+        );
+        assert_eq!(
+            held.refresh(),
+            Err(WalkError::ScanLocked),
+            "test_scan_lock: refresh after takeover fails"
+        );
+        drop(held);
+        drop(again);
+        assert!(
+            channel.join(SCAN_LOCK_FILE_NAME).exists(),
+            "test_scan_lock: dropped stale lock leaves the new one"
+        );
+        let Ok(taken) = taken else { return };
+        assert!(
+            taken.release().is_ok() && !channel.join(SCAN_LOCK_FILE_NAME).exists(),
+            "test_scan_lock: release removes the lock"
+        );
+
+        // Garbled leftovers are replaced; renumbering releases its lock
+        assert!(
+            fs::write(channel.join(SCAN_LOCK_FILE_NAME), b"garbage").is_ok(),
+            "test_scan_lock: failed to write garbled lock"
+        );
+        assert_eq!(
+            renumber_messages(
+                &channel,
+                RenumberConfig::new()
+                    .walk_config(b.clone())
+                    .mode(Mode::Execute)
+            )
+            .map(|r| (r.renames.len(), r.malformed)),
+            Ok((1, 0)),
+            "test_scan_lock: renumbering takes a garbled lock"
+        );
+        assert!(
+            channel.join("2__b.toml").exists() && !channel.join(SCAN_LOCK_FILE_NAME).exists(),
+            "test_scan_lock: renumbered and lock released"
+        );
+
+        cleanup(&base);
+    }
+
+    /// Test: a staged copy left by a crashed copy is never taken for a
+    /// staged rename, and a fresh rename refuses a taken staging name or
+    /// a missing source before writing its journal.
+    #[test]
+    fn test_rename_ignores_leftover_staged_copy() {
+        let dir = test_dir("rename_leftover");
+        cleanup(&dir);
+        assert!(
+            fs::create_dir_all(&dir).is_ok()
+                && fs::write(dir.join("1__a.toml"), b"message").is_ok()
+                && fs::write(dir.join(".~dwm.2__a.toml"), b"stale copy").is_ok(),
+            "test_rename_leftover: failed to create files"
+        );
+        let renames = vec![("1__a.toml".to_string(), "2__a.toml".to_string())];
+
+        assert!(
+            rename_two_phase(&dir, &renames, usize::MAX).is_ok(),
+            "test_rename_leftover: rename failed"
+        );
+        assert_eq!(
+            (
+                fs::read(dir.join("2__a.toml")).ok(),
+                dir.join("1__a.toml").exists(),
+                fs::read(dir.join(".~dwm.2__a.toml")).ok(),
+            ),
+            (
+                Some(b"message".to_vec()),
+                false,
+                Some(b"stale copy".to_vec())
+            ),
+            "test_rename_leftover: message moved, leftover untouched"
+        );
+
+        // Fresh runs refuse instead of trusting what they find
+        let back = vec![("2__a.toml".to_string(), "1__a.toml".to_string())];
+        assert!(
+            fs::write(dir.join(".~dwm.rn.0"), b"planted").is_ok(),
+            "test_rename_leftover: failed to plant staged name"
+        );
+        assert_eq!(
+            rename_two_phase(&dir, &back, usize::MAX),
+            Err(WalkError::RenameConflict),
+            "test_rename_leftover: taken staging name refused"
+        );
+        assert!(
+            fs::remove_file(dir.join(".~dwm.rn.0")).is_ok(),
+            "test_rename_leftover: failed to remove planted name"
+        );
+        let missing = vec![("9__gone.toml".to_string(), "1__a.toml".to_string())];
+        assert_eq!(
+            rename_two_phase(&dir, &missing, usize::MAX),
+            Err(WalkError::RenameConflict),
+            "test_rename_leftover: missing source refused"
+        );
+        assert!(
+            !dir.join(JOURNAL_STAGE).exists()
+                && !dir.join(JOURNAL_PART).exists()
+                && dir.join("2__a.toml").exists(),
+            "test_rename_leftover: refused runs wrote nothing"
+        );
+
+        cleanup(&dir);
+    }
+
+    /// Test: 250-byte names, too long for a prefixed staging name, are
+    /// renamed and copied; a target over `MAX_NAME_BYTES` is refused
+    /// before the journal is written.
+    #[test]
+    fn test_long_names_stage_within_name_max() {
+        let base = test_dir("long_names");
+        cleanup(&base);
+        let source = base.join("channel");
+        let long = |number: usize| format!("{}__{}.toml", number, "m".repeat(242));
+        assert!(
+            fs::create_dir_all(&source).is_ok() && fs::write(source.join(long(1)), b"one").is_ok(),
+            "test_long_names: failed to create source"
+        );
+        assert_eq!(long(1).len(), 250, "test_long_names: name length");
+
+        let renames = vec![(long(1), long(2))];
+        assert!(
+            rename_two_phase(&source, &renames, usize::MAX).is_ok(),
+            "test_long_names: rename failed"
+        );
+        assert!(
+            source.join(long(2)).exists()
+                && !source.join(long(1)).exists()
+                && !source.join(JOURNAL_COMMIT).exists(),
+            "test_long_names: rename finished"
+        );
+
+        let too_long = vec![(long(2), format!("{}{}", long(2), "x".repeat(6)))];
+        assert_eq!(
+            rename_two_phase(&source, &too_long, usize::MAX),
+            Err(WalkError::InvalidName),
+            "test_long_names: over-long target refused"
+        );
+        assert!(
+            !source.join(JOURNAL_PART).exists() && !source.join(JOURNAL_STAGE).exists(),
+            "test_long_names: refused rename wrote no journal"
+        );
+
+        let backup = base.join("backup");
+        let report = copy_tree(&source, &backup, WalkConfig::new(), Mode::Execute);
+        assert_eq!(
+            report.as_ref().map(|r| r.count(ActionKind::CopyFile)),
+            Ok(1),
+            "test_long_names: copy report"
+        );
+        assert_eq!(
+            fs::read(backup.join(long(2))).ok(),
+            Some(b"one".to_vec()),
+            "test_long_names: long name copied"
+        );
+
+        cleanup(&base);
+    }
+
+    /// Test: a sync with a rollback plan backs up what it overwrites and
+    /// deletes, and applying the plan restores the mirror exactly.
+    #[test]
+    fn test_sync_rollback_plan_restores_mirror() {
+        let base = test_dir("sync_rollback");
+        cleanup(&base);
+        let source = base.join("channel");
+        let mirror = base.join("mirror");
+        assert!(
+            fs::create_dir_all(source.join("sub")).is_ok()
+                && fs::create_dir_all(&mirror).is_ok()
+                && fs::write(source.join("1__msg.toml"), b"new content").is_ok()
+                && fs::write(source.join("sub").join("2__msg.toml"), b"two").is_ok()
+                && fs::write(mirror.join("1__msg.toml"), b"old").is_ok()
+                && fs::write(mirror.join("stale.toml"), b"stale").is_ok(),
+            "test_sync_rollback: failed to create trees"
+        );
+
+        let planned = sync_tree(
+            &source,
+            &mirror,
+            WalkConfig::new().rollback_plan(true),
+            Mode::DryRun,
+        );
+        assert!(
+            planned.is_ok_and(|r| r.rollback.is_none()),
+            "test_sync_rollback: dry runs write no plan"
+        );
+
+        let done = sync_tree(
+            &source,
+            &mirror,
+            WalkConfig::new().rollback_plan(true),
+            Mode::Execute,
+        );
+        assert!(done.is_ok(), "test_sync_rollback: sync failed");
+        let Ok(done) = done else { return };
+        assert!(
+            done.rollback.is_some(),
+            "test_sync_rollback: executing run must report its plan"
+        );
+        let Some(plan) = done.rollback else { return };
+        assert_eq!(
+            plan.dir.parent(),
+            Some(base.as_path()),
+            "test_sync_rollback: plan sits next to the mirror"
+        );
+        assert_eq!(
+            plan.steps,
+            vec![
+                RollbackStep::CopyBack(PathBuf::from("stale.toml")),
+                RollbackStep::DeleteFile(PathBuf::from("sub/2__msg.toml")),
+                RollbackStep::RemoveDir(PathBuf::from("sub")),
+                RollbackStep::CopyBack(PathBuf::from("1__msg.toml")),
+            ],
+            "test_sync_rollback: undo steps in reverse order"
+        );
+        assert_eq!(
+            fs::read_to_string(plan.dir.join(ROLLBACK_PLAN_FILE)).ok(),
+            Some(
+                "copy_back\tstale.toml\ndelete_file\tsub/2__msg.toml\n\
+                 remove_dir\tsub\ncopy_back\t1__msg.toml\n"
+                    .to_string()
+            ),
+            "test_sync_rollback: plan file"
+        );
+
+        // Apply the plan as an operator would
+        for line in fs::read_to_string(plan.dir.join(ROLLBACK_PLAN_FILE))
+            .unwrap_or_default()
+            .lines()
+        {
+            let applied = match line.split_once('\t') {
+                Some(("delete_file", path)) => fs::remove_file(mirror.join(path)),
+                Some(("remove_dir", path)) => fs::remove_dir(mirror.join(path)),
+                Some(("copy_back", path)) => fs::copy(
+                    plan.dir.join(ROLLBACK_FILES_DIR).join(path),
+                    mirror.join(path),
+                )
+                .map(drop),
+                _ => Err(std::io::Error::other("unknown step")),
+            };
+            assert!(
+                applied.is_ok(),
+                "test_sync_rollback: step {:?} failed",
+                line
+            );
+        }
+        let mut restored: Vec<(String, Vec<u8>)> = fs::read_dir(&mirror)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|e| {
+                (
+                    e.file_name().to_string_lossy().into_owned(),
+                    fs::read(e.path()).unwrap_or_default(),
+                )
+            })
+            .collect();
+        restored.sort();
+        assert_eq!(
+            restored,
+            vec![
+                ("1__msg.toml".to_string(), b"old".to_vec()),
+                ("stale.toml".to_string(), b"stale".to_vec())
+            ],
+            "test_sync_rollback: mirror not restored"
+        );
+
+        cleanup(&base);
+    }
+
+    /// Test: executed actions are appended to a bounded log next to the
+    /// root, one line each with relative paths; dry runs log nothing.
+    #[test]
+    fn test_audit_log_records_actions() {
+        use crate::dir_walk_module::messages::{RenumberConfig, renumber_messages};
+
+        let base = test_dir("audit_log");
+        cleanup(&base);
+        let source = base.join("channel");
+        let mirror = base.join("mirror");
+        assert!(
+            fs::create_dir_all(source.join("sub")).is_ok()
+                && fs::write(source.join("3__msg.toml"), b"one").is_ok()
+                && fs::write(source.join("7__msg.toml"), b"two").is_ok()
+                && fs::write(source.join("sub").join("1__msg.toml"), b"three").is_ok(),
+            "test_audit_log: failed to create source"
+        );
+        let log_path = base.join("mirror.dwm-audit.log");
+        assert_eq!(
+            audit_log_path(&mirror),
+            Some(log_path.clone()),
+            "test_audit_log: log sits next to the root"
+        );
+        let read_log = |path: &Path| fs::read_to_string(path).unwrap_or_default();
+        let config = WalkConfig::new().audit_log(64 * 1024);
+
+        let planned = sync_tree(&source, &mirror, config.clone(), Mode::DryRun);
+        assert!(
+            planned.is_ok() && !log_path.exists(),
+            "test_audit_log: dry run must not write a log"
+        );
+        let done = sync_tree(&source, &mirror, config.clone(), Mode::Execute);
+        assert!(done.is_ok(), "test_audit_log: sync failed");
+        let Ok(done) = done else { return };
+
+        let log = read_log(&log_path);
+        let lines: Vec<Vec<&str>> = log.lines().map(|l| l.split('\t').collect()).collect();
+        assert_eq!(
+            lines.len(),
+            done.actions.len(),
+            "test_audit_log: one line per action in {:?}",
+            log
+        );
+        assert!(
+            lines.iter().all(|fields| fields.len() == 4
+                && fields[0].parse::<u64>().is_ok()
+                && fields[1] == "sync_tree"),
+            "test_audit_log: malformed lines in {:?}",
+            log
+        );
+        assert!(
+            lines.iter().any(|f| f[2] == "create_dir" && f[3] == "sub"),
+            "test_audit_log: directory creation logged in {:?}",
+            log
+        );
+        assert!(
+            lines
+                .iter()
+                .any(|f| f[2] == "copy_file" && f[3] == "sub/1__msg.toml")
+                && !log.contains(base.to_string_lossy().as_ref()),
+            "test_audit_log: paths must be relative in {:?}",
+            log
+        );
+
+        // Appends: a later run adds its lines after the earlier ones
+        let before = log.lines().count();
+        assert!(
+            delete_files(&mirror.join("sub"), config.clone(), Mode::Execute).is_ok(),
+            "test_audit_log: delete failed"
+        );
+        assert!(
+            read_log(&base.join("mirror").join("sub.dwm-audit.log"))
+                .starts_with(|c: char| c.is_ascii_digit())
+                && read_log(&log_path).lines().count() == before,
+            "test_audit_log: each root has its own log"
+        );
+
+        // Renumbering logs old and new name of each rename
+        let renumbered = renumber_messages(
+            &source,
+            RenumberConfig::new()
+                .walk_config(WalkConfig::message_loading().audit_log(64 * 1024))
+                .mode(Mode::Execute),
+        );
+        assert!(renumbered.is_ok(), "test_audit_log: renumber failed");
+        let renames: Vec<String> = read_log(&base.join("channel.dwm-audit.log"))
+            .lines()
+            .map(|l| l.split('\t').skip(1).collect::<Vec<_>>().join(" "))
+            .collect();
+        assert_eq!(
+            renames,
+            vec![
+                "renumber_messages rename 3__msg.toml 1__msg.toml",
+                "renumber_messages rename 7__msg.toml 2__msg.toml"
+            ],
+            "test_audit_log: renumber lines"
+        );
+
+        // Bounded: lines that would pass the limit are dropped
+        let small = base.join("small");
+        assert!(
+            copy_tree(
+                &source,
+                &small,
+                WalkConfig::new().audit_log(40),
+                Mode::Execute
+            )
+            .is_ok(),
+            "test_audit_log: bounded copy failed"
+        );
+        let small_log = read_log(&base.join("small.dwm-audit.log"));
+        assert!(
+            small_log.len() <= 40 && small_log.lines().count() == 1,
+            "test_audit_log: log must stay within its bound: {:?}",
+            small_log
+        );
+
+        // A link planted at the log path refuses the run before any write
+        #[cfg(unix)]
+        {
+            let trap = base.join("trap");
+            assert!(
+                std::os::unix::fs::symlink(
+                    base.join("elsewhere.log"),
+                    base.join("trap.dwm-audit.log")
+                )
+                .is_ok(),
+                "test_audit_log: failed to plant link"
+            );
+            assert_eq!(
+                copy_tree(&source, &trap, config, Mode::Execute).map(|r| r.actions.len()),
+                Err(WalkError::SpecialFile),
+                "test_audit_log: symlinked log must be refused"
+            );
+            assert!(
+                !trap.exists() && !base.join("elsewhere.log").exists(),
+                "test_audit_log: nothing may be written when refused"
+            );
+
+            // A link planted between the check and the open (the path was
+            // absent when checked) is caught on the opened handle
+            let victim = base.join("victim.log");
+            let raced = base.join("raced.dwm-audit.log");
+            assert!(
+                fs::write(&victim, b"keep").is_ok()
+                    && std::os::unix::fs::symlink(&victim, &raced).is_ok(),
+                "test_audit_log: failed to plant raced link"
+            );
+            assert_eq!(
+                open_audit_file(&raced, None).map(|(_, len)| len),
+                Err(WalkError::SpecialFile),
+                "test_audit_log: raced link must be refused"
+            );
+
+            // So is a regular file swapped in for the one that was checked
+            let swapped = base.join("swapped.dwm-audit.log");
+            let checked = fs::write(&swapped, b"old")
+                .and_then(|()| fs::symlink_metadata(&swapped))
+                .ok();
+            assert!(
+                checked.is_some()
+                    && fs::rename(&swapped, base.join("swapped.old")).is_ok()
+                    && fs::write(base.join("swap.tmp"), b"new").is_ok()
+                    && fs::rename(base.join("swap.tmp"), &swapped).is_ok(),
+                "test_audit_log: failed to swap log"
+            );
+            assert_eq!(
+                open_audit_file(&swapped, checked.as_ref()).map(|(_, len)| len),
+                Err(WalkError::SpecialFile),
+                "test_audit_log: swapped file must be refused"
+            );
+            assert_eq!(
+                fs::read(&victim).ok(),
+                Some(b"keep".to_vec()),
+                "test_audit_log: link target untouched"
+            );
+        }
+
+        cleanup(&base);
+    }
+}
@@ -0,0 +1,568 @@
+//! # Two-Phase Scan (`dir_walk_module::names`)
+//!
+//! A walk split in two: `scan_names()` lists the tree without stat-ing
+//! anything, and `hydrate()` stats only the entries the caller kept.
+//!
+//! ## Project Context
+//! Loading only the `.gpgtoml` files of a mixed archive with a
+//! `DirWalker` stats every entry, including the many that are discarded
+//! by name a moment later. Here the name filter runs first:
+//!
+//! ```ignore
+//! let mut kept: Vec<NameEntry> = scan_names(&archive, &WalkConfig::new())
+//!     .filter_map(|r| r.ok())
+//!     .filter(|e| e.file_name().is_some_and(|n| n.ends_with(".gpgtoml")))
+//!     .collect();
+//! let unreadable = hydrate(&mut kept);
+//! ```
+//!
+//! ## Cost
+//! Types come from `fs::DirEntry::file_type()`, which the OS reports
+//! with the listing on Linux, macOS, BSD and Windows. On filesystems
+//! that do not (`DT_UNKNOWN`), `std` falls back to one `lstat` per
+//! entry and the first phase saves nothing. `hydrate()` takes one
+//! `lstat` per kept entry, as the walker would have.
+//!
+//! ## Scope
+//! `scan_names()` reads its bounds and name-level filters from a
+//! `WalkConfig`: `max_depth`, `max_dirs`, `max_queue_size`,
+//! `max_entries_per_dir`, `yield_directories`, `skip_symlinks`,
+//! `skip_temp_files`, `skip_trash`, `skip_non_utf8_names` and
+//! `continue_on_error`.
+//! Settings that need metadata (`min_file_size`, `skip_executables`,
+//! `skip_dirs_older_than`, `follow_symlinks`) do not apply: symlinks are
+//! listed, never followed. The listing is breadth-first in OS order.
+//! Paths are always full paths, even with `yield_relative_paths`, since
+//! `hydrate()` stats by path.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::{
+    DirEntry, EntryPath, EntryTag, TRASH_DIR_NAME, WalkConfig, WalkError, WalkStats,
+    is_temp_file_name, next_walk_id, normalize_root,
+};
+
+// ============================================================================
+// NAME-ONLY ENTRIES
+// ============================================================================
+
+/// An entry found by `scan_names()`: its path, depth and listed type,
+/// plus its metadata once `hydrate()` has stat-ed it.
+///
+/// `Debug` shows the file name, not the path (as `DirEntry`'s does in
+/// release builds).
+#[derive(Clone)]
+pub struct NameEntry {
+    path: PathBuf,
+    depth: usize,
+
+    /// Type reported with the listing (`None` if it could not be read).
+    file_type: Option<fs::FileType>,
+
+    /// Filled by `hydrate()`.
+    metadata: Option<fs::Metadata>,
+}
+
+impl NameEntry {
+    /// Full path of the entry.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// File name, if it is valid UTF-8.
+    pub fn file_name(&self) -> Option<&str> {
+        self.path.file_name().and_then(|name| name.to_str())
+    }
+
+    /// Depth below the root (0 = the root's own entries).
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Type reported with the listing, without following symlinks.
+    pub fn file_type(&self) -> Option<fs::FileType> {
+        self.file_type
+    }
+
+    /// Whether the listing reports a directory (symlinks are not).
+    pub fn is_dir(&self) -> bool {
+        self.file_type.is_some_and(|file_type| file_type.is_dir())
+    }
+
+    /// Whether the listing reports a regular file.
+    pub fn is_file(&self) -> bool {
+        self.file_type.is_some_and(|file_type| file_type.is_file())
+    }
+
+    /// Whether the listing reports a symlink.
+    pub fn is_symlink(&self) -> bool {
+        self.file_type
+            .is_some_and(|file_type| file_type.is_symlink())
+    }
+
+    /// Metadata from `hydrate()` (not following symlinks); `None` before
+    /// hydration or if the stat failed.
+    pub fn metadata(&self) -> Option<&fs::Metadata> {
+        self.metadata.as_ref()
+    }
+
+    /// Whether `hydrate()` has stat-ed this entry.
+    pub fn is_hydrated(&self) -> bool {
+        self.metadata.is_some()
+    }
+
+    /// Turn a hydrated entry into a walker `DirEntry`.
+    ///
+    /// The entry is untagged (`WalkConfig::tag_entries` is not applied).
+    ///
+    /// # Returns
+    /// `None` if the entry is not hydrated (check `is_hydrated()` first
+    /// to keep it).
+    pub fn into_entry(self) -> Option<DirEntry> {
+        let metadata = self.metadata?;
+        Some(DirEntry {
+            path: EntryPath::from_path(self.path),
+            depth: self.depth,
+            is_dir: metadata.is_dir(),
+            is_file: metadata.is_file(),
+            is_symlink: metadata.is_symlink(),
+            metadata,
+            tag: EntryTag::Untagged,
+            truncated: None,
+            via_symlink: false,
+        })
+    }
+}
+
+/// Manual Debug impl: the file name only, never the path.
+impl fmt::Debug for NameEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NameEntry")
+            .field("name", &self.file_name().unwrap_or("<non-utf8>"))
+            .field("depth", &self.depth)
+            .field("is_dir", &self.is_dir())
+            .field("hydrated", &self.is_hydrated())
+            .finish()
+    }
+}
+
+/// Stat every entry that is not hydrated yet (second phase).
+///
+/// Uses `fs::symlink_metadata`, like the walker. Entries that fail to
+/// stat (e.g. deleted since the scan) stay unhydrated.
+///
+/// # Arguments
+/// * `entries` - Entries kept from `scan_names()`
+///
+/// # Returns
+/// Number of entries that could not be stat-ed.
+pub fn hydrate(entries: &mut [NameEntry]) -> usize {
+    let mut failed: usize = 0;
+    for entry in entries.iter_mut().filter(|entry| entry.metadata.is_none()) {
+        match fs::symlink_metadata(&entry.path) {
+            Ok(metadata) => {
+                entry.file_type = Some(metadata.file_type());
+                entry.metadata = Some(metadata);
+            }
+            Err(_e) => {
+                #[cfg(debug_assertions)]
+                eprintln!(
+                    "DWEM: Failed to hydrate entry at depth {}: {}",
+                    entry.depth, _e
+                );
+                failed = failed.saturating_add(1);
+            }
+        }
+    }
+    failed
+}
+
+// ============================================================================
+// FIRST PHASE
+// ============================================================================
+
+/// A directory whose listing is being read.
+struct OpenListing {
+    read_dir: fs::ReadDir,
+
+    /// Depth of the entries found in it.
+    depth: usize,
+    entries_read: usize,
+}
+
+/// Iterator returned by `scan_names()`.
+///
+/// Yields `Result<NameEntry, WalkError>` with the same error policy as
+/// `DirWalker`: skipped and counted under `continue_on_error`, otherwise
+/// yielded once, ending the scan.
+pub struct NameScan {
+    config: WalkConfig,
+
+    /// Directories still to be listed, with the depth of their entries.
+    /// Bounded by `config.max_queue_size`.
+    queue: VecDeque<(PathBuf, usize)>,
+    current: Option<OpenListing>,
+    stats: WalkStats,
+
+    /// Set once a fatal error has been yielded.
+    failed: bool,
+}
+
+/// List the tree under `root` by name and type, without stat-ing
+/// entries (first phase; see the module docs).
+///
+/// # Arguments
+/// * `root` - Starting directory path (normalized like `DirWalker`'s)
+/// * `config` - Bounds and name filters (see "Scope" in the module docs)
+pub fn scan_names(root: &Path, config: &WalkConfig) -> NameScan {
+    let mut queue = VecDeque::new();
+    queue.push_back((normalize_root(root), 0));
+    NameScan {
+        config: config.clone(),
+        queue,
+        current: None,
+        stats: WalkStats {
+            walk_id: next_walk_id(),
+            ..WalkStats::default()
+        },
+        failed: false,
+    }
+}
+
+impl NameScan {
+    /// Counters accumulated so far. Only the counters this phase can
+    /// fill are set (directories read, errors and limits, name filters,
+    /// queue peak); byte totals stay 0.
+    pub fn stats(&self) -> &WalkStats {
+        &self.stats
+    }
+
+    /// Skip `error` under `continue_on_error`, else end the scan with it.
+    ///
+    /// # Returns
+    /// The item to yield, if any.
+    fn handle_error(&mut self, error: WalkError) -> Option<Result<NameEntry, WalkError>> {
+        if self.config.continue_on_error {
+            self.stats.errors_skipped = self.stats.errors_skipped.saturating_add(1);
+            if error.is_limit() {
+                self.stats.limit_errors_skipped = self.stats.limit_errors_skipped.saturating_add(1);
+            }
+            None
+        } else {
+            self.failed = true;
+            self.queue.clear();
+            self.current = None;
+            Some(Err(error))
+        }
+    }
+
+    /// Open the next queued directory (bounded by `max_dirs`).
+    fn open_next(&mut self) -> Option<Result<(), WalkError>> {
+        let (path, depth) = self.queue.pop_front()?;
+        if let Some(max_dirs) = self.config.max_dirs
+            && self.stats.directories_read >= max_dirs
+        {
+            self.stats.dirs_limit_skipped = self.stats.dirs_limit_skipped.saturating_add(1);
+            return Some(Ok(()));
+        }
+        self.stats.directories_read = self.stats.directories_read.saturating_add(1);
+
+        match fs::read_dir(&path) {
+            Ok(read_dir) => {
+                self.current = Some(OpenListing {
+                    read_dir,
+                    depth,
+                    entries_read: 0,
+                });
+                Some(Ok(()))
+            }
+            Err(e) => {
+                #[cfg(debug_assertions)]
+                eprintln!(
+                    "DWRD: [walk {}] Failed to read directory at depth {}: {}",
+                    self.stats.walk_id, depth, e
+                );
+                Some(Err(super::io_walk_error(&e, WalkError::ReadDirectory)))
+            }
+        }
+    }
+
+    /// Queue a subdirectory found at `depth`, unless `max_depth` stops
+    /// the scan above it.
+    fn enqueue(&mut self, path: &Path, depth: usize) -> Result<(), WalkError> {
+        let Some(next_depth) = depth.checked_add(1) else {
+            return Err(WalkError::DepthOverflow);
+        };
+        if self.config.max_depth.is_some_and(|max| next_depth > max) {
+            return Ok(());
+        }
+        if self.queue.len() >= self.config.max_queue_size {
+            #[cfg(debug_assertions)]
+            eprintln!(
+                "DWQS: [walk {}] Queue size limit ({}) reached — skipping subdirectory at depth {}",
+                self.stats.walk_id, self.config.max_queue_size, next_depth
+            );
+            return Err(WalkError::QueueSizeExceeded);
+        }
+        self.queue.push_back((path.to_path_buf(), next_depth));
+        self.stats.peak_queued_dirs = self.stats.peak_queued_dirs.max(self.queue.len());
+        Ok(())
+    }
+}
+
+/// Manual Debug impl: counters only, never queued paths.
+impl fmt::Debug for NameScan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NameScan")
+            .field("walk_id", &self.stats.walk_id)
+            .field("queued_dirs", &self.queue.len())
+            .field("failed", &self.failed)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Iterator for NameScan {
+    type Item = Result<NameEntry, WalkError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Bounded: every pass takes one name from a finite listing or one
+        // directory from the bounded queue
+        loop {
+            if self.failed {
+                return None;
+            }
+            let Some(open) = self.current.as_mut() else {
+                match self.open_next()? {
+                    Ok(()) => continue,
+                    Err(error) => match self.handle_error(error) {
+                        Some(item) => return Some(item),
+                        None => continue,
+                    },
+                }
+            };
+            let depth = open.depth;
+            let Some(listed) = open.read_dir.next() else {
+                self.current = None;
+                continue;
+            };
+
+            // Production catch: enforce per-directory entry limit
+            if open.entries_read >= self.config.max_entries_per_dir {
+                #[cfg(debug_assertions)]
+                eprintln!(
+                    "DWEL: [walk {}] Entry limit ({}) reached for directory at depth {}",
+                    self.stats.walk_id, self.config.max_entries_per_dir, depth
+                );
+                self.current = None;
+                match self.handle_error(WalkError::EntryLimitExceeded) {
+                    Some(item) => return Some(item),
+                    None => continue,
+                }
+            }
+            open.entries_read = open.entries_read.saturating_add(1);
+
+            let listed = match listed {
+                Ok(listed) => listed,
+                Err(_e) => {
+                    #[cfg(debug_assertions)]
+                    eprintln!(
+                        "DWRD: [walk {}] Failed to read dir entry at depth {}: {}",
+                        self.stats.walk_id, depth, _e
+                    );
+                    match self.handle_error(WalkError::ReadDirectory) {
+                        Some(item) => return Some(item),
+                        None => continue,
+                    }
+                }
+            };
+            let path = listed.path();
+            let file_type = listed.file_type().ok();
+
+            if self.config.skip_non_utf8_names
+                && path.file_name().is_some_and(|name| name.to_str().is_none())
+            {
+                self.stats.non_utf8_names_skipped =
+                    self.stats.non_utf8_names_skipped.saturating_add(1);
+                continue;
+            }
+            let is_symlink = file_type.is_some_and(|file_type| file_type.is_symlink());
+            if is_symlink && self.config.skip_symlinks {
+                continue;
+            }
+
+            let is_dir = file_type.is_some_and(|file_type| file_type.is_dir());
+            if is_dir
+                && self.config.skip_trash
+                && path.file_name().is_some_and(|name| name == TRASH_DIR_NAME)
+            {
+                continue;
+            }
+            let should_yield = if is_dir {
+                if let Err(error) = self.enqueue(&path, depth)
+                    && let Some(item) = self.handle_error(error)
+                {
+                    return Some(item);
+                }
+                self.config.yield_directories
+            } else if self.config.skip_temp_files && path.file_name().is_some_and(is_temp_file_name)
+            {
+                self.stats.temp_files_skipped = self.stats.temp_files_skipped.saturating_add(1);
+                false
+            } else {
+                true
+            };
+
+            if should_yield {
+                return Some(Ok(NameEntry {
+                    path,
+                    depth,
+                    file_type,
+                    metadata: None,
+                }));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_dir(name: &str) -> PathBuf {
+        crate::dir_walk_module::testing::unique_test_dir(&format!("names_{}", name))
+    }
+
+    fn cleanup(path: &Path) {
+        let _ = fs::remove_dir_all(path);
+    }
+
+    /// Test: the name scan finds what a walk finds, and hydrating the
+    /// kept entries gives the walk's metadata for them only.
+    #[test]
+    fn test_scan_names_then_hydrate() {
+        let dir = test_dir("scan_then_hydrate");
+        cleanup(&dir);
+        let created = fs::create_dir_all(dir.join("alpha/old")).is_ok()
+            && fs::write(dir.join("alpha/1.gpgtoml"), b"sealed").is_ok()
+            && fs::write(dir.join("alpha/2.toml"), b"plain").is_ok()
+            && fs::write(dir.join("alpha/old/3.gpgtoml"), b"old").is_ok()
+            && fs::write(dir.join("alpha/.4.toml.tmp"), b"").is_ok()
+            && fs::write(dir.join("notes.txt"), b"x").is_ok();
+        assert!(created, "test_scan_names: failed to create tree");
+
+        let config = WalkConfig::new().skip_temp_files(true);
+        let mut scan = scan_names(&dir, &config);
+        let listed: Vec<NameEntry> = scan.by_ref().filter_map(|r| r.ok()).collect();
+        let mut walked: Vec<PathBuf> = config
+            .walk(&dir)
+            .ok_entries()
+            .map(|e| e.path().to_path_buf())
+            .collect();
+        let mut names: Vec<PathBuf> = listed.iter().map(|e| e.path().to_path_buf()).collect();
+        walked.sort();
+        names.sort();
+        assert_eq!(names, walked, "test_scan_names: same entries as a walk");
+        assert!(
+            listed.iter().all(|e| !e.is_hydrated())
+                && scan.stats().directories_read == 3
+                && scan.stats().temp_files_skipped == 1,
+            "test_scan_names: nothing stat-ed, counters filled"
+        );
+
+        let mut kept: Vec<NameEntry> = listed
+            .into_iter()
+            .filter(|e| e.file_name().is_some_and(|n| n.ends_with(".gpgtoml")))
+            .collect();
+        assert_eq!(
+            hydrate(&mut kept),
+            0,
+            "test_scan_names: every kept entry stats"
+        );
+        let lengths: Vec<u64> = kept
+            .iter()
+            .filter_map(|e| e.metadata().map(|m| m.len()))
+            .collect();
+        assert!(
+            kept.len() == 2 && lengths.iter().all(|len| *len == 6 || *len == 3),
+            "test_scan_names: hydrated metadata matches the files"
+        );
+
+        let entry = kept.pop().map(NameEntry::into_entry);
+        assert!(
+            matches!(entry, Some(Some(ref e)) if e.is_file() && e.path().starts_with(&dir)),
+            "test_scan_names: hydrated entry converts to DirEntry"
+        );
+
+        cleanup(&dir);
+    }
+
+    /// Test: bounds and the error policy match the walker's, and an
+    /// entry deleted between the phases is reported by `hydrate()`.
+    #[test]
+    fn test_scan_names_bounds_and_errors() {
+        let dir = test_dir("bounds_and_errors");
+        cleanup(&dir);
+        let created = fs::create_dir_all(dir.join("a/b/c")).is_ok()
+            && (0..4).all(|i| fs::write(dir.join(format!("a/{}.toml", i)), b"x").is_ok());
+        assert!(created, "test_scan_names_bounds: failed to create tree");
+
+        let depths: Vec<usize> = scan_names(&dir, &WalkConfig::new().max_depth(1))
+            .filter_map(|r| r.ok())
+            .map(|e| e.depth())
+            .collect();
+        assert!(
+            depths.len() == 6 && depths.iter().all(|depth| *depth <= 1),
+            "test_scan_names_bounds: max_depth stops the listing"
+        );
+
+        let strict = WalkConfig::new()
+            .max_entries_per_dir(2)
+            .continue_on_error(false);
+        let results: Vec<Result<NameEntry, WalkError>> = scan_names(&dir, &strict).collect();
+        assert!(
+            matches!(results.last(), Some(Err(WalkError::EntryLimitExceeded))),
+            "test_scan_names_bounds: strict scan ends on the entry limit"
+        );
+        let mut lenient = scan_names(&dir, &WalkConfig::new().max_entries_per_dir(2));
+        assert!(
+            lenient.by_ref().all(|r| r.is_ok()) && lenient.stats().limit_errors_skipped == 1,
+            "test_scan_names_bounds: lenient scan counts the limit"
+        );
+
+        let mut missing = scan_names(&dir.join("missing"), &strict);
+        assert!(
+            matches!(missing.next(), Some(Err(WalkError::ReadDirectory)))
+                && missing.next().is_none(),
+            "test_scan_names_bounds: unreadable root fails once"
+        );
+
+        let mut kept: Vec<NameEntry> = scan_names(&dir.join("a"), &WalkConfig::new())
+            .filter_map(|r| r.ok())
+            .filter(|e| e.is_file())
+            .collect();
+        assert!(
+            fs::remove_file(dir.join("a/0.toml")).is_ok(),
+            "test_scan_names_bounds: failed to remove file"
+        );
+        assert!(
+            kept.len() == 4 && hydrate(&mut kept) == 1,
+            "test_scan_names_bounds: deleted entry reported"
+        );
+        assert!(
+            kept.iter().filter(|e| e.is_hydrated()).count() == 3
+                && matches!(
+                    kept.iter()
+                        .find(|e| !e.is_hydrated())
+                        .cloned()
+                        .map(NameEntry::into_entry),
+                    Some(None)
+                ),
+            "test_scan_names_bounds: unhydrated entry does not convert"
+        );
+
+        cleanup(&dir);
+    }
+}
@@ -0,0 +1,454 @@
+//! # Platform Self-Test (`dir_walk_module::platform`)
+//!
+//! Probes the filesystem the application actually runs on and reports
+//! what it supports: directory symlinks, modification-time granularity,
+//! case sensitivity, and paths longer than the classic Windows limit.
+//!
+//! ## Project Context
+//! The same build runs on desktop NTFS, ext4, FAT-formatted SD cards and
+//! Android FUSE storage. Polling intervals and hash settings used to be
+//! picked from the OS name, which guesses wrong for removable media.
+//! `self_test()` measures instead:
+//!
+//! ```ignore
+//! let report = platform::self_test(&app_data_dir)?;
+//! let hash_config = report.hash_config();
+//! let poll_every = report.min_poll_interval().max(Duration::from_secs(5));
+//! ```
+//!
+//! Single probes are also available on their own (`supports_symlinks()`,
+//! `mtime_granularity()`, `is_case_insensitive()`) for code that needs
+//! one answer, such as `HashConfig::for_filesystem()`.
+//!
+//! ## Method
+//! Every probe works inside a fresh scratch directory under the given
+//! directory, which is removed afterwards. A probe that cannot run (e.g.
+//! the platform refuses to set a timestamp) reports "unknown" rather than
+//! failing the whole test.
+
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::WalkError;
+use super::hashing::HashConfig;
+
+/// Prefix of the scratch directory created under `self_test()`'s
+/// directory.
+pub const SELF_TEST_DIR_PREFIX: &str = ".dwm-selftest-";
+
+/// Most scratch directory names tried before giving up.
+const MAX_SCRATCH_ATTEMPTS: u64 = 16;
+
+/// Path length the long-path probe must exceed: `MAX_PATH` (260) on
+/// Windows without the long-path opt-in.
+const LONG_PATH_LEN: usize = 300;
+
+/// Component length used to build the long path; below `NAME_MAX` (255).
+const LONG_COMPONENT_LEN: usize = 120;
+
+/// Granularities the mtime probe recognizes, finest first. FAT stores
+/// two-second steps; NTFS 100 ns; most Unix filesystems 1 ns.
+const GRANULARITIES: [Duration; 7] = [
+    Duration::from_nanos(1),
+    Duration::from_nanos(100),
+    Duration::from_micros(1),
+    Duration::from_millis(1),
+    Duration::from_millis(10),
+    Duration::from_secs(1),
+    Duration::from_secs(2),
+];
+
+/// The one granularity stored by rounding up rather than down: FAT
+/// rounds modification times up to even seconds.
+const ROUND_UP_GRANULARITY: Duration = Duration::from_secs(2);
+
+/// Modification time the mtime probe sets: an odd second count plus
+/// nanoseconds with no trailing zeros, so every granularity truncates it.
+const PROBE_MTIME_SECS: u64 = 1_700_000_001;
+const PROBE_MTIME_NANOS: u32 = 123_456_789;
+
+/// Distinguishes scratch directories of concurrent self-tests in one
+/// process.
+static SCRATCH_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+// ============================================================================
+// SELF-TEST REPORT
+// ============================================================================
+
+/// Capabilities found by `self_test()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SelfTestReport {
+    /// A directory symlink could be created and is reported as a link to
+    /// a directory (`follow_symlinks` walks are meaningful here).
+    pub symlinks: bool,
+
+    /// Finest step modification times are stored at; `None` when the
+    /// probe could not set or read back a timestamp.
+    pub mtime_granularity: Option<Duration>,
+
+    /// Whether names differing only in case are different files; `None`
+    /// when the probe file could not be created.
+    pub case_sensitive: Option<bool>,
+
+    /// A file whose path is longer than `MAX_PATH` could be created and
+    /// read back.
+    pub long_paths: bool,
+}
+
+impl SelfTestReport {
+    /// Hash settings that match the measured mtime granularity
+    /// (`HashConfig::new()` defaults when it is unknown).
+    ///
+    /// A granularity finer than the default whole second is kept as the
+    /// default: hashes then stay comparable with replicas on coarser
+    /// filesystems.
+    pub fn hash_config(&self) -> HashConfig {
+        hash_config_for(self.mtime_granularity)
+    }
+
+    /// Shortest polling interval that can tell two writes apart by
+    /// modification time: the mtime granularity (one second when
+    /// unknown). Faster polls only cost I/O.
+    pub fn min_poll_interval(&self) -> Duration {
+        self.mtime_granularity
+            .unwrap_or(Duration::from_secs(1))
+            .max(Duration::from_millis(1))
+    }
+}
+
+// ============================================================================
+// SELF-TEST
+// ============================================================================
+
+/// Probe the filesystem holding `temp_dir` and report its capabilities.
+///
+/// # Arguments
+/// * `temp_dir` - Existing writable directory on the filesystem to test
+///   (e.g. the application's data directory, not the system temp, when
+///   the two may differ)
+///
+/// # Returns
+/// * `Ok(SelfTestReport)` - Probe results; individual probes that could
+///   not run report "unknown" / unsupported
+/// * `Err(WalkError::IoError)` - No scratch directory could be created
+/// * `Err(WalkError::RenameConflict)` - Every scratch name was taken
+pub fn self_test(temp_dir: &Path) -> Result<SelfTestReport, WalkError> {
+    with_scratch_dir(temp_dir, |scratch| SelfTestReport {
+        symlinks: probe_symlinks(scratch),
+        mtime_granularity: probe_mtime_granularity(scratch),
+        case_sensitive: probe_case_sensitivity(scratch),
+        long_paths: probe_long_paths(scratch),
+    })
+}
+
+/// Whether the filesystem holding `dir` supports directory symlinks
+/// (`SelfTestReport::symlinks`).
+///
+/// # Arguments
+/// * `dir` - Existing writable directory on the filesystem to test
+///
+/// # Returns
+/// `Ok(bool)`, or `Err` as for `self_test()` when no scratch directory
+/// could be created.
+pub fn supports_symlinks(dir: &Path) -> Result<bool, WalkError> {
+    with_scratch_dir(dir, probe_symlinks)
+}
+
+/// Finest step the filesystem holding `dir` stores modification times
+/// at (`SelfTestReport::mtime_granularity`).
+///
+/// # Arguments
+/// * `dir` - Existing writable directory on the filesystem to test
+///
+/// # Returns
+/// `Ok(Some(step))`, `Ok(None)` when no timestamp could be set or read
+/// back, or `Err` as for `self_test()`.
+pub fn mtime_granularity(dir: &Path) -> Result<Option<Duration>, WalkError> {
+    with_scratch_dir(dir, probe_mtime_granularity)
+}
+
+/// Whether the filesystem holding `dir` treats names differing only in
+/// case as the same file (the inverse of
+/// `SelfTestReport::case_sensitive`).
+///
+/// # Arguments
+/// * `dir` - Existing writable directory on the filesystem to test
+///
+/// # Returns
+/// `Ok(Some(bool))`, `Ok(None)` when the probe file could not be
+/// created, or `Err` as for `self_test()`.
+pub fn is_case_insensitive(dir: &Path) -> Result<Option<bool>, WalkError> {
+    with_scratch_dir(dir, |scratch| {
+        probe_case_sensitivity(scratch).map(|sensitive| !sensitive)
+    })
+}
+
+/// Hash settings for a measured mtime granularity; shared by
+/// `SelfTestReport::hash_config()` and `HashConfig::for_filesystem()`.
+///
+/// A granularity finer than the default whole second keeps the default,
+/// so hashes stay comparable with replicas on coarser filesystems.
+pub(super) fn hash_config_for(granularity: Option<Duration>) -> HashConfig {
+    match granularity {
+        Some(granularity) if granularity > Duration::from_secs(1) => {
+            HashConfig::new().mtime_granularity(granularity)
+        }
+        _ => HashConfig::new(),
+    }
+}
+
+/// Run `probe` in a fresh scratch directory under `dir`, then remove it.
+fn with_scratch_dir<T>(dir: &Path, probe: impl FnOnce(&Path) -> T) -> Result<T, WalkError> {
+    let scratch = create_scratch_dir(dir)?;
+    let result = probe(&scratch);
+    remove_scratch_dir(&scratch);
+    Ok(result)
+}
+
+/// Remove a scratch directory; failures are only logged.
+fn remove_scratch_dir(scratch: &Path) {
+    if let Err(_e) = fs::remove_dir_all(scratch) {
+        #[cfg(debug_assertions)]
+        eprintln!("DWIO: self-test scratch directory not removed: {}", _e);
+    }
+}
+
+/// Create a fresh scratch directory under `temp_dir`.
+fn create_scratch_dir(temp_dir: &Path) -> Result<PathBuf, WalkError> {
+    for _ in 0..MAX_SCRATCH_ATTEMPTS {
+        let n = SCRATCH_COUNTER.fetch_add(1, Ordering::Relaxed);
+        let dir = temp_dir.join(format!(
+            "{}{}-{}",
+            SELF_TEST_DIR_PREFIX,
+            std::process::id(),
+            n
+        ));
+        match fs::create_dir(&dir) {
+            Ok(()) => return Ok(dir),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(_e) => {
+                #[cfg(debug_assertions)]
+                eprintln!("DWIO: self-test scratch directory not created: {}", _e);
+                return Err(WalkError::IoError);
+            }
+        }
+    }
+
+    #[cfg(debug_assertions)]
+    eprintln!("DWRC: no free self-test scratch directory name");
+    Err(WalkError::RenameConflict)
+}
+
+// ============================================================================
+// PROBES
+// ============================================================================
+
+/// Create a symlink to a directory and check it is seen as one.
+fn probe_symlinks(scratch: &Path) -> bool {
+    let target = scratch.join("link_target");
+    let link = scratch.join("link");
+    if fs::create_dir(&target).is_err() {
+        return false;
+    }
+
+    #[cfg(unix)]
+    let created = std::os::unix::fs::symlink(&target, &link);
+    #[cfg(windows)]
+    let created = std::os::windows::fs::symlink_dir(&target, &link);
+    #[cfg(not(any(unix, windows)))]
+    let created: std::io::Result<()> = Err(std::io::Error::from(std::io::ErrorKind::Unsupported));
+
+    if let Err(_e) = created {
+        #[cfg(debug_assertions)]
+        eprintln!("DWSL: self-test symlink not created: {}", _e);
+        return false;
+    }
+    fs::symlink_metadata(&link).is_ok_and(|m| m.file_type().is_symlink())
+        && fs::metadata(&link).is_ok_and(|m| m.is_dir())
+}
+
+/// Set a known modification time and infer the storage step from how it
+/// reads back.
+fn probe_mtime_granularity(scratch: &Path) -> Option<Duration> {
+    let path = scratch.join("mtime_probe");
+    let set = Duration::new(PROBE_MTIME_SECS, PROBE_MTIME_NANOS);
+    let stored = File::create(&path)
+        .and_then(|file| file.set_modified(UNIX_EPOCH + set))
+        .and_then(|()| fs::metadata(&path))
+        .and_then(|m| m.modified());
+    let stored = match stored {
+        Ok(time) => time,
+        Err(_e) => {
+            #[cfg(debug_assertions)]
+            eprintln!("DWIO: self-test mtime probe failed: {}", _e);
+            return None;
+        }
+    };
+    granularity_of(set, stored)
+}
+
+/// The finest known granularity that explains `stored` as `set`
+/// truncated to it (or, for `ROUND_UP_GRANULARITY`, rounded up).
+fn granularity_of(set: Duration, stored: SystemTime) -> Option<Duration> {
+    let stored = stored.duration_since(UNIX_EPOCH).ok()?.as_nanos();
+    let set = set.as_nanos();
+    GRANULARITIES.into_iter().find(|&step| {
+        let step_nanos = step.as_nanos();
+        let down = set - set % step_nanos;
+        stored == down
+            || (step == ROUND_UP_GRANULARITY && stored == down.saturating_add(step_nanos))
+    })
+}
+
+/// Create a lowercase file and look it up in uppercase.
+fn probe_case_sensitivity(scratch: &Path) -> Option<bool> {
+    let lower = scratch.join("case_probe");
+    if let Err(_e) = File::create(&lower) {
+        #[cfg(debug_assertions)]
+        eprintln!("DWIO: self-test case probe failed: {}", _e);
+        return None;
+    }
+    Some(fs::symlink_metadata(scratch.join("CASE_PROBE")).is_err())
+}
+
+/// Create a file whose path exceeds `LONG_PATH_LEN` and read it back.
+fn probe_long_paths(scratch: &Path) -> bool {
+    let component = "d".repeat(LONG_COMPONENT_LEN);
+    let mut path = scratch.to_path_buf();
+    // Bounded: LONG_PATH_LEN / LONG_COMPONENT_LEN + 1 components
+    while path.as_os_str().len() <= LONG_PATH_LEN {
+        path.push(&component);
+    }
+    let dir = path.clone();
+    path.push("long_path_probe");
+    let written = fs::create_dir_all(&dir).and_then(|()| fs::write(&path, b"probe"));
+    if let Err(_e) = written {
+        #[cfg(debug_assertions)]
+        eprintln!("DWIO: self-test long path probe failed: {}", _e);
+        return false;
+    }
+    fs::read(&path).is_ok_and(|content| content == b"probe")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dir_walk_module::testing;
+
+    fn test_dir(name: &str) -> PathBuf {
+        testing::unique_test_dir(name)
+    }
+
+    fn cleanup(path: &Path) {
+        let _ = fs::remove_dir_all(path);
+    }
+
+    /// Test: the self-test runs on the test filesystem, cleans up after
+    /// itself, and reports what the host platform is known to support.
+    #[test]
+    fn test_self_test_reports_capabilities() {
+        let dir = test_dir("self_test");
+        cleanup(&dir);
+        assert!(
+            fs::create_dir_all(&dir).is_ok(),
+            "test_self_test: failed to create dir"
+        );
+
+        let report = self_test(&dir);
+        assert!(report.is_ok(), "test_self_test: self_test failed");
+        let Ok(report) = report else { return };
+
+        let leftovers = fs::read_dir(&dir).map_or(usize::MAX, |entries| entries.count());
+        assert_eq!(
+            leftovers, 0,
+            "test_self_test: scratch directory left behind"
+        );
+        assert!(
+            report.mtime_granularity.is_some() && report.case_sensitive.is_some(),
+            "test_self_test: probes must run: {:?}",
+            report
+        );
+        #[cfg(unix)]
+        assert!(report.symlinks, "test_self_test: Unix supports symlinks");
+        assert!(
+            report.min_poll_interval() >= Duration::from_millis(1),
+            "test_self_test: poll interval must be positive"
+        );
+
+        assert!(
+            self_test(&dir.join("missing")) == Err(WalkError::IoError),
+            "test_self_test: missing directory must fail"
+        );
+
+        // The standalone probes agree with the full report
+        assert_eq!(
+            (
+                supports_symlinks(&dir),
+                mtime_granularity(&dir),
+                is_case_insensitive(&dir),
+            ),
+            (
+                Ok(report.symlinks),
+                Ok(report.mtime_granularity),
+                Ok(report.case_sensitive.map(|sensitive| !sensitive)),
+            ),
+            "test_self_test: standalone probes must match the report"
+        );
+        let leftovers = fs::read_dir(&dir).map_or(usize::MAX, |entries| entries.count());
+        assert_eq!(
+            leftovers, 0,
+            "test_self_test: standalone probes left scratch behind"
+        );
+
+        cleanup(&dir);
+    }
+
+    /// Test: stored mtimes map to the granularity that explains them,
+    /// including FAT's round-up to even seconds.
+    #[test]
+    fn test_granularity_of() {
+        let set = Duration::new(PROBE_MTIME_SECS, PROBE_MTIME_NANOS);
+        let cases = [
+            (set, Some(Duration::from_nanos(1))),
+            (
+                Duration::new(PROBE_MTIME_SECS, 123_456_700),
+                Some(Duration::from_nanos(100)),
+            ),
+            (
+                Duration::new(PROBE_MTIME_SECS, 0),
+                Some(Duration::from_secs(1)),
+            ),
+            (
+                Duration::new(PROBE_MTIME_SECS + 1, 0),
+                Some(Duration::from_secs(2)),
+            ),
+            (Duration::new(PROBE_MTIME_SECS + 10, 0), None),
+        ];
+        for (stored, expected) in cases {
+            assert_eq!(
+                granularity_of(set, UNIX_EPOCH + stored),
+                expected,
+                "test_granularity_of: stored {:?}",
+                stored
+            );
+        }
+
+        let fat = SelfTestReport {
+            symlinks: false,
+            mtime_granularity: Some(Duration::from_secs(2)),
+            case_sensitive: Some(false),
+            long_paths: false,
+        };
+        // HashConfig has no PartialEq; its Debug output shows every field
+        assert_eq!(
+            format!("{:?}", fat.hash_config()),
+            format!(
+                "{:?}",
+                HashConfig::new().mtime_granularity(Duration::from_secs(2))
+            ),
+            "test_granularity_of: FAT needs two-second hashing"
+        );
+    }
+}
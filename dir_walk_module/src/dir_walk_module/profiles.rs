@@ -0,0 +1,399 @@
+//! # Named Walk Profiles (`dir_walk_module::profiles`)
+//!
+//! Named `WalkConfig` bound-sets loaded at runtime from the application's
+//! TOML configuration.
+//!
+//! ## Project Context
+//! Operators tune limits (queue size, entries per directory, depth) per
+//! deployment. Keeping those numbers in the config file instead of the
+//! source means a NAS with a million-entry archive and a laptop can run
+//! the same binary: the code asks for `profiles.get("channel_scan")` and
+//! the config decides what that means.
+//!
+//! ## Format
+//! Profiles are tables named `[walk_profiles.<name>]`; every other table
+//! in the file is skipped unread, so the whole application config can be
+//! passed in. Inside a profile table, one `key = value` per line:
+//!
+//! ```toml
+//! [walk_profiles.channel_scan]
+//! base = "channel_scan"          # preset to start from (optional)
+//! max_entries_per_dir = 200_000
+//! skip_dirs_older_than_secs = 2_592_000
+//!
+//! [walk_profiles.nas_archive]
+//! max_depth = 3
+//! follow_symlinks = false
+//! ```
+//!
+//! Values are integers (underscores allowed), booleans, or (for `base`)
+//! basic strings; `#` starts a comment. This is the subset of TOML the
+//! profiles need, not a general TOML parser: arrays, inline tables and
+//! multi-line values are not accepted inside profile tables. Unknown
+//! keys, duplicate keys or profiles, and out-of-range values are errors,
+//! so a typo never silently falls back to a default.
+
+use std::collections::BTreeMap;
+
+use super::{WalkConfig, WalkError};
+
+/// Table prefix that marks a profile: `[walk_profiles.<name>]`.
+const PROFILE_TABLE_PREFIX: &str = "walk_profiles.";
+
+/// Most profiles one configuration may define.
+const MAX_PROFILES: usize = 1024;
+
+/// Longest configuration text accepted, in bytes.
+const MAX_CONFIG_LEN: usize = 1024 * 1024;
+
+// ============================================================================
+// PROFILE REGISTRY
+// ============================================================================
+
+/// Named walk configurations loaded from a configuration string.
+#[derive(Debug, Clone, Default)]
+pub struct WalkProfiles {
+    /// Profiles by name.
+    profiles: BTreeMap<String, WalkConfig>,
+}
+
+/// One parsed value of a `key = value` line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ProfileValue {
+    Integer(u64),
+    Boolean(bool),
+    Text(String),
+}
+
+impl WalkProfiles {
+    /// Parse every `[walk_profiles.<name>]` table of a configuration.
+    ///
+    /// # Arguments
+    /// * `config_str` - TOML text (see the module docs for the accepted
+    ///   subset); tables outside `walk_profiles` are ignored
+    ///
+    /// # Returns
+    /// * `Ok(WalkProfiles)` - All profiles parsed (possibly none)
+    /// * `Err(WalkError::ProfileFormat)` - A profile table is malformed,
+    ///   names an unknown key or preset, repeats a key or profile name,
+    ///   or the text exceeds the size / profile-count bounds
+    pub fn load(config_str: &str) -> Result<Self, WalkError> {
+        if config_str.len() > MAX_CONFIG_LEN {
+            return Err(profile_format_error(0, "configuration too large"));
+        }
+
+        let mut profiles = BTreeMap::new();
+        // Profile being collected: name and its key-value lines
+        let mut current: Option<(String, Vec<(String, ProfileValue)>)> = None;
+
+        for (index, raw_line) in config_str.lines().enumerate() {
+            let line_number = index.saturating_add(1);
+            let line = raw_line.trim();
+
+            if let Some(header) = table_header(line) {
+                if let Some((name, values)) = current.take() {
+                    insert_profile(&mut profiles, name, &values, line_number)?;
+                }
+                if let Some(name) = header.strip_prefix(PROFILE_TABLE_PREFIX) {
+                    if !is_valid_profile_name(name) {
+                        return Err(profile_format_error(line_number, "invalid profile name"));
+                    }
+                    current = Some((name.to_string(), Vec::new()));
+                }
+                continue;
+            }
+
+            // Lines of other tables are not ours to validate
+            let Some((_, values)) = current.as_mut() else {
+                continue;
+            };
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(profile_format_error(line_number, "expected key = value"));
+            };
+            let key = key.trim();
+            if values.iter().any(|(existing, _)| existing == key) {
+                return Err(profile_format_error(line_number, "duplicate key"));
+            }
+            let value = parse_value(value.trim())
+                .ok_or_else(|| profile_format_error(line_number, "unsupported value"))?;
+            values.push((key.to_string(), value));
+        }
+
+        if let Some((name, values)) = current.take() {
+            insert_profile(&mut profiles, name, &values, config_str.lines().count())?;
+        }
+
+        Ok(WalkProfiles { profiles })
+    }
+
+    /// Configuration of a profile, by name.
+    ///
+    /// # Returns
+    /// A copy of the profile's `WalkConfig`, or `None` if no profile has
+    /// that name (callers typically fall back to the matching preset).
+    pub fn get(&self, name: &str) -> Option<WalkConfig> {
+        self.profiles.get(name).cloned()
+    }
+
+    /// Profile names, in sorted order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.profiles.keys().map(String::as_str)
+    }
+
+    /// Number of profiles.
+    pub fn len(&self) -> usize {
+        self.profiles.len()
+    }
+
+    /// Whether no profiles were defined.
+    pub fn is_empty(&self) -> bool {
+        self.profiles.is_empty()
+    }
+}
+
+// ============================================================================
+// PARSING HELPERS
+// ============================================================================
+
+/// Log a profile format problem (debug builds) and return the error.
+fn profile_format_error(_line_number: usize, _reason: &str) -> WalkError {
+    #[cfg(debug_assertions)]
+    eprintln!("DWPF: walk profile line {}: {}", _line_number, _reason);
+    WalkError::ProfileFormat
+}
+
+/// The name inside a `[table]` header line, if the line is one.
+///
+/// Array-of-tables headers (`[[x]]`) count as headers too, so their
+/// contents are skipped like any other foreign table.
+fn table_header(line: &str) -> Option<&str> {
+    let without_comment = match line.split_once('#') {
+        Some((before, _)) => before.trim_end(),
+        None => line,
+    };
+    let inner = without_comment.strip_prefix('[')?.strip_suffix(']')?;
+    if inner.contains('=') {
+        return None;
+    }
+    Some(inner.trim_matches(['[', ']']).trim())
+}
+
+/// Profile names are bare TOML keys: ASCII letters, digits, `_` and `-`.
+fn is_valid_profile_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .bytes()
+            .all(|byte| byte.is_ascii_alphanumeric() || byte == b'_' || byte == b'-')
+}
+
+/// Parse a value (with an optional trailing comment).
+fn parse_value(text: &str) -> Option<ProfileValue> {
+    if let Some(rest) = text.strip_prefix('"') {
+        let (content, after) = rest.split_once('"')?;
+        let after = after.trim();
+        if !(after.is_empty() || after.starts_with('#')) || content.contains('\\') {
+            return None;
+        }
+        return Some(ProfileValue::Text(content.to_string()));
+    }
+
+    let text = match text.split_once('#') {
+        Some((before, _)) => before.trim(),
+        None => text,
+    };
+    match text {
+        "true" => Some(ProfileValue::Boolean(true)),
+        "false" => Some(ProfileValue::Boolean(false)),
+        _ => {
+            // TOML allows `_` only between digits
+            if text.starts_with('_') || text.ends_with('_') || text.contains("__") {
+                return None;
+            }
+            text.replace('_', "")
+                .parse()
+                .ok()
+                .map(ProfileValue::Integer)
+        }
+    }
+}
+
+/// Build a profile from its values and add it to the registry.
+fn insert_profile(
+    profiles: &mut BTreeMap<String, WalkConfig>,
+    name: String,
+    values: &[(String, ProfileValue)],
+    line_number: usize,
+) -> Result<(), WalkError> {
+    if profiles.contains_key(&name) {
+        return Err(profile_format_error(line_number, "duplicate profile"));
+    }
+    if profiles.len() >= MAX_PROFILES {
+        return Err(profile_format_error(line_number, "too many profiles"));
+    }
+
+    // The preset goes first wherever `base` appears in the table
+    let mut config = match values.iter().find(|(key, _)| key == "base") {
+        None => WalkConfig::new(),
+        Some((_, ProfileValue::Text(preset))) => match preset.as_str() {
+            "default" => WalkConfig::new(),
+            "channel_scan" => WalkConfig::channel_scan(),
+            "message_loading" => WalkConfig::message_loading(),
+            "low_memory" => WalkConfig::low_memory_profile(),
+            _ => return Err(profile_format_error(line_number, "unknown base preset")),
+        },
+        Some(_) => return Err(profile_format_error(line_number, "base must be a string")),
+    };
+
+    for (key, value) in values.iter().filter(|(key, _)| key != "base") {
+        config = apply_setting(config, key, value)
+            .ok_or_else(|| profile_format_error(line_number, "unknown key or wrong value type"))?;
+    }
+
+    profiles.insert(name, config);
+    Ok(())
+}
+
+/// Apply one `key = value` setting through the `WalkConfig` builders.
+///
+/// # Returns
+/// `None` for an unknown key, a value of the wrong type, or an integer
+/// that does not fit the setting.
+fn apply_setting(config: WalkConfig, key: &str, value: &ProfileValue) -> Option<WalkConfig> {
+    let integer = || match value {
+        ProfileValue::Integer(number) => Some(*number),
+        _ => None,
+    };
+    let count = || integer().and_then(|number| usize::try_from(number).ok());
+    let flag = || match value {
+        ProfileValue::Boolean(enabled) => Some(*enabled),
+        _ => None,
+    };
+    let text = || match value {
+        ProfileValue::Text(text) => Some(text.as_str()),
+        _ => None,
+    };
+
+    Some(match key {
+        "max_depth" => config.max_depth(count()?),
+        "max_queue_size" => config.max_queue_size(count()?),
+        "max_entries_per_dir" => config.max_entries_per_dir(count()?),
+        "max_bytes_per_dir_listing" => config.max_bytes_per_dir_listing(count()?),
+        "max_yield_per_dir" => config.max_yield_per_dir(count()?),
+        "max_dirs" => config.max_dirs(count()?),
+        "max_errors" => config.max_errors(count()?),
+        "max_total_entries" => config.max_total_entries(count()?),
+        "max_entries_via_symlinks" => config.max_entries_via_symlinks(count()?),
+        "max_destructive_entries" => config.max_destructive_entries(count()?),
+        "max_destructive_bytes" => config.max_destructive_bytes(integer()?),
+        "min_file_size" => config.min_file_size(integer()?),
+        "shuffle_seed" => config.shuffle_seed(integer()?),
+        "skip_dirs_older_than_secs" => {
+            config.skip_dirs_older_than(std::time::Duration::from_secs(integer()?))
+        }
+        "scan_lock_owner" => config.scan_lock_owner(text()?),
+        "scan_lock_ttl_secs" => config.scan_lock_ttl(std::time::Duration::from_secs(integer()?)),
+        "time_budget_millis" => config.time_budget(std::time::Duration::from_millis(integer()?)),
+        "yield_directories" => config.yield_directories(flag()?),
+        "continue_on_error" => config.continue_on_error(flag()?),
+        "resumable_errors" => config.resumable_errors(flag()?),
+        "snapshot_dir_listing" => config.snapshot_dir_listing(flag()?),
+        "yield_relative_paths" => config.yield_relative_paths(flag()?),
+        "follow_symlinks" => config.follow_symlinks(flag()?),
+        "skip_symlinks" => config.skip_symlinks(flag()?),
+        "skip_temp_files" => config.skip_temp_files(flag()?),
+        "skip_trash" => config.skip_trash(flag()?),
+        "skip_locked_dirs" => config.skip_locked_dirs(flag()?),
+        "skip_executables" => config.skip_executables(flag()?),
+        "yield_root_file" => config.yield_root_file(flag()?),
+        _ => return None,
+    })
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: profiles load from a full application config, start from
+    /// their base preset, and foreign tables are ignored.
+    #[test]
+    fn test_load_profiles() {
+        let config = r#"
+# Application settings
+[server]
+name = "uma"
+peers = [
+  "a",
+]
+
+[walk_profiles.channel_scan]
+base = "channel_scan"   # start from the preset
+max_entries_per_dir = 200_000
+skip_dirs_older_than_secs = 86400
+
+[walk_profiles.shallow]
+max_depth = 1
+follow_symlinks = false
+"#;
+        let profiles = WalkProfiles::load(config);
+        assert!(profiles.is_ok(), "test_load_profiles: load failed");
+        let Ok(profiles) = profiles else {
+            return;
+        };
+
+        assert_eq!(
+            profiles.names().collect::<Vec<_>>(),
+            vec!["channel_scan", "shallow"],
+            "test_load_profiles: profile names"
+        );
+        let Some(channel_scan) = profiles.get("channel_scan") else {
+            return;
+        };
+        assert!(
+            channel_scan.skip_temp_files
+                && channel_scan.max_entries_per_dir == 200_000
+                && channel_scan.skip_dirs_older_than == Some(std::time::Duration::from_secs(86400)),
+            "test_load_profiles: channel_scan settings"
+        );
+        assert!(
+            profiles
+                .get("shallow")
+                .is_some_and(|shallow| shallow.max_depth == Some(1) && !shallow.skip_temp_files),
+            "test_load_profiles: shallow settings"
+        );
+        assert!(
+            profiles.get("missing").is_none(),
+            "test_load_profiles: unknown profile"
+        );
+    }
+
+    /// Test: typos and malformed profile lines are errors, not defaults.
+    #[test]
+    fn test_load_profiles_rejects_malformed() {
+        for config in [
+            "[walk_profiles.a]\nmax_deph = 1",
+            "[walk_profiles.a]\nmax_depth = \"1\"",
+            "[walk_profiles.a]\nmax_depth = -1",
+            "[walk_profiles.a]\nmax_depth = 1\nmax_depth = 2",
+            "[walk_profiles.a]\nfollow_symlinks = yes",
+            "[walk_profiles.a]\nbase = \"fast\"",
+            "[walk_profiles.a]\njust text",
+            "[walk_profiles.a]\n[walk_profiles.a]",
+            "[walk_profiles.bad name]",
+        ] {
+            assert_eq!(
+                WalkProfiles::load(config).map(|profiles| profiles.len()),
+                Err(WalkError::ProfileFormat),
+                "test_load_profiles_rejects_malformed: {:?}",
+                config
+            );
+        }
+    }
+}
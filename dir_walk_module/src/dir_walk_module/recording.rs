@@ -0,0 +1,608 @@
+//! # Walk Recordings (`dir_walk_module::recording`)
+//!
+//! A bounded log of what a walk yielded, for diagnosing scans that give
+//! surprising results in the field.
+//!
+//! ## Project Context
+//! "The channel shows no messages" reports used to need a debugger on the
+//! user's device. A scan can instead tee its entries into a recorder and
+//! attach the dump to the bug report:
+//!
+//! ```ignore
+//! let mut recorder = WalkRecorder::new();
+//! let entries: Vec<DirEntry> = DirWalker::new(&channel, config)
+//!     .record_to(&mut recorder, 200)
+//!     .filter_map(|r| r.ok())
+//!     .collect();
+//! if entries.is_empty() {
+//!     recorder.write_dump(&mut diagnostics_file)?;
+//! }
+//! ```
+//!
+//! A `WalkExpectation` checks a recording against contracts written as
+//! data, so tests of higher layers can state what they rely on from the
+//! walker:
+//!
+//! ```ignore
+//! let contract = WalkExpectation::new()
+//!     .yields(EntryMatch::Tag(EntryTag::Metadata))
+//!     .before(EntryMatch::Tag(EntryTag::Metadata), EntryMatch::Tag(EntryTag::Message));
+//! assert!(contract.verify(&recorder).is_ok());
+//! ```
+//!
+//! ## Privacy
+//! Only names, depths and entry types are kept. Full paths, which may
+//! contain user names and mount points, are recorded in debug builds
+//! only.
+
+use std::fmt;
+use std::io;
+#[cfg(debug_assertions)]
+use std::path::PathBuf;
+
+use super::{DirEntry, DirWalker, EntryTag, WalkError, WalkStats};
+
+// ============================================================================
+// RECORDED ENTRIES
+// ============================================================================
+
+/// Type of a recorded entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RecordedKind {
+    /// Regular file (or a followed link to one).
+    File,
+    /// Directory (or a followed link to one).
+    Dir,
+    /// Link that was not followed, or whose target was unreadable.
+    Symlink,
+    /// FIFO, socket, device node, or anything else.
+    Other,
+}
+
+impl RecordedKind {
+    /// Classify a yielded entry.
+    pub fn of(entry: &DirEntry) -> Self {
+        if entry.is_dir() {
+            RecordedKind::Dir
+        } else if entry.is_file() {
+            RecordedKind::File
+        } else if entry.is_symlink() {
+            RecordedKind::Symlink
+        } else {
+            RecordedKind::Other
+        }
+    }
+
+    /// Short lowercase name used in dumps (`"file"`, `"dir"`, ...).
+    pub fn name(self) -> &'static str {
+        match self {
+            RecordedKind::File => "file",
+            RecordedKind::Dir => "dir",
+            RecordedKind::Symlink => "symlink",
+            RecordedKind::Other => "other",
+        }
+    }
+}
+
+/// One yielded entry, as recorded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordedEntry {
+    /// File name (lossily converted when not UTF-8).
+    pub name: String,
+
+    /// Depth below the walk root (see `DirEntry::depth()`).
+    pub depth: usize,
+
+    /// Entry type.
+    pub kind: RecordedKind,
+
+    /// Classification from `WalkConfig::tag_entries`.
+    pub tag: EntryTag,
+
+    /// Full path as yielded. Debug builds only.
+    #[cfg(debug_assertions)]
+    pub path: PathBuf,
+}
+
+impl RecordedEntry {
+    /// Record `entry`.
+    pub fn of(entry: &DirEntry) -> Self {
+        RecordedEntry {
+            name: entry.file_name_lossy().into_owned(),
+            depth: entry.depth(),
+            kind: RecordedKind::of(entry),
+            tag: entry.tag(),
+            #[cfg(debug_assertions)]
+            path: entry.path().to_path_buf(),
+        }
+    }
+}
+
+// ============================================================================
+// WALK RECORDER
+// ============================================================================
+
+/// Bounded recording of one or more walks (see `DirWalker::record_to()`).
+///
+/// Entries past the cap are counted, not stored, so a recorder left
+/// attached to a huge scan stays small.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WalkRecorder {
+    /// Recorded entries, in yield order.
+    entries: Vec<RecordedEntry>,
+
+    /// Entries yielded after the cap was reached.
+    dropped: usize,
+
+    /// Errors yielded (never stored; `WalkError` kinds are in the stats).
+    errors: usize,
+
+    /// `walk_id` of the last walk recorded (0 before any).
+    walk_id: u64,
+}
+
+impl WalkRecorder {
+    /// Create an empty recorder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Recorded entries, in yield order.
+    pub fn entries(&self) -> &[RecordedEntry] {
+        &self.entries
+    }
+
+    /// Entries yielded after the cap was reached (not recorded).
+    pub fn dropped(&self) -> usize {
+        self.dropped
+    }
+
+    /// Errors yielded during recording.
+    pub fn errors(&self) -> usize {
+        self.errors
+    }
+
+    /// `DirWalker::walk_id()` of the last walk recorded (0 before any).
+    pub fn walk_id(&self) -> u64 {
+        self.walk_id
+    }
+
+    /// Forget everything recorded.
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Record one walk result, keeping at most `cap` entries.
+    fn record(&mut self, result: &Result<DirEntry, WalkError>, cap: usize) {
+        match result {
+            Ok(_) if self.entries.len() >= cap => {
+                self.dropped = self.dropped.saturating_add(1);
+            }
+            Ok(entry) => self.entries.push(RecordedEntry::of(entry)),
+            Err(_) => self.errors = self.errors.saturating_add(1),
+        }
+    }
+
+    /// Write the recording as text: a `# walk` header line, then one
+    /// tab-separated `depth kind name` line per entry (names escaped as
+    /// by `str::escape_debug`), then a `# dropped` line when the cap cut
+    /// the recording short.
+    ///
+    /// # Arguments
+    /// * `writer` - Destination
+    ///
+    /// # Returns
+    /// * `Ok(())` - Dump written
+    /// * `Err(WalkError::IoError)` - Write failed
+    pub fn write_dump(&self, writer: &mut impl io::Write) -> Result<(), WalkError> {
+        let mut text = format!(
+            "# walk {} entries={} errors={}\n",
+            self.walk_id,
+            self.entries.len(),
+            self.errors
+        );
+        for entry in &self.entries {
+            text.push_str(&format!(
+                "{}\t{}\t{}\n",
+                entry.depth,
+                entry.kind.name(),
+                entry.name.escape_debug()
+            ));
+        }
+        if self.dropped > 0 {
+            text.push_str(&format!("# dropped {}\n", self.dropped));
+        }
+
+        writer.write_all(text.as_bytes()).map_err(|_e| {
+            #[cfg(debug_assertions)]
+            eprintln!("DWIO: walk recording dump failed: {}", _e);
+            WalkError::IoError
+        })
+    }
+}
+
+// ============================================================================
+// WALKER ADAPTER
+// ============================================================================
+
+/// Iterator returned by `DirWalker::record_to()`.
+///
+/// Yields the walker's items unchanged, recording each one.
+#[derive(Debug)]
+pub struct RecordTo<'a> {
+    walker: DirWalker,
+    recorder: &'a mut WalkRecorder,
+    cap: usize,
+}
+
+impl DirWalker {
+    /// Tee the walk into `recorder`, keeping at most `cap` entries.
+    ///
+    /// Items are passed through unchanged. The recorder is appended to,
+    /// so several walks can share one (`clear()` starts over); `cap`
+    /// bounds its total size.
+    ///
+    /// # Arguments
+    /// * `recorder` - Destination of the recording
+    /// * `cap` - Most entries kept; later ones are only counted
+    pub fn record_to(self, recorder: &mut WalkRecorder, cap: usize) -> RecordTo<'_> {
+        recorder.walk_id = self.walk_id();
+        RecordTo {
+            walker: self,
+            recorder,
+            cap,
+        }
+    }
+}
+
+impl RecordTo<'_> {
+    /// Counters accumulated so far (see `DirWalker::stats()`).
+    pub fn stats(&self) -> &WalkStats {
+        self.walker.stats()
+    }
+}
+
+impl Iterator for RecordTo<'_> {
+    type Item = Result<DirEntry, WalkError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let result = self.walker.next()?;
+        self.recorder.record(&result, self.cap);
+        Some(result)
+    }
+}
+
+// ============================================================================
+// EXPECTATIONS
+// ============================================================================
+
+/// Which recorded entries a `WalkExpectation` rule applies to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EntryMatch {
+    /// Name equal to this.
+    Name(String),
+    /// Name starting with this.
+    Prefix(String),
+    /// Name ending with this (e.g. `".toml"`).
+    Suffix(String),
+    /// Entries of this type.
+    Kind(RecordedKind),
+    /// Entries with this tag.
+    Tag(EntryTag),
+}
+
+impl EntryMatch {
+    /// Whether `entry` is matched.
+    pub fn matches(&self, entry: &RecordedEntry) -> bool {
+        match self {
+            EntryMatch::Name(name) => entry.name == *name,
+            EntryMatch::Prefix(prefix) => entry.name.starts_with(prefix.as_str()),
+            EntryMatch::Suffix(suffix) => entry.name.ends_with(suffix.as_str()),
+            EntryMatch::Kind(kind) => entry.kind == *kind,
+            EntryMatch::Tag(tag) => entry.tag == *tag,
+        }
+    }
+}
+
+/// One rule of a `WalkExpectation`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExpectRule {
+    /// At least one entry matches.
+    Yields(EntryMatch),
+    /// No entry matches.
+    Excludes(EntryMatch),
+    /// The first entry matching `first` comes before every other entry
+    /// matching `then` (holds trivially when nothing matches `then`).
+    Before {
+        /// Entries that must come first.
+        first: EntryMatch,
+        /// Entries that must come after.
+        then: EntryMatch,
+    },
+    /// No entry is deeper than this.
+    MaxDepth(usize),
+}
+
+/// A rule that a recording broke, with the entry that broke it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Violation {
+    /// `Yields`: nothing matched.
+    Missing(EntryMatch),
+    /// `Excludes`: this entry name matched.
+    Unexpected(EntryMatch, String),
+    /// `Before`: this entry name matched `then` before any `first`.
+    OutOfOrder {
+        /// The rule's `first`.
+        first: EntryMatch,
+        /// Name of the offending `then` entry.
+        name: String,
+    },
+    /// `MaxDepth`: this entry name was found at this depth.
+    TooDeep(String, usize),
+    /// The recording was cut short by its cap (`dropped` entries), so
+    /// rules about the absence or order of entries cannot be confirmed.
+    Incomplete(usize),
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Violation::Missing(pattern) => write!(f, "no entry matched {:?}", pattern),
+            Violation::Unexpected(pattern, name) => {
+                write!(f, "{:?} matched excluded {:?}", name, pattern)
+            }
+            Violation::OutOfOrder { first, name } => {
+                write!(f, "{:?} came before any entry matching {:?}", name, first)
+            }
+            Violation::TooDeep(name, depth) => write!(f, "{:?} found at depth {}", name, depth),
+            Violation::Incomplete(dropped) => {
+                write!(f, "recording incomplete ({} entries dropped)", dropped)
+            }
+        }
+    }
+}
+
+/// Contract a walk must satisfy, checked against a `WalkRecorder`.
+///
+/// Rules are data (`ExpectRule`), so a contract can be built once and
+/// verified against recordings of several walks.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WalkExpectation {
+    rules: Vec<ExpectRule>,
+}
+
+impl WalkExpectation {
+    /// Create a contract with no rules (every recording satisfies it).
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a rule.
+    pub fn rule(mut self, rule: ExpectRule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    /// Require at least one entry matching `pattern`.
+    pub fn yields(self, pattern: EntryMatch) -> Self {
+        self.rule(ExpectRule::Yields(pattern))
+    }
+
+    /// Require that no entry matches `pattern`.
+    pub fn excludes(self, pattern: EntryMatch) -> Self {
+        self.rule(ExpectRule::Excludes(pattern))
+    }
+
+    /// Require the first entry matching `first` before every other entry
+    /// matching `then`.
+    pub fn before(self, first: EntryMatch, then: EntryMatch) -> Self {
+        self.rule(ExpectRule::Before { first, then })
+    }
+
+    /// Require that no entry is deeper than `depth`.
+    pub fn max_depth(self, depth: usize) -> Self {
+        self.rule(ExpectRule::MaxDepth(depth))
+    }
+
+    /// The rules, in the order they were added.
+    pub fn rules(&self) -> &[ExpectRule] {
+        &self.rules
+    }
+
+    /// Check `recording` against every rule.
+    ///
+    /// # Returns
+    /// * `Ok(())` - Every rule holds
+    /// * `Err(violations)` - One violation per broken rule, in rule
+    ///   order. A recording with dropped entries also yields
+    ///   `Violation::Incomplete` (once) when any rule other than
+    ///   `Yields` could be hiding a violation in the dropped part.
+    pub fn verify(&self, recording: &WalkRecorder) -> Result<(), Vec<Violation>> {
+        let entries = recording.entries();
+        let mut violations = Vec::new();
+        let mut needs_all = false;
+
+        for rule in &self.rules {
+            let violation = match rule {
+                ExpectRule::Yields(pattern) => (!entries.iter().any(|e| pattern.matches(e)))
+                    .then(|| Violation::Missing(pattern.clone())),
+                ExpectRule::Excludes(pattern) => {
+                    needs_all = true;
+                    entries
+                        .iter()
+                        .find(|e| pattern.matches(e))
+                        .map(|e| Violation::Unexpected(pattern.clone(), e.name.clone()))
+                }
+                ExpectRule::Before { first, then } => {
+                    needs_all = true;
+                    entries
+                        .iter()
+                        .take_while(|e| !first.matches(e))
+                        .find(|e| then.matches(e))
+                        .map(|e| Violation::OutOfOrder {
+                            first: first.clone(),
+                            name: e.name.clone(),
+                        })
+                }
+                ExpectRule::MaxDepth(max) => {
+                    needs_all = true;
+                    entries
+                        .iter()
+                        .find(|e| e.depth > *max)
+                        .map(|e| Violation::TooDeep(e.name.clone(), e.depth))
+                }
+            };
+            violations.extend(violation);
+        }
+
+        if needs_all && recording.dropped() > 0 {
+            violations.push(Violation::Incomplete(recording.dropped()));
+        }
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(violations)
+        }
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dir_walk_module::{WalkConfig, testing};
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    /// Helper: unique test directory path (caller cleans up).
+    fn test_dir(name: &str) -> PathBuf {
+        testing::unique_test_dir(&format!("recording_{}", name))
+    }
+
+    /// Helper: safely remove test directory, ignoring errors.
+    fn cleanup(path: &Path) {
+        let _ = fs::remove_dir_all(path);
+    }
+
+    /// Test: the tee passes items through, keeps at most `cap` entries
+    /// and counts the rest, and dumps them as text.
+    #[test]
+    fn test_record_to_is_bounded() {
+        let dir = test_dir("bounded");
+        cleanup(&dir);
+        let created = fs::create_dir_all(dir.join("sub")).is_ok()
+            && fs::write(dir.join("0.toml"), b"x").is_ok()
+            && fs::write(dir.join("sub").join("1__a.toml"), b"x").is_ok();
+        assert!(created, "test_record_to: failed to create files");
+
+        let mut recorder = WalkRecorder::new();
+        let walker = DirWalker::new(&dir, WalkConfig::new());
+        let walk_id = walker.walk_id();
+        let yielded = walker.record_to(&mut recorder, 2).count();
+        assert_eq!(yielded, 3, "test_record_to: tee must pass every item");
+        assert_eq!(
+            (
+                recorder.entries().len(),
+                recorder.dropped(),
+                recorder.walk_id()
+            ),
+            (2, 1, walk_id),
+            "test_record_to: cap and walk id"
+        );
+        // Breadth-first: the two depth-0 entries fill the cap
+        assert!(
+            recorder.entries().iter().all(|e| e.depth == 0)
+                && recorder
+                    .entries()
+                    .iter()
+                    .any(|e| e.name == "0.toml" && e.kind == RecordedKind::File),
+            "test_record_to: unexpected entries {:?}",
+            recorder.entries()
+        );
+
+        let mut dump = Vec::new();
+        assert!(
+            recorder.write_dump(&mut dump).is_ok(),
+            "test_record_to: dump failed"
+        );
+        let dump = String::from_utf8_lossy(&dump);
+        assert!(
+            dump.starts_with(&format!("# walk {} entries=2 errors=0\n", walk_id))
+                && dump.contains("0\tfile\t0.toml\n")
+                && dump.contains("0\tdir\tsub\n")
+                && dump.ends_with("# dropped 1\n"),
+            "test_record_to: unexpected dump {:?}",
+            dump
+        );
+
+        cleanup(&dir);
+    }
+
+    /// Test: a channel-scan contract ("metadata before any message")
+    /// holds for a breadth-first walk, and broken rules are reported.
+    #[test]
+    fn test_expectation_verify() {
+        let dir = test_dir("expectation");
+        cleanup(&dir);
+        let created = fs::create_dir_all(dir.join("sub")).is_ok()
+            && fs::write(dir.join("0.toml"), b"x").is_ok()
+            && fs::write(dir.join("sub").join("1__a.toml"), b"x").is_ok();
+        assert!(created, "test_expectation_verify: failed to create files");
+
+        let config = WalkConfig::new().tag_entries(|entry: &DirEntry| match entry.file_name() {
+            Some("0.toml") => EntryTag::Metadata,
+            Some(name) if name.ends_with(".toml") => EntryTag::Message,
+            _ => EntryTag::Untagged,
+        });
+        let mut recorder = WalkRecorder::new();
+        let _ = DirWalker::new(&dir, config)
+            .record_to(&mut recorder, 10)
+            .count();
+
+        let metadata = EntryMatch::Tag(EntryTag::Metadata);
+        let message = EntryMatch::Tag(EntryTag::Message);
+        let contract = WalkExpectation::new()
+            .yields(metadata.clone())
+            .before(metadata.clone(), message.clone())
+            .excludes(EntryMatch::Suffix(".tmp".to_string()))
+            .max_depth(1);
+        assert_eq!(
+            contract.verify(&recorder),
+            Ok(()),
+            "test_expectation_verify: contract must hold"
+        );
+
+        let broken = WalkExpectation::new()
+            .yields(EntryMatch::Name("missing.toml".to_string()))
+            .before(message.clone(), metadata.clone())
+            .max_depth(0);
+        assert_eq!(
+            broken.verify(&recorder),
+            Err(vec![
+                Violation::Missing(EntryMatch::Name("missing.toml".to_string())),
+                Violation::OutOfOrder {
+                    first: message,
+                    name: "0.toml".to_string(),
+                },
+                Violation::TooDeep("1__a.toml".to_string(), 1),
+            ]),
+            "test_expectation_verify: violations in rule order"
+        );
+
+        // A truncated recording cannot confirm absence or order
+        let mut truncated = WalkRecorder::new();
+        let _ = DirWalker::new(&dir, WalkConfig::new())
+            .record_to(&mut truncated, 1)
+            .count();
+        assert_eq!(
+            contract.verify(&truncated).map_err(|v| v.last().cloned()),
+            Err(Some(Violation::Incomplete(2))),
+            "test_expectation_verify: truncation must be reported"
+        );
+
+        cleanup(&dir);
+    }
+}
@@ -0,0 +1,260 @@
+//! # Aggregate Reports (`dir_walk_module::reports`)
+//!
+//! Single-pass breakdowns of the files under a directory (by extension,
+//! by size class), all built on one aggregation routine over
+//! `walk_with()`.
+//!
+//! ## Project Context
+//! The admin view shows what a channel is made of: foreign files dumped
+//! into a channel stand out in an extension histogram, and oversized
+//! files in a size histogram. Every report here is one `walk_with()` pass
+//! (no entry buffering) that counts files and bytes per key, so adding a
+//! report only means choosing a key.
+
+use std::collections::BTreeMap;
+use std::ops::ControlFlow;
+use std::path::Path;
+
+use super::{DirEntry, WalkConfig, WalkError, WalkStats, walk_with};
+
+// ============================================================================
+// HISTOGRAM
+// ============================================================================
+
+/// Files and bytes counted for one histogram key.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HistogramBucket {
+    /// Regular files with this key.
+    pub files: usize,
+
+    /// Sum of their logical lengths.
+    pub bytes: u64,
+}
+
+/// Per-key file counts from one walk, in key order.
+#[derive(Debug, Clone)]
+pub struct Histogram<K: Ord> {
+    /// Buckets by key (only keys that occurred).
+    buckets: BTreeMap<K, HistogramBucket>,
+
+    /// Counters of the walk that produced the histogram.
+    stats: WalkStats,
+}
+
+impl<K: Ord> Histogram<K> {
+    /// Bucket for `key`, if any file had it.
+    pub fn get(&self, key: &K) -> Option<&HistogramBucket> {
+        self.buckets.get(key)
+    }
+
+    /// Buckets in key order.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &HistogramBucket)> {
+        self.buckets.iter()
+    }
+
+    /// Number of distinct keys.
+    pub fn len(&self) -> usize {
+        self.buckets.len()
+    }
+
+    /// Whether no files were counted.
+    pub fn is_empty(&self) -> bool {
+        self.buckets.is_empty()
+    }
+
+    /// Total files across all buckets.
+    pub fn total_files(&self) -> usize {
+        self.buckets
+            .values()
+            .fold(0usize, |total, bucket| total.saturating_add(bucket.files))
+    }
+
+    /// Counters of the walk (filtered files, skipped errors, ...).
+    pub fn stats(&self) -> &WalkStats {
+        &self.stats
+    }
+}
+
+/// The shared aggregation: one `walk_with()` pass, counting each regular
+/// file under `key_of(entry)`.
+///
+/// Directories and other non-file entries are not counted; the number of
+/// distinct keys is bounded by the walk's own entry bounds.
+fn histogram_by<K, F>(
+    path: &Path,
+    config: WalkConfig,
+    mut key_of: F,
+) -> Result<Histogram<K>, WalkError>
+where
+    K: Ord,
+    F: FnMut(&DirEntry) -> K,
+{
+    let mut buckets: BTreeMap<K, HistogramBucket> = BTreeMap::new();
+
+    let stats = walk_with(path, config, |entry| {
+        if entry.is_file() {
+            let bucket = buckets.entry(key_of(entry)).or_default();
+            bucket.files = bucket.files.saturating_add(1);
+            bucket.bytes = bucket.bytes.saturating_add(entry.len());
+        }
+        ControlFlow::Continue(())
+    })?;
+
+    Ok(Histogram { buckets, stats })
+}
+
+// ============================================================================
+// EXTENSION HISTOGRAM
+// ============================================================================
+
+/// Count files per extension, with `None` as the bucket for files that
+/// have no extension.
+///
+/// Extensions are compared ASCII-case-insensitively (stored lowercase)
+/// and without the dot; non-UTF-8 extensions are converted lossily.
+///
+/// # Arguments
+/// * `path` - Directory to report on
+/// * `config` - Walk configuration (depth, filters, symlink policy)
+///
+/// # Returns
+/// * `Ok(Histogram)` - Keys like `Some("toml")`, `Some("gpgtoml")`, `None`
+/// * `Err(WalkError)` - Fatal walk error (strict mode only)
+pub fn extension_histogram(
+    path: &Path,
+    config: WalkConfig,
+) -> Result<Histogram<Option<String>>, WalkError> {
+    histogram_by(path, config, |entry| {
+        Path::new(entry.file_name_os())
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_ascii_lowercase())
+    })
+}
+
+impl Histogram<Option<String>> {
+    /// Buckets whose extension is not in `known` (including the
+    /// no-extension bucket) — the "foreign files" report.
+    ///
+    /// # Arguments
+    /// * `known` - Expected extensions without the dot, e.g.
+    ///   `&["toml", "gpgtoml"]` (compared case-insensitively)
+    pub fn unknown_extensions(&self, known: &[&str]) -> Vec<(Option<&str>, HistogramBucket)> {
+        self.buckets
+            .iter()
+            .filter(|(extension, _)| match extension {
+                Some(extension) => !known.iter().any(|k| k.eq_ignore_ascii_case(extension)),
+                None => true,
+            })
+            .map(|(extension, bucket)| (extension.as_deref(), *bucket))
+            .collect()
+    }
+}
+
+// ============================================================================
+// SIZE HISTOGRAM
+// ============================================================================
+
+/// Count files per power-of-two size class.
+///
+/// Key `0` holds empty files; key `k` (1..=64) holds files of
+/// `2^(k-1) ..= 2^k - 1` bytes (so `1` is exactly 1 byte, `11` is
+/// 1 KiB up to just under 2 KiB).
+///
+/// # Arguments
+/// * `path` - Directory to report on
+/// * `config` - Walk configuration
+///
+/// # Returns
+/// * `Ok(Histogram)` - Buckets by size class
+/// * `Err(WalkError)` - Fatal walk error (strict mode only)
+pub fn size_histogram(path: &Path, config: WalkConfig) -> Result<Histogram<u32>, WalkError> {
+    histogram_by(path, config, |entry| {
+        u64::BITS - entry.len().leading_zeros()
+    })
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+
+    /// Helper: unique test directory path (caller cleans up).
+    fn test_dir(name: &str) -> PathBuf {
+        crate::dir_walk_module::testing::unique_test_dir(&format!("reports_{}", name))
+    }
+
+    /// Helper: safely remove test directory, ignoring errors.
+    fn cleanup(path: &Path) {
+        let _ = fs::remove_dir_all(path);
+    }
+
+    /// Test: extension buckets (case-folded, with a no-extension bucket),
+    /// the unknown-extension report, and size classes.
+    #[test]
+    fn test_extension_and_size_histograms() {
+        let dir = test_dir("histograms");
+        cleanup(&dir);
+        let files: [(&str, usize); 6] = [
+            ("1__a.toml", 10),
+            ("2__b.TOML", 1),
+            ("3__c.gpgtoml", 1024),
+            ("notes", 0),
+            ("sub/dump.exe", 3),
+            ("sub/4__d.toml", 2),
+        ];
+        assert!(
+            fs::create_dir_all(dir.join("sub")).is_ok()
+                && files
+                    .iter()
+                    .all(|(name, size)| fs::write(dir.join(name), vec![b'x'; *size]).is_ok()),
+            "test_histograms: failed to create files"
+        );
+
+        let by_extension = extension_histogram(&dir, WalkConfig::new());
+        assert_eq!(
+            by_extension
+                .as_ref()
+                .ok()
+                .and_then(|h| h.get(&Some("toml".to_string())).copied()),
+            Some(HistogramBucket {
+                files: 3,
+                bytes: 13
+            }),
+            "test_histograms: toml bucket (case-folded)"
+        );
+        assert_eq!(
+            by_extension.as_ref().map(|h| h.total_files()),
+            Ok(6),
+            "test_histograms: total files"
+        );
+        assert_eq!(
+            by_extension
+                .as_ref()
+                .map(|h| h.unknown_extensions(&["toml", "gpgtoml"])),
+            Ok(vec![
+                (None, HistogramBucket { files: 1, bytes: 0 }),
+                (Some("exe"), HistogramBucket { files: 1, bytes: 3 }),
+            ]),
+            "test_histograms: unknown extensions"
+        );
+
+        // 0 B -> 0; 1 B -> 1; 2,3 B -> 2; 10 B -> 4; 1024 B -> 11
+        let by_size = size_histogram(&dir, WalkConfig::new()).map(|h| {
+            h.iter()
+                .map(|(class, bucket)| (*class, bucket.files))
+                .collect::<Vec<_>>()
+        });
+        assert_eq!(
+            by_size,
+            Ok(vec![(0, 1), (1, 1), (2, 2), (4, 1), (11, 1)]),
+            "test_histograms: size classes"
+        );
+
+        cleanup(&dir);
+    }
+}
@@ -0,0 +1,1095 @@
+//! # Scan Scheduler (`dir_walk_module::scheduler`)
+//!
+//! Spreads change detection for many watched directories across ticks,
+//! within a per-tick time budget.
+//!
+//! ## Project Context
+//! Watching 200 channels with one `ChannelMonitor::poll()` each means 200
+//! full walks per poll interval. `ScanScheduler` instead hands each channel
+//! a small slice of work (a few directory reads, via `DirWalker::step()`)
+//! in round-robin order until the tick's budget is spent. Walks that do
+//! not finish continue on the next tick; each channel's changes are
+//! reported when its walk completes.
+//!
+//! ```ignore
+//! let mut scheduler = ScanScheduler::new();
+//! for dir in &channel_dirs {
+//!     scheduler.register(dir, WalkConfig::channel_scan());
+//! }
+//! loop {
+//!     for (channel_id, changes) in scheduler.tick(Duration::from_millis(20)) {
+//!         reload(channel_id, &changes);
+//!     }
+//!     std::thread::sleep(poll_interval);
+//! }
+//! ```
+//!
+//! Channels are joined and left at runtime with `add_watch()` /
+//! `remove_watch()` (also on a running `PollingThread`); each directory
+//! keeps its own `WalkConfig`, and ids are never reused.
+//!
+//! Event loops that already wait on a channel can hand the scheduler to
+//! an owned polling thread instead of calling `tick()` themselves:
+//!
+//! ```ignore
+//! let (events_tx, events_rx) = std::sync::mpsc::channel();
+//! let poller = scheduler.spawn_polling(events_tx, poll_interval, Duration::from_millis(20))?;
+//! for event in events_rx {
+//!     reload(event.channel_id, &event.changes);
+//! }
+//! ```
+//!
+//! A scheduler given a `WalkCache` (`cache()`) evicts whatever each
+//! completed walk found changed, so cached listings of watched channels
+//! are never staler than one completed walk.
+//!
+//! With `prioritize_new_dirs(true)`, a subdirectory that a completed walk
+//! reports as new gets a scan of its own, run ahead of the round-robin
+//! slices, so a channel created inside a watched directory is filled in
+//! without waiting for its parent's next full walk.
+
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use super::cache::{SharedWalkCache, lock_shared};
+use super::clock::{self, Clock};
+use super::monitor::{ChannelMonitor, PollStep};
+use super::snapshot::{ChangeSummary, DirectorySnapshot};
+use super::{DirWalker, StepStatus, WalkConfig, WalkError};
+
+/// Default number of directory reads per channel slice.
+const DEFAULT_DIRS_PER_SLICE: usize = 4;
+
+// ============================================================================
+// SCAN SCHEDULER
+// ============================================================================
+
+/// Round-robin, time-budgeted change detection across registered
+/// directories.
+///
+/// Channel ids are indices returned by `register()` / `add_watch()`,
+/// stable for the scheduler's lifetime and never reused after
+/// `remove_watch()`.
+#[derive(Debug)]
+pub struct ScanScheduler {
+    /// One slot per registered directory (index = channel id); `None`
+    /// once removed.
+    channels: Vec<Option<ChannelMonitor>>,
+
+    /// Next channel to receive a slice; persists across ticks so no
+    /// channel is starved when budgets are tight.
+    cursor: usize,
+
+    /// Directory reads per slice.
+    dirs_per_slice: usize,
+
+    /// Time source the tick budget is measured on.
+    clock: Arc<dyn Clock>,
+
+    /// Cache evicted by completed walks (`cache()`).
+    cache: Option<SharedWalkCache>,
+
+    /// Whether new subdirectories get a priority scan
+    /// (`prioritize_new_dirs()`).
+    prioritize_new_dirs: bool,
+
+    /// Pending priority scans, served in discovery order before any
+    /// round-robin slice.
+    priority: VecDeque<PriorityScan>,
+}
+
+/// Scan of one newly discovered subdirectory of a watched channel.
+#[derive(Debug)]
+struct PriorityScan {
+    /// Channel the subdirectory belongs to.
+    channel_id: usize,
+
+    /// Walker rooted at the new subdirectory.
+    walker: DirWalker,
+
+    /// Entries seen so far, keyed relative to the channel's root.
+    found: DirectorySnapshot,
+}
+
+impl Default for ScanScheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ScanScheduler {
+    /// Create an empty scheduler (4 directory reads per slice).
+    pub fn new() -> Self {
+        ScanScheduler {
+            channels: Vec::new(),
+            cursor: 0,
+            dirs_per_slice: DEFAULT_DIRS_PER_SLICE,
+            clock: clock::system_clock(),
+            cache: None,
+            prioritize_new_dirs: false,
+            priority: VecDeque::new(),
+        }
+    }
+
+    /// Set the directory reads per slice (clamped to at least 1).
+    ///
+    /// Smaller slices interleave channels more finely; larger slices
+    /// spend less time switching between walkers.
+    pub fn dirs_per_slice(mut self, dirs: usize) -> Self {
+        self.dirs_per_slice = dirs.max(1);
+        self
+    }
+
+    /// Set the clock tick budgets are measured on (default:
+    /// `SystemClock`).
+    ///
+    /// Each watched directory's walk keeps its own `WalkConfig::clock`.
+    pub fn clock<C: Clock + 'static>(mut self, clock: C) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Evict changed directories from `cache` as walks complete.
+    ///
+    /// After each completed walk of a channel, listings under what it
+    /// reported as added, removed, modified or renamed are dropped. A
+    /// channel's first walk (nothing to diff against yet) and a failed
+    /// walk drop every listing under its root.
+    ///
+    /// # Project Context
+    /// Pairs with `WalkCache::trust_watcher(true)`: views read cached
+    /// listings without hashing, and the poller they already run keeps
+    /// them no staler than one completed walk.
+    pub fn cache(mut self, cache: SharedWalkCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Give newly created subdirectories a scan of their own, ahead of
+    /// the regular round-robin (default: `false`).
+    ///
+    /// When a channel's completed walk reports a new directory, the
+    /// subtree under it is walked again in the next slices, before any
+    /// channel's regular slice. Entries that appeared there since the
+    /// channel's walk are reported right away (as an extra `tick()` result
+    /// for the same channel, holding only additions) and are not reported
+    /// again by the channel's next walk. The scan stays within the
+    /// channel's `max_depth`, so it never records what the channel's own
+    /// walks would not see.
+    ///
+    /// # Project Context
+    /// A channel created under a watched directory shows up in the first
+    /// walk that sees it, often before its first messages are written.
+    /// Without this, those messages wait for the parent's next full walk,
+    /// one whole cycle of every other channel later.
+    pub fn prioritize_new_dirs(mut self, enabled: bool) -> Self {
+        self.prioritize_new_dirs = enabled;
+        self
+    }
+
+    /// Start watching `root`.
+    ///
+    /// # Arguments
+    /// * `root` - Directory to watch
+    /// * `config` - Walk bounds and filters for this directory
+    ///
+    /// # Returns
+    /// The channel id used in `tick()` results and `monitor()`.
+    pub fn register(&mut self, root: &Path, config: WalkConfig) -> usize {
+        self.channels.push(Some(ChannelMonitor::new(root, config)));
+        self.channels.len() - 1
+    }
+
+    /// Start watching `root` at runtime (a joined channel); same as
+    /// `register()`.
+    ///
+    /// The new directory's first completed walk is its baseline, so
+    /// existing files are not reported as added.
+    pub fn add_watch(&mut self, root: &Path, config: WalkConfig) -> usize {
+        self.register(root, config)
+    }
+
+    /// Stop watching a channel (a left channel), dropping its snapshot.
+    ///
+    /// Other channels keep their ids and snapshots; the removed id is not
+    /// reused.
+    ///
+    /// # Returns
+    /// `false` if `channel_id` was not being watched.
+    pub fn remove_watch(&mut self, channel_id: usize) -> bool {
+        self.channels
+            .get_mut(channel_id)
+            .and_then(Option::take)
+            .is_some()
+    }
+
+    /// Number of priority scans of new subdirectories still pending (see
+    /// `prioritize_new_dirs()`).
+    pub fn pending_priority_scans(&self) -> usize {
+        self.priority.len()
+    }
+
+    /// Number of watched directories.
+    pub fn len(&self) -> usize {
+        self.channels.iter().filter(|slot| slot.is_some()).count()
+    }
+
+    /// `true` when no directories are watched.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Access a watched channel's monitor (snapshot, last error).
+    pub fn monitor(&self, channel_id: usize) -> Option<&ChannelMonitor> {
+        self.channels.get(channel_id).and_then(Option::as_ref)
+    }
+
+    /// Spend up to `budget` advancing walks in round-robin order.
+    ///
+    /// At least one slice runs per tick (so a zero budget still makes
+    /// progress), and no channel completes more than one walk per tick.
+    /// The budget is checked between slices, so a tick may overrun by
+    /// one slice. Pending priority scans (`prioritize_new_dirs()`) take
+    /// the first slices.
+    ///
+    /// # Arguments
+    /// * `budget` - Wall-clock time allowed for this tick
+    ///
+    /// # Returns
+    /// `(channel_id, changes)` for every channel whose walk completed this
+    /// tick with changes, and for every priority scan that found new
+    /// entries. A channel's first completed walk is its baseline and
+    /// reports nothing.
+    pub fn tick(&mut self, budget: Duration) -> Vec<(usize, ChangeSummary)> {
+        let mut changes = Vec::new();
+        let channel_count = self.channels.len();
+        if channel_count == 0 {
+            return changes;
+        }
+
+        let started = self.clock.instant();
+        // Removed slots count as finished
+        let mut finished_this_tick: Vec<bool> =
+            self.channels.iter().map(|slot| slot.is_none()).collect();
+        let mut unfinished = self.len();
+
+        // Bounded: each slice reads at least one directory of a finite
+        // walk, a channel leaves the rotation once its walk completes, and
+        // priority scans are only queued by completed channel walks
+        while unfinished > 0 || !self.priority.is_empty() {
+            if !self.priority.is_empty() {
+                if let Some(reported) = self.step_priority_scan() {
+                    changes.push(reported);
+                }
+                if self.clock.instant().saturating_duration_since(started) >= budget {
+                    break;
+                }
+                continue;
+            }
+
+            let channel_id = self.cursor % channel_count;
+            self.cursor = (channel_id + 1) % channel_count;
+
+            let monitor = match &mut self.channels[channel_id] {
+                Some(monitor) if !finished_this_tick[channel_id] => monitor,
+                _ => continue,
+            };
+
+            let had_baseline = monitor.has_baseline();
+            if let PollStep::Done(summary) = monitor.poll_step(self.dirs_per_slice) {
+                if let Some(cache) = &self.cache {
+                    evict_stale(cache, monitor, had_baseline, summary.as_ref());
+                }
+                finished_this_tick[channel_id] = true;
+                unfinished -= 1;
+                if let Some(summary) = summary {
+                    if self.prioritize_new_dirs {
+                        queue_new_dirs(&mut self.priority, channel_id, monitor, &summary);
+                    }
+                    changes.push((channel_id, summary));
+                }
+            }
+
+            if self.clock.instant().saturating_duration_since(started) >= budget {
+                break;
+            }
+        }
+
+        changes
+    }
+
+    /// Run one slice of the oldest priority scan.
+    ///
+    /// # Returns
+    /// The scan's report when it completed with new entries.
+    fn step_priority_scan(&mut self) -> Option<(usize, ChangeSummary)> {
+        let scan = self.priority.front_mut()?;
+        let channel_id = scan.channel_id;
+        let monitor = match self.channels.get_mut(channel_id) {
+            Some(Some(monitor)) => monitor,
+            // The channel was removed while its scan waited
+            _ => {
+                let _dropped = self.priority.pop_front();
+                return None;
+            }
+        };
+
+        let root = monitor.root();
+        let found = &mut scan.found;
+        let status = scan
+            .walker
+            .step(self.dirs_per_slice, |entry| found.record(root, entry));
+
+        match status {
+            Ok(StepStatus::Pending) => None,
+            Ok(StepStatus::Complete) => {
+                let scan = self.priority.pop_front()?;
+                let summary = ChangeSummary {
+                    added: monitor.absorb(&scan.found),
+                    ..ChangeSummary::default()
+                };
+                if summary.is_empty() {
+                    return None;
+                }
+                if let Some(cache) = &self.cache {
+                    lock_shared(cache).apply_changes(monitor.root(), &summary);
+                }
+                Some((channel_id, summary))
+            }
+            Err(_e) => {
+                // The channel's next walk reports the subtree instead
+                #[cfg(debug_assertions)]
+                eprintln!(
+                    "DWCM: [walk {}] priority scan failed: {}",
+                    scan.walker.walk_id(),
+                    _e
+                );
+                let _dropped = self.priority.pop_front();
+                None
+            }
+        }
+    }
+
+    /// Move the scheduler onto an owned thread that ticks every
+    /// `interval` and sends each completed change report to `events`.
+    ///
+    /// The thread stops when `PollingThread::stop()` is called, when the
+    /// `PollingThread` is dropped, or when the receiving end of `events`
+    /// is gone.
+    ///
+    /// # Arguments
+    /// * `events` - Caller's channel; one `ChangeEvent` per channel per
+    ///   completed walk with changes
+    /// * `interval` - Pause between ticks
+    /// * `budget` - Budget of each tick (see `tick()`)
+    ///
+    /// # Returns
+    /// * `Ok(PollingThread)` - Handle owning the thread
+    /// * `Err(WalkError::IoError)` - The thread could not be spawned
+    ///
+    /// # Project Context
+    /// UI event loops `select` over their own channels; receiving change
+    /// events there removes the need to interleave `tick()` calls.
+    pub fn spawn_polling(
+        mut self,
+        events: mpsc::Sender<ChangeEvent>,
+        interval: Duration,
+        budget: Duration,
+    ) -> Result<PollingThread, WalkError> {
+        let (commands_tx, commands_rx) = mpsc::channel::<PollingCommand>();
+        // Ids keep being assigned in order on the thread, so the handle
+        // can predict them
+        let next_id = self.channels.len();
+
+        let spawned = std::thread::Builder::new()
+            .name("dwm-scan-scheduler".to_string())
+            .spawn(move || {
+                // Bounded by the stop command, a dropped handle or a
+                // dropped receiver
+                loop {
+                    for (channel_id, changes) in self.tick(budget) {
+                        // The completed walk keeps its id until the
+                        // channel's next poll starts
+                        let walk_id = self.monitor(channel_id).map_or(0, ChannelMonitor::walk_id);
+                        if events
+                            .send(ChangeEvent {
+                                channel_id,
+                                walk_id,
+                                changes,
+                            })
+                            .is_err()
+                        {
+                            return self;
+                        }
+                    }
+
+                    let mut command = match commands_rx.recv_timeout(interval) {
+                        Ok(command) => command,
+                        Err(RecvTimeoutError::Disconnected) => return self,
+                        Err(RecvTimeoutError::Timeout) => continue,
+                    };
+                    // Apply every queued command before the next tick
+                    loop {
+                        match command {
+                            PollingCommand::Stop => return self,
+                            PollingCommand::AddWatch(root, config) => {
+                                let _id = self.register(&root, *config);
+                            }
+                            PollingCommand::RemoveWatch(channel_id) => {
+                                let _removed = self.remove_watch(channel_id);
+                            }
+                        }
+                        command = match commands_rx.try_recv() {
+                            Ok(next) => next,
+                            Err(_) => break,
+                        };
+                    }
+                }
+            });
+
+        match spawned {
+            Ok(thread) => Ok(PollingThread {
+                commands: commands_tx,
+                thread: Some(thread),
+                next_id,
+            }),
+            Err(_e) => {
+                #[cfg(debug_assertions)]
+                eprintln!("DWIO: polling thread could not be spawned: {}", _e);
+                Err(WalkError::IoError)
+            }
+        }
+    }
+}
+
+/// Queue a priority scan for each new subdirectory in `summary` that the
+/// channel's depth limit lets it look into.
+///
+/// Only the top of each new subtree is queued; its scan covers the rest.
+fn queue_new_dirs(
+    priority: &mut VecDeque<PriorityScan>,
+    channel_id: usize,
+    monitor: &ChannelMonitor,
+    summary: &ChangeSummary,
+) {
+    let snapshot = monitor.snapshot();
+    let is_new_dir = |path: &Path| {
+        summary
+            .added
+            .binary_search_by(|added| added.as_path().cmp(path))
+            .is_ok()
+            && snapshot.get(path).is_some_and(|entry| entry.is_dir)
+    };
+
+    for relative in &summary.added {
+        if !is_new_dir(relative) || relative.parent().is_some_and(is_new_dir) {
+            continue;
+        }
+
+        // The subdirectory's children sit at depth `components` of the
+        // channel's walk, which is depth 0 of the scan
+        let mut config = monitor.config().clone().yield_relative_paths(false);
+        if let Some(max_depth) = config.max_depth {
+            match max_depth.checked_sub(relative.components().count()) {
+                Some(remaining) => config = config.max_depth(remaining),
+                None => continue,
+            }
+        }
+
+        priority.push_back(PriorityScan {
+            channel_id,
+            walker: DirWalker::new(&monitor.root().join(relative), config),
+            found: DirectorySnapshot::new(),
+        });
+    }
+}
+
+/// Drop the cached listings a completed walk of `monitor` may have made
+/// stale (see `ScanScheduler::cache`).
+fn evict_stale(
+    cache: &SharedWalkCache,
+    monitor: &ChannelMonitor,
+    had_baseline: bool,
+    summary: Option<&ChangeSummary>,
+) {
+    let mut cache = lock_shared(cache);
+    if !had_baseline || monitor.last_error().is_some() {
+        cache.invalidate_tree(monitor.root());
+    } else if let Some(summary) = summary {
+        cache.apply_changes(monitor.root(), summary);
+    }
+}
+
+// ============================================================================
+// POLLING THREAD
+// ============================================================================
+
+/// One change report delivered by a polling thread.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChangeEvent {
+    /// Channel id returned by `ScanScheduler::register()`.
+    pub channel_id: usize,
+
+    /// Id of the walk that found the changes (`DirWalker::walk_id()`),
+    /// matching the `[walk N]` tag of its debug diagnostics and
+    /// `WalkStats::walk_id`.
+    pub walk_id: u64,
+
+    /// Changes found by the channel's completed walk.
+    pub changes: ChangeSummary,
+}
+
+/// Control messages from a `PollingThread` handle to its thread.
+#[derive(Debug)]
+enum PollingCommand {
+    /// Finish the current tick and exit.
+    Stop,
+
+    /// `ScanScheduler::register()` (config boxed: it dwarfs the other
+    /// variants).
+    AddWatch(PathBuf, Box<WalkConfig>),
+
+    /// `ScanScheduler::remove_watch()`.
+    RemoveWatch(usize),
+}
+
+/// Handle to a scheduler running on its own thread
+/// (`ScanScheduler::spawn_polling()`).
+///
+/// Dropping the handle stops the thread and waits for it.
+#[derive(Debug)]
+pub struct PollingThread {
+    /// Commands to the thread.
+    commands: mpsc::Sender<PollingCommand>,
+
+    /// The thread; `None` once joined.
+    thread: Option<JoinHandle<ScanScheduler>>,
+
+    /// Id the thread will assign to the next added directory.
+    next_id: usize,
+}
+
+impl PollingThread {
+    /// Start watching `root` on the running thread (see
+    /// `ScanScheduler::add_watch()`).
+    ///
+    /// Applied before the thread's next tick.
+    ///
+    /// # Returns
+    /// * `Ok(channel_id)` - Id the directory's events will carry
+    /// * `Err(WalkError::IoError)` - The thread has exited
+    pub fn add_watch(&mut self, root: &Path, config: WalkConfig) -> Result<usize, WalkError> {
+        self.send(PollingCommand::AddWatch(
+            root.to_path_buf(),
+            Box::new(config),
+        ))?;
+        let channel_id = self.next_id;
+        self.next_id = self.next_id.saturating_add(1);
+        Ok(channel_id)
+    }
+
+    /// Stop watching a channel on the running thread (see
+    /// `ScanScheduler::remove_watch()`).
+    ///
+    /// Applied before the thread's next tick; events already sent for the
+    /// channel may still be waiting in the caller's receiver.
+    ///
+    /// # Returns
+    /// * `Ok(())` - Removal queued
+    /// * `Err(WalkError::IoError)` - The thread has exited
+    pub fn remove_watch(&self, channel_id: usize) -> Result<(), WalkError> {
+        self.send(PollingCommand::RemoveWatch(channel_id))
+    }
+
+    /// Queue a command for the thread.
+    fn send(&self, command: PollingCommand) -> Result<(), WalkError> {
+        self.commands.send(command).map_err(|_| {
+            #[cfg(debug_assertions)]
+            eprintln!("DWIO: polling thread has exited");
+            WalkError::IoError
+        })
+    }
+
+    /// Stop the thread and take the scheduler back (with its snapshots),
+    /// e.g. to resume polling later with `tick()`.
+    ///
+    /// Waits for the current tick to finish.
+    ///
+    /// # Returns
+    /// `None` if the thread panicked.
+    pub fn stop(mut self) -> Option<ScanScheduler> {
+        self.join()
+    }
+
+    /// Send `Stop` (ignored if the thread already exited) and join.
+    fn join(&mut self) -> Option<ScanScheduler> {
+        let _ = self.commands.send(PollingCommand::Stop);
+        self.thread.take().and_then(|thread| thread.join().ok())
+    }
+}
+
+impl Drop for PollingThread {
+    fn drop(&mut self) {
+        let _ = self.join();
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+
+    /// Helper: unique test directory path (caller cleans up).
+    fn test_dir(name: &str) -> PathBuf {
+        crate::dir_walk_module::testing::unique_test_dir(&format!("scheduler_{}", name))
+    }
+
+    /// Helper: safely remove test directory, ignoring errors.
+    fn cleanup(path: &Path) {
+        let _ = fs::remove_dir_all(path);
+    }
+
+    /// Test: changes are attributed to the right channel, and with a zero
+    /// budget and one-directory slices, walks spread over several ticks
+    /// but still complete.
+    #[test]
+    fn test_scheduler_round_robin_and_budget() {
+        let base = test_dir("round_robin");
+        cleanup(&base);
+
+        let channels: Vec<PathBuf> = (0..3)
+            .map(|i| base.join(format!("channel_{}", i)))
+            .collect();
+        for channel in &channels {
+            assert!(
+                fs::create_dir_all(channel.join("a").join("b")).is_ok()
+                    && fs::write(channel.join("1__msg.toml"), b"m").is_ok(),
+                "test_scheduler: failed to create channel"
+            );
+        }
+
+        let mut scheduler = ScanScheduler::new().dirs_per_slice(1);
+        for channel in &channels {
+            let _id = scheduler.register(channel, WalkConfig::new());
+        }
+
+        // Generous budget: every channel records its baseline in one tick
+        assert!(
+            scheduler.tick(Duration::from_secs(60)).is_empty(),
+            "test_scheduler: baseline tick must report nothing"
+        );
+
+        assert!(
+            fs::write(channels[1].join("a").join("b").join("2__msg.toml"), b"n").is_ok(),
+            "test_scheduler: failed to add message"
+        );
+
+        // Zero budget: one slice (one directory read) per tick
+        let mut reported = Vec::new();
+        let mut ticks: usize = 0;
+        while reported.is_empty() && ticks < 100 {
+            reported = scheduler.tick(Duration::ZERO);
+            ticks += 1;
+        }
+        assert!(
+            ticks > 1,
+            "test_scheduler: zero budget should spread the walks over several ticks"
+        );
+        assert_eq!(
+            reported.len(),
+            1,
+            "test_scheduler: exactly one channel changed"
+        );
+        let (channel_id, summary) = &reported[0];
+        assert_eq!(*channel_id, 1, "test_scheduler: wrong channel reported");
+        assert_eq!(
+            summary.added,
+            vec![Path::new("a").join("b").join("2__msg.toml")],
+            "test_scheduler: wrong change reported"
+        );
+
+        cleanup(&base);
+    }
+
+    /// Test: the tick budget is measured on the scheduler's clock, so a
+    /// mock clock fixes the number of slices per tick.
+    #[test]
+    fn test_tick_budget_on_mock_clock() {
+        use crate::dir_walk_module::clock::MockClock;
+
+        let base = test_dir("mock_clock");
+        cleanup(&base);
+        let channels: Vec<PathBuf> = (0..3)
+            .map(|i| base.join(format!("channel_{}", i)))
+            .collect();
+        for channel in &channels {
+            assert!(
+                fs::create_dir_all(channel.join("a").join("b")).is_ok(),
+                "test_tick_budget_on_mock_clock: failed to create channel"
+            );
+        }
+
+        // Every reading moves the clock 1 ms: each slice "takes" 1 ms
+        let clock =
+            MockClock::new(std::time::SystemTime::now()).auto_advance(Duration::from_millis(1));
+        let mut scheduler = ScanScheduler::new().dirs_per_slice(1).clock(clock);
+        for channel in &channels {
+            let _id = scheduler.register(channel, WalkConfig::new());
+        }
+
+        let _ = scheduler.tick(Duration::from_millis(2));
+        assert_eq!(
+            scheduler.cursor, 2,
+            "test_tick_budget_on_mock_clock: a 2 ms budget buys two slices"
+        );
+
+        cleanup(&base);
+    }
+
+    /// Test: a scheduler with a trusted cache evicts exactly the listings
+    /// its completed walks report as changed, so views see a change after
+    /// at most one completed walk.
+    #[test]
+    fn test_scheduler_evicts_cached_listings() {
+        use crate::dir_walk_module::DirEntry;
+        use crate::dir_walk_module::cache::WalkCache;
+
+        let base = test_dir("cache");
+        cleanup(&base);
+        let general = base.join("general");
+        let random = base.join("random");
+        assert!(
+            fs::create_dir_all(general.join("sub")).is_ok()
+                && fs::create_dir_all(&random).is_ok()
+                && fs::write(general.join("sub").join("1__msg.toml"), b"a").is_ok()
+                && fs::write(random.join("1__msg.toml"), b"b").is_ok(),
+            "test_scheduler_cache: failed to create channels"
+        );
+
+        let cache = WalkCache::new().trust_watcher(true).into_shared();
+        let mut scheduler = ScanScheduler::new().cache(Arc::clone(&cache));
+        let _general_id = scheduler.register(&general, WalkConfig::new());
+        let _random_id = scheduler.register(&random, WalkConfig::new());
+        let view = |dir: &Path| -> usize {
+            lock_shared(&cache)
+                .get_or_collect(dir, || {
+                    Ok(crate::dir_walk_module::walk_dir(dir).ok_entries().collect())
+                })
+                .map_or(0, <[DirEntry]>::len)
+        };
+
+        // Views before the baseline walk are dropped by it
+        assert_eq!(view(&random), 1, "test_scheduler_cache: early view");
+        assert!(
+            scheduler.tick(Duration::from_secs(60)).is_empty(),
+            "test_scheduler_cache: baseline tick reports nothing"
+        );
+        assert!(
+            lock_shared(&cache).is_empty(),
+            "test_scheduler_cache: baseline walk must drop cached listings"
+        );
+
+        let sub = general.join("sub");
+        assert!(
+            view(&sub) == 1 && view(&general) == 2 && view(&random) == 1,
+            "test_scheduler_cache: initial views"
+        );
+
+        // Stale until the next completed walk, then evicted
+        assert!(
+            fs::write(sub.join("2__msg.toml"), b"c").is_ok(),
+            "test_scheduler_cache: failed to add message"
+        );
+        assert_eq!(
+            view(&sub),
+            1,
+            "test_scheduler_cache: trusted hit before the walk"
+        );
+        assert_eq!(
+            scheduler.tick(Duration::from_secs(60)).len(),
+            1,
+            "test_scheduler_cache: one channel changed"
+        );
+        {
+            let mut locked = lock_shared(&cache);
+            assert!(
+                locked.len() == 1 && !locked.invalidate(&general) && locked.invalidate(&random),
+                "test_scheduler_cache: only the changed channel's listings are evicted"
+            );
+        }
+        assert_eq!(
+            view(&sub),
+            2,
+            "test_scheduler_cache: fresh listing after the walk"
+        );
+
+        // A failed walk (channel gone) drops everything under its root
+        assert_eq!(view(&random), 1, "test_scheduler_cache: random view");
+        let mut strict = ScanScheduler::new().cache(Arc::clone(&cache));
+        let strict_id = strict.register(&random, WalkConfig::new().continue_on_error(false));
+        let _baseline = strict.tick(Duration::from_secs(60));
+        assert_eq!(
+            view(&random),
+            1,
+            "test_scheduler_cache: random view after baseline"
+        );
+        cleanup(&random);
+        let _failed = strict.tick(Duration::from_secs(60));
+        assert!(
+            strict
+                .monitor(strict_id)
+                .is_some_and(|m| m.last_error().is_some())
+                && !lock_shared(&cache).invalidate(&random),
+            "test_scheduler_cache: failed walk must evict its channel"
+        );
+
+        cleanup(&base);
+    }
+
+    /// Test: a polling thread delivers changes over the caller's channel
+    /// and hands the scheduler back on stop.
+    #[test]
+    fn test_spawn_polling_sends_events() {
+        let base = test_dir("polling");
+        cleanup(&base);
+        assert!(
+            fs::create_dir_all(&base).is_ok(),
+            "test_spawn_polling: failed to create dir"
+        );
+
+        let mut scheduler = ScanScheduler::new();
+        let channel_id = scheduler.register(&base, WalkConfig::new());
+        // Baseline before spawning, so the new file is always a change
+        assert!(
+            scheduler.tick(Duration::from_secs(60)).is_empty(),
+            "test_spawn_polling: baseline tick must report nothing"
+        );
+
+        let (events_tx, events_rx) = mpsc::channel();
+        let poller =
+            scheduler.spawn_polling(events_tx, Duration::from_millis(10), Duration::from_secs(1));
+        assert!(poller.is_ok(), "test_spawn_polling: spawn failed");
+
+        assert!(
+            fs::write(base.join("1__msg.toml"), b"m").is_ok(),
+            "test_spawn_polling: failed to add message"
+        );
+        let event = events_rx.recv_timeout(Duration::from_secs(10));
+        let walk_id = event.as_ref().map_or(0, |e| e.walk_id);
+        assert_eq!(
+            event.map(|e| (e.channel_id, e.changes.added)),
+            Ok((channel_id, vec![PathBuf::from("1__msg.toml")])),
+            "test_spawn_polling: change event"
+        );
+
+        let returned = poller.ok().and_then(PollingThread::stop);
+        // Later polls only ever take newer ids
+        let latest_walk_id = returned
+            .as_ref()
+            .and_then(|s| s.monitor(channel_id))
+            .map_or(0, ChannelMonitor::walk_id);
+        assert!(
+            walk_id > 0 && latest_walk_id >= walk_id,
+            "test_spawn_polling: event walk id {} vs latest {}",
+            walk_id,
+            latest_walk_id
+        );
+        assert_eq!(
+            returned.map(|s| s.len()),
+            Some(1),
+            "test_spawn_polling: scheduler handed back on stop"
+        );
+
+        cleanup(&base);
+    }
+
+    /// Test: directories added and removed at runtime, directly and on a
+    /// running polling thread; ids stay stable and are not reused.
+    #[test]
+    fn test_add_and_remove_watch() {
+        let base = test_dir("add_remove");
+        cleanup(&base);
+        let dirs: Vec<PathBuf> = (0..3).map(|i| base.join(format!("c{}", i))).collect();
+        assert!(
+            dirs.iter().all(|d| fs::create_dir_all(d).is_ok()),
+            "test_add_remove_watch: failed to create dirs"
+        );
+
+        let mut scheduler = ScanScheduler::new();
+        let first = scheduler.add_watch(&dirs[0], WalkConfig::new());
+        let second = scheduler.add_watch(&dirs[1], WalkConfig::new().max_depth(0));
+        assert!(
+            scheduler.remove_watch(first) && !scheduler.remove_watch(first),
+            "test_add_remove_watch: remove once"
+        );
+        assert_eq!(
+            (scheduler.len(), scheduler.monitor(first).is_none(), second),
+            (1, true, 1),
+            "test_add_remove_watch: remaining channel keeps its id"
+        );
+        assert!(
+            scheduler.tick(Duration::from_secs(60)).is_empty(),
+            "test_add_remove_watch: baseline"
+        );
+
+        let (events_tx, events_rx) = mpsc::channel();
+        let spawned =
+            scheduler.spawn_polling(events_tx, Duration::from_millis(10), Duration::from_secs(1));
+        assert!(spawned.is_ok(), "test_add_remove_watch: spawn failed");
+        let Ok(mut poller) = spawned else {
+            return;
+        };
+
+        // Commands apply in order: once the joined channel reports, the
+        // removal before it has been applied
+        assert!(
+            poller.remove_watch(second).is_ok(),
+            "test_add_remove_watch: remove on thread"
+        );
+        let third = poller.add_watch(&dirs[2], WalkConfig::new());
+        assert_eq!(third, Ok(2), "test_add_remove_watch: id not reused");
+
+        // A write may land before the joined channel's baseline walk; keep
+        // adding files until one is reported
+        let mut event = Err(RecvTimeoutError::Timeout);
+        for attempt in 0..20 {
+            assert!(
+                fs::write(dirs[2].join(format!("{}__new.toml", attempt + 1)), b"n").is_ok(),
+                "test_add_remove_watch: failed to write"
+            );
+            event = events_rx.recv_timeout(Duration::from_millis(500));
+            if event.is_ok() {
+                break;
+            }
+        }
+        assert_eq!(
+            event.map(|e| e.channel_id),
+            Ok(2),
+            "test_add_remove_watch: joined channel reports"
+        );
+
+        // The removed channel no longer reports: the next event is from
+        // the joined one
+        assert!(
+            fs::write(dirs[1].join("1__ignored.toml"), b"i").is_ok()
+                && fs::write(dirs[2].join("99__last.toml"), b"l").is_ok(),
+            "test_add_remove_watch: failed to write"
+        );
+        assert_eq!(
+            events_rx
+                .recv_timeout(Duration::from_secs(10))
+                .map(|e| (e.channel_id, e.changes.added)),
+            Ok((2, vec![PathBuf::from("99__last.toml")])),
+            "test_add_remove_watch: removed channel must not report"
+        );
+        assert_eq!(
+            poller.stop().map(|s| s.len()),
+            Some(1),
+            "test_add_remove_watch: one channel left"
+        );
+
+        cleanup(&base);
+    }
+
+    /// Test: a new subdirectory gets a priority scan ahead of the regular
+    /// walks; what it finds is reported once, and a depth-limited channel
+    /// queues nothing it could not see.
+    #[test]
+    fn test_priority_scan_of_new_dirs() {
+        let base = test_dir("priority");
+        cleanup(&base);
+        let watched = base.join("channels");
+        let shallow = base.join("shallow");
+        assert!(
+            fs::create_dir_all(watched.join("general")).is_ok()
+                && fs::create_dir_all(&shallow).is_ok(),
+            "test_priority_scan: failed to create channels"
+        );
+
+        let mut scheduler = ScanScheduler::new()
+            .dirs_per_slice(1)
+            .prioritize_new_dirs(true);
+        let _watched_id = scheduler.register(&watched, WalkConfig::new());
+        let shallow_id = scheduler.register(&shallow, WalkConfig::new().max_depth(0));
+        assert!(
+            scheduler.tick(Duration::from_secs(60)).is_empty(),
+            "test_priority_scan: baseline tick must report nothing"
+        );
+
+        let new_channel = watched.join("new_channel");
+        assert!(
+            fs::create_dir_all(&new_channel).is_ok()
+                && fs::write(new_channel.join("1__msg.toml"), b"a").is_ok()
+                && fs::create_dir_all(shallow.join("sub")).is_ok(),
+            "test_priority_scan: failed to create new channel"
+        );
+
+        // Zero budget: the priority scan is queued but not yet run
+        let mut reported = Vec::new();
+        let mut ticks: usize = 0;
+        while reported.len() < 2 && ticks < 100 {
+            reported.extend(scheduler.tick(Duration::ZERO));
+            ticks += 1;
+        }
+        reported.sort_by_key(|(id, _)| *id);
+        assert_eq!(
+            reported
+                .iter()
+                .map(|(id, c)| (*id, c.added.clone()))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    0,
+                    vec![
+                        PathBuf::from("new_channel"),
+                        Path::new("new_channel").join("1__msg.toml")
+                    ]
+                ),
+                (shallow_id, vec![PathBuf::from("sub")]),
+            ],
+            "test_priority_scan: new directories not reported"
+        );
+        assert_eq!(
+            scheduler.pending_priority_scans(),
+            1,
+            "test_priority_scan: only the unlimited channel queues a scan"
+        );
+
+        // Written after the walk: found by the priority scan first
+        assert!(
+            fs::write(new_channel.join("2__msg.toml"), b"b").is_ok(),
+            "test_priority_scan: failed to add message"
+        );
+        let first = scheduler.tick(Duration::ZERO);
+        assert_eq!(
+            first
+                .iter()
+                .map(|(id, c)| (*id, c.added.clone()))
+                .collect::<Vec<_>>(),
+            vec![(0, vec![Path::new("new_channel").join("2__msg.toml")])],
+            "test_priority_scan: priority scan must report the new message first"
+        );
+        assert_eq!(
+            scheduler.pending_priority_scans(),
+            0,
+            "test_priority_scan: scan should be done"
+        );
+
+        // The regular walk does not report it again
+        assert!(
+            scheduler.tick(Duration::from_secs(60)).is_empty(),
+            "test_priority_scan: absorbed entry reported twice"
+        );
+
+        cleanup(&base);
+    }
+}
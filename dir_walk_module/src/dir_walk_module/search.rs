@@ -0,0 +1,629 @@
+//! # Content Search (`dir_walk_module::search`)
+//!
+//! Bounded, line-oriented substring search over the files a walk
+//! discovers, and a cheap top-level TOML key probe for pre-filtering
+//! message files.
+//!
+//! ## Project Context
+//! Operators regularly ask "which message mentions X?". Running `grep`
+//! outside the application bypasses its sandbox rules (symlink policy,
+//! special-file refusal, size bounds). `search_files()` answers the same
+//! question inside those rules:
+//! - Files come from a `DirWalker` (bounds, filters, symlink policy)
+//! - Each file is opened with `DirEntry::open_verified()`
+//! - At most `max_bytes_per_file` bytes are read per file, and at most
+//!   `max_line_len` bytes of each line are kept in memory
+//!
+//! ```ignore
+//! for found in search_files(&channel_dir, b"release plan", SearchConfig::new()).flatten() {
+//!     println!("{}:{}", found.entry.file_name().unwrap_or("?"), found.line_number);
+//! }
+//! ```
+
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+use super::{DirEntry, DirWalker, WalkConfig, WalkError, WalkStats, open_regular_file};
+
+/// Default cap on bytes read from one file (8 MiB).
+const DEFAULT_MAX_BYTES_PER_FILE: u64 = 8 * 1024 * 1024;
+
+/// Default cap on bytes of one line kept for matching (64 KiB).
+const DEFAULT_MAX_LINE_LEN: usize = 64 * 1024;
+
+// ============================================================================
+// CONFIGURATION
+// ============================================================================
+
+/// Configuration for `search_files()`.
+#[derive(Debug, Clone)]
+pub struct SearchConfig {
+    /// Walk used to discover files (bounds, filters, symlink policy).
+    walk_config: WalkConfig,
+
+    /// Bytes read from one file before the rest of it is ignored.
+    max_bytes_per_file: u64,
+
+    /// Bytes of one line that are searched; the rest of a longer line is
+    /// skipped without being buffered.
+    max_line_len: usize,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        SearchConfig {
+            walk_config: WalkConfig::channel_scan(),
+            max_bytes_per_file: DEFAULT_MAX_BYTES_PER_FILE,
+            max_line_len: DEFAULT_MAX_LINE_LEN,
+        }
+    }
+}
+
+impl SearchConfig {
+    /// Create a config with defaults: `WalkConfig::channel_scan()`,
+    /// 8 MiB per file, 64 KiB per line.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the walk configuration used to discover files.
+    ///
+    /// # Arguments
+    /// * `config` - `continue_on_error` also decides whether a file that
+    ///   cannot be opened or read ends the search with an error.
+    pub fn walk_config(mut self, config: WalkConfig) -> Self {
+        self.walk_config = config;
+        self
+    }
+
+    /// Set the maximum bytes read from each file.
+    pub fn max_bytes_per_file(mut self, bytes: u64) -> Self {
+        self.max_bytes_per_file = bytes;
+        self
+    }
+
+    /// Set the maximum bytes of each line that are searched (minimum 1).
+    pub fn max_line_len(mut self, bytes: usize) -> Self {
+        self.max_line_len = bytes.max(1);
+        self
+    }
+}
+
+// ============================================================================
+// SEARCH
+// ============================================================================
+
+/// One matching line.
+#[derive(Debug)]
+pub struct SearchMatch {
+    /// File containing the match.
+    pub entry: DirEntry,
+
+    /// 1-based line number of the matching line.
+    pub line_number: usize,
+}
+
+/// Iterator returned by `search_files()`.
+///
+/// Yields one `SearchMatch` per matching line (a line with several
+/// occurrences is reported once), file by file in walk order.
+#[derive(Debug)]
+pub struct FileSearch {
+    /// Source of candidate files.
+    walker: DirWalker,
+
+    /// Bytes to find.
+    needle: Vec<u8>,
+
+    /// Per-file and per-line bounds.
+    max_bytes_per_file: u64,
+    max_line_len: usize,
+
+    /// Whether open/read failures are skipped rather than yielded.
+    continue_on_error: bool,
+
+    /// File currently being scanned, with its reader.
+    current: Option<(DirEntry, BoundedLines)>,
+
+    /// Reused line buffer.
+    line: Vec<u8>,
+
+    /// Set after an error is yielded; the iterator then ends.
+    stopped: bool,
+}
+
+/// Search the regular files under `path` for lines containing `needle`.
+///
+/// An empty `needle` matches every line. Matching is on raw bytes and
+/// case-sensitive; only the first `max_line_len` bytes of each line are
+/// searched.
+///
+/// # Arguments
+/// * `path` - Directory to search
+/// * `needle` - Bytes to find (e.g. `b"release plan"`)
+/// * `config` - Walk and read bounds
+///
+/// # Returns
+/// A `FileSearch` iterator of `Result<SearchMatch, WalkError>`. Errors are
+/// only yielded in strict mode (`continue_on_error(false)`), after which
+/// the iterator ends.
+pub fn search_files(path: &Path, needle: &[u8], config: SearchConfig) -> FileSearch {
+    FileSearch {
+        continue_on_error: config.walk_config.continue_on_error,
+        walker: DirWalker::new(path, config.walk_config),
+        needle: needle.to_vec(),
+        max_bytes_per_file: config.max_bytes_per_file,
+        max_line_len: config.max_line_len,
+        current: None,
+        line: Vec::new(),
+        stopped: false,
+    }
+}
+
+impl FileSearch {
+    /// Walk counters so far (see `DirWalker::stats()`).
+    pub fn stats(&self) -> &WalkStats {
+        self.walker.stats()
+    }
+
+    /// Open the next walked regular file, or `None` when the walk ends.
+    fn open_next(&mut self) -> Option<Result<(DirEntry, BoundedLines), WalkError>> {
+        // Bounded: each iteration consumes one walker item
+        for result in self.walker.by_ref() {
+            let entry = match result {
+                Ok(entry) => entry,
+                Err(e) => return Some(Err(e)),
+            };
+            if !entry.is_file() {
+                continue;
+            }
+
+            match entry.open_verified() {
+                Ok(file) => {
+                    let lines = BoundedLines::new(file, self.max_bytes_per_file);
+                    return Some(Ok((entry, lines)));
+                }
+                Err(e) if !self.continue_on_error => return Some(Err(e)),
+                Err(_) => {}
+            }
+        }
+        None
+    }
+}
+
+impl Iterator for FileSearch {
+    type Item = Result<SearchMatch, WalkError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.stopped {
+            return None;
+        }
+
+        // Bounded: every iteration reads one line of the (size-capped)
+        // current file or advances the finite walk
+        loop {
+            let (entry, lines) = match &mut self.current {
+                Some(current) => current,
+                None => match self.open_next()? {
+                    Ok(opened) => self.current.insert(opened),
+                    Err(e) => {
+                        self.stopped = true;
+                        return Some(Err(e));
+                    }
+                },
+            };
+
+            match lines.next_line(&mut self.line, self.max_line_len) {
+                Ok(true) => {
+                    if contains_bytes(&self.line, &self.needle) {
+                        return Some(Ok(SearchMatch {
+                            entry: entry.clone(),
+                            line_number: lines.line_number(),
+                        }));
+                    }
+                }
+                Ok(false) => self.current = None,
+                Err(e) => {
+                    self.current = None;
+                    if !self.continue_on_error {
+                        self.stopped = true;
+                        return Some(Err(e));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Whether `haystack` contains `needle` (empty needle: always).
+fn contains_bytes(haystack: &[u8], needle: &[u8]) -> bool {
+    needle.is_empty()
+        || haystack
+            .windows(needle.len())
+            .any(|window| window == needle)
+}
+
+// ============================================================================
+// BOUNDED LINE READER
+// ============================================================================
+
+/// Line reader over an open file that never reads more than a byte budget
+/// and never buffers more than a per-line cap.
+#[derive(Debug)]
+pub(super) struct BoundedLines {
+    /// Buffered file handle.
+    reader: BufReader<fs::File>,
+
+    /// Bytes still allowed to be read from the file.
+    remaining: u64,
+
+    /// Number of the last line returned (1-based; 0 before the first).
+    line_number: usize,
+}
+
+impl BoundedLines {
+    /// Wrap an open file with a total read budget of `max_bytes`.
+    pub(super) fn new(file: fs::File, max_bytes: u64) -> Self {
+        BoundedLines {
+            reader: BufReader::new(file),
+            remaining: max_bytes,
+            line_number: 0,
+        }
+    }
+
+    /// 1-based number of the line most recently returned.
+    pub(super) fn line_number(&self) -> usize {
+        self.line_number
+    }
+
+    /// Read the next line into `line` (without its `\n` / `\r\n`),
+    /// keeping at most `max_line_len` bytes of it.
+    ///
+    /// # Returns
+    /// * `Ok(true)` - A line was read
+    /// * `Ok(false)` - End of file, or the byte budget is used up
+    /// * `Err(WalkError::IoError)` - Read failed
+    pub(super) fn next_line(
+        &mut self,
+        line: &mut Vec<u8>,
+        max_line_len: usize,
+    ) -> Result<bool, WalkError> {
+        line.clear();
+        let mut read_any = false;
+
+        // Bounded: every pass consumes at least one byte of the budget or
+        // returns
+        while self.remaining > 0 {
+            let available = match self.reader.fill_buf() {
+                Ok(buffer) => buffer,
+                Err(_e) => {
+                    #[cfg(debug_assertions)]
+                    eprintln!("DWIO: search read failed: {}", _e);
+                    return Err(WalkError::IoError);
+                }
+            };
+            if available.is_empty() {
+                break;
+            }
+
+            let budget = usize::try_from(self.remaining).unwrap_or(usize::MAX);
+            let window = &available[..available.len().min(budget)];
+            let (chunk, found_newline) = match window.iter().position(|b| *b == b'\n') {
+                Some(index) => (&window[..index], true),
+                None => (window, false),
+            };
+
+            let room = max_line_len.saturating_sub(line.len());
+            line.extend_from_slice(&chunk[..chunk.len().min(room)]);
+
+            let consumed = chunk.len() + usize::from(found_newline);
+            self.reader.consume(consumed);
+            self.remaining = self.remaining.saturating_sub(consumed as u64);
+            read_any = true;
+
+            if found_newline {
+                break;
+            }
+        }
+
+        if !read_any {
+            return Ok(false);
+        }
+        if line.last() == Some(&b'\r') {
+            line.pop();
+        }
+        self.line_number = self.line_number.saturating_add(1);
+        Ok(true)
+    }
+}
+
+// ============================================================================
+// TOML KEY PROBE
+// ============================================================================
+
+/// Bytes read from one file by `probe_toml_key()` (1 MiB); top-level keys
+/// precede every table, so they sit at the start of a message file.
+const PROBE_MAX_BYTES_PER_FILE: u64 = 1024 * 1024;
+
+/// Longest line `probe_toml_key()` inspects (4 KiB); only its start
+/// matters.
+const PROBE_MAX_LINE_LEN: usize = 4 * 1024;
+
+/// Keep the paths whose file appears to define `key` at TOML top level.
+///
+/// A minimal line scan, not a TOML parse: a line matches when, before the
+/// first table header (`[table]` / `[[array]]`), it starts (after
+/// indentation) with `key`, `"key"` or `'key'` followed by optional
+/// whitespace and `=` (or `.` for a dotted key). Comment lines are
+/// ignored, and so are lines inside a multi-line string (`"""` / `'''`),
+/// which neither match nor end the top level.
+///
+/// As a pre-filter it may report false positives (e.g. a matching line
+/// after malformed quoting) but not false negatives for ordinary message
+/// files, so the application's real parser still decides.
+///
+/// # Project Context
+/// Message polling only needs the real TOML parse for files that can
+/// contain the field of interest. Each file is opened with
+/// `open_regular_file()` and read for at most 1 MiB; files that cannot be
+/// opened or read are left out.
+///
+/// # Arguments
+/// * `paths` - Candidate files (e.g. paths from a walk)
+/// * `key` - Bare top-level key to look for
+///
+/// # Returns
+/// The matching paths, in input order.
+pub fn probe_toml_key<I, P>(paths: I, key: &str) -> Vec<PathBuf>
+where
+    I: IntoIterator<Item = P>,
+    P: AsRef<Path>,
+{
+    let mut matching: Vec<PathBuf> = Vec::new();
+    let mut line: Vec<u8> = Vec::new();
+
+    for path in paths {
+        let path = path.as_ref();
+        if file_has_top_level_key(path, key.as_bytes(), &mut line) {
+            matching.push(path.to_path_buf());
+        }
+    }
+    matching
+}
+
+/// Scan one file for a top-level `key` assignment (see `probe_toml_key`).
+fn file_has_top_level_key(path: &Path, key: &[u8], line: &mut Vec<u8>) -> bool {
+    let file = match open_regular_file(path) {
+        Ok(f) => f,
+        Err(_e) => {
+            #[cfg(debug_assertions)]
+            eprintln!("DWTP: toml probe skipped unreadable file: {}", _e);
+            return false;
+        }
+    };
+
+    let mut lines = BoundedLines::new(file, PROBE_MAX_BYTES_PER_FILE);
+    let mut open_string: Option<u8> = None;
+    // Bounded by the per-file byte budget
+    while let Ok(true) = lines.next_line(line, PROBE_MAX_LINE_LEN) {
+        let trimmed = trim_ascii_start(line);
+        if open_string.is_none() {
+            if trimmed.starts_with(b"[") {
+                // First table header: everything after is not top level
+                return false;
+            }
+            if line_assigns_key(trimmed, key) {
+                return true;
+            }
+        }
+        open_string = multiline_string_after(trimmed, open_string);
+    }
+    false
+}
+
+/// Which multi-line string (by quote byte) is still open after `line`.
+///
+/// `open` is the string open at the start of the line, if any. Skips
+/// single-line strings and stops at a comment; `\` escapes apply in
+/// basic (`"`) strings only, as in TOML.
+fn multiline_string_after(line: &[u8], mut open: Option<u8>) -> Option<u8> {
+    let mut i = 0;
+    while i < line.len() {
+        let byte = line[i];
+        match open {
+            Some(quote) => {
+                if quote == b'"' && byte == b'\\' {
+                    i = i.saturating_add(2);
+                } else if line[i..].starts_with(&[quote; 3]) {
+                    // Up to two extra quotes belong to the string's content
+                    i = i.saturating_add(3);
+                    let mut extra = 0;
+                    while extra < 2 && line.get(i) == Some(&quote) {
+                        i = i.saturating_add(1);
+                        extra += 1;
+                    }
+                    open = None;
+                } else {
+                    i = i.saturating_add(1);
+                }
+            }
+            None => match byte {
+                b'#' => break,
+                b'"' | b'\'' if line[i..].starts_with(&[byte; 3]) => {
+                    open = Some(byte);
+                    i = i.saturating_add(3);
+                }
+                b'"' | b'\'' => {
+                    // Single-line string: skip to its closing quote
+                    i = i.saturating_add(1);
+                    while i < line.len() && line[i] != byte {
+                        let step = if byte == b'"' && line[i] == b'\\' {
+                            2
+                        } else {
+                            1
+                        };
+                        i = i.saturating_add(step);
+                    }
+                    i = i.saturating_add(1);
+                }
+                _ => i = i.saturating_add(1),
+            },
+        }
+    }
+    open
+}
+
+/// Whether a (left-trimmed) line starts with an assignment to `key`.
+fn line_assigns_key(line: &[u8], key: &[u8]) -> bool {
+    let rest = if let Some(rest) = line.strip_prefix(key) {
+        rest
+    } else if let Some(quote) = line.first().copied().filter(|q| *q == b'"' || *q == b'\'') {
+        match line[1..].strip_prefix(key) {
+            Some(after_key) if after_key.first() == Some(&quote) => &after_key[1..],
+            _ => return false,
+        }
+    } else {
+        return false;
+    };
+
+    matches!(trim_ascii_start(rest).first(), Some(b'=') | Some(b'.'))
+}
+
+/// `bytes` without leading spaces and tabs.
+fn trim_ascii_start(bytes: &[u8]) -> &[u8] {
+    let start = bytes
+        .iter()
+        .position(|b| *b != b' ' && *b != b'\t')
+        .unwrap_or(bytes.len());
+    &bytes[start..]
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Helper: unique test directory path (caller cleans up).
+    fn test_dir(name: &str) -> PathBuf {
+        crate::dir_walk_module::testing::unique_test_dir(&format!("search_{}", name))
+    }
+
+    /// Helper: safely remove test directory, ignoring errors.
+    fn cleanup(path: &Path) {
+        let _ = fs::remove_dir_all(path);
+    }
+
+    /// Test: matches are reported per line with 1-based numbers, across
+    /// nested files, and the per-file byte bound stops reading early.
+    #[test]
+    fn test_search_files_reports_lines() {
+        let dir = test_dir("lines");
+        cleanup(&dir);
+        assert!(
+            fs::create_dir_all(dir.join("sub")).is_ok()
+                && fs::write(
+                    dir.join("1__msg.toml"),
+                    b"title = \"a\"\r\nbody = \"release plan\"\n"
+                )
+                .is_ok()
+                && fs::write(
+                    dir.join("sub").join("2__msg.toml"),
+                    b"x\ny\nrelease plan release plan\n"
+                )
+                .is_ok()
+                && fs::write(dir.join("3__msg.toml"), b"nothing here\n").is_ok(),
+            "test_search_files: failed to create test tree"
+        );
+
+        let mut found: Vec<(String, usize)> =
+            search_files(&dir, b"release plan", SearchConfig::new())
+                .filter_map(|r| r.ok())
+                .map(|m| (m.entry.file_name().unwrap_or("").to_string(), m.line_number))
+                .collect();
+        found.sort();
+        assert_eq!(
+            found,
+            vec![
+                ("1__msg.toml".to_string(), 2),
+                ("2__msg.toml".to_string(), 3)
+            ],
+            "test_search_files: one match per matching line"
+        );
+
+        // 4-byte budget: only "x\ny\n" of file 2 is read
+        let bounded = search_files(&dir, b"release", SearchConfig::new().max_bytes_per_file(4))
+            .filter_map(|r| r.ok())
+            .count();
+        assert_eq!(
+            bounded, 0,
+            "test_search_files: byte bound must stop reading"
+        );
+
+        // 3-byte lines: "release plan" can never fit
+        let truncated = search_files(&dir, b"release plan", SearchConfig::new().max_line_len(3))
+            .filter_map(|r| r.ok())
+            .count();
+        assert_eq!(
+            truncated, 0,
+            "test_search_files: only max_line_len bytes of a line are searched"
+        );
+
+        cleanup(&dir);
+    }
+
+    /// Test: probe_toml_key finds bare, quoted, spaced and dotted
+    /// top-level keys, and ignores comments, table sections, prefixes of
+    /// longer keys, multi-line string contents and unreadable paths.
+    #[test]
+    fn test_probe_toml_key_top_level_only() {
+        let dir = test_dir("toml_probe");
+        cleanup(&dir);
+        let files: [(&str, &[u8]); 10] = [
+            ("bare.toml", b"title = \"x\"\nowner=\"me\"\n"),
+            ("quoted.toml", b"# owner = no\n  \"owner\"   = \"me\"\n"),
+            ("dotted.toml", b"owner.name = \"me\"\n"),
+            ("in_table.toml", b"title = \"x\"\n[meta]\nowner = \"me\"\n"),
+            ("comment.toml", b"# owner = \"me\"\n"),
+            ("longer.toml", b"owners = [\"me\"]\n"),
+            ("crlf.toml", b"title = \"x\"\r\nowner = \"me\"\r\n"),
+            (
+                "after_basic.toml",
+                b"body = \"\"\"\n[not a header]\n\"\"\"\nowner = \"me\"\n",
+            ),
+            (
+                "after_literal.toml",
+                b"body = '''a\n  [[nor this]] \\\n'''\nowner = \"me\"\n",
+            ),
+            ("inside.toml", b"body = \"\"\"\nowner = \"me\"\n\"\"\"\n"),
+        ];
+        assert!(
+            fs::create_dir_all(&dir).is_ok()
+                && files
+                    .iter()
+                    .all(|(name, content)| fs::write(dir.join(name), content).is_ok()),
+            "test_probe_toml_key: failed to create files"
+        );
+
+        let mut candidates: Vec<PathBuf> = files.iter().map(|(name, _)| dir.join(name)).collect();
+        candidates.push(dir.join("missing.toml"));
+        let found = probe_toml_key(&candidates, "owner");
+        assert_eq!(
+            found,
+            vec![
+                dir.join("bare.toml"),
+                dir.join("quoted.toml"),
+                dir.join("dotted.toml"),
+                dir.join("crlf.toml"),
+                dir.join("after_basic.toml"),
+                dir.join("after_literal.toml"),
+            ],
+            "test_probe_toml_key: matched files"
+        );
+
+        cleanup(&dir);
+    }
+}
@@ -0,0 +1,1258 @@
+//! # Directory Snapshots and Diffs (`dir_walk_module::snapshot`)
+//!
+//! Captures a bounded walk's entries (relative path → size, mtime, kind,
+//! permissions) and compares two captures to report what was added,
+//! removed, or modified.
+//!
+//! ## Project Context
+//! A directory hash (see `hashing`) answers "did anything change?"; a
+//! snapshot diff answers "what changed?", which is what the application
+//! needs to load only new messages instead of re-reading a whole channel.
+//!
+//! ## Allocation Stability
+//! Entries are kept in a `HashMap` that is cleared (not dropped) between
+//! captures, so a long-lived owner (e.g. `ChannelMonitor`) reuses the
+//! same table on every poll once it has grown to the channel's size.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::{DirEntry, DirWalker, WalkConfig, WalkError, strip_root};
+
+/// First line of the persisted format; the number is the format version.
+const FORMAT_HEADER: &str = "dwm-snapshot 2";
+
+/// Header of version 1 (no permissions field), still accepted by
+/// `read_from()`.
+const LEGACY_FORMAT_HEADER: &str = "dwm-snapshot 1";
+
+/// Longest persisted line accepted by `read_from()` (header, entry, end).
+const MAX_PERSISTED_LINE_LEN: u64 = 64 * 1024;
+
+/// Most entries `read_from()` accepts.
+const MAX_PERSISTED_ENTRIES: usize = 10_000_000;
+
+// ============================================================================
+// SNAPSHOT TYPES
+// ============================================================================
+
+/// What a snapshot remembers about one entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SnapshotEntry {
+    /// Whether the entry was a directory.
+    pub is_dir: bool,
+
+    /// Size in bytes (as reported by the walk's stat).
+    pub len: u64,
+
+    /// Last modification time, where the platform reports it.
+    pub modified: Option<SystemTime>,
+
+    /// Inode number on Unix; `None` elsewhere.
+    pub inode: Option<u64>,
+
+    /// Permission bits (`DirEntry::permission_bits()`); `None` when read
+    /// from a version-1 persisted snapshot.
+    pub permissions: Option<u32>,
+}
+
+impl SnapshotEntry {
+    /// Build from an entry's discovery-time metadata (no extra syscall).
+    fn from_entry(entry: &DirEntry) -> Self {
+        let snapshot = entry.snapshot();
+        SnapshotEntry {
+            is_dir: entry.is_dir(),
+            len: snapshot.len,
+            modified: snapshot.modified,
+            inode: snapshot.inode,
+            permissions: Some(entry.permission_bits()),
+        }
+    }
+
+    /// Whether `newer` describes a changed entry.
+    ///
+    /// Directories only count as modified when their kind (or, with
+    /// `include_permissions`, their permissions) changes: a directory's
+    /// own mtime moves whenever a child is added or removed, and those
+    /// children are reported individually.
+    fn is_modified_in(&self, newer: &SnapshotEntry, options: &DiffOptions) -> bool {
+        if self.is_dir != newer.is_dir {
+            return true;
+        }
+        if options.include_permissions
+            && let (Some(old), Some(new)) = (self.permissions, newer.permissions)
+            && old != new
+        {
+            return true;
+        }
+        if self.is_dir {
+            return false;
+        }
+        self.len != newer.len || self.modified != newer.modified
+    }
+}
+
+/// What `DirectorySnapshot::diff_with()` compares.
+///
+/// Built using method chaining, like `WalkConfig`. The defaults match
+/// `diff()`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DiffOptions {
+    /// Whether a permission change alone reports an entry as modified.
+    include_permissions: bool,
+
+    /// Whether removed/added pairs are reported as renames.
+    detect_renames: bool,
+}
+
+impl DiffOptions {
+    /// Create options with defaults: permissions ignored, no rename
+    /// detection.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set whether a permission-only change counts as a modification.
+    ///
+    /// # Arguments
+    /// * `include` - If `true`, an entry (file or directory) whose
+    ///   permission bits differ is reported in `modified`. Entries loaded
+    ///   from a version-1 snapshot have no recorded permissions and are
+    ///   never reported for this reason.
+    ///
+    /// # Project Context
+    /// Pairs with `HashConfig::include_permissions()`: a deployment that
+    /// reloads on chmod should also be told which entries it affected.
+    pub fn include_permissions(mut self, include: bool) -> Self {
+        self.include_permissions = include;
+        self
+    }
+
+    /// Set whether removed/added pairs are reported as renames (see
+    /// `DirectorySnapshot::diff_with_renames()`).
+    pub fn detect_renames(mut self, detect: bool) -> Self {
+        self.detect_renames = detect;
+        self
+    }
+}
+
+/// Entries seen by one bounded walk, keyed by path relative to the root.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DirectorySnapshot {
+    /// Relative path → recorded state.
+    entries: HashMap<PathBuf, SnapshotEntry>,
+}
+
+/// Result of comparing two snapshots.
+///
+/// All paths are relative to the snapshot root, and each list is sorted
+/// so output is deterministic regardless of `read_dir` order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChangeSummary {
+    /// Present in the newer snapshot only.
+    pub added: Vec<PathBuf>,
+
+    /// Present in the older snapshot only.
+    pub removed: Vec<PathBuf>,
+
+    /// Present in both, with a different size, mtime, or kind (or
+    /// permissions, when the diff includes them).
+    pub modified: Vec<PathBuf>,
+
+    /// Removed/added pairs identified as one entry under a new name.
+    ///
+    /// Only filled by `diff_with_renames()`; sorted by `from`.
+    pub renamed: Vec<Rename>,
+}
+
+/// One detected rename (paths relative to the snapshot root).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rename {
+    /// Path in the older snapshot.
+    pub from: PathBuf,
+
+    /// Path in the newer snapshot.
+    pub to: PathBuf,
+}
+
+/// Formats as `3__x.toml renamed to 4__x.toml`.
+impl fmt::Display for Rename {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} renamed to {}",
+            self.from.display(),
+            self.to.display()
+        )
+    }
+}
+
+impl ChangeSummary {
+    /// `true` when no change of any kind was found.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.modified.is_empty()
+            && self.renamed.is_empty()
+    }
+
+    /// Total number of reported changes (a rename counts once).
+    pub fn len(&self) -> usize {
+        self.added
+            .len()
+            .saturating_add(self.removed.len())
+            .saturating_add(self.modified.len())
+            .saturating_add(self.renamed.len())
+    }
+}
+
+/// Identity used to pair a removed entry with an added one.
+///
+/// A rename keeps size and mtime (and the inode, where the platform
+/// reports one), and never changes the entry's kind.
+type RenameKey = (bool, u64, Option<SystemTime>, Option<u64>);
+
+/// Removed and added candidates (indices) sharing one `RenameKey`.
+type RenameCandidates = (Vec<usize>, Vec<usize>);
+
+// ============================================================================
+// CAPTURE AND DIFF
+// ============================================================================
+
+impl DirectorySnapshot {
+    /// Create an empty snapshot.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Walk `root` with `config` and record every yielded entry.
+    ///
+    /// Zero-byte files are recorded unless `config` skips them:
+    /// `WalkConfig::channel_scan()` keeps them, while
+    /// `WalkConfig::message_loading()` drops them (`skip_empty_files`).
+    /// Add `skip_empty_files(true)` for a snapshot that agrees with
+    /// message loading.
+    ///
+    /// # Arguments
+    /// * `root` - Directory to capture
+    /// * `config` - Walk bounds and filters (e.g. `WalkConfig::channel_scan()`)
+    ///
+    /// # Returns
+    /// * `Ok(DirectorySnapshot)` - Entries seen by the walk
+    /// * `Err(WalkError)` - Only when `continue_on_error` is false
+    pub fn capture(root: &Path, config: WalkConfig) -> Result<Self, WalkError> {
+        let mut walker = DirWalker::new(root, config);
+        let mut snapshot = Self::new();
+        snapshot.refresh(&mut walker, root)?;
+        Ok(snapshot)
+    }
+
+    /// Replace this snapshot's contents with a fresh walk of `root`,
+    /// reusing both the walker's and the snapshot's allocations.
+    ///
+    /// The walker is `reset()` to `root` first, so it may be reused across
+    /// calls. On error the snapshot holds the entries read before the
+    /// failure; callers that keep a baseline should discard it.
+    ///
+    /// # Arguments
+    /// * `walker` - Walker to drive (its configuration is kept)
+    /// * `root` - Directory to capture; entry keys are relative to it
+    pub fn refresh(&mut self, walker: &mut DirWalker, root: &Path) -> Result<(), WalkError> {
+        self.entries.clear();
+        walker.reset(root);
+
+        for entry_result in walker.by_ref() {
+            let entry = entry_result?;
+            self.record(root, &entry);
+        }
+
+        Ok(())
+    }
+
+    /// Remove all entries, keeping the table's capacity.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Drop recorded non-directory entries longer than `bytes`, the snapshot
+    /// counterpart of `HashConfig::ignore_files_larger_than`.
+    ///
+    /// Applied to both sides of a diff, a file still being written past
+    /// the limit is never reported as modified.
+    ///
+    /// # Returns
+    /// Number of entries dropped.
+    pub fn ignore_files_larger_than(&mut self, bytes: u64) -> usize {
+        let before = self.entries.len();
+        self.entries
+            .retain(|_, entry| entry.is_dir || entry.len <= bytes);
+        before.saturating_sub(self.entries.len())
+    }
+
+    /// Add (or replace) one walked entry, keyed relative to `root`.
+    ///
+    /// For callers that drive the walk themselves, e.g. in bounded slices
+    /// via `DirWalker::step()`.
+    pub fn record(&mut self, root: &Path, entry: &DirEntry) {
+        let relative = strip_root(entry.path(), root)
+            .unwrap_or(entry.path())
+            .to_path_buf();
+        self.entries
+            .insert(relative, SnapshotEntry::from_entry(entry));
+    }
+
+    /// Add (or replace) one entry under an already relative path.
+    pub(super) fn insert(&mut self, relative_path: PathBuf, entry: SnapshotEntry) {
+        self.entries.insert(relative_path, entry);
+    }
+
+    /// Number of recorded entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// `true` when no entries were recorded.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Look up one entry by its path relative to the snapshot root.
+    pub fn get(&self, relative_path: &Path) -> Option<&SnapshotEntry> {
+        self.entries.get(relative_path)
+    }
+
+    /// Iterate recorded entries (arbitrary order).
+    pub fn iter(&self) -> impl Iterator<Item = (&Path, &SnapshotEntry)> {
+        self.entries
+            .iter()
+            .map(|(path, entry)| (path.as_path(), entry))
+    }
+
+    /// Compare this (older) snapshot with `newer`.
+    ///
+    /// # Arguments
+    /// * `newer` - A later capture of the same root
+    ///
+    /// # Returns
+    /// Sorted lists of added, removed and modified relative paths.
+    /// Permission changes are ignored (see `diff_with()`).
+    pub fn diff(&self, newer: &DirectorySnapshot) -> ChangeSummary {
+        self.diff_with(newer, &DiffOptions::new())
+    }
+
+    /// Compare this (older) snapshot with `newer` using `options`.
+    ///
+    /// # Arguments
+    /// * `newer` - A later capture of the same root
+    /// * `options` - Whether permissions count and renames are paired
+    ///
+    /// # Returns
+    /// Sorted lists of added, removed, modified and (when detected)
+    /// renamed relative paths.
+    pub fn diff_with(&self, newer: &DirectorySnapshot, options: &DiffOptions) -> ChangeSummary {
+        let mut summary = ChangeSummary::default();
+
+        for (path, old_entry) in &self.entries {
+            match newer.entries.get(path) {
+                None => summary.removed.push(path.clone()),
+                Some(new_entry) => {
+                    if old_entry.is_modified_in(new_entry, options) {
+                        summary.modified.push(path.clone());
+                    }
+                }
+            }
+        }
+
+        for path in newer.entries.keys() {
+            if !self.entries.contains_key(path) {
+                summary.added.push(path.clone());
+            }
+        }
+
+        summary.added.sort();
+        summary.removed.sort();
+        summary.modified.sort();
+        if options.detect_renames {
+            self.pair_renames(newer, &mut summary);
+        }
+        summary
+    }
+
+    /// Like `diff()`, but reports a removed + added pair as one rename
+    /// when both sides describe the same entry.
+    ///
+    /// A pair matches on kind, size, mtime, and inode (when both
+    /// snapshots have one). To avoid guessing, a pair is only formed when
+    /// its key is unique on both sides: two identical empty files renamed
+    /// at once stay reported as removes and adds.
+    ///
+    /// # Arguments
+    /// * `newer` - A later capture of the same root
+    ///
+    /// # Project Context
+    /// Message renumbering renames `3__x.toml` to `4__x.toml`; the UI
+    /// should show that as a move, not as a deleted and a new message.
+    pub fn diff_with_renames(&self, newer: &DirectorySnapshot) -> ChangeSummary {
+        self.diff_with(newer, &DiffOptions::new().detect_renames(true))
+    }
+
+    /// Move uniquely matching removed/added pairs of `summary` into
+    /// `summary.renamed`.
+    fn pair_renames(&self, newer: &DirectorySnapshot, summary: &mut ChangeSummary) {
+        let mut candidates: HashMap<RenameKey, RenameCandidates> = HashMap::new();
+        for (index, path) in summary.removed.iter().enumerate() {
+            if let Some(entry) = self.entries.get(path) {
+                candidates
+                    .entry(rename_key(entry))
+                    .or_default()
+                    .0
+                    .push(index);
+            }
+        }
+        for (index, path) in summary.added.iter().enumerate() {
+            if let Some(entry) = newer.entries.get(path) {
+                candidates
+                    .entry(rename_key(entry))
+                    .or_default()
+                    .1
+                    .push(index);
+            }
+        }
+
+        let mut paired_removed = vec![false; summary.removed.len()];
+        let mut paired_added = vec![false; summary.added.len()];
+        for (removed, added) in candidates.values() {
+            if let ([from], [to]) = (removed.as_slice(), added.as_slice()) {
+                paired_removed[*from] = true;
+                paired_added[*to] = true;
+                summary.renamed.push(Rename {
+                    from: summary.removed[*from].clone(),
+                    to: summary.added[*to].clone(),
+                });
+            }
+        }
+
+        // Drop paired paths; retain keeps the existing sort order
+        let mut index: usize = 0;
+        summary.removed.retain(|_| {
+            let keep = !paired_removed[index];
+            index += 1;
+            keep
+        });
+        let mut index: usize = 0;
+        summary.added.retain(|_| {
+            let keep = !paired_added[index];
+            index += 1;
+            keep
+        });
+
+        summary.renamed.sort_by(|a, b| a.from.cmp(&b.from));
+    }
+}
+
+/// Build the rename-matching key for one entry.
+fn rename_key(entry: &SnapshotEntry) -> RenameKey {
+    (entry.is_dir, entry.len, entry.modified, entry.inode)
+}
+
+// ============================================================================
+// PERSISTENCE
+// ============================================================================
+//
+// Versioned line format (no serde), one entry per line, sorted by path:
+//
+//     dwm-snapshot 2
+//     <kind> <len> <mtime> <inode> <permissions> <path>
+//     ...
+//     end <entry count>
+//
+// - kind: `d` (directory) or `f` (anything else)
+// - mtime: `<secs>.<nanos>` since the Unix epoch (`-<secs>.<nanos>`
+//   before it), or `-` when unknown
+// - inode: decimal, or `-` when unknown
+// - permissions: octal, or `-` when unknown; version 1 lines have no
+//   such field and are still read
+// - path: relative path bytes, with `%`, control bytes and DEL written as
+//   `%XX` (so names containing newlines stay on one line)
+//
+// The `end` line makes a file truncated by a crash detectable.
+
+impl DirectorySnapshot {
+    /// Write this snapshot in the versioned line format.
+    ///
+    /// Output is deterministic (sorted by path). Paths that are not valid
+    /// Unicode are written byte-exact on Unix and lossily elsewhere.
+    ///
+    /// # Arguments
+    /// * `out` - Destination (file, buffer, ...)
+    ///
+    /// # Returns
+    /// * `Ok(())` - Everything written
+    /// * `Err(WalkError::IoError)` - Write failed
+    ///
+    /// # Project Context
+    /// Persisting the snapshot at shutdown lets change detection resume at
+    /// startup from a diff instead of re-hashing every channel.
+    pub fn write_to(&self, out: &mut impl Write) -> Result<(), WalkError> {
+        let mut entries: Vec<(&PathBuf, &SnapshotEntry)> = self.entries.iter().collect();
+        entries.sort_by(|a, b| a.0.cmp(b.0));
+
+        let mut line: Vec<u8> = Vec::new();
+        write_persisted_line(out, FORMAT_HEADER.as_bytes())?;
+        for (path, entry) in entries {
+            line.clear();
+            line.extend_from_slice(
+                format!(
+                    "{} {} {} {} {} ",
+                    if entry.is_dir { 'd' } else { 'f' },
+                    entry.len,
+                    format_mtime(entry.modified),
+                    entry
+                        .inode
+                        .map_or_else(|| "-".to_string(), |inode| inode.to_string()),
+                    entry
+                        .permissions
+                        .map_or_else(|| "-".to_string(), |bits| format!("{:o}", bits)),
+                )
+                .as_bytes(),
+            );
+            escape_path(path, &mut line);
+            write_persisted_line(out, &line)?;
+        }
+        write_persisted_line(out, format!("end {}", self.entries.len()).as_bytes())
+    }
+
+    /// Read a snapshot written by `write_to()`.
+    ///
+    /// Reads up to and including the `end` line; bounded by a maximum
+    /// line length (64 KiB) and entry count (10 million).
+    ///
+    /// # Arguments
+    /// * `input` - Source positioned at the header line
+    ///
+    /// # Returns
+    /// * `Ok(DirectorySnapshot)` - Snapshot as written (version 1 data
+    ///   loads with unknown permissions)
+    /// * `Err(WalkError::SnapshotFormat)` - Unknown version, malformed or
+    ///   duplicate line, truncated data, or a bound exceeded
+    /// * `Err(WalkError::IoError)` - Read failed
+    pub fn read_from(input: &mut impl Read) -> Result<Self, WalkError> {
+        let mut reader = BufReader::new(input);
+        let mut line: Vec<u8> = Vec::new();
+
+        if !read_persisted_line(&mut reader, &mut line)? {
+            return Err(snapshot_format_error("missing header"));
+        }
+        let has_permissions = if line == FORMAT_HEADER.as_bytes() {
+            true
+        } else if line == LEGACY_FORMAT_HEADER.as_bytes() {
+            false
+        } else {
+            return Err(snapshot_format_error("unsupported header"));
+        };
+
+        let mut snapshot = Self::new();
+        // Bounded by MAX_PERSISTED_ENTRIES
+        loop {
+            if !read_persisted_line(&mut reader, &mut line)? {
+                return Err(snapshot_format_error("missing end line"));
+            }
+
+            if let Some(count) = line.strip_prefix(b"end ") {
+                let count = parse_field::<usize>(count)?;
+                if count != snapshot.entries.len() {
+                    return Err(snapshot_format_error("entry count mismatch"));
+                }
+                return Ok(snapshot);
+            }
+
+            if snapshot.entries.len() >= MAX_PERSISTED_ENTRIES {
+                return Err(snapshot_format_error("too many entries"));
+            }
+            let (path, entry) = parse_entry_line(&line, has_permissions)?;
+            if snapshot.entries.insert(path, entry).is_some() {
+                return Err(snapshot_format_error("duplicate path"));
+            }
+        }
+    }
+}
+
+/// Log (debug builds) and return `WalkError::SnapshotFormat`.
+fn snapshot_format_error(_reason: &str) -> WalkError {
+    #[cfg(debug_assertions)]
+    eprintln!("DWSN: snapshot read failed: {}", _reason);
+    WalkError::SnapshotFormat
+}
+
+/// Write one line plus its newline.
+fn write_persisted_line(out: &mut impl Write, line: &[u8]) -> Result<(), WalkError> {
+    out.write_all(line)
+        .and_then(|()| out.write_all(b"\n"))
+        .map_err(|_e| {
+            #[cfg(debug_assertions)]
+            eprintln!("DWIO: snapshot write failed: {}", _e);
+            WalkError::IoError
+        })
+}
+
+/// Read one newline-terminated line (without the newline) into `line`.
+///
+/// # Returns
+/// `Ok(false)` at end of input; an unterminated or over-long line is
+/// `SnapshotFormat`.
+fn read_persisted_line(reader: &mut impl BufRead, line: &mut Vec<u8>) -> Result<bool, WalkError> {
+    line.clear();
+    let read = reader
+        .take(MAX_PERSISTED_LINE_LEN + 1)
+        .read_until(b'\n', line);
+    match read {
+        Ok(0) => Ok(false),
+        Ok(_) if line.last() == Some(&b'\n') => {
+            line.pop();
+            Ok(true)
+        }
+        Ok(_) => Err(snapshot_format_error("unterminated or over-long line")),
+        Err(_e) => {
+            #[cfg(debug_assertions)]
+            eprintln!("DWIO: snapshot read failed: {}", _e);
+            Err(WalkError::IoError)
+        }
+    }
+}
+
+/// Parse `<kind> <len> <mtime> <inode> [<permissions>] <path>`.
+///
+/// # Arguments
+/// * `line` - Entry line without its newline
+/// * `has_permissions` - Whether the line has the version 2 permissions
+///   field
+fn parse_entry_line(
+    line: &[u8],
+    has_permissions: bool,
+) -> Result<(PathBuf, SnapshotEntry), WalkError> {
+    let mut fields = line.splitn(if has_permissions { 6 } else { 5 }, |b| *b == b' ');
+    let (kind, len, mtime, inode) =
+        match (fields.next(), fields.next(), fields.next(), fields.next()) {
+            (Some(kind), Some(len), Some(mtime), Some(inode)) => (kind, len, mtime, inode),
+            _ => return Err(snapshot_format_error("too few fields")),
+        };
+    let permissions = if has_permissions {
+        match fields.next() {
+            Some(b"-") => None,
+            Some(octal) => Some(parse_octal_field(octal)?),
+            None => return Err(snapshot_format_error("too few fields")),
+        }
+    } else {
+        None
+    };
+    let path = match fields.next() {
+        Some(path) => path,
+        None => return Err(snapshot_format_error("too few fields")),
+    };
+
+    let is_dir = match kind {
+        b"d" => true,
+        b"f" => false,
+        _ => return Err(snapshot_format_error("unknown kind")),
+    };
+    let inode = match inode {
+        b"-" => None,
+        digits => Some(parse_field::<u64>(digits)?),
+    };
+
+    let entry = SnapshotEntry {
+        is_dir,
+        len: parse_field::<u64>(len)?,
+        modified: parse_mtime(mtime)?,
+        inode,
+        permissions,
+    };
+    Ok((unescape_path(path)?, entry))
+}
+
+/// Parse an ASCII decimal field.
+fn parse_field<T: std::str::FromStr>(field: &[u8]) -> Result<T, WalkError> {
+    std::str::from_utf8(field)
+        .ok()
+        .filter(|text| !text.is_empty() && text.bytes().all(|b| b.is_ascii_digit()))
+        .and_then(|text| text.parse::<T>().ok())
+        .ok_or_else(|| snapshot_format_error("bad number"))
+}
+
+/// Parse an ASCII octal field (permission bits).
+fn parse_octal_field(field: &[u8]) -> Result<u32, WalkError> {
+    std::str::from_utf8(field)
+        .ok()
+        .filter(|text| !text.is_empty() && text.bytes().all(|b| (b'0'..=b'7').contains(&b)))
+        .and_then(|text| u32::from_str_radix(text, 8).ok())
+        .ok_or_else(|| snapshot_format_error("bad permissions"))
+}
+
+/// Format an mtime as `<secs>.<nanos>` relative to the Unix epoch.
+fn format_mtime(modified: Option<SystemTime>) -> String {
+    match modified.map(|time| time.duration_since(UNIX_EPOCH)) {
+        None => "-".to_string(),
+        Some(Ok(after)) => format!("{}.{:09}", after.as_secs(), after.subsec_nanos()),
+        Some(Err(before)) => {
+            let before = before.duration();
+            format!("-{}.{:09}", before.as_secs(), before.subsec_nanos())
+        }
+    }
+}
+
+/// Inverse of `format_mtime()`.
+fn parse_mtime(field: &[u8]) -> Result<Option<SystemTime>, WalkError> {
+    if field == b"-" {
+        return Ok(None);
+    }
+
+    let (before_epoch, magnitude) = match field.strip_prefix(b"-") {
+        Some(rest) => (true, rest),
+        None => (false, field),
+    };
+    let dot = match magnitude.iter().position(|b| *b == b'.') {
+        Some(dot) => dot,
+        None => return Err(snapshot_format_error("bad mtime")),
+    };
+    let secs = parse_field::<u64>(&magnitude[..dot])?;
+    let nanos = parse_field::<u32>(&magnitude[dot + 1..])?;
+    if nanos >= 1_000_000_000 {
+        return Err(snapshot_format_error("bad mtime nanoseconds"));
+    }
+
+    let offset = Duration::new(secs, nanos);
+    let time = if before_epoch {
+        UNIX_EPOCH.checked_sub(offset)
+    } else {
+        UNIX_EPOCH.checked_add(offset)
+    };
+    time.map(Some)
+        .ok_or_else(|| snapshot_format_error("mtime out of range"))
+}
+
+/// Append `path` with `%`, control bytes and DEL escaped as `%XX`.
+fn escape_path(path: &Path, out: &mut Vec<u8>) {
+    #[cfg(unix)]
+    let bytes: std::borrow::Cow<'_, [u8]> = {
+        use std::os::unix::ffi::OsStrExt;
+        std::borrow::Cow::Borrowed(path.as_os_str().as_bytes())
+    };
+    #[cfg(not(unix))]
+    let bytes: std::borrow::Cow<'_, [u8]> =
+        std::borrow::Cow::Owned(path.to_string_lossy().into_owned().into_bytes());
+
+    for &byte in bytes.iter() {
+        if byte == b'%' || byte < 0x20 || byte == 0x7F {
+            out.extend_from_slice(format!("%{:02X}", byte).as_bytes());
+        } else {
+            out.push(byte);
+        }
+    }
+}
+
+/// Inverse of `escape_path()`.
+fn unescape_path(field: &[u8]) -> Result<PathBuf, WalkError> {
+    let mut bytes: Vec<u8> = Vec::with_capacity(field.len());
+    let mut index: usize = 0;
+    while index < field.len() {
+        if field[index] == b'%' {
+            let byte = field
+                .get(index + 1..index + 3)
+                .filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+                .and_then(|hex| std::str::from_utf8(hex).ok())
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                .ok_or_else(|| snapshot_format_error("bad path escape"))?;
+            bytes.push(byte);
+            index += 3;
+        } else {
+            bytes.push(field[index]);
+            index += 1;
+        }
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        Ok(PathBuf::from(std::ffi::OsStr::from_bytes(&bytes)))
+    }
+    #[cfg(not(unix))]
+    {
+        String::from_utf8(bytes)
+            .map(PathBuf::from)
+            .map_err(|_| snapshot_format_error("path is not UTF-8"))
+    }
+}
+
+// ============================================================================
+// STARTUP RECONCILIATION
+// ============================================================================
+
+/// Result of `reconcile()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReconcileReport {
+    /// Changes since the persisted snapshot, renames paired
+    /// (`diff_with_renames()`). Everything present is `added` when no
+    /// snapshot could be loaded.
+    pub changes: ChangeSummary,
+
+    /// Fresh capture of the directory, to persist for the next run.
+    pub snapshot: DirectorySnapshot,
+
+    /// Whether a valid persisted snapshot was loaded. `false` on a first
+    /// run (empty input) or when the persisted data was corrupt or of
+    /// another version.
+    pub previous_loaded: bool,
+}
+
+/// The cold-start sequence in one call: load the snapshot persisted by
+/// the last run, walk `path`, and diff.
+///
+/// Unreadable snapshot *data* (missing, truncated by a crash, foreign
+/// version) is not an error: the run proceeds as a first run and every
+/// entry is reported as added. Only a failing reader or a fatal walk
+/// error is returned as `Err`.
+///
+/// # Arguments
+/// * `snapshot` - Persisted snapshot (`DirectorySnapshot::write_to()`
+///   output); pass `&mut std::io::empty()` on a first run
+/// * `path` - Directory to reconcile
+/// * `config` - Walk bounds and filters; should match those of the
+///   persisted capture, or filter changes show up as adds/removes
+///
+/// # Returns
+/// * `Ok(ReconcileReport)` - Changes plus the refreshed snapshot
+/// * `Err(WalkError::IoError)` - Reading the snapshot failed
+/// * `Err(WalkError)` - Fatal walk error (strict mode only)
+///
+/// # Project Context
+/// On startup the messaging app loads only what changed while it was
+/// down, then persists `report.snapshot` for the next start.
+pub fn reconcile(
+    snapshot: &mut impl Read,
+    path: &Path,
+    config: WalkConfig,
+) -> Result<ReconcileReport, WalkError> {
+    let (previous, previous_loaded) = match DirectorySnapshot::read_from(snapshot) {
+        Ok(previous) => (previous, true),
+        Err(WalkError::SnapshotFormat) => (DirectorySnapshot::new(), false),
+        Err(e) => return Err(e),
+    };
+
+    let current = DirectorySnapshot::capture(path, config)?;
+
+    Ok(ReconcileReport {
+        changes: previous.diff_with_renames(&current),
+        snapshot: current,
+        previous_loaded,
+    })
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    /// Helper: unique test directory path (caller cleans up).
+    fn test_dir(name: &str) -> PathBuf {
+        crate::dir_walk_module::testing::unique_test_dir(&format!("snapshot_{}", name))
+    }
+
+    /// Helper: safely remove test directory, ignoring errors.
+    fn cleanup(path: &Path) {
+        let _ = fs::remove_dir_all(path);
+    }
+
+    /// Test: diff reports added, removed and modified files with paths
+    /// relative to the root, and nothing for an unchanged tree.
+    #[test]
+    fn test_snapshot_diff_reports_changes() {
+        let dir = test_dir("diff");
+        cleanup(&dir);
+
+        assert!(
+            fs::create_dir_all(dir.join("sub")).is_ok()
+                && fs::write(dir.join("1__msg.toml"), b"one").is_ok()
+                && fs::write(dir.join("2__msg.toml"), b"two").is_ok()
+                && fs::write(dir.join("sub").join("3__msg.toml"), b"three").is_ok(),
+            "test_snapshot_diff: failed to create tree"
+        );
+
+        let before = DirectorySnapshot::capture(&dir, WalkConfig::new());
+        assert!(before.is_ok(), "test_snapshot_diff: first capture failed");
+        let Ok(before) = before else {
+            return;
+        };
+        assert_eq!(
+            before.len(),
+            4,
+            "test_snapshot_diff: expected 3 files + 1 dir"
+        );
+
+        let unchanged = DirectorySnapshot::capture(&dir, WalkConfig::new());
+        assert!(
+            unchanged.as_ref().is_ok_and(|s| before.diff(s).is_empty()),
+            "test_snapshot_diff: unchanged tree should produce an empty diff"
+        );
+
+        assert!(
+            fs::remove_file(dir.join("1__msg.toml")).is_ok()
+                && fs::write(dir.join("2__msg.toml"), b"two, edited").is_ok()
+                && fs::write(dir.join("sub").join("4__msg.toml"), b"four").is_ok(),
+            "test_snapshot_diff: failed to modify tree"
+        );
+
+        let after = DirectorySnapshot::capture(&dir, WalkConfig::new());
+        assert!(after.is_ok(), "test_snapshot_diff: second capture failed");
+        let Ok(after) = after else {
+            return;
+        };
+
+        let summary = before.diff(&after);
+        assert_eq!(
+            summary.added,
+            vec![Path::new("sub").join("4__msg.toml")],
+            "test_snapshot_diff: added"
+        );
+        assert_eq!(
+            summary.removed,
+            vec![PathBuf::from("1__msg.toml")],
+            "test_snapshot_diff: removed"
+        );
+        assert_eq!(
+            summary.modified,
+            vec![PathBuf::from("2__msg.toml")],
+            "test_snapshot_diff: modified (directory 'sub' must not be reported)"
+        );
+        assert_eq!(summary.len(), 3, "test_snapshot_diff: total");
+
+        cleanup(&dir);
+    }
+
+    /// Test: a file growing past the size limit is dropped from both
+    /// snapshots, so the diff stays empty; directories are always kept.
+    #[test]
+    fn test_ignore_files_larger_than() {
+        let dir = test_dir("size_limit");
+        cleanup(&dir);
+        assert!(
+            fs::create_dir_all(dir.join("sub")).is_ok()
+                && fs::write(dir.join("1__msg.toml"), b"one").is_ok()
+                && fs::write(dir.join("upload.bin"), [0_u8; 64]).is_ok(),
+            "test_ignore_files_larger_than: failed to create tree"
+        );
+
+        let capture = || {
+            let mut snapshot = DirectorySnapshot::capture(&dir, WalkConfig::new()).ok()?;
+            let dropped = snapshot.ignore_files_larger_than(16);
+            Some((snapshot, dropped))
+        };
+        let before = capture();
+        assert!(
+            fs::write(dir.join("upload.bin"), [0_u8; 128]).is_ok(),
+            "test_ignore_files_larger_than: failed to grow file"
+        );
+        let after = capture();
+
+        assert!(
+            before.is_some() && after.is_some(),
+            "test_ignore_files_larger_than: capture failed"
+        );
+        let (Some((before, dropped)), Some((after, _))) = (before, after) else {
+            return;
+        };
+        assert_eq!(
+            (dropped, before.len()),
+            (1, 2),
+            "test_ignore_files_larger_than: only the large file is dropped"
+        );
+        assert!(
+            before.diff(&after).is_empty(),
+            "test_ignore_files_larger_than: growing excluded file was reported"
+        );
+
+        cleanup(&dir);
+    }
+
+    /// Test: a chmod-only change is reported by diff_with() only when
+    /// permissions are included, for files and directories alike.
+    #[cfg(unix)]
+    #[test]
+    fn test_diff_include_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = test_dir("permissions");
+        cleanup(&dir);
+        let file = dir.join("1__msg.toml");
+        let sub = dir.join("sub");
+        assert!(
+            fs::create_dir_all(&sub).is_ok() && fs::write(&file, b"msg").is_ok(),
+            "test_diff_include_permissions: failed to create tree"
+        );
+
+        let before = DirectorySnapshot::capture(&dir, WalkConfig::new());
+        let chmodded = fs::set_permissions(&file, fs::Permissions::from_mode(0o600)).is_ok()
+            && fs::set_permissions(&sub, fs::Permissions::from_mode(0o700)).is_ok();
+        assert!(chmodded, "test_diff_include_permissions: chmod failed");
+        let after = DirectorySnapshot::capture(&dir, WalkConfig::new());
+        assert!(
+            before.is_ok() && after.is_ok(),
+            "test_diff_include_permissions: capture failed"
+        );
+        let (Ok(before), Ok(after)) = (before, after) else {
+            return;
+        };
+
+        assert!(
+            before.diff(&after).is_empty(),
+            "test_diff_include_permissions: ignored by default"
+        );
+        let summary = before.diff_with(&after, &DiffOptions::new().include_permissions(true));
+        assert_eq!(
+            summary.modified,
+            vec![PathBuf::from("1__msg.toml"), PathBuf::from("sub")],
+            "test_diff_include_permissions: chmod reported when included"
+        );
+
+        cleanup(&dir);
+    }
+
+    /// Test: a captured snapshot survives a write/read round trip
+    /// (including names with spaces, `%` and newlines), and truncated or
+    /// foreign data is refused.
+    #[test]
+    fn test_snapshot_persistence_round_trip() {
+        let dir = test_dir("persist");
+        cleanup(&dir);
+        assert!(
+            fs::create_dir_all(dir.join("sub dir")).is_ok()
+                && fs::write(dir.join("1__msg.toml"), b"one").is_ok()
+                && fs::write(dir.join("sub dir").join("50% off\nline.toml"), b"x").is_ok(),
+            "test_snapshot_persistence: failed to create tree"
+        );
+
+        let captured = DirectorySnapshot::capture(&dir, WalkConfig::new());
+        assert!(
+            captured.is_ok(),
+            "test_snapshot_persistence: capture failed"
+        );
+        let Ok(mut captured) = captured else {
+            return;
+        };
+        // Also cover unknown mtime/inode and a pre-epoch time
+        captured.entries.insert(
+            PathBuf::from("synthetic"),
+            SnapshotEntry {
+                is_dir: false,
+                len: 7,
+                modified: UNIX_EPOCH.checked_sub(Duration::new(5, 250)),
+                inode: None,
+                permissions: Some(0o4755),
+            },
+        );
+        captured.entries.insert(
+            PathBuf::from("no_mtime"),
+            SnapshotEntry {
+                is_dir: true,
+                len: 0,
+                modified: None,
+                inode: None,
+                permissions: None,
+            },
+        );
+
+        let mut written: Vec<u8> = Vec::new();
+        assert!(
+            captured.write_to(&mut written).is_ok(),
+            "test_snapshot_persistence: write failed"
+        );
+        assert_eq!(
+            written.iter().filter(|b| **b == b'\n').count(),
+            captured.len() + 2,
+            "test_snapshot_persistence: one line per entry plus header and end"
+        );
+        assert_eq!(
+            DirectorySnapshot::read_from(&mut written.as_slice()),
+            Ok(captured.clone()),
+            "test_snapshot_persistence: round trip"
+        );
+
+        // Truncated (no end line), wrong version, wrong count
+        let truncated = &written[..written.len() - 4];
+        assert_eq!(
+            DirectorySnapshot::read_from(&mut &truncated[..]),
+            Err(WalkError::SnapshotFormat),
+            "test_snapshot_persistence: truncated data"
+        );
+        assert_eq!(
+            DirectorySnapshot::read_from(&mut &b"dwm-snapshot 3\nend 0\n"[..]),
+            Err(WalkError::SnapshotFormat),
+            "test_snapshot_persistence: unknown version"
+        );
+        assert_eq!(
+            DirectorySnapshot::read_from(&mut &b"dwm-snapshot 1\nf 1 - - a\nend 2\n"[..]),
+            Err(WalkError::SnapshotFormat),
+            "test_snapshot_persistence: count mismatch"
+        );
+        assert_eq!(
+            DirectorySnapshot::read_from(&mut &b"dwm-snapshot 1\nf 1 - - a b\nend 1\n"[..])
+                .map(|legacy| legacy.get(Path::new("a b")).copied()),
+            Ok(Some(SnapshotEntry {
+                is_dir: false,
+                len: 1,
+                modified: None,
+                inode: None,
+                permissions: None,
+            })),
+            "test_snapshot_persistence: version 1 still readable"
+        );
+
+        cleanup(&dir);
+    }
+
+    /// Test: reconcile reports changes since a persisted snapshot, and
+    /// treats missing or corrupt snapshot data as a first run.
+    #[test]
+    fn test_reconcile_since_persisted_snapshot() {
+        let dir = test_dir("reconcile");
+        cleanup(&dir);
+        assert!(
+            fs::create_dir_all(&dir).is_ok()
+                && fs::write(dir.join("1__msg.toml"), b"one").is_ok()
+                && fs::write(dir.join("2__msg.toml"), b"two").is_ok(),
+            "test_reconcile: failed to create tree"
+        );
+
+        let first = reconcile(&mut std::io::empty(), &dir, WalkConfig::new());
+        assert_eq!(
+            first
+                .as_ref()
+                .map(|r| (r.previous_loaded, r.changes.added.len())),
+            Ok((false, 2)),
+            "test_reconcile: first run reports everything as added"
+        );
+
+        let mut persisted: Vec<u8> = Vec::new();
+        assert!(
+            first
+                .as_ref()
+                .is_ok_and(|r| r.snapshot.write_to(&mut persisted).is_ok()),
+            "test_reconcile: persist failed"
+        );
+
+        // While the app is down: one message removed, one added
+        assert!(
+            fs::remove_file(dir.join("1__msg.toml")).is_ok()
+                && fs::write(dir.join("3__msg.toml"), b"three, new").is_ok(),
+            "test_reconcile: failed to modify tree"
+        );
+
+        let second = reconcile(&mut persisted.as_slice(), &dir, WalkConfig::new());
+        assert_eq!(
+            second.as_ref().map(|r| (
+                r.previous_loaded,
+                r.changes.added.clone(),
+                r.changes.removed.clone()
+            )),
+            Ok((
+                true,
+                vec![PathBuf::from("3__msg.toml")],
+                vec![PathBuf::from("1__msg.toml")]
+            )),
+            "test_reconcile: changes since last run"
+        );
+        assert_eq!(
+            second.as_ref().map(|r| r.snapshot.len()),
+            Ok(2),
+            "test_reconcile: refreshed snapshot"
+        );
+
+        let corrupt = reconcile(&mut &persisted[..10], &dir, WalkConfig::new());
+        assert_eq!(
+            corrupt.map(|r| (r.previous_loaded, r.changes.added.len())),
+            Ok((false, 2)),
+            "test_reconcile: corrupt snapshot is a first run"
+        );
+
+        cleanup(&dir);
+    }
+
+    /// Test: a renumbered message is reported as a rename, while a
+    /// genuinely new file and a deleted file are not paired.
+    #[test]
+    fn test_diff_with_renames_pairs_moves() {
+        let dir = test_dir("renames");
+        cleanup(&dir);
+        assert!(
+            fs::create_dir_all(&dir).is_ok()
+                && fs::write(dir.join("3__x.toml"), b"message three").is_ok()
+                && fs::write(dir.join("old.toml"), b"deleted").is_ok(),
+            "test_diff_with_renames: failed to create tree"
+        );
+
+        let before = DirectorySnapshot::capture(&dir, WalkConfig::new());
+        assert!(
+            before.is_ok(),
+            "test_diff_with_renames: first capture failed"
+        );
+        let Ok(before) = before else {
+            return;
+        };
+
+        assert!(
+            fs::rename(dir.join("3__x.toml"), dir.join("4__x.toml")).is_ok()
+                && fs::remove_file(dir.join("old.toml")).is_ok()
+                && fs::write(dir.join("new.toml"), b"brand new message").is_ok(),
+            "test_diff_with_renames: failed to modify tree"
+        );
+
+        let after = DirectorySnapshot::capture(&dir, WalkConfig::new());
+        assert!(
+            after.is_ok(),
+            "test_diff_with_renames: second capture failed"
+        );
+        let Ok(after) = after else {
+            return;
+        };
+
+        let plain = before.diff(&after);
+        assert!(
+            plain.renamed.is_empty() && plain.removed.len() == 2 && plain.added.len() == 2,
+            "test_diff_with_renames: plain diff must not pair renames"
+        );
+
+        let summary = before.diff_with_renames(&after);
+        assert_eq!(
+            summary.renamed,
+            vec![Rename {
+                from: PathBuf::from("3__x.toml"),
+                to: PathBuf::from("4__x.toml"),
+            }],
+            "test_diff_with_renames: rename not detected"
+        );
+        assert_eq!(
+            summary.renamed[0].to_string(),
+            "3__x.toml renamed to 4__x.toml",
+            "test_diff_with_renames: display format"
+        );
+        assert_eq!(
+            summary.removed,
+            vec![PathBuf::from("old.toml")],
+            "test_diff_with_renames: unpaired removal kept"
+        );
+        assert_eq!(
+            summary.added,
+            vec![PathBuf::from("new.toml")],
+            "test_diff_with_renames: unpaired addition kept"
+        );
+        assert_eq!(summary.len(), 3, "test_diff_with_renames: total");
+
+        cleanup(&dir);
+    }
+}
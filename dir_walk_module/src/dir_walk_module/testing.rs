@@ -0,0 +1,556 @@
+//! # Test and Demo Fixtures (`dir_walk_module::testing`)
+//!
+//! Helpers for code that builds throwaway directory trees: the module's
+//! own tests, the demo binary, and tests of projects that vendor the
+//! module.
+//!
+//! ## Project Context
+//! Fixtures used to live at `<temp>/dwm_test_{name}`. Two runs of the
+//! same test binary at once (`cargo test` in two worktrees, CI jobs
+//! sharing a runner) then built, walked and deleted the *same* trees,
+//! and each run's cleanup broke the other's assertions. Names from
+//! `unique_test_dir()` carry the process id and a per-process counter,
+//! so no two fixtures ever share a path.
+//!
+//! `TempTreeGuard` removes a fixture when it goes out of scope, including
+//! on early returns, so demos no longer leave trees in the temp directory
+//! unless asked to keep them.
+//!
+//! The adversarial generators (`wide_dir`, `deep_chain`, `symlink_farm`,
+//! `symlink_cycle`, `hostile_names`, `adversarial_corpus`) build the
+//! pathological trees the walker's bounds exist for. They are compiled for
+//! this crate's tests and, in other builds, only with the `testing` Cargo
+//! feature, so projects vendoring the module regression-test their own
+//! limits against the same corpus.
+//!
+//! `link_dir` creates a directory link that works on Windows runners
+//! without the symlink privilege: it falls back to an NTFS junction
+//! (`junction`), which any user may create and which the walker treats as
+//! a symlink. Cycle and link tests built on it run instead of skipping.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Prefix of every fixture directory name, so leftovers are easy to find.
+const FIXTURE_PREFIX: &str = "dwm_test";
+
+/// Fixtures handed out so far by this process.
+static FIXTURE_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// A fresh fixture path under the system temp directory.
+///
+/// The path is `<temp>/dwm_test_{name}_{pid}_{n}`, where `n` counts the
+/// calls made by this process. Nothing is created: the caller makes the
+/// directory and removes it afterwards. Within one process the result is
+/// deterministic (call order decides `n`); across processes the pid keeps
+/// paths apart.
+///
+/// # Arguments
+/// * `name` - Readable part of the name, e.g. the test's name
+///
+/// # Returns
+/// A path no other call (in this or any concurrently running process)
+/// returns.
+pub fn unique_test_dir(name: &str) -> PathBuf {
+    let n = FIXTURE_COUNTER.fetch_add(1, Ordering::Relaxed);
+    std::env::temp_dir().join(format!(
+        "{}_{}_{}_{}",
+        FIXTURE_PREFIX,
+        name,
+        std::process::id(),
+        n
+    ))
+}
+
+// ============================================================================
+// CLEANUP GUARD
+// ============================================================================
+
+/// Owns a fixture directory and removes it (recursively) when dropped.
+///
+/// Removal is best-effort and never panics: a tree that cannot be fully
+/// removed is left behind, and the failure is logged in debug builds.
+/// The guard does not create the directory.
+///
+/// ## Example
+/// ```rust,no_run
+/// # use crate::dir_walk_module::testing::{TempTreeGuard, unique_test_dir};
+/// let fixture = TempTreeGuard::new(unique_test_dir("demo"));
+/// std::fs::create_dir_all(fixture.path())?;
+/// // ... build and walk the tree; it is removed at the end of scope
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct TempTreeGuard {
+    /// Directory removed on drop.
+    path: PathBuf,
+
+    /// When `true`, the directory is left in place on drop.
+    keep: bool,
+}
+
+impl TempTreeGuard {
+    /// Guard `path`; it is removed when the guard is dropped.
+    ///
+    /// # Arguments
+    /// * `path` - Fixture directory (need not exist yet)
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        TempTreeGuard {
+            path: path.into(),
+            keep: false,
+        }
+    }
+
+    /// Set whether the directory is kept on drop (default `false`).
+    ///
+    /// # Arguments
+    /// * `keep` - `true` for manual inspection after a demo or a failing
+    ///   test (e.g. from a `--keep` flag)
+    pub fn keep(mut self, keep: bool) -> Self {
+        self.keep = keep;
+        self
+    }
+
+    /// Whether the directory will be kept on drop.
+    pub fn is_kept(&self) -> bool {
+        self.keep
+    }
+
+    /// The guarded directory.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempTreeGuard {
+    fn drop(&mut self) {
+        if self.keep {
+            return;
+        }
+        match fs::remove_dir_all(&self.path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(_e) => {
+                #[cfg(debug_assertions)]
+                eprintln!("DWIO: fixture directory not fully removed: {}", _e);
+            }
+        }
+    }
+}
+
+// ============================================================================
+// ADVERSARIAL TREES (feature `testing`)
+// ============================================================================
+
+/// Entries in the directory built by `adversarial_corpus()`'s `wide_dir`.
+#[cfg(any(test, feature = "testing"))]
+pub const WIDE_DIR_ENTRIES: usize = 10_000;
+
+/// Levels of the chain built by `adversarial_corpus()`'s `deep_chain`.
+#[cfg(any(test, feature = "testing"))]
+pub const DEEP_CHAIN_LEVELS: usize = 1_000;
+
+/// Links in the farm built by `adversarial_corpus()`'s `symlink_farm`.
+#[cfg(any(test, feature = "testing"))]
+pub const SYMLINK_FARM_LINKS: usize = 1_000;
+
+/// Paths of one corpus built by `adversarial_corpus()`.
+///
+/// The link-based trees are `None` where symlinks cannot be created
+/// (Windows without the symlink privilege, for instance).
+#[cfg(any(test, feature = "testing"))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AdversarialCorpus {
+    /// Directory holding `WIDE_DIR_ENTRIES` files.
+    pub wide: PathBuf,
+
+    /// Top of a `DEEP_CHAIN_LEVELS`-deep chain.
+    pub deep: PathBuf,
+
+    /// `SYMLINK_FARM_LINKS` links into one target directory.
+    pub farm: Option<PathBuf>,
+
+    /// Tree whose links loop back on themselves.
+    pub cycle: Option<PathBuf>,
+
+    /// Directory of hostile file names, and how many were created.
+    pub names: (PathBuf, usize),
+}
+
+/// Create a directory of `width` empty files (`0__wide.toml`,
+/// `1__wide.toml`, ...).
+///
+/// # Arguments
+/// * `root` - Existing parent; the tree is created at `root/wide`
+/// * `width` - Files to create (`WIDE_DIR_ENTRIES` in the corpus)
+///
+/// # Returns
+/// The wide directory.
+#[cfg(any(test, feature = "testing"))]
+pub fn wide_dir(root: &Path, width: usize) -> io::Result<PathBuf> {
+    let dir = root.join("wide");
+    fs::create_dir_all(&dir)?;
+    for index in 0..width {
+        fs::File::create(dir.join(format!("{}__wide.toml", index)))?;
+    }
+    Ok(dir)
+}
+
+/// Create a chain of `levels` nested directories (`d/d/d/...`) with one
+/// file, `1__bottom.toml`, in the deepest.
+///
+/// Names are one byte so 1,000 levels stay under Linux's 4,096-byte
+/// `PATH_MAX`; on Windows, paths past 260 characters need long-path
+/// support enabled.
+///
+/// # Arguments
+/// * `root` - Existing parent; the chain starts at `root/deep`
+/// * `levels` - Directories below `root/deep` (`DEEP_CHAIN_LEVELS` in the
+///   corpus)
+///
+/// # Returns
+/// The top of the chain (`root/deep`).
+#[cfg(any(test, feature = "testing"))]
+pub fn deep_chain(root: &Path, levels: usize) -> io::Result<PathBuf> {
+    let top = root.join("deep");
+    let mut bottom = top.clone();
+    for _ in 0..levels {
+        bottom.push("d");
+    }
+    fs::create_dir_all(&bottom)?;
+    fs::write(bottom.join("1__bottom.toml"), b"bottom")?;
+    Ok(top)
+}
+
+/// Create `links` symlinks to one target directory (which holds a single
+/// file), alternating with links to that file.
+///
+/// A walk following symlinks reads the target once per directory link,
+/// so this tests `max_dirs`, `max_queue_size` and visited-set handling.
+///
+/// # Arguments
+/// * `root` - Existing parent; the farm is `root/farm`, the target
+///   `root/farm_target`
+/// * `links` - Links to create (`SYMLINK_FARM_LINKS` in the corpus)
+///
+/// # Returns
+/// The farm directory, or the error from the first link that could not be
+/// created (`Unsupported` on platforms without symlinks).
+#[cfg(any(test, feature = "testing"))]
+pub fn symlink_farm(root: &Path, links: usize) -> io::Result<PathBuf> {
+    let farm = root.join("farm");
+    let target = root.join("farm_target");
+    fs::create_dir_all(&farm)?;
+    fs::create_dir_all(&target)?;
+    let target_file = target.join("1__target.toml");
+    fs::write(&target_file, b"target")?;
+    for index in 0..links {
+        if index % 2 == 0 {
+            link_dir(&target, &farm.join(format!("dir_link_{}", index)))?;
+        } else {
+            symlink_file(&target_file, &farm.join(format!("{}__link.toml", index)))?;
+        }
+    }
+    Ok(farm)
+}
+
+/// Create a tree whose links loop: `cycle/a/b/up -> cycle` (a directory
+/// cycle), `cycle/self -> cycle/.`, and `cycle/ping <-> cycle/pong` (links
+/// to each other, which never resolve).
+///
+/// # Arguments
+/// * `root` - Existing parent; the tree is `root/cycle`
+///
+/// # Returns
+/// The `cycle` directory, or the first link error (`Unsupported` on
+/// platforms without symlinks).
+#[cfg(any(test, feature = "testing"))]
+pub fn symlink_cycle(root: &Path) -> io::Result<PathBuf> {
+    let top = root.join("cycle");
+    let inner = top.join("a").join("b");
+    fs::create_dir_all(&inner)?;
+    fs::write(inner.join("1__inner.toml"), b"inner")?;
+    link_dir(&top, &inner.join("up"))?;
+    link_dir(&top.join("."), &top.join("self"))?;
+    symlink_file(&top.join("pong"), &top.join("ping"))?;
+    symlink_file(&top.join("ping"), &top.join("pong"))?;
+    Ok(top)
+}
+
+/// Create one file per hostile name: leading dash, spaces, tab, control
+/// characters, right-to-left override, a 255-byte name, and (unix)
+/// newlines and invalid UTF-8, or (Windows) an unpaired UTF-16 surrogate.
+///
+/// Names the filesystem rejects are skipped, so the count varies by
+/// platform.
+///
+/// # Arguments
+/// * `root` - Existing parent; the files go in `root/names`
+///
+/// # Returns
+/// The directory and the number of files created in it.
+#[cfg(any(test, feature = "testing"))]
+pub fn hostile_names(root: &Path) -> io::Result<(PathBuf, usize)> {
+    use std::ffi::OsString;
+
+    let dir = root.join("names");
+    fs::create_dir_all(&dir)?;
+
+    let mut names: Vec<OsString> = [
+        "-rf".to_string(),
+        " leading and trailing space ".to_string(),
+        "tab\there".to_string(),
+        "bell\u{7}escape\u{1b}[31m".to_string(),
+        "rtl\u{202e}lmth.exe".to_string(),
+        "ünïcødé".to_string(),
+        "n".repeat(255),
+    ]
+    .into_iter()
+    .map(OsString::from)
+    .collect();
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStringExt;
+        names.push(OsString::from("new\nline"));
+        names.push(OsString::from("\n"));
+        names.push(OsString::from_vec(vec![b'b', 0xFF, 0xFE, b'd']));
+        names.push(OsString::from_vec(vec![0xC0, 0xAF]));
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::ffi::OsStringExt;
+        names.push(OsString::from_wide(&[0x0062, 0xD800, 0x0064]));
+    }
+
+    let created = names
+        .iter()
+        .filter(|name| fs::write(dir.join(name), b"x").is_ok())
+        .count();
+    Ok((dir, created))
+}
+
+/// Build every adversarial tree under `root` at corpus sizes
+/// (`WIDE_DIR_ENTRIES`, `DEEP_CHAIN_LEVELS`, `SYMLINK_FARM_LINKS`).
+///
+/// Link trees that cannot be created are `None`; any other failure is an
+/// error. Building takes a moment (about 11,000 files and links).
+///
+/// # Arguments
+/// * `root` - Directory to build in (created if missing), e.g. a
+///   `TempTreeGuard` path
+#[cfg(any(test, feature = "testing"))]
+pub fn adversarial_corpus(root: &Path) -> io::Result<AdversarialCorpus> {
+    fs::create_dir_all(root)?;
+    Ok(AdversarialCorpus {
+        wide: wide_dir(root, WIDE_DIR_ENTRIES)?,
+        deep: deep_chain(root, DEEP_CHAIN_LEVELS)?,
+        farm: symlink_farm(root, SYMLINK_FARM_LINKS).ok(),
+        cycle: symlink_cycle(root).ok(),
+        names: hostile_names(root)?,
+    })
+}
+
+/// Create a link to a directory: a symlink, or on Windows without the
+/// symlink privilege, a junction.
+///
+/// # Arguments
+/// * `target` - Existing directory (absolute, for the junction fallback)
+/// * `link` - Path of the new link
+///
+/// # Returns
+/// `Ok(())` once a link exists; `Unsupported` on platforms without links.
+#[cfg(any(test, feature = "testing"))]
+pub fn link_dir(target: &Path, link: &Path) -> io::Result<()> {
+    #[cfg(unix)]
+    return std::os::unix::fs::symlink(target, link);
+    #[cfg(windows)]
+    return std::os::windows::fs::symlink_dir(target, link).or_else(|_e| junction(target, link));
+    #[cfg(not(any(unix, windows)))]
+    {
+        let _ = (target, link);
+        Err(io::Error::from(io::ErrorKind::Unsupported))
+    }
+}
+
+/// Create a symlink to a file; `Unsupported` where symlinks do not exist.
+///
+/// Files have no junction equivalent, so on Windows this needs the
+/// symlink privilege (or Developer Mode).
+#[cfg(any(test, feature = "testing"))]
+fn symlink_file(target: &Path, link: &Path) -> io::Result<()> {
+    #[cfg(unix)]
+    return std::os::unix::fs::symlink(target, link);
+    #[cfg(windows)]
+    return std::os::windows::fs::symlink_file(target, link);
+    #[cfg(not(any(unix, windows)))]
+    {
+        let _ = (target, link);
+        Err(io::Error::from(io::ErrorKind::Unsupported))
+    }
+}
+
+/// Create an NTFS junction (directory mount point) at `link` pointing to
+/// `target`, as `mklink /J` does.
+///
+/// Unlike symlinks, junctions need no privilege, so Windows CI can always
+/// build link trees. `std` reports them as symlinks
+/// (`FileType::is_symlink`), so the walker handles both alike.
+///
+/// # Arguments
+/// * `target` - Existing local directory, absolute
+/// * `link` - Path of the new junction (must not exist)
+///
+/// # Returns
+/// `Ok(())` if `mklink` succeeded; an error naming its exit status
+/// otherwise.
+#[cfg(all(windows, any(test, feature = "testing")))]
+pub fn junction(target: &Path, link: &Path) -> io::Result<()> {
+    let status = std::process::Command::new("cmd")
+        .arg("/C")
+        .arg("mklink")
+        .arg("/J")
+        .arg(link)
+        .arg(target)
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!("mklink /J failed: {}", status)))
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test: repeated calls with one name never collide, and the name and
+    /// process id are part of the path.
+    #[test]
+    fn test_unique_test_dir() {
+        let first = unique_test_dir("same");
+        let second = unique_test_dir("same");
+        assert_ne!(first, second, "test_unique_test_dir: same name collided");
+
+        let file_name = first
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or_default()
+            .to_string();
+        let pid = std::process::id().to_string();
+        assert!(
+            file_name.starts_with("dwm_test_same_") && file_name.contains(pid.as_str()),
+            "test_unique_test_dir: unexpected name {}",
+            file_name
+        );
+        assert_eq!(
+            first.parent(),
+            Some(std::env::temp_dir().as_path()),
+            "test_unique_test_dir: not under the temp dir"
+        );
+    }
+
+    /// Test: a guard removes its tree on drop (also when the tree never
+    /// existed), and a kept guard leaves it in place.
+    #[test]
+    fn test_temp_tree_guard() {
+        let removed = unique_test_dir("guard_removed");
+        {
+            let guard = TempTreeGuard::new(&removed);
+            assert!(
+                fs::create_dir_all(guard.path().join("sub")).is_ok()
+                    && fs::write(guard.path().join("sub").join("1__a.toml"), b"a").is_ok(),
+                "test_temp_tree_guard: failed to create tree"
+            );
+        }
+        assert!(!removed.exists(), "test_temp_tree_guard: tree not removed");
+
+        drop(TempTreeGuard::new(unique_test_dir("guard_missing")));
+
+        let kept = unique_test_dir("guard_kept");
+        {
+            let guard = TempTreeGuard::new(&kept).keep(true);
+            assert!(
+                guard.is_kept() && fs::create_dir_all(guard.path()).is_ok(),
+                "test_temp_tree_guard: failed to create kept tree"
+            );
+        }
+        assert!(kept.is_dir(), "test_temp_tree_guard: kept tree removed");
+        let _ = fs::remove_dir_all(&kept);
+    }
+
+    /// Test: the walker stays within its bounds over the adversarial
+    /// corpus: the wide directory is capped by `max_entries_per_dir`, the
+    /// deep chain by `max_depth` (and walks to the bottom without one),
+    /// followed links terminate, and hostile names all come back.
+    #[test]
+    fn test_adversarial_corpus_bounds() {
+        use crate::dir_walk_module::{DirWalker, WalkConfig};
+
+        let guard = TempTreeGuard::new(unique_test_dir("adversarial"));
+        let corpus = adversarial_corpus(guard.path());
+        assert!(
+            corpus.is_ok(),
+            "test_adversarial_corpus: failed to build: {:?}",
+            corpus.err()
+        );
+        let Ok(corpus) = corpus else { return };
+
+        let count = |dir: &Path, config: WalkConfig| {
+            DirWalker::new(dir, config).filter_map(|r| r.ok()).count()
+        };
+        assert_eq!(
+            count(&corpus.wide, WalkConfig::new()),
+            WIDE_DIR_ENTRIES,
+            "test_adversarial_corpus: wide dir, default limits"
+        );
+        assert_eq!(
+            count(&corpus.wide, WalkConfig::new().max_entries_per_dir(100)),
+            100,
+            "test_adversarial_corpus: wide dir capped"
+        );
+
+        assert_eq!(
+            count(&corpus.deep, WalkConfig::new()),
+            DEEP_CHAIN_LEVELS + 1,
+            "test_adversarial_corpus: deep chain walked to the bottom"
+        );
+        assert_eq!(
+            count(&corpus.deep, WalkConfig::new().max_depth(9)),
+            10,
+            "test_adversarial_corpus: deep chain capped by max_depth"
+        );
+
+        if cfg!(unix) {
+            assert!(
+                corpus.farm.is_some() && corpus.cycle.is_some(),
+                "test_adversarial_corpus: links not created on unix"
+            );
+        }
+        for links in [&corpus.farm, &corpus.cycle].into_iter().flatten() {
+            let followed = count(links, WalkConfig::new().follow_symlinks(true).max_depth(20));
+            assert!(
+                followed > 0 && followed < 100_000,
+                "test_adversarial_corpus: {:?} yielded {}",
+                links,
+                followed
+            );
+        }
+
+        let (names_dir, created) = &corpus.names;
+        assert!(*created >= 7, "test_adversarial_corpus: names created");
+        assert_eq!(
+            count(names_dir, WalkConfig::new()),
+            *created,
+            "test_adversarial_corpus: every hostile name yielded"
+        );
+    }
+}
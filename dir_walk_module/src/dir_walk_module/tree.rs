@@ -0,0 +1,926 @@
+//! # In-Memory Tree (`dir_walk_module::tree`)
+//!
+//! A walk collected into a navigable tree: `build_tree()` records every
+//! yielded entry as a node, with parent and child links, and indexes the
+//! nodes by their path relative to the root.
+//!
+//! ## Project Context
+//! The UI's folder pane expands and collapses directories, jumps to a
+//! parent, and selects a path typed by the user. A flat entry stream
+//! answers none of these without rescanning or re-sorting; `DirTree`
+//! answers each with an index lookup:
+//!
+//! ```ignore
+//! let tree = build_tree(&channel_dir, WalkConfig::channel_scan())?;
+//! if let Some(folder) = tree.lookup(Path::new("attachments")) {
+//!     for &child in tree.children(folder) {
+//!         render(tree.node(child));
+//!     }
+//! }
+//! ```
+//!
+//! ## Layout
+//! Nodes live in one `Vec` and refer to each other by `NodeId` (an index
+//! into it), never by nesting. Building, traversing (`descendants()`)
+//! and dropping a tree therefore use no recursion, so a pathologically
+//! deep directory chain cannot overflow the stack.
+//!
+//! ## Bounds
+//! The walk honors every bound and filter of the given `WalkConfig`
+//! (directories are always yielded, since they are the tree's inner
+//! nodes). On top of those, a tree holds at most `MAX_TREE_NODES` nodes;
+//! a walk that finds more, or that a budget pauses, stops there and the
+//! tree is marked `is_truncated()`.
+//!
+//! ## Keeping Current
+//! A tree is built once and then follows the watcher: each `ChangeEvent`
+//! of a `ScanScheduler` (or `ChangeSummary` of a `ChannelMonitor`)
+//! watching the same root is applied with `DirTree::apply_event()`,
+//! which inserts, removes, moves and re-stats only the changed nodes:
+//!
+//! ```ignore
+//! let mut tree = build_tree(&channel_dir, config.clone())?;
+//! let channel_id = scheduler.register(&channel_dir, config);
+//! let poller = scheduler.spawn_polling(events_tx, poll_interval, budget)?;
+//! for event in events_rx {
+//!     if event.channel_id == channel_id {
+//!         tree.apply_event(&event);
+//!         redraw(&tree);
+//!     }
+//! }
+//! ```
+
+use std::collections::HashMap;
+use std::ffi::{OsStr, OsString};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+#[cfg(not(feature = "minimal"))]
+use super::scheduler::ChangeEvent;
+#[cfg(not(feature = "minimal"))]
+use super::snapshot::ChangeSummary;
+use super::{DirEntry, DirWalker, WalkConfig, WalkError, WalkStats, normalize_root, strip_root};
+
+/// Most nodes one `DirTree` holds, the root included.
+///
+/// A node with its index key takes roughly 200 bytes plus its name
+/// twice, so the cap keeps a tree under a few hundred megabytes.
+pub const MAX_TREE_NODES: usize = 1_000_000;
+
+// ============================================================================
+// NODES
+// ============================================================================
+
+/// Handle of one node in a `DirTree`.
+///
+/// Only meaningful for the tree that returned it. Once the node is
+/// removed by a change, its id may be reused for a node inserted later:
+/// look ids up again (`DirTree::lookup()`) after applying changes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeId(usize);
+
+impl NodeId {
+    /// Position of the node in its tree's node table.
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
+/// One entry of a `DirTree`, with its metadata as the walk saw it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeNode {
+    /// File name; empty for the root.
+    name: OsString,
+
+    /// Containing directory; `None` for the root.
+    parent: Option<NodeId>,
+
+    /// Entries directly inside, in walk order.
+    children: Vec<NodeId>,
+
+    /// Distance from the root (the root is 0, its entries 1).
+    depth: usize,
+
+    is_dir: bool,
+    is_symlink: bool,
+    len: u64,
+    modified: Option<SystemTime>,
+}
+
+impl TreeNode {
+    /// Node for a walked entry.
+    fn from_entry(entry: &DirEntry, name: &OsStr, parent: NodeId, depth: usize) -> Self {
+        TreeNode {
+            name: name.to_os_string(),
+            parent: Some(parent),
+            children: Vec::new(),
+            depth,
+            is_dir: entry.is_dir(),
+            is_symlink: entry.is_symlink(),
+            len: entry.len(),
+            modified: entry.snapshot().modified,
+        }
+    }
+
+    /// Node for a path stat-ed after a change event.
+    fn from_metadata(name: &OsStr, parent: NodeId, depth: usize, metadata: &fs::Metadata) -> Self {
+        let mut node = TreeNode {
+            name: name.to_os_string(),
+            parent: Some(parent),
+            children: Vec::new(),
+            depth,
+            is_dir: false,
+            is_symlink: false,
+            len: 0,
+            modified: None,
+        };
+        node.set_metadata(metadata);
+        node
+    }
+
+    /// Replace the node's metadata with a fresh stat's.
+    fn set_metadata(&mut self, metadata: &fs::Metadata) {
+        self.is_dir = metadata.is_dir();
+        self.is_symlink = metadata.file_type().is_symlink();
+        self.len = metadata.len();
+        self.modified = metadata.modified().ok();
+    }
+
+    /// File name, if valid UTF-8 (empty for the root).
+    pub fn name(&self) -> Option<&str> {
+        self.name.to_str()
+    }
+
+    /// File name as the OS reported it (empty for the root).
+    pub fn os_name(&self) -> &OsStr {
+        &self.name
+    }
+
+    /// Containing directory's node; `None` for the root.
+    pub fn parent(&self) -> Option<NodeId> {
+        self.parent
+    }
+
+    /// Nodes directly inside this one, in walk order (sorted by name
+    /// when the walk ranked with `top_n_per_dir(.., NameAscending)`).
+    pub fn children(&self) -> &[NodeId] {
+        &self.children
+    }
+
+    /// Distance from the root: 0 for the root, 1 for its entries.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Whether the entry is a directory (the root always is).
+    pub fn is_dir(&self) -> bool {
+        self.is_dir
+    }
+
+    /// Whether the entry is a symbolic link.
+    pub fn is_symlink(&self) -> bool {
+        self.is_symlink
+    }
+
+    /// Size in bytes when walked (0 for the root).
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Modification time when walked, where the platform reports it
+    /// (`None` for the root).
+    pub fn modified(&self) -> Option<SystemTime> {
+        self.modified
+    }
+}
+
+// ============================================================================
+// TREE
+// ============================================================================
+
+/// Walk results as a tree of `TreeNode`s (see the module docs).
+///
+/// `Debug` shows counts only, not the root path (as `DirEntry`'s does
+/// in release builds).
+#[derive(Clone)]
+pub struct DirTree {
+    /// Walked directory (normalized like `DirWalker`'s root).
+    root: PathBuf,
+
+    /// Node table; index 0 is the root. Removed nodes leave `None`
+    /// slots, listed in `free` for reuse.
+    nodes: Vec<Option<TreeNode>>,
+
+    /// Empty slots of `nodes`.
+    free: Vec<NodeId>,
+
+    /// Nodes in the table (slots that are not `None`).
+    live: usize,
+
+    /// Path relative to `root` → node (the root under the empty path).
+    index: HashMap<PathBuf, NodeId>,
+
+    /// Counters of the walk that built the tree.
+    stats: WalkStats,
+
+    /// Whether the walk stopped at `MAX_TREE_NODES` or a budget.
+    truncated: bool,
+}
+
+/// Walk `path` with `config` and collect the entries into a `DirTree`.
+///
+/// `config`'s `yield_directories` is turned on and `yield_relative_paths`
+/// off; everything else applies as configured. An entry whose directory
+/// was not yielded (a filter dropped the directory but its contents were
+/// still walked) has no parent node and is left out.
+///
+/// # Arguments
+/// * `path` - Directory to walk; becomes the root node
+/// * `config` - Walk bounds and filters (e.g. `WalkConfig::channel_scan()`)
+///
+/// # Returns
+/// * `Ok(DirTree)` - Every kept entry, up to `MAX_TREE_NODES`
+/// * `Err(WalkError)` - Only when `continue_on_error` is false
+pub fn build_tree(path: &Path, config: WalkConfig) -> Result<DirTree, WalkError> {
+    let root = normalize_root(path);
+    let mut tree = DirTree {
+        nodes: vec![Some(TreeNode {
+            name: OsString::new(),
+            parent: None,
+            children: Vec::new(),
+            depth: 0,
+            is_dir: true,
+            is_symlink: false,
+            len: 0,
+            modified: None,
+        })],
+        free: Vec::new(),
+        live: 1,
+        index: HashMap::from([(PathBuf::new(), NodeId(0))]),
+        stats: WalkStats::default(),
+        truncated: false,
+        root,
+    };
+
+    let config = config.yield_directories(true).yield_relative_paths(false);
+    let mut walker = DirWalker::new(path, config);
+    for entry_result in walker.by_ref() {
+        let entry = entry_result?;
+        if tree.live >= MAX_TREE_NODES {
+            #[cfg(debug_assertions)]
+            eprintln!(
+                "DWEL: [walk {}] Tree node limit ({}) reached, stopping",
+                walker.walk_id(),
+                MAX_TREE_NODES
+            );
+            tree.truncated = true;
+            break;
+        }
+        tree.insert_entry(path, &entry);
+    }
+
+    tree.truncated = tree.truncated || walker.is_paused();
+    tree.stats = walker.stats().clone();
+    Ok(tree)
+}
+
+impl DirTree {
+    /// Add one walked entry under its (already present) parent node.
+    ///
+    /// # Returns
+    /// The new node; `None` if the entry is outside the root, is the root
+    /// itself, or its parent is not in the tree.
+    fn insert_entry(&mut self, root: &Path, entry: &DirEntry) -> Option<NodeId> {
+        let relative = strip_root(entry.path(), root).ok()?;
+        let name = relative.file_name()?;
+        let parent = *self.index.get(relative.parent()?)?;
+        let depth = self.node(parent)?.depth.saturating_add(1);
+        Some(self.attach(relative, TreeNode::from_entry(entry, name, parent, depth)))
+    }
+
+    /// Store `node` (in a free slot if there is one), link it into its
+    /// parent's children and index it under `relative`.
+    fn attach(&mut self, relative: &Path, node: TreeNode) -> NodeId {
+        let parent = node.parent;
+        let id = match self.free.pop() {
+            Some(id) => {
+                if let Some(slot) = self.nodes.get_mut(id.0) {
+                    *slot = Some(node);
+                }
+                id
+            }
+            None => {
+                let id = NodeId(self.nodes.len());
+                self.nodes.push(Some(node));
+                id
+            }
+        };
+        if let Some(parent_node) = parent.and_then(|parent| self.node_mut(parent)) {
+            parent_node.children.push(id);
+        }
+        self.index.insert(relative.to_path_buf(), id);
+        self.live = self.live.saturating_add(1);
+        id
+    }
+
+    /// Mutable access to the node behind `id`.
+    fn node_mut(&mut self, id: NodeId) -> Option<&mut TreeNode> {
+        self.nodes.get_mut(id.0)?.as_mut()
+    }
+
+    /// The walked directory (normalized like `DirWalker`'s root).
+    pub fn root_path(&self) -> &Path {
+        &self.root
+    }
+
+    /// The root node (the walked directory itself).
+    pub fn root(&self) -> NodeId {
+        NodeId(0)
+    }
+
+    /// Number of nodes, the root included.
+    pub fn node_count(&self) -> usize {
+        self.live
+    }
+
+    /// The node behind `id`; `None` for a removed node or an id from
+    /// another tree.
+    pub fn node(&self, id: NodeId) -> Option<&TreeNode> {
+        self.nodes.get(id.0)?.as_ref()
+    }
+
+    /// Nodes directly inside `id` (empty for files and unknown ids).
+    pub fn children(&self, id: NodeId) -> &[NodeId] {
+        self.node(id).map_or(&[], TreeNode::children)
+    }
+
+    /// Find a node by its path relative to the root.
+    ///
+    /// # Arguments
+    /// * `relative` - e.g. `attachments/1__a.png`; the empty path is the
+    ///   root. Matched component-wise, so `attachments/` also matches.
+    pub fn lookup(&self, relative: &Path) -> Option<NodeId> {
+        self.index.get(relative).copied()
+    }
+
+    /// Path of `id` relative to the root (empty for the root).
+    ///
+    /// # Returns
+    /// `None` for a removed node or an id from another tree.
+    pub fn relative_path(&self, id: NodeId) -> Option<PathBuf> {
+        let mut names: Vec<&OsStr> = Vec::new();
+        let mut current = self.node(id)?;
+        // Bounded: each step moves one level closer to the root
+        while let Some(parent) = current.parent {
+            names.push(&current.name);
+            current = self.node(parent)?;
+        }
+        Some(names.iter().rev().collect())
+    }
+
+    /// Full path of `id`: the root path joined with its relative path.
+    pub fn path(&self, id: NodeId) -> Option<PathBuf> {
+        Some(self.root.join(self.relative_path(id)?))
+    }
+
+    /// Iterate the nodes below `id`, depth-first and in pre-order
+    /// (a directory before its contents, siblings in `children()`
+    /// order), without recursion.
+    ///
+    /// # Project Context
+    /// Flattening an expanded folder into the rows of a list view.
+    pub fn descendants(&self, id: NodeId) -> Descendants<'_> {
+        Descendants {
+            tree: self,
+            stack: self.children(id).iter().rev().copied().collect(),
+        }
+    }
+
+    /// Whether the walk stopped early (`MAX_TREE_NODES`, or a
+    /// `max_total_entries` / `time_budget` pause): directories may be
+    /// missing contents.
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// Counters of the walk that built the tree.
+    pub fn stats(&self) -> &WalkStats {
+        &self.stats
+    }
+}
+
+/// Manual Debug impl: counts only, never paths.
+impl fmt::Debug for DirTree {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DirTree")
+            .field("nodes", &self.live)
+            .field("truncated", &self.truncated)
+            .field("walk_id", &self.stats.walk_id)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Iterator returned by `DirTree::descendants()`.
+#[derive(Debug)]
+pub struct Descendants<'a> {
+    tree: &'a DirTree,
+
+    /// Nodes still to visit, next on top. Holds at most the unvisited
+    /// siblings along one root-to-leaf chain.
+    stack: Vec<NodeId>,
+}
+
+impl Iterator for Descendants<'_> {
+    type Item = NodeId;
+
+    fn next(&mut self) -> Option<NodeId> {
+        let id = self.stack.pop()?;
+        self.stack
+            .extend(self.tree.children(id).iter().rev().copied());
+        Some(id)
+    }
+}
+
+// ============================================================================
+// CHANGE EVENTS
+// ============================================================================
+
+/// What one `DirTree::apply_changes()` call did.
+#[cfg(not(feature = "minimal"))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TreeUpdate {
+    /// Nodes added for new paths.
+    pub inserted: usize,
+
+    /// Nodes removed, counting each node of a removed directory.
+    pub removed: usize,
+
+    /// Nodes re-stat-ed for modified paths.
+    pub updated: usize,
+
+    /// Nodes moved to a new path (with their subtrees).
+    pub moved: usize,
+
+    /// Changed paths left out: their directory is not in the tree, they
+    /// vanished before the stat, or the tree is full (`MAX_TREE_NODES`).
+    pub skipped: usize,
+}
+
+#[cfg(not(feature = "minimal"))]
+impl DirTree {
+    /// Apply the changes of one polling-thread event (see
+    /// `apply_changes()`).
+    ///
+    /// The caller matches `event.channel_id` to this tree: an event of
+    /// another watched directory would be applied to the wrong paths.
+    pub fn apply_event(&mut self, event: &ChangeEvent) -> TreeUpdate {
+        self.apply_changes(&event.changes)
+    }
+
+    /// Bring the tree up to date with the changes a watcher found under
+    /// the same root, without walking again.
+    ///
+    /// Applied in order: removals (with their subtrees), renames (the
+    /// node and its subtree move, keeping their ids), additions, then
+    /// modifications. Each added or modified path is stat-ed once
+    /// (without following symlinks); one that vanished in the meantime
+    /// is removed, and a path whose directory is not in the tree (beyond
+    /// the tree's depth, or filtered when it was built) is skipped.
+    /// Additions are sorted parent-first, so a new directory's contents
+    /// land under it.
+    ///
+    /// # Arguments
+    /// * `changes` - Relative paths, as reported by `ChannelMonitor::poll()`
+    ///   or carried by a `ChangeEvent`
+    ///
+    /// # Returns
+    /// Counts of what changed; `skipped` paths are worth a full rebuild
+    /// when the tree must mirror the disk exactly.
+    pub fn apply_changes(&mut self, changes: &ChangeSummary) -> TreeUpdate {
+        let mut update = TreeUpdate::default();
+
+        for relative in &changes.removed {
+            // A path under a removed directory is already gone
+            if let Some(id) = self.lookup(relative) {
+                update.removed = update.removed.saturating_add(self.remove_subtree(id));
+            }
+        }
+        for rename in &changes.renamed {
+            if self.move_node(&rename.from, &rename.to, &mut update) {
+                update.moved = update.moved.saturating_add(1);
+            } else {
+                if let Some(id) = self.lookup(&rename.from) {
+                    update.removed = update.removed.saturating_add(self.remove_subtree(id));
+                }
+                self.refresh_path(&rename.to, &mut update);
+            }
+        }
+        for relative in changes.added.iter().chain(&changes.modified) {
+            self.refresh_path(relative, &mut update);
+        }
+
+        update
+    }
+
+    /// Stat `relative` and insert, update or remove its node to match.
+    fn refresh_path(&mut self, relative: &Path, update: &mut TreeUpdate) {
+        let existing = self.lookup(relative);
+        let metadata = match fs::symlink_metadata(self.root.join(relative)) {
+            Ok(metadata) => metadata,
+            Err(_) => {
+                match existing {
+                    Some(id) => {
+                        update.removed = update.removed.saturating_add(self.remove_subtree(id))
+                    }
+                    None => update.skipped = update.skipped.saturating_add(1),
+                }
+                return;
+            }
+        };
+
+        match existing {
+            Some(id) => {
+                let mut emptied: Vec<NodeId> = Vec::new();
+                if let Some(node) = self.node_mut(id) {
+                    node.set_metadata(&metadata);
+                    // A directory replaced by a file loses its contents
+                    if !node.is_dir {
+                        emptied = std::mem::take(&mut node.children);
+                    }
+                }
+                for child in emptied {
+                    update.removed = update.removed.saturating_add(self.remove_subtree(child));
+                }
+                update.updated = update.updated.saturating_add(1);
+            }
+            None => {
+                if self.insert_path(relative, &metadata).is_some() {
+                    update.inserted = update.inserted.saturating_add(1);
+                } else {
+                    update.skipped = update.skipped.saturating_add(1);
+                }
+            }
+        }
+    }
+
+    /// Add a node for `relative` under its (already present) directory.
+    ///
+    /// # Returns
+    /// `None` if the directory is not in the tree (or is a file), or the
+    /// tree is full (then marked truncated).
+    fn insert_path(&mut self, relative: &Path, metadata: &fs::Metadata) -> Option<NodeId> {
+        let name = relative.file_name()?;
+        let parent = self.lookup(relative.parent()?)?;
+        let parent_node = self.node(parent)?;
+        if !parent_node.is_dir {
+            return None;
+        }
+        if self.live >= MAX_TREE_NODES {
+            #[cfg(debug_assertions)]
+            eprintln!(
+                "DWEL: Tree node limit ({}) reached, not adding a changed path",
+                MAX_TREE_NODES
+            );
+            self.truncated = true;
+            return None;
+        }
+        let depth = parent_node.depth.saturating_add(1);
+        Some(self.attach(
+            relative,
+            TreeNode::from_metadata(name, parent, depth, metadata),
+        ))
+    }
+
+    /// Remove `id` and everything below it (never the root).
+    ///
+    /// # Returns
+    /// Number of nodes removed.
+    fn remove_subtree(&mut self, id: NodeId) -> usize {
+        let (Some(relative), Some(parent)) = (
+            self.relative_path(id),
+            self.node(id).and_then(TreeNode::parent),
+        ) else {
+            return 0;
+        };
+        if let Some(parent_node) = self.node_mut(parent) {
+            parent_node.children.retain(|child| *child != id);
+        }
+
+        let mut removed: usize = 0;
+        // Bounded by the subtree's size; no recursion
+        let mut stack: Vec<(NodeId, PathBuf)> = vec![(id, relative)];
+        while let Some((id, relative)) = stack.pop() {
+            let Some(node) = self.nodes.get_mut(id.0).and_then(Option::take) else {
+                continue;
+            };
+            for &child in &node.children {
+                if let Some(child_node) = self.node(child) {
+                    stack.push((child, relative.join(&child_node.name)));
+                }
+            }
+            self.index.remove(&relative);
+            self.free.push(id);
+            self.live = self.live.saturating_sub(1);
+            removed = removed.saturating_add(1);
+        }
+        removed
+    }
+
+    /// Move the node at `from` (with its subtree) to `to`, replacing a
+    /// node already at `to`.
+    ///
+    /// # Returns
+    /// `false` (nothing changed) if `from` is not in the tree, is the
+    /// root, or `to`'s directory is not in the tree; or if one path lies
+    /// inside the other.
+    fn move_node(&mut self, from: &Path, to: &Path, update: &mut TreeUpdate) -> bool {
+        let (Some(id), Some(name), Some(to_parent)) = (
+            self.lookup(from),
+            to.file_name(),
+            to.parent().and_then(|parent| self.lookup(parent)),
+        ) else {
+            return false;
+        };
+        let Some(old_parent) = self.node(id).and_then(TreeNode::parent) else {
+            return false;
+        };
+        if to.starts_with(from)
+            || from.starts_with(to)
+            || !self.node(to_parent).is_some_and(TreeNode::is_dir)
+        {
+            return false;
+        }
+        if let Some(replaced) = self.lookup(to) {
+            update.removed = update.removed.saturating_add(self.remove_subtree(replaced));
+        }
+
+        if let Some(node) = self.node_mut(old_parent) {
+            node.children.retain(|child| *child != id);
+        }
+        let depth = self
+            .node(to_parent)
+            .map_or(1, |node| node.depth.saturating_add(1));
+        if let Some(node) = self.node_mut(to_parent) {
+            node.children.push(id);
+        }
+        if let Some(node) = self.node_mut(id) {
+            node.name = name.to_os_string();
+            node.parent = Some(to_parent);
+        }
+
+        // Re-key and re-depth the subtree
+        let mut stack: Vec<(NodeId, PathBuf, PathBuf, usize)> =
+            vec![(id, from.to_path_buf(), to.to_path_buf(), depth)];
+        while let Some((id, old, new, depth)) = stack.pop() {
+            let Some(node) = self.node_mut(id) else {
+                continue;
+            };
+            node.depth = depth;
+            let children = node.children.clone();
+            for child in children {
+                if let Some(child_node) = self.node(child) {
+                    stack.push((
+                        child,
+                        old.join(&child_node.name),
+                        new.join(&child_node.name),
+                        depth.saturating_add(1),
+                    ));
+                }
+            }
+            self.index.remove(&old);
+            self.index.insert(new, id);
+        }
+        true
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    /// Helper: unique test directory path (caller cleans up).
+    fn test_dir(name: &str) -> PathBuf {
+        crate::dir_walk_module::testing::unique_test_dir(&format!("tree_{}", name))
+    }
+
+    /// Helper: safely remove test directory, ignoring errors.
+    fn cleanup(path: &Path) {
+        let _ = fs::remove_dir_all(path);
+    }
+
+    /// Test: the tree links parents and children, looks nodes up by
+    /// relative path, rebuilds paths, and lists a subtree in pre-order.
+    #[test]
+    fn test_build_tree_navigation() {
+        let base = test_dir("navigation");
+        cleanup(&base);
+        assert!(
+            fs::create_dir_all(base.join("a").join("deep")).is_ok()
+                && fs::create_dir_all(base.join("b")).is_ok()
+                && fs::write(base.join("1__top.toml"), b"top").is_ok()
+                && fs::write(base.join("a").join("2__x.toml"), b"xx").is_ok()
+                && fs::write(base.join("a").join("deep").join("3__y.toml"), b"y").is_ok(),
+            "test_build_tree_navigation: failed to create tree"
+        );
+
+        let built = build_tree(&base, WalkConfig::new().yield_directories(false));
+        assert!(built.is_ok(), "test_build_tree_navigation: build failed");
+        let Ok(tree) = built else { return };
+        assert_eq!(
+            (tree.node_count(), tree.is_truncated()),
+            (7, false),
+            "test_build_tree_navigation: root plus six entries"
+        );
+
+        let deep_file = tree.lookup(Path::new("a/deep/3__y.toml"));
+        assert!(
+            deep_file.is_some(),
+            "test_build_tree_navigation: lookup by relative path"
+        );
+        let Some(deep_file) = deep_file else { return };
+        let node = tree.node(deep_file);
+        assert!(
+            node.is_some_and(|n| n.name() == Some("3__y.toml")
+                && n.depth() == 3
+                && n.len() == 1
+                && !n.is_dir()),
+            "test_build_tree_navigation: node metadata"
+        );
+        let parent = node.and_then(TreeNode::parent);
+        assert_eq!(
+            parent,
+            tree.lookup(Path::new("a/deep")),
+            "test_build_tree_navigation: parent link"
+        );
+        assert_eq!(
+            tree.path(deep_file),
+            Some(base.join("a").join("deep").join("3__y.toml")),
+            "test_build_tree_navigation: full path rebuilt"
+        );
+        assert_eq!(
+            tree.lookup(Path::new("")),
+            Some(tree.root()),
+            "test_build_tree_navigation: empty path is the root"
+        );
+
+        let a = tree.lookup(Path::new("a"));
+        assert!(a.is_some(), "test_build_tree_navigation: directory lookup");
+        let Some(a) = a else { return };
+        assert_eq!(
+            tree.lookup(Path::new("a/")),
+            Some(a),
+            "test_build_tree_navigation: trailing separator matches"
+        );
+        let mut below: Vec<PathBuf> = tree
+            .descendants(a)
+            .filter_map(|id| tree.relative_path(id))
+            .collect();
+        let deep_position = below.iter().position(|p| p == Path::new("a/deep"));
+        let file_position = below
+            .iter()
+            .position(|p| p == Path::new("a/deep/3__y.toml"));
+        assert!(
+            deep_position.is_some() && deep_position < file_position,
+            "test_build_tree_navigation: pre-order puts a directory first"
+        );
+        below.sort();
+        assert_eq!(
+            below,
+            vec![
+                PathBuf::from("a/2__x.toml"),
+                PathBuf::from("a/deep"),
+                PathBuf::from("a/deep/3__y.toml"),
+            ],
+            "test_build_tree_navigation: descendants of a"
+        );
+        assert!(
+            tree.lookup(Path::new("b"))
+                .is_some_and(|b| tree.children(b).is_empty()),
+            "test_build_tree_navigation: empty directory has no children"
+        );
+
+        // Walk bounds shape the tree
+        let shallow = build_tree(&base, WalkConfig::new().max_depth(0));
+        assert!(
+            shallow
+                .is_ok_and(|t| t.node_count() == 4 && t.lookup(Path::new("a/2__x.toml")).is_none()),
+            "test_build_tree_navigation: max_depth bounds the tree"
+        );
+
+        cleanup(&base);
+    }
+
+    /// Test: changes found by a monitor, and a rename, brought into a
+    /// built tree leave it equal to a fresh build, with moved nodes
+    /// keeping their ids.
+    #[cfg(not(feature = "minimal"))]
+    #[test]
+    fn test_apply_change_events() {
+        use crate::dir_walk_module::monitor::ChannelMonitor;
+        use crate::dir_walk_module::snapshot::Rename;
+
+        let base = test_dir("apply_changes");
+        cleanup(&base);
+        assert!(
+            fs::create_dir_all(base.join("sub")).is_ok()
+                && fs::create_dir_all(base.join("old").join("deep")).is_ok()
+                && fs::write(base.join("1__a.toml"), b"a").is_ok()
+                && fs::write(base.join("sub").join("2__b.toml"), b"b").is_ok()
+                && fs::write(base.join("old").join("3__c.toml"), b"c").is_ok()
+                && fs::write(base.join("old").join("deep").join("4__d.toml"), b"d").is_ok(),
+            "test_apply_change_events: failed to create tree"
+        );
+        // Files with their sizes; directory sizes vary by filesystem
+        let listing = |tree: &DirTree| {
+            let mut rows: Vec<(PathBuf, u64)> = tree
+                .descendants(tree.root())
+                .filter_map(|id| {
+                    let node = tree.node(id)?;
+                    let len = if node.is_dir() { 0 } else { node.len() };
+                    Some((tree.relative_path(id)?, len))
+                })
+                .collect();
+            rows.sort();
+            rows
+        };
+        let fresh = || build_tree(&base, WalkConfig::new()).map(|tree| listing(&tree));
+
+        let built = build_tree(&base, WalkConfig::new());
+        assert!(built.is_ok(), "test_apply_change_events: build failed");
+        let Ok(mut tree) = built else { return };
+        let mut monitor = ChannelMonitor::new(&base, WalkConfig::new());
+        assert!(
+            monitor.poll().is_none(),
+            "test_apply_change_events: baseline poll"
+        );
+
+        assert!(
+            fs::remove_dir_all(base.join("old")).is_ok()
+                && fs::remove_file(base.join("sub").join("2__b.toml")).is_ok()
+                && fs::write(base.join("1__a.toml"), b"longer").is_ok()
+                && fs::create_dir_all(base.join("new")).is_ok()
+                && fs::write(base.join("new").join("5__e.toml"), b"e").is_ok(),
+            "test_apply_change_events: failed to change tree"
+        );
+        let changes = monitor.poll();
+        assert!(
+            changes.is_some(),
+            "test_apply_change_events: no changes found"
+        );
+        let Some(changes) = changes else { return };
+        let update = tree.apply_event(&ChangeEvent {
+            channel_id: 0,
+            walk_id: monitor.walk_id(),
+            changes,
+        });
+        assert_eq!(
+            (update.inserted, update.removed, update.skipped),
+            (2, 5, 0),
+            "test_apply_change_events: new dir and file in, old subtree and file out"
+        );
+        assert_eq!(
+            Ok(listing(&tree)),
+            fresh(),
+            "test_apply_change_events: tree matches a fresh build"
+        );
+
+        // A renamed directory moves with its contents and ids
+        let moved_file = tree.lookup(Path::new("new/5__e.toml"));
+        assert!(
+            moved_file.is_some() && fs::rename(base.join("new"), base.join("renamed")).is_ok(),
+            "test_apply_change_events: failed to rename directory"
+        );
+        let update = tree.apply_changes(&ChangeSummary {
+            renamed: vec![Rename {
+                from: PathBuf::from("new"),
+                to: PathBuf::from("renamed"),
+            }],
+            ..ChangeSummary::default()
+        });
+        assert_eq!(
+            (update.moved, tree.lookup(Path::new("renamed/5__e.toml"))),
+            (1, moved_file),
+            "test_apply_change_events: moved node keeps its id"
+        );
+        assert!(
+            tree.lookup(Path::new("new")).is_none()
+                && moved_file.and_then(|id| tree.path(id))
+                    == Some(base.join("renamed").join("5__e.toml")),
+            "test_apply_change_events: old path gone, new path rebuilt"
+        );
+        assert_eq!(
+            Ok(listing(&tree)),
+            fresh(),
+            "test_apply_change_events: tree matches a fresh build after the rename"
+        );
+
+        cleanup(&base);
+    }
+}
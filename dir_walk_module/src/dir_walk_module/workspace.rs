@@ -0,0 +1,487 @@
+//! # Workspace Scan (`dir_walk_module::workspace`)
+//!
+//! One bounded walk over a workspace that reports everything the
+//! application needs about it at startup: the channels, their message
+//! counts, when each was last active, how much space each uses against
+//! its quota, and entries with unsafe permissions.
+//!
+//! ## Project Context
+//! Startup used to run four separate walks over the same tree: a channel
+//! listing, per-channel message counts, a quota check and a permission
+//! audit. On a large workspace (or a network share) that was four times
+//! the directory reads for the same metadata. `scan_workspace()` gathers
+//! all of it from a single `walk_with()` pass, under one set of bounds.
+//!
+//! ## Workspace Layout
+//! Every directory under the root is a channel, except message
+//! attachment directories (`N__attachments`) and anything inside them,
+//! which belong to the channel holding them (see `channels`). Files
+//! directly in the root belong to no channel.
+//!
+//! ```ignore
+//! let report = scan_workspace(&workspace, WorkspaceConfig::new().channel_quota_bytes(quota))?;
+//! for channel in report.over_quota() {
+//!     warn_operator(&channel.path);
+//! }
+//! ```
+
+use std::collections::BTreeMap;
+use std::ops::ControlFlow;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use super::channels::{ChannelEntryClass, attachments_dir_number, channel_entry_class};
+use super::{WalkConfig, WalkError, WalkStats, walk_with};
+
+/// Default cap on permission findings kept in a report.
+const DEFAULT_MAX_FINDINGS: usize = 1000;
+
+/// Permission bits that make an entry a finding: writable by others.
+#[cfg(unix)]
+const WORLD_WRITABLE_BITS: u32 = 0o002;
+
+// ============================================================================
+// CONFIGURATION
+// ============================================================================
+
+/// Configuration for `scan_workspace()`.
+#[derive(Debug, Clone)]
+pub struct WorkspaceConfig {
+    /// Walk over the workspace (bounds, filters, symlink policy,
+    /// metadata file names).
+    walk_config: WalkConfig,
+
+    /// Bytes one channel may use (its files and attachments); `None`
+    /// for no quota.
+    channel_quota_bytes: Option<u64>,
+
+    /// Permission findings kept; further ones are only counted.
+    max_findings: usize,
+}
+
+impl Default for WorkspaceConfig {
+    fn default() -> Self {
+        WorkspaceConfig {
+            walk_config: WalkConfig::channel_scan(),
+            channel_quota_bytes: None,
+            max_findings: DEFAULT_MAX_FINDINGS,
+        }
+    }
+}
+
+impl WorkspaceConfig {
+    /// Create a config with defaults: `WalkConfig::channel_scan()`, no
+    /// channel quota, at most 1000 permission findings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the walk configuration used for the scan.
+    ///
+    /// # Arguments
+    /// * `config` - Its bounds and filters apply as in any walk;
+    ///   directories are always yielded and paths are always relative
+    ///   (the scan needs both to find the channels)
+    pub fn walk_config(mut self, config: WalkConfig) -> Self {
+        self.walk_config = config;
+        self
+    }
+
+    /// Set the bytes one channel may use before it is reported by
+    /// `WorkspaceReport::over_quota()`.
+    pub fn channel_quota_bytes(mut self, bytes: u64) -> Self {
+        self.channel_quota_bytes = Some(bytes);
+        self
+    }
+
+    /// Set the maximum number of permission findings kept in the report.
+    pub fn max_findings(mut self, max: usize) -> Self {
+        self.max_findings = max;
+        self
+    }
+}
+
+// ============================================================================
+// REPORT
+// ============================================================================
+
+/// What `scan_workspace()` found in one channel.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChannelSummary {
+    /// Channel directory, relative to the workspace root.
+    pub path: PathBuf,
+
+    /// Plaintext (`.toml`) messages.
+    pub plaintext_messages: usize,
+
+    /// Encrypted (`.gpgtoml`) messages.
+    pub encrypted_messages: usize,
+
+    /// Attachment files, including those in attachment directories.
+    pub attachments: usize,
+
+    /// Logical bytes of all the channel's files (messages, metadata,
+    /// attachments, anything else); what the quota is checked against.
+    pub bytes: u64,
+
+    /// Newest modification time among the channel's files; `None` for
+    /// a channel with no files (or unreadable times).
+    pub latest_activity: Option<SystemTime>,
+
+    /// Whether `bytes` exceeds the configured channel quota.
+    pub over_quota: bool,
+}
+
+impl ChannelSummary {
+    /// Total messages, plaintext and encrypted.
+    pub fn messages(&self) -> usize {
+        self.plaintext_messages
+            .saturating_add(self.encrypted_messages)
+    }
+}
+
+/// Why an entry was reported by the permission check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PermissionIssue {
+    /// Writable by users other than the owner and group (Unix `o+w`).
+    WorldWritable,
+}
+
+/// One entry with unsafe permissions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PermissionFinding {
+    /// Entry path, relative to the workspace root.
+    pub path: PathBuf,
+
+    /// What is wrong with it.
+    pub issue: PermissionIssue,
+
+    /// The entry's permission bits (`DirEntry::permission_bits()`).
+    pub mode: u32,
+}
+
+/// Result of `scan_workspace()`.
+#[derive(Debug, Clone)]
+pub struct WorkspaceReport {
+    /// Channels, sorted by path.
+    pub channels: Vec<ChannelSummary>,
+
+    /// Files directly in the workspace root (in no channel).
+    pub loose_files: usize,
+
+    /// Entries with unsafe permissions, in walk order; at most the
+    /// configured `max_findings`. Always empty off Unix, where the
+    /// platform reports no comparable bits.
+    pub permission_findings: Vec<PermissionFinding>,
+
+    /// Findings beyond `max_findings`, counted but not kept.
+    pub findings_dropped: usize,
+
+    /// Counters of the walk (check `WalkStats::complete()` before
+    /// trusting the totals of a bounded scan).
+    pub stats: WalkStats,
+}
+
+impl WorkspaceReport {
+    /// Channels whose files use more than the configured quota.
+    pub fn over_quota(&self) -> impl Iterator<Item = &ChannelSummary> {
+        self.channels.iter().filter(|channel| channel.over_quota)
+    }
+
+    /// Logical bytes of every channel's files (loose files excluded).
+    pub fn total_bytes(&self) -> u64 {
+        self.channels
+            .iter()
+            .fold(0u64, |total, channel| total.saturating_add(channel.bytes))
+    }
+
+    /// Newest activity in any channel.
+    pub fn latest_activity(&self) -> Option<SystemTime> {
+        self.channels
+            .iter()
+            .filter_map(|channel| channel.latest_activity)
+            .max()
+    }
+
+    /// Look up a channel by its path relative to the workspace root.
+    pub fn channel(&self, path: &Path) -> Option<&ChannelSummary> {
+        self.channels
+            .binary_search_by(|channel| channel.path.as_path().cmp(path))
+            .ok()
+            .and_then(|index| self.channels.get(index))
+    }
+}
+
+// ============================================================================
+// SCAN
+// ============================================================================
+
+/// Scan a workspace in one bounded walk: channels, message counts,
+/// latest activity, quota usage and permission findings.
+///
+/// Channel and file classification follow `channels`
+/// (`channel_entry_class()`), with the metadata file names of the walk
+/// config. Every bound of the walk config applies; what a bounded walk
+/// did not reach is missing from the report, and `stats` says so.
+///
+/// # Arguments
+/// * `root` - Workspace base directory
+/// * `config` - Walk, quota and finding bounds
+///
+/// # Returns
+/// * `Ok(WorkspaceReport)` - The aggregated report
+/// * `Err(WalkError)` - Fatal walk error (strict mode only)
+pub fn scan_workspace(root: &Path, config: WorkspaceConfig) -> Result<WorkspaceReport, WalkError> {
+    let metadata_names = config.walk_config.metadata_file_names.clone();
+    let walk_config = config
+        .walk_config
+        .yield_directories(true)
+        .yield_relative_paths(true);
+
+    let mut channels: BTreeMap<PathBuf, ChannelSummary> = BTreeMap::new();
+    let mut loose_files: usize = 0;
+    let mut permission_findings: Vec<PermissionFinding> = Vec::new();
+    let mut findings_dropped: usize = 0;
+
+    let stats = walk_with(root, walk_config, |entry| {
+        #[cfg(unix)]
+        if !entry.is_symlink() && entry.permission_bits() & WORLD_WRITABLE_BITS != 0 {
+            if permission_findings.len() < config.max_findings {
+                permission_findings.push(PermissionFinding {
+                    path: entry.path().to_path_buf(),
+                    issue: PermissionIssue::WorldWritable,
+                    mode: entry.permission_bits(),
+                });
+            } else {
+                findings_dropped = findings_dropped.saturating_add(1);
+            }
+        }
+
+        let class = channel_entry_class(entry, &metadata_names);
+        if entry.is_dir() {
+            if class != ChannelEntryClass::Attachment {
+                channel_summary(&mut channels, entry.path());
+            }
+            return ControlFlow::Continue(());
+        }
+
+        let Some(channel_path) = owning_channel(entry.path()) else {
+            loose_files = loose_files.saturating_add(1);
+            return ControlFlow::Continue(());
+        };
+        let channel = channel_summary(&mut channels, channel_path);
+        match class {
+            ChannelEntryClass::PlaintextMessage => {
+                channel.plaintext_messages = channel.plaintext_messages.saturating_add(1);
+            }
+            ChannelEntryClass::EncryptedMessage => {
+                channel.encrypted_messages = channel.encrypted_messages.saturating_add(1);
+            }
+            ChannelEntryClass::Attachment => {
+                channel.attachments = channel.attachments.saturating_add(1);
+            }
+            ChannelEntryClass::Metadata | ChannelEntryClass::Unknown => {}
+        }
+        channel.bytes = channel.bytes.saturating_add(entry.len());
+        if let Ok(modified) = entry.metadata.modified() {
+            channel.latest_activity = channel.latest_activity.max(Some(modified));
+        }
+        ControlFlow::Continue(())
+    })?;
+
+    let channels = channels
+        .into_values()
+        .map(|mut channel| {
+            channel.over_quota = config
+                .channel_quota_bytes
+                .is_some_and(|quota| channel.bytes > quota);
+            channel
+        })
+        .collect();
+
+    Ok(WorkspaceReport {
+        channels,
+        loose_files,
+        permission_findings,
+        findings_dropped,
+        stats,
+    })
+}
+
+/// The summary of the channel at `path`, created empty on first use.
+fn channel_summary<'a>(
+    channels: &'a mut BTreeMap<PathBuf, ChannelSummary>,
+    path: &Path,
+) -> &'a mut ChannelSummary {
+    channels
+        .entry(path.to_path_buf())
+        .or_insert_with(|| ChannelSummary {
+            path: path.to_path_buf(),
+            ..ChannelSummary::default()
+        })
+}
+
+/// The channel a file belongs to: the directory holding its outermost
+/// attachment directory, or else its own directory. `None` for files
+/// directly in the root.
+///
+/// # Arguments
+/// * `path` - File path relative to the workspace root
+fn owning_channel(path: &Path) -> Option<&Path> {
+    let attachments_dir = path.ancestors().skip(1).filter(|ancestor| {
+        ancestor
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(attachments_dir_number)
+            .is_some()
+    });
+    let holder = match attachments_dir.last() {
+        Some(dir) => dir.parent(),
+        None => path.parent(),
+    }?;
+    if holder.as_os_str().is_empty() {
+        None
+    } else {
+        Some(holder)
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    /// Helper: unique test directory path (caller cleans up).
+    fn test_dir(name: &str) -> PathBuf {
+        crate::dir_walk_module::testing::unique_test_dir(&format!("workspace_{}", name))
+    }
+
+    /// Helper: safely remove test directory, ignoring errors.
+    fn cleanup(path: &Path) {
+        let _ = fs::remove_dir_all(path);
+    }
+
+    /// Test: one scan counts each channel's messages, attachments and
+    /// bytes, keeps attachment directories out of the channel list, and
+    /// flags channels over quota.
+    #[test]
+    fn test_scan_workspace() {
+        let dir = test_dir("scan");
+        cleanup(&dir);
+        let team = dir.join("team");
+        let nested = team.join("design");
+        let created = fs::create_dir_all(team.join("1__attachments")).is_ok()
+            && fs::create_dir_all(&nested).is_ok()
+            && fs::create_dir_all(dir.join("empty")).is_ok()
+            && fs::write(dir.join("loose.txt"), b"x").is_ok()
+            && fs::write(team.join("0.toml"), b"meta").is_ok()
+            && fs::write(team.join("1__hello.toml"), b"hello").is_ok()
+            && fs::write(team.join("2__secret.gpgtoml"), b"secret").is_ok()
+            && fs::write(team.join("1__attachments").join("a.png"), b"0123456789").is_ok()
+            && fs::write(nested.join("1__plan.toml"), b"plan").is_ok();
+        assert!(created, "test_scan_workspace: failed to create workspace");
+
+        let report = scan_workspace(&dir, WorkspaceConfig::new().channel_quota_bytes(10));
+        assert!(report.is_ok(), "test_scan_workspace: scan failed");
+        let Ok(report) = report else { return };
+
+        let paths: Vec<&Path> = report.channels.iter().map(|c| c.path.as_path()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                Path::new("empty"),
+                Path::new("team"),
+                Path::new("team/design")
+            ],
+            "test_scan_workspace: channel list"
+        );
+        assert_eq!(report.loose_files, 1, "test_scan_workspace: loose files");
+
+        let team_summary = report.channel(Path::new("team"));
+        assert_eq!(
+            team_summary.map(|c| (c.plaintext_messages, c.encrypted_messages, c.attachments)),
+            Some((1, 1, 1)),
+            "test_scan_workspace: team counts"
+        );
+        assert_eq!(
+            team_summary.map(|c| c.bytes),
+            Some(4 + 5 + 6 + 10),
+            "test_scan_workspace: team bytes include metadata and attachments"
+        );
+        assert!(
+            team_summary.is_some_and(|c| c.latest_activity.is_some()),
+            "test_scan_workspace: team activity"
+        );
+        assert!(
+            report
+                .channel(Path::new("empty"))
+                .is_some_and(|c| c.messages() == 0 && c.latest_activity.is_none()),
+            "test_scan_workspace: empty channel"
+        );
+
+        let over: Vec<&Path> = report.over_quota().map(|c| c.path.as_path()).collect();
+        assert_eq!(
+            over,
+            vec![Path::new("team")],
+            "test_scan_workspace: over quota"
+        );
+        assert_eq!(report.total_bytes(), 25 + 4, "test_scan_workspace: total");
+
+        cleanup(&dir);
+    }
+
+    /// Test (Unix): world-writable entries are reported, up to the
+    /// configured cap.
+    #[cfg(unix)]
+    #[test]
+    fn test_scan_workspace_permission_findings() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = test_dir("permissions");
+        cleanup(&dir);
+        let channel = dir.join("team");
+        let created = fs::create_dir_all(&channel).is_ok()
+            && ["1__a.toml", "2__b.toml", "3__c.toml"].iter().all(|name| {
+                fs::write(channel.join(name), b"x").is_ok()
+                    && fs::set_permissions(channel.join(name), fs::Permissions::from_mode(0o600))
+                        .is_ok()
+            })
+            && fs::set_permissions(channel.join("2__b.toml"), fs::Permissions::from_mode(0o666))
+                .is_ok()
+            && fs::set_permissions(channel.join("3__c.toml"), fs::Permissions::from_mode(0o602))
+                .is_ok()
+            && fs::set_permissions(&channel, fs::Permissions::from_mode(0o755)).is_ok();
+        assert!(
+            created,
+            "test_scan_workspace_permission_findings: failed to create files"
+        );
+
+        let report = scan_workspace(&dir, WorkspaceConfig::new());
+        let mut found: Vec<PathBuf> = report
+            .iter()
+            .flat_map(|r| r.permission_findings.iter())
+            .map(|f| f.path.clone())
+            .collect();
+        found.sort();
+        assert_eq!(
+            found,
+            vec![
+                PathBuf::from("team/2__b.toml"),
+                PathBuf::from("team/3__c.toml")
+            ],
+            "test_scan_workspace_permission_findings: findings"
+        );
+
+        let capped = scan_workspace(&dir, WorkspaceConfig::new().max_findings(1));
+        assert!(
+            capped.is_ok_and(|r| r.permission_findings.len() == 1 && r.findings_dropped == 1),
+            "test_scan_workspace_permission_findings: cap"
+        );
+
+        cleanup(&dir);
+    }
+}